@@ -1,4 +1,5 @@
 use libc::*;
+use std::mem;
 use ENetBuffer;
 
 pub type ENetPacketFreeCallback = extern "C" fn(packet: *mut ENetPacket);
@@ -24,6 +25,55 @@ bitflags! {
     }
 }
 
+// `ENetPacketFlags` is passed by value to `enet_packet_create`
+const _ASSERT_PACKET_FLAGS_SIZE: [(); mem::size_of::<uint32_t>()] =
+    [(); mem::size_of::<ENetPacketFlags>()];
+const _ASSERT_PACKET_FLAGS_ALIGN: [(); mem::align_of::<uint32_t>()] =
+    [(); mem::align_of::<ENetPacketFlags>()];
+
+/// A single flag of `ENetPacketFlags`. Corresponds to `ENetPacketFlag` of
+/// the C API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ENetPacketFlag {
+    Reliable,
+    Unsequenced,
+    NoAllocate,
+    UnreliableFragment,
+    Sent,
+}
+
+impl ENetPacketFlag {
+    /// Convert a raw value of `ENetPacketFlag` to `Self`. Returns `None` if
+    /// the value does not represent exactly one known flag.
+    pub fn from_raw(x: uint32_t) -> Option<Self> {
+        match x {
+            1 => Some(ENetPacketFlag::Reliable),
+            0b10 => Some(ENetPacketFlag::Unsequenced),
+            0b100 => Some(ENetPacketFlag::NoAllocate),
+            0b1000 => Some(ENetPacketFlag::UnreliableFragment),
+            0b100000000 => Some(ENetPacketFlag::Sent),
+            _ => None,
+        }
+    }
+
+    /// Get the raw value of `ENetPacketFlag`.
+    pub fn to_raw(self) -> uint32_t {
+        ENetPacketFlags::from(self).bits()
+    }
+}
+
+impl From<ENetPacketFlag> for ENetPacketFlags {
+    fn from(x: ENetPacketFlag) -> Self {
+        match x {
+            ENetPacketFlag::Reliable => ENetPacketFlags::RELIABLE,
+            ENetPacketFlag::Unsequenced => ENetPacketFlags::UNSEQUENCED,
+            ENetPacketFlag::NoAllocate => ENetPacketFlags::NO_ALLOCATE,
+            ENetPacketFlag::UnreliableFragment => ENetPacketFlags::UNRELIABLE_FRAGMENT,
+            ENetPacketFlag::Sent => ENetPacketFlags::SENT,
+        }
+    }
+}
+
 extern "C" {
     pub fn enet_crc32(buffers: *const ENetBuffer, bufferCount: size_t) -> uint32_t;
     pub fn enet_packet_create(
@@ -1,4 +1,5 @@
 use libc::*;
+use std::mem;
 use ::list::{ENetList, ENetListNode};
 use ::address::ENetAddress;
 use ::{ENetChannel, ENetAcknowledgement, ENetIncomingCommand, ENetOutgoingCommand};
@@ -39,7 +40,7 @@ pub struct ENetPeer {
     pub incoming_session_id: uint8_t,
     pub address: ENetAddress,
     pub data: *mut c_void,
-    pub state: ENetPeerState,
+    pub state: ENetPeerStateRaw,
     pub channels: *mut ENetChannel,
     pub channel_count: size_t,
     pub incoming_bandwidth: uint32_t,
@@ -92,6 +93,13 @@ pub struct ENetPeer {
     pub total_waiting_data: size_t,
 }
 
+impl ENetPeer {
+    /// Get the state of the peer. Returns `None` if the state is unknown.
+    pub fn state(&self) -> Option<ENetPeerState> {
+        self.state.get()
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ENetPeerState {
@@ -107,6 +115,57 @@ pub enum ENetPeerState {
     Zombie = 9,
 }
 
+impl ENetPeerState {
+    /// Convert a raw value of `ENetPeerState` to `Self`. Returns `None` if
+    /// the value is unknown.
+    pub fn from_raw(x: c_int) -> Option<Self> {
+        use self::ENetPeerState::*;
+        Some(match x {
+            0 => Disconnected,
+            1 => Connecting,
+            2 => AcknowledgingConnect,
+            3 => ConnectionPending,
+            4 => ConnectionSucceeded,
+            5 => Connected,
+            6 => DisconnectLater,
+            7 => Disconnecting,
+            8 => AcknowledgingDisconnect,
+            9 => Zombie,
+            _ => return None,
+        })
+    }
+
+    /// Get the raw value of `ENetPeerState`.
+    pub fn to_raw(self) -> c_int {
+        self as c_int
+    }
+}
+
+/// The raw representation of `ENetPeerState`, which is used in FFI structures
+/// where ENet might store a value unknown to us. (Storing such a value in
+/// `ENetPeerState` would be undefined behavior.)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ENetPeerStateRaw(pub c_int);
+
+const _ASSERT_PEER_STATE_SIZE: [(); mem::size_of::<ENetPeerState>()] =
+    [(); mem::size_of::<ENetPeerStateRaw>()];
+const _ASSERT_PEER_STATE_ALIGN: [(); mem::align_of::<ENetPeerState>()] =
+    [(); mem::align_of::<ENetPeerStateRaw>()];
+
+impl ENetPeerStateRaw {
+    /// Get the typed value. Returns `None` if the state is unknown.
+    pub fn get(self) -> Option<ENetPeerState> {
+        ENetPeerState::from_raw(self.0)
+    }
+}
+
+impl From<ENetPeerState> for ENetPeerStateRaw {
+    fn from(x: ENetPeerState) -> Self {
+        ENetPeerStateRaw(x.to_raw())
+    }
+}
+
 extern {
     pub fn enet_peer_disconnect(peer: *mut ENetPeer, data: uint32_t);
     pub fn enet_peer_disconnect_later(peer: *mut ENetPeer, data: uint32_t);
@@ -0,0 +1,90 @@
+//! Validates the constants against the C header so the FFI boundary can't
+//! silently drift.
+extern crate enet_ll;
+
+use enet_ll::packet::{ENetPacketFlag, ENetPacketFlags};
+use enet_ll::peer::{ENetPeerState, ENetPeerStateRaw};
+use std::collections::HashMap;
+
+/// Extract the enumerators of `enum` definitions whose names start with
+/// `prefix` from `enet.h`.
+fn header_constants(prefix: &str) -> HashMap<String, u32> {
+    let header = include_str!("../libenet/include/enet/enet.h");
+    let mut constants = HashMap::new();
+
+    for line in header.lines() {
+        let line = line.trim().trim_end_matches(',');
+        if !line.starts_with(prefix) {
+            continue;
+        }
+        let mut parts = line.splitn(2, '=');
+        let name = parts.next().unwrap().trim();
+        let value = match parts.next() {
+            Some(x) => x.trim(),
+            None => continue,
+        };
+
+        let value = value.trim_start_matches('(').trim_end_matches(')');
+        let value = if let Some(i) = value.find("<<") {
+            let base: u32 = value[..i].trim().parse().unwrap();
+            let shift: u32 = value[i + 2..].trim().parse().unwrap();
+            base << shift
+        } else {
+            value.parse().unwrap()
+        };
+
+        constants.insert(name[prefix.len()..].to_owned(), value);
+    }
+
+    constants
+}
+
+#[test]
+fn packet_flags() {
+    let constants = header_constants("ENET_PACKET_FLAG_");
+    let flags = [
+        ("RELIABLE", ENetPacketFlag::Reliable),
+        ("UNSEQUENCED", ENetPacketFlag::Unsequenced),
+        ("NO_ALLOCATE", ENetPacketFlag::NoAllocate),
+        ("UNRELIABLE_FRAGMENT", ENetPacketFlag::UnreliableFragment),
+        ("SENT", ENetPacketFlag::Sent),
+    ];
+    assert_eq!(constants.len(), flags.len());
+
+    for &(name, flag) in flags.iter() {
+        assert_eq!(flag.to_raw(), constants[name], "{}", name);
+        assert_eq!(ENetPacketFlag::from_raw(constants[name]), Some(flag));
+        assert_eq!(ENetPacketFlags::from(flag).bits(), constants[name]);
+    }
+
+    assert_eq!(ENetPacketFlag::from_raw(0), None);
+    assert_eq!(ENetPacketFlag::from_raw(0b11), None);
+}
+
+#[test]
+fn peer_states() {
+    use ENetPeerState::*;
+    let constants = header_constants("ENET_PEER_STATE_");
+    let states = [
+        ("DISCONNECTED", Disconnected),
+        ("CONNECTING", Connecting),
+        ("ACKNOWLEDGING_CONNECT", AcknowledgingConnect),
+        ("CONNECTION_PENDING", ConnectionPending),
+        ("CONNECTION_SUCCEEDED", ConnectionSucceeded),
+        ("CONNECTED", Connected),
+        ("DISCONNECT_LATER", DisconnectLater),
+        ("DISCONNECTING", Disconnecting),
+        ("ACKNOWLEDGING_DISCONNECT", AcknowledgingDisconnect),
+        ("ZOMBIE", Zombie),
+    ];
+    assert_eq!(constants.len(), states.len());
+
+    for &(name, state) in states.iter() {
+        assert_eq!(state.to_raw() as u32, constants[name], "{}", name);
+        assert_eq!(ENetPeerState::from_raw(constants[name] as _), Some(state));
+        assert_eq!(ENetPeerStateRaw::from(state).get(), Some(state));
+    }
+
+    assert_eq!(ENetPeerStateRaw(10).get(), None);
+    assert_eq!(ENetPeerStateRaw(-1).get(), None);
+}
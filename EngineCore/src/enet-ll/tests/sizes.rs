@@ -34,9 +34,12 @@ fn test () {
 
   // packet.rs
   assert_eq!(size_of::<ENetPacket>(), 48);
+  assert_eq!(size_of::<ENetPacketFlags>(), 4);
 
   // peer.rs
   assert_eq!(size_of::<ENetPeer>(), 472);
+  assert_eq!(size_of::<ENetPeerState>(), 4);
+  assert_eq!(size_of::<ENetPeerStateRaw>(), 4);
 
   // protocol.rs
   assert_eq!(size_of::<ENetProtocol>(), 48);
@@ -19,7 +19,7 @@ use {ngspf_core as core, ngspf_viewport as viewport};
 
 pub(crate) fn translate_context_error(e: core::ContextError) -> HResult {
     match e {
-        core::ContextError::LockFailed => E_PF_LOCKED,
+        core::ContextError::LockFailed | core::ContextError::ProducerLockHeld => E_PF_LOCKED,
     }
 }
 
@@ -0,0 +1,278 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+//! Procedural macros for [`ngspf_core`](../ngspf_core/index.html).
+//!
+//! # `#[derive(NodeAccessors)]`
+//!
+//! Generates property accessor methods on a node reference type (a wrapper
+//! type like `XxxRef(Arc<Xxx>)`) for the fields of a node type. The macro is
+//! applied to the node type, and the reference type is specified by the
+//! `#[accessor(ref = "...")]` attribute.
+//!
+//!  - A field of type `KeyedProperty<T>` yields a method returning
+//!    `impl PropertyAccessor<T>`.
+//!  - A field marked with `#[accessor(ro)]` yields a method returning
+//!    `impl RoPropertyAccessor<T>` where `T` is the type of the field.
+//!  - A field of type `Property<T>` or `WoProperty<T>` is rejected with a
+//!    compile error because there is no accessor type for them. Mark such a
+//!    field with `#[accessor(skip)]` or `#[accessor(ro)]`.
+//!  - A field marked with `#[accessor(skip)]` and other fields are ignored.
+//!
+//! Doc comments on the fields are copied to the generated methods.
+//!
+//! The generated code refers to the items of `ngspf_core` by absolute paths,
+//! so `ngspf_core` must be a direct dependency of the crate using this macro.
+//!
+//! # Examples
+//!
+//!     use ngspf_core::{KeyedProperty, NodeAccessors, ProducerFrame};
+//!     use ngspf_core::prelude::*;
+//!     use std::sync::Arc;
+//!
+//!     #[derive(NodeAccessors)]
+//!     #[accessor(ref = "PegasusRef")]
+//!     struct Pegasus {
+//!         /// Set or retrieve the derpiness.
+//!         derp: KeyedProperty<f32>,
+//!         #[accessor(ro)]
+//!         wings: u32,
+//!     }
+//!
+//!     struct PegasusRef(Arc<Pegasus>);
+//!
+//!     fn foo(frame: &mut ProducerFrame, pegasus: &PegasusRef) {
+//!         pegasus.derp().set(frame, 4.0).unwrap();
+//!         assert_eq!(pegasus.wings().get(frame).unwrap(), 2);
+//!     }
+//!
+//! The reference type must be specified:
+//!
+//! ```compile_fail
+//! use ngspf_core::{KeyedProperty, NodeAccessors};
+//!
+//! #[derive(NodeAccessors)]
+//! struct Pegasus {
+//!     derp: KeyedProperty<f32>,
+//! }
+//! ```
+//!
+//! `#[accessor(ro)]` cannot be used on a `KeyedProperty`:
+//!
+//! ```compile_fail
+//! use ngspf_core::{KeyedProperty, NodeAccessors};
+//! use std::sync::Arc;
+//!
+//! #[derive(NodeAccessors)]
+//! #[accessor(ref = "PegasusRef")]
+//! struct Pegasus {
+//!     #[accessor(ro)]
+//!     derp: KeyedProperty<f32>,
+//! }
+//!
+//! struct PegasusRef(Arc<Pegasus>);
+//! ```
+//!
+//! `Property` fields must be explicitly skipped:
+//!
+//! ```compile_fail
+//! use ngspf_core::{NodeAccessors, Property};
+//! use std::sync::Arc;
+//!
+//! #[derive(NodeAccessors)]
+//! #[accessor(ref = "PegasusRef")]
+//! struct Pegasus {
+//!     derp: Property<f32>,
+//! }
+//!
+//! struct PegasusRef(Arc<Pegasus>);
+//! ```
+//!
+//! Read-only accessors do not provide write access:
+//!
+//! ```compile_fail
+//! use ngspf_core::{NodeAccessors, ProducerFrame};
+//! use ngspf_core::prelude::*;
+//! use std::sync::Arc;
+//!
+//! #[derive(NodeAccessors)]
+//! #[accessor(ref = "PegasusRef")]
+//! struct Pegasus {
+//!     #[accessor(ro)]
+//!     wings: u32,
+//! }
+//!
+//! struct PegasusRef(Arc<Pegasus>);
+//!
+//! fn foo(frame: &mut ProducerFrame, pegasus: &PegasusRef) {
+//!     pegasus.wings().set(frame, 4).unwrap();
+//! }
+//! ```
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    Attribute, Data, DeriveInput, Field, Fields, GenericArgument, Lit, Meta, NestedMeta, Path,
+    PathArguments, Type,
+};
+
+#[proc_macro_derive(NodeAccessors, attributes(accessor))]
+pub fn derive_node_accessors(input: TokenStream) -> TokenStream {
+    let ast: DeriveInput = syn::parse(input).unwrap();
+
+    if ast.generics.params.len() > 0 {
+        panic!("`derive(NodeAccessors)` does not support generics");
+    }
+
+    let fields = match ast.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => panic!("`derive(NodeAccessors)` requires named fields"),
+        },
+        _ => panic!("`derive(NodeAccessors)` may only be applied to structs"),
+    };
+
+    let ref_path = ast
+        .attrs
+        .iter()
+        .filter_map(accessor_args)
+        .flatten()
+        .filter_map(|meta| match meta {
+            Meta::NameValue(ref nv) if nv.ident == "ref" => Some(match nv.lit {
+                Lit::Str(ref s) => s
+                    .parse::<Path>()
+                    .expect("`#[accessor(ref = \"...\")]` must specify a type path"),
+                _ => panic!("`#[accessor(ref = \"...\")]` must specify a type path"),
+            }),
+            _ => panic!("unknown attribute argument: `{}`", quote! { #meta }),
+        })
+        .next()
+        .expect("`derive(NodeAccessors)` requires `#[accessor(ref = \"...\")]`");
+
+    let ident = &ast.ident;
+    let methods = fields.iter().filter_map(|field| gen_accessor(ident, field));
+
+    let tokens = quote! {
+        impl #ref_path {
+            #(#methods)*
+        }
+    };
+
+    tokens.into()
+}
+
+/// Extract the arguments of an `#[accessor(...)]` attribute.
+fn accessor_args(attr: &Attribute) -> Option<Vec<Meta>> {
+    if attr.path.segments.len() != 1 || attr.path.segments[0].ident != "accessor" {
+        return None;
+    }
+
+    match attr.parse_meta() {
+        Ok(Meta::List(list)) => Some(
+            list.nested
+                .into_iter()
+                .map(|nested| match nested {
+                    NestedMeta::Meta(meta) => meta,
+                    NestedMeta::Literal(_) => panic!("malformed `#[accessor(...)]` attribute"),
+                })
+                .collect(),
+        ),
+        _ => panic!("malformed `#[accessor(...)]` attribute"),
+    }
+}
+
+/// Retrieve `T` if a given type looks like `Name<T>`, where `Name` is
+/// `type_name`.
+fn property_value_type<'a>(ty: &'a Type, type_name: &str) -> Option<&'a Type> {
+    let path = match ty {
+        Type::Path(ref ty_path) if ty_path.qself.is_none() => &ty_path.path,
+        _ => return None,
+    };
+
+    let segment = path.segments.iter().last()?;
+    if segment.ident != type_name {
+        return None;
+    }
+
+    match segment.arguments {
+        PathArguments::AngleBracketed(ref args) if args.args.len() == 1 => match args.args[0] {
+            GenericArgument::Type(ref ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn gen_accessor(node_ident: &syn::Ident, field: &Field) -> Option<TokenStream2> {
+    let mut ro = false;
+    let mut skip = false;
+
+    for meta in field.attrs.iter().filter_map(accessor_args).flatten() {
+        match meta {
+            Meta::Word(ref word) if word == "ro" => ro = true,
+            Meta::Word(ref word) if word == "skip" => skip = true,
+            _ => panic!("unknown attribute argument: `{}`", quote! { #meta }),
+        }
+    }
+
+    if skip {
+        return None;
+    }
+
+    let field_ident = field.ident.as_ref().unwrap();
+    let field_ty = &field.ty;
+    let docs = field.attrs.iter().filter(|attr| {
+        attr.path.segments.len() == 1 && attr.path.segments[0].ident == "doc"
+    });
+
+    if ro {
+        if property_value_type(field_ty, "KeyedProperty").is_some() {
+            let message = format!(
+                "`#[accessor(ro)]` cannot be applied to `KeyedProperty` field `{}`",
+                field_ident
+            );
+            return Some(syn::Error::new_spanned(field_ty, message).to_compile_error());
+        }
+
+        return Some(quote! {
+            #(#docs)*
+            pub fn #field_ident<'a>(&'a self)
+                -> impl ::ngspf_core::RoPropertyAccessor<#field_ty> + 'a
+            {
+                ::ngspf_core::RefPropertyAccessor::new(&self.0.#field_ident)
+            }
+        });
+    }
+
+    for &type_name in ["Property", "WoProperty"].iter() {
+        if property_value_type(field_ty, type_name).is_some() {
+            // These don't have an `UpdateId` required to record updates
+            let message = format!(
+                "`{}` field `{}` is not supported; use `KeyedProperty` or \
+                 mark it with `#[accessor(skip)]`",
+                type_name, field_ident
+            );
+            return Some(syn::Error::new_spanned(field_ty, message).to_compile_error());
+        }
+    }
+
+    let value_ty = property_value_type(field_ty, "KeyedProperty")?;
+
+    // `select` is a named function (not a closure) to work around
+    // <https://github.com/rust-lang/rust/issues/23501>
+    Some(quote! {
+        #(#docs)*
+        pub fn #field_ident<'a>(&'a self)
+            -> impl ::ngspf_core::PropertyAccessor<#value_ty> + 'a
+        {
+            fn select(this: &::std::sync::Arc<#node_ident>) -> &#field_ty {
+                &this.#field_ident
+            }
+            ::ngspf_core::KeyedPropertyAccessor::new(&self.0, select)
+        }
+    })
+}
@@ -14,19 +14,54 @@
 //! nodes. They automatically record a changeset to the frame whenever a
 //! property value is updated.
 //!
-//! See the documentation of [`KeyedPropertyAccessor`] for the usage.
+//! Accessor methods are usually generated by [`NodeAccessors`]:
+//!
+//!     use ngspf_core::{Context, KeyedProperty, NodeAccessors};
+//!     use ngspf_core::prelude::*;
+//!     use std::sync::Arc;
+//!
+//!     #[derive(NodeAccessors)]
+//!     #[accessor(ref = "PegasusRef")]
+//!     struct Pegasus {
+//!         derp: KeyedProperty<f32>,
+//!     }
+//!
+//!     struct PegasusRef(Arc<Pegasus>);
+//!
+//!     let context = Context::new();
+//!     let pegasus = PegasusRef(Arc::new(Pegasus {
+//!         derp: KeyedProperty::new(&context, 1.0),
+//!     }));
+//!
+//!     {
+//!         let mut frame = context.lock_producer_frame().unwrap();
+//!         pegasus.derp().set(&mut frame, 4.0).unwrap();
+//!     }
+//!     context.commit().unwrap();
+//!
+//!     let frame = context.lock_presenter_frame().unwrap();
+//!     assert_eq!(pegasus.derp().get_presenter(&frame).unwrap(), 4.0);
+//!
+//! See the documentation of [`KeyedPropertyAccessor`] and
+//! [`RefPropertyAccessor`] for how to write them manually.
 //!
 //! [`KeyedPropertyAccessor`]: struct.KeyedPropertyAccessor.html
+//! [`RefPropertyAccessor`]: struct.RefPropertyAccessor.html
+//! [`NodeAccessors`]: ../ngspf_core_derive/index.html
 extern crate arclock;
 extern crate refeq;
 extern crate tokenlock;
 
 mod handler;
 
+pub use ngspf_core_derive::NodeAccessors;
+
 use arclock::{ArcLock, ArcLockGuard};
-use refeq::RefEqArc;
+use refeq::{RefEqArc, RefEqWeak};
 use std::any::Any;
-use std::sync::Mutex;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, ThreadId};
 use std::{borrow, fmt, hash, ops};
 use tokenlock::{Token, TokenLock, TokenRef};
 
@@ -39,12 +74,17 @@ pub struct Context {
     producer_token_ref: TokenRef,
     presenter_token_ref: TokenRef,
     on_commit: Mutex<handler::CommitHandlerList>,
+    /// The thread that acquired the current `ProducerFrame`, if any.
+    producer_lock_owner: Arc<Mutex<Option<ThreadId>>>,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum ContextError {
     /// Could not acquire a lock on the current frame.
     LockFailed,
+    /// Could not acquire a lock on the current frame because the calling
+    /// thread already holds a `ProducerFrame`. It must be dropped first.
+    ProducerLockHeld,
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
@@ -65,22 +105,50 @@ impl Context {
                 frame_id: 0,
                 producer_token,
             }),
-            presenter_frame: ArcLock::new(PresenterFrameInner { presenter_token }),
+            presenter_frame: ArcLock::new(PresenterFrameInner {
+                presenter_token,
+                frame_id: None,
+            }),
             changelog: Mutex::default(),
             on_commit: Mutex::new(handler::CommitHandlerList::new()),
+            producer_lock_owner: Arc::new(Mutex::new(None)),
         }
     }
 
     /// Acquire a lock on the current frame of `Context` for the producer access.
     ///
-    /// Returns `None` if it is already locked. It does not wait until it is
+    /// Returns `Err(_)` if it is already locked. It does not wait until it is
     /// unlocked because doing so has a possibility of a deadlock, which only
     /// can happen as a result of a programming error.
+    /// `ContextError::ProducerLockHeld` is returned if the lock is held by the
+    /// calling thread.
     pub fn lock_producer_frame(&self) -> Result<ProducerFrame, ContextError> {
-        self.producer_frame
+        let frame_inner = self
+            .producer_frame
             .try_lock()
-            .map_err(|_| ContextError::LockFailed)
-            .map(ProducerFrame)
+            .map_err(|_| self.producer_lock_error())?;
+
+        let thread_id = thread::current().id();
+        *self.producer_lock_owner.lock().unwrap() = Some(thread_id);
+
+        Ok(ProducerFrame(
+            frame_inner,
+            ProducerLockOwnerGuard {
+                owner: self.producer_lock_owner.clone(),
+                thread_id,
+            },
+        ))
+    }
+
+    /// Construct an error value describing a failure to lock the producer
+    /// frame.
+    fn producer_lock_error(&self) -> ContextError {
+        let owner = *self.producer_lock_owner.lock().unwrap();
+        if owner == Some(thread::current().id()) {
+            ContextError::ProducerLockHeld
+        } else {
+            ContextError::LockFailed
+        }
     }
 
     pub fn num_pending_frames(&self) -> usize {
@@ -93,12 +161,52 @@ impl Context {
         self.on_commit.lock().unwrap().push(handler);
     }
 
+    /// Start recording a trace of updates applied to the presenter frame.
+    ///
+    /// When enabled, every update applied by [`lock_presenter_frame`] appends
+    /// an [`UpdateTraceEntry`] to a ring buffer that holds up to `capacity`
+    /// entries. The oldest entries are discarded when it overflows. The
+    /// recorded entries can be retrieved by [`take_update_trace`].
+    ///
+    /// Calling this method while the trace is already enabled only changes
+    /// the capacity.
+    ///
+    /// [`lock_presenter_frame`]: #method.lock_presenter_frame
+    /// [`take_update_trace`]: #method.take_update_trace
+    pub fn enable_update_trace(&self, capacity: usize) {
+        let mut changelog = self.changelog.lock().unwrap();
+        let trace = changelog.trace.get_or_insert_with(Default::default);
+        trace.capacity = capacity;
+        while trace.entries.len() > capacity {
+            trace.entries.pop_front();
+        }
+    }
+
+    /// Stop recording a trace of updates and discard the recorded entries.
+    pub fn disable_update_trace(&self) {
+        self.changelog.lock().unwrap().trace = None;
+    }
+
+    /// Retrieve and clear the recorded trace of updates, in the order in which
+    /// they were applied.
+    ///
+    /// Returns an empty `Vec` if the trace is not enabled.
+    pub fn take_update_trace(&self) -> Vec<UpdateTraceEntry> {
+        let mut changelog = self.changelog.lock().unwrap();
+        if let Some(trace) = &mut changelog.trace {
+            trace.entries.drain(..).collect()
+        } else {
+            Vec::new()
+        }
+    }
+
     /// Finalize the current frame for presentation.
     ///
     /// If you have a lock on the current frame, it must be unlocked first (by
     /// dropping `ProducerFrame`). It does not wait until it is unlocked because
     /// doing so has a possibility of a deadlock, which only can happen as a
-    /// result of a programming error.
+    /// result of a programming error. `ContextError::ProducerLockHeld` is
+    /// returned if the calling thread holds a `ProducerFrame`.
     ///
     /// **Panics** if too many frames were generated (> `2^64`) during the
     /// lifetime of the `Context`.
@@ -108,15 +216,16 @@ impl Context {
             let mut frame: ArcLockGuard<ProducerFrameInner> = self
                 .producer_frame
                 .try_lock()
-                .map_err(|_| ContextError::LockFailed)?;
+                .map_err(|_| self.producer_lock_error())?;
 
+            let frame_id = frame.frame_id;
             frame.frame_id = frame.frame_id.checked_add(1).expect("frame ID overflow");
 
             let mut changelog = self.changelog.lock().unwrap();
 
             let mut changeset = Vec::with_capacity(frame.changeset.len() * 2);
             swap(&mut changeset, &mut frame.changeset);
-            changelog.changesets.push(changeset);
+            changelog.changesets.push((frame_id, changeset));
         }
 
         self.on_commit.lock().unwrap().emit();
@@ -131,7 +240,10 @@ impl Context {
     /// can happen as a result of a programming error.
     ///
     /// If locking succeeds, it first applies all changes commited by the
-    /// producer so far.
+    /// producer so far. The ID of the last frame applied can be retrieved by
+    /// [`PresenterFrame::current_frame_id`].
+    ///
+    /// [`PresenterFrame::current_frame_id`]: struct.PresenterFrame.html#method.current_frame_id
     pub fn lock_presenter_frame(&self) -> Result<PresenterFrame, ContextError> {
         let frame_inner: ArcLockGuard<PresenterFrameInner> = self
             .presenter_frame
@@ -142,11 +254,20 @@ impl Context {
 
         // Apply pending changes
         let mut changelog = self.changelog.lock().unwrap();
-
-        for mut changeset in changelog.changesets.drain(..) {
-            for mut update in changeset.drain(..) {
+        let Changelog { changesets, trace } = &mut *changelog;
+
+        for (frame_id, mut changeset) in changesets.drain(..) {
+            for (update_index, mut update) in changeset.drain(..).enumerate() {
+                if let Some(trace) = trace {
+                    trace.push(UpdateTraceEntry {
+                        frame_id,
+                        update_index,
+                        description: format!("{:?}", update),
+                    });
+                }
                 update.apply(&mut frame);
             }
+            frame.0.frame_id = Some(frame.0.frame_id.map_or(frame_id, |x| x.max(frame_id)));
         }
 
         Ok(frame)
@@ -154,11 +275,38 @@ impl Context {
 }
 
 #[derive(Debug)]
-pub struct ProducerFrame(ArcLockGuard<ProducerFrameInner>);
+pub struct ProducerFrame(ArcLockGuard<ProducerFrameInner>, ProducerLockOwnerGuard);
+
+/// Clears `Context::producer_lock_owner` when a `ProducerFrame` is dropped.
+#[derive(Debug)]
+struct ProducerLockOwnerGuard {
+    owner: Arc<Mutex<Option<ThreadId>>>,
+    thread_id: ThreadId,
+}
+
+impl Drop for ProducerLockOwnerGuard {
+    fn drop(&mut self) {
+        let mut owner = self.owner.lock().unwrap();
+        // The lock might have been acquired by another thread in the meantime
+        if *owner == Some(self.thread_id) {
+            *owner = None;
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct PresenterFrame(ArcLockGuard<PresenterFrameInner>);
 
+/// A presenter frame that can be shared by multiple threads but only allows
+/// read access.
+///
+/// This is created by [`PresenterFrame::shared`]. The lock on the presenter
+/// frame is held until all clones of `SharedPresenterFrame` are dropped.
+///
+/// [`PresenterFrame::shared`]: struct.PresenterFrame.html#method.shared
+#[derive(Debug, Clone)]
+pub struct SharedPresenterFrame(Arc<ArcLockGuard<PresenterFrameInner>>);
+
 #[derive(Debug)]
 struct ProducerFrameInner {
     changeset: Vec<Box<Update>>,
@@ -169,11 +317,56 @@ struct ProducerFrameInner {
 #[derive(Debug)]
 struct PresenterFrameInner {
     presenter_token: Token,
+    /// The ID of the most recent frame whose changeset was applied. `None` if
+    /// no frames have been applied yet.
+    frame_id: Option<u64>,
 }
 
 #[derive(Debug, Default)]
 struct Changelog {
-    changesets: Vec<Vec<Box<Update>>>,
+    /// Committed changesets and the IDs of the frames they belong to.
+    changesets: Vec<(u64, Vec<Box<Update>>)>,
+    /// The update trace. `None` if disabled.
+    trace: Option<UpdateTrace>,
+}
+
+#[derive(Debug, Default)]
+struct UpdateTrace {
+    entries: VecDeque<UpdateTraceEntry>,
+    capacity: usize,
+}
+
+impl UpdateTrace {
+    fn push(&mut self, entry: UpdateTraceEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+}
+
+/// An entry of the update trace recorded by `Context`.
+///
+/// See [`Context::enable_update_trace`].
+///
+/// [`Context::enable_update_trace`]: struct.Context.html#method.enable_update_trace
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UpdateTraceEntry {
+    /// The ID of the frame in which the update was recorded. Starts at `0`
+    /// and is incremented by every `Context::commit`.
+    pub frame_id: u64,
+    /// The index of the update in the frame's changeset.
+    pub update_index: usize,
+    /// The `Debug` representation of the update.
+    ///
+    /// This includes the value if the update was recorded by
+    /// [`ProducerFrame::record_keyed_update_debug`].
+    ///
+    /// [`ProducerFrame::record_keyed_update_debug`]: struct.ProducerFrame.html#method.record_keyed_update_debug
+    pub description: String,
 }
 
 /// Marker trait for nodes.
@@ -194,6 +387,23 @@ impl NodeRef {
         Any::downcast_ref(&*self.0)
     }
 
+    /// Create a [`WeakNodeRef`] pointing to the same node.
+    ///
+    /// [`WeakNodeRef`]: struct.WeakNodeRef.html
+    pub fn downgrade(&self) -> WeakNodeRef {
+        WeakNodeRef(RefEqArc::downgrade(&self.0))
+    }
+
+    /// Return a value uniquely identifying the node while it is alive.
+    ///
+    /// The returned value is identical to that of [`WeakNodeRef::ptr_id`] of
+    /// any `WeakNodeRef` created from this node.
+    ///
+    /// [`WeakNodeRef::ptr_id`]: struct.WeakNodeRef.html#method.ptr_id
+    pub fn ptr_id(&self) -> usize {
+        RefEqArc::ptr_id(&self.0)
+    }
+
     /// Iterate through non-group nodes reachable from a given root node via
     /// zero or more group nodes.
     ///
@@ -274,6 +484,58 @@ impl hash::Hash for NodeRef {
     }
 }
 
+/// Weak reference to a node.
+///
+/// `WeakNodeRef` does not keep the node alive. This is useful for caches
+/// indexed by nodes, which should not extend the lifetime of the nodes. Dead
+/// entries can be detected (and pruned) by checking the result of
+/// [`upgrade`](#method.upgrade).
+///
+/// The equality and hash value are based on the address of the node captured
+/// at the time of [`NodeRef::downgrade`], so they do not change even after the
+/// node was dropped. Two `WeakNodeRef`s compare equal if and only if they
+/// were created from the same node. (The node's storage is not released until
+/// all `WeakNodeRef`s to it are dropped, so another node cannot take over the
+/// same address while a `WeakNodeRef` is alive.)
+///
+/// [`NodeRef::downgrade`]: struct.NodeRef.html#method.downgrade
+#[derive(Clone)]
+pub struct WeakNodeRef(pub RefEqWeak<Any + Sync + Send>);
+
+impl fmt::Debug for WeakNodeRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WeakNodeRef").finish()
+    }
+}
+
+impl WeakNodeRef {
+    /// Attempt to upgrade to a `NodeRef`. Returns `None` if the node has
+    /// already been dropped.
+    pub fn upgrade(&self) -> Option<NodeRef> {
+        self.0.upgrade().map(NodeRef)
+    }
+
+    /// Return the value identifying the node, captured at the time when this
+    /// `WeakNodeRef` was created.
+    pub fn ptr_id(&self) -> usize {
+        self.0.ptr_id()
+    }
+}
+
+impl PartialEq for WeakNodeRef {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq(&other.0)
+    }
+}
+
+impl Eq for WeakNodeRef {}
+
+impl hash::Hash for WeakNodeRef {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
 /// Represents an immutable set of nodes.
 struct Group {
     nodes: Vec<NodeRef>,
@@ -344,6 +606,51 @@ impl ProducerFrame {
         trans_fn: TF,
         update_fn_fac: FF,
     ) -> UpdateId
+    where
+        T: Sync + Send + 'static,
+        TF: FnOnce(Option<T>) -> T,
+        FF: FnOnce() -> F,
+        F: FnOnce(&mut PresenterFrame, T) + 'static + Sync + Send,
+    {
+        self.record_keyed_update_inner(last_update, trans_fn, update_fn_fac, None)
+    }
+
+    /// Record a update to the frame's changeset and return the identifier of
+    /// the update. The value is included in the update trace.
+    ///
+    /// This is identical to [`record_keyed_update`] except that the `Debug`
+    /// representation of the update (which is used by the update trace; see
+    /// [`Context::enable_update_trace`]) includes the value.
+    ///
+    /// [`record_keyed_update`]: #method.record_keyed_update
+    /// [`Context::enable_update_trace`]: struct.Context.html#method.enable_update_trace
+    pub fn record_keyed_update_debug<T, TF, F, FF>(
+        &mut self,
+        last_update: UpdateId,
+        trans_fn: TF,
+        update_fn_fac: FF,
+    ) -> UpdateId
+    where
+        T: Sync + Send + fmt::Debug + 'static,
+        TF: FnOnce(Option<T>) -> T,
+        FF: FnOnce() -> F,
+        F: FnOnce(&mut PresenterFrame, T) + 'static + Sync + Send,
+    {
+        self.record_keyed_update_inner(
+            last_update,
+            trans_fn,
+            update_fn_fac,
+            Some(<T as fmt::Debug>::fmt),
+        )
+    }
+
+    fn record_keyed_update_inner<T, TF, F, FF>(
+        &mut self,
+        last_update: UpdateId,
+        trans_fn: TF,
+        update_fn_fac: FF,
+        fmt_value: Option<FmtValueFn<T>>,
+    ) -> UpdateId
     where
         T: Sync + Send + 'static,
         TF: FnOnce(Option<T>) -> T,
@@ -356,16 +663,20 @@ impl ProducerFrame {
             if let Some(updater) = Any::downcast_mut::<KeyedUpdate<T, F>>(ent.as_any_mut()) {
                 let (old_value, update_fn) = updater.0.take().unwrap();
                 updater.0 = Some((trans_fn(Some(old_value)), update_fn));
+                updater.1 = fmt_value;
                 return last_update;
             }
 
-            *ent = Box::new(KeyedUpdate(Some((trans_fn(None), update_fn_fac()))));
+            *ent = Box::new(KeyedUpdate(
+                Some((trans_fn(None), update_fn_fac())),
+                fmt_value,
+            ));
             last_update
         } else {
-            self.0.changeset.push(Box::new(KeyedUpdate(Some((
-                trans_fn(None),
-                update_fn_fac(),
-            )))));
+            self.0.changeset.push(Box::new(KeyedUpdate(
+                Some((trans_fn(None), update_fn_fac())),
+                fmt_value,
+            )));
 
             UpdateId {
                 frame_id: self.0.frame_id,
@@ -375,7 +686,54 @@ impl ProducerFrame {
     }
 }
 
-struct KeyedUpdate<T, F>(Option<(T, F)>);
+impl PresenterFrame {
+    /// Convert this `PresenterFrame` into a `SharedPresenterFrame`, which can
+    /// be cloned and sent to other threads to read property values
+    /// concurrently.
+    pub fn shared(self) -> SharedPresenterFrame {
+        SharedPresenterFrame(Arc::new(self.0))
+    }
+
+    /// Get the ID of the most recent frame whose changes were applied to the
+    /// presenter frame.
+    ///
+    /// Frame IDs start at `0` and are incremented by every `Context::commit`
+    /// (see [`UpdateTraceEntry::frame_id`]). Returns `None` if no frames have
+    /// been applied yet.
+    ///
+    /// [`UpdateTraceEntry::frame_id`]: struct.UpdateTraceEntry.html#structfield.frame_id
+    pub fn current_frame_id(&self) -> Option<u64> {
+        self.0.frame_id
+    }
+}
+
+impl SharedPresenterFrame {
+    /// Convert this `SharedPresenterFrame` back into a `PresenterFrame`.
+    ///
+    /// Returns `Err(self)` if there are other clones of the
+    /// `SharedPresenterFrame`.
+    pub fn into_exclusive(self) -> Result<PresenterFrame, Self> {
+        Arc::try_unwrap(self.0)
+            .map(PresenterFrame)
+            .map_err(SharedPresenterFrame)
+    }
+
+    /// Get the ID of the most recent frame whose changes were applied to the
+    /// presenter frame.
+    ///
+    /// See [`PresenterFrame::current_frame_id`].
+    ///
+    /// [`PresenterFrame::current_frame_id`]: struct.PresenterFrame.html#method.current_frame_id
+    pub fn current_frame_id(&self) -> Option<u64> {
+        self.0.frame_id
+    }
+}
+
+/// A function to produce the `Debug` representation of a value stored in
+/// `KeyedUpdate`.
+type FmtValueFn<T> = fn(&T, &mut fmt::Formatter) -> fmt::Result;
+
+struct KeyedUpdate<T, F>(Option<(T, F)>, Option<FmtValueFn<T>>);
 
 impl<T, F> Update for KeyedUpdate<T, F>
 where
@@ -393,7 +751,18 @@ where
 
 impl<T, F> fmt::Debug for KeyedUpdate<T, F> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("KeyedUpdate").finish()
+        struct Value<'a, T>(&'a T, FmtValueFn<T>);
+        impl<T> fmt::Debug for Value<'_, T> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                (self.1)(self.0, f)
+            }
+        }
+
+        let mut d = f.debug_struct("KeyedUpdate");
+        if let (Some((value, _)), Some(fmt_value)) = (&self.0, self.1) {
+            d.field("value", &Value(value, fmt_value));
+        }
+        d.finish()
     }
 }
 
@@ -433,6 +802,24 @@ impl<T> WoProperty<T> {
             .read(&frame.0.presenter_token)
             .ok_or(PropertyError::InvalidContext)
     }
+
+    /// Get a reference to the presenter-side value through a
+    /// [`SharedPresenterFrame`].
+    ///
+    /// This is equivalent to [`read_presenter`](#method.read_presenter) but
+    /// can be called from multiple threads at the same time. `Property` and
+    /// `KeyedProperty` provide this method through `Deref<Target =
+    /// WoProperty<T>>`.
+    ///
+    /// [`SharedPresenterFrame`]: struct.SharedPresenterFrame.html
+    pub fn read_presenter_shared<'a>(
+        &'a self,
+        frame: &'a SharedPresenterFrame,
+    ) -> Result<&'a T, PropertyError> {
+        self.presenter_data
+            .read(&frame.0.presenter_token)
+            .ok_or(PropertyError::InvalidContext)
+    }
 }
 
 impl<T: Clone> Property<T> {
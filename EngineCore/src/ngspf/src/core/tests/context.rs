@@ -0,0 +1,135 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use ngspf_core::{Context, ContextError, KeyedProperty, Property, WoProperty};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn commit_while_producer_frame_held() {
+    let context = Context::new();
+    let frame = context.lock_producer_frame().unwrap();
+
+    assert_eq!(context.commit(), Err(ContextError::ProducerLockHeld));
+    assert_eq!(
+        context.lock_producer_frame().err(),
+        Some(ContextError::ProducerLockHeld)
+    );
+
+    drop(frame);
+    context.commit().unwrap();
+}
+
+#[test]
+fn commit_while_locked_by_other_thread() {
+    let context = Arc::new(Context::new());
+    let frame = context.lock_producer_frame().unwrap();
+
+    let context2 = Arc::clone(&context);
+    thread::spawn(move || {
+        assert_eq!(context2.commit(), Err(ContextError::LockFailed));
+    })
+    .join()
+    .unwrap();
+
+    drop(frame);
+    context.commit().unwrap();
+}
+
+#[test]
+fn shared_presenter_frame() {
+    let context = Context::new();
+    let prop = Arc::new(WoProperty::new(&context, 1u32));
+
+    let mut frame = context.lock_presenter_frame().unwrap();
+    *prop.write_presenter(&mut frame).unwrap() = 42;
+
+    let frame = frame.shared();
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let frame = frame.clone();
+            let prop = Arc::clone(&prop);
+            thread::spawn(move || {
+                assert_eq!(*prop.read_presenter_shared(&frame).unwrap(), 42);
+                frame
+            })
+        })
+        .collect();
+
+    assert_eq!(*prop.read_presenter_shared(&frame).unwrap(), 42);
+
+    // Other clones still exist
+    let frame = frame.into_exclusive().unwrap_err();
+    assert_eq!(
+        context.lock_presenter_frame().err(),
+        Some(ContextError::LockFailed)
+    );
+
+    for thread in threads {
+        let thread_frame = thread.join().unwrap();
+        assert_eq!(
+            context.lock_presenter_frame().err(),
+            Some(ContextError::LockFailed)
+        );
+        drop(thread_frame);
+    }
+
+    let mut frame = frame.into_exclusive().unwrap();
+    *prop.write_presenter(&mut frame).unwrap() = 43;
+    drop(frame);
+
+    let frame = context.lock_presenter_frame().unwrap();
+    assert_eq!(*prop.read_presenter(&frame).unwrap(), 43);
+}
+
+#[test]
+fn presenter_frame_id() {
+    let context = Context::new();
+
+    // No frames have been applied yet
+    let frame = context.lock_presenter_frame().unwrap();
+    assert_eq!(frame.current_frame_id(), None);
+    drop(frame);
+
+    // Commit a frame
+    context.commit().unwrap();
+
+    let frame = context.lock_presenter_frame().unwrap();
+    assert_eq!(frame.current_frame_id(), Some(0));
+    drop(frame);
+
+    // Commit two frames before the presenter catches up
+    context.commit().unwrap();
+    context.commit().unwrap();
+
+    let frame = context.lock_presenter_frame().unwrap();
+    assert_eq!(frame.current_frame_id(), Some(2));
+    drop(frame);
+
+    context.commit().unwrap();
+
+    let frame = context.lock_presenter_frame().unwrap().shared();
+    assert_eq!(frame.current_frame_id(), Some(3));
+    drop(frame);
+
+    // The ID is retained if there are no pending frames
+    let frame = context.lock_presenter_frame().unwrap();
+    assert_eq!(frame.current_frame_id(), Some(3));
+}
+
+#[test]
+fn shared_presenter_frame_deref() {
+    let context = Context::new();
+    let prop = Property::new(&context, 1u32);
+    let keyed_prop = KeyedProperty::new(&context, 2u32);
+
+    let mut frame = context.lock_presenter_frame().unwrap();
+    *prop.write_presenter(&mut frame).unwrap() = 42;
+    *keyed_prop.write_presenter(&mut frame).unwrap() = 43;
+
+    let frame = frame.shared();
+    assert_eq!(*prop.read_presenter_shared(&frame).unwrap(), 42);
+    assert_eq!(*keyed_prop.read_presenter_shared(&frame).unwrap(), 43);
+}
@@ -0,0 +1,53 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use ngspf_core::prelude::*;
+use ngspf_core::{Context, KeyedProperty, NodeAccessors};
+use std::sync::Arc;
+
+#[derive(NodeAccessors)]
+#[accessor(ref = "PegasusRef")]
+struct Pegasus {
+    derp: KeyedProperty<f32>,
+    #[accessor(ro)]
+    wings: u32,
+    #[accessor(skip)]
+    #[allow(dead_code)]
+    mane: KeyedProperty<u32>,
+}
+
+struct PegasusRef(Arc<Pegasus>);
+
+#[test]
+fn node_accessors() {
+    let context = Context::new();
+    let pegasus = PegasusRef(Arc::new(Pegasus {
+        derp: KeyedProperty::new(&context, 1.0),
+        wings: 2,
+        mane: KeyedProperty::new(&context, 3),
+    }));
+
+    {
+        let mut frame = context.lock_producer_frame().unwrap();
+        assert_eq!(pegasus.derp().get(&frame).unwrap(), 1.0);
+        assert_eq!(pegasus.wings().get(&frame).unwrap(), 2);
+
+        pegasus.derp().set(&mut frame, 4.0).unwrap();
+        assert_eq!(pegasus.derp().get(&frame).unwrap(), 4.0);
+    }
+
+    {
+        let frame = context.lock_presenter_frame().unwrap();
+        assert_eq!(pegasus.derp().get_presenter(&frame).unwrap(), 1.0);
+    }
+
+    context.commit().unwrap();
+
+    {
+        let frame = context.lock_presenter_frame().unwrap();
+        assert_eq!(pegasus.derp().get_presenter(&frame).unwrap(), 4.0);
+        assert_eq!(pegasus.wings().get_presenter(&frame).unwrap(), 2);
+    }
+}
@@ -0,0 +1,138 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use ngspf_core::{Context, KeyedProperty, ProducerFrame, UpdateId, UpdateTraceEntry};
+use std::sync::Arc;
+
+/// Record an update of `prop` with a traceable value.
+fn set(
+    frame: &mut ProducerFrame,
+    prop: &Arc<KeyedProperty<u32>>,
+    last: UpdateId,
+    value: u32,
+) -> UpdateId {
+    let prop = Arc::clone(prop);
+    frame.record_keyed_update_debug(
+        last,
+        |_| value,
+        move || move |frame, value| *prop.write_presenter(frame).unwrap() = value,
+    )
+}
+
+fn entry(frame_id: u64, update_index: usize, value: u32) -> UpdateTraceEntry {
+    UpdateTraceEntry {
+        frame_id,
+        update_index,
+        description: format!("KeyedUpdate {{ value: {} }}", value),
+    }
+}
+
+#[test]
+fn trace_order() {
+    let context = Context::new();
+    let prop1 = Arc::new(KeyedProperty::new(&context, 0u32));
+    let prop2 = Arc::new(KeyedProperty::new(&context, 0u32));
+    context.enable_update_trace(16);
+
+    {
+        let mut frame = context.lock_producer_frame().unwrap();
+        set(&mut frame, &prop1, UpdateId::new(), 1);
+        set(&mut frame, &prop2, UpdateId::new(), 2);
+    }
+    context.commit().unwrap();
+
+    {
+        let mut frame = context.lock_producer_frame().unwrap();
+        set(&mut frame, &prop2, UpdateId::new(), 3);
+        set(&mut frame, &prop1, UpdateId::new(), 4);
+    }
+    context.commit().unwrap();
+
+    // Nothing is recorded until the updates are applied
+    assert_eq!(context.take_update_trace(), vec![]);
+
+    let frame = context.lock_presenter_frame().unwrap();
+    assert_eq!(*prop1.read_presenter(&frame).unwrap(), 4);
+    assert_eq!(*prop2.read_presenter(&frame).unwrap(), 3);
+    drop(frame);
+
+    assert_eq!(
+        context.take_update_trace(),
+        vec![
+            entry(0, 0, 1),
+            entry(0, 1, 2),
+            entry(1, 0, 3),
+            entry(1, 1, 4)
+        ]
+    );
+
+    // The trace was cleared by `take_update_trace`
+    assert_eq!(context.take_update_trace(), vec![]);
+}
+
+#[test]
+fn trace_overwritten_update() {
+    let context = Context::new();
+    let prop = Arc::new(KeyedProperty::new(&context, 0u32));
+    context.enable_update_trace(16);
+
+    {
+        let mut frame = context.lock_producer_frame().unwrap();
+        let id = set(&mut frame, &prop, UpdateId::new(), 1);
+        set(&mut frame, &prop, id, 2);
+    }
+    context.commit().unwrap();
+    drop(context.lock_presenter_frame().unwrap());
+
+    // The update was overwritten in place, so only the last value is visible
+    assert_eq!(context.take_update_trace(), vec![entry(0, 0, 2)]);
+}
+
+#[test]
+fn trace_bounded() {
+    let context = Context::new();
+    let prop = Arc::new(KeyedProperty::new(&context, 0u32));
+    context.enable_update_trace(2);
+
+    for i in 0..4 {
+        let mut frame = context.lock_producer_frame().unwrap();
+        set(&mut frame, &prop, UpdateId::new(), i);
+        drop(frame);
+        context.commit().unwrap();
+    }
+    drop(context.lock_presenter_frame().unwrap());
+
+    // The oldest entries were discarded
+    assert_eq!(
+        context.take_update_trace(),
+        vec![entry(2, 0, 2), entry(3, 0, 3)]
+    );
+}
+
+#[test]
+fn trace_disabled() {
+    let context = Context::new();
+    let prop = Arc::new(KeyedProperty::new(&context, 0u32));
+
+    {
+        let mut frame = context.lock_producer_frame().unwrap();
+        set(&mut frame, &prop, UpdateId::new(), 1);
+    }
+    context.commit().unwrap();
+    drop(context.lock_presenter_frame().unwrap());
+
+    assert_eq!(context.take_update_trace(), vec![]);
+
+    context.enable_update_trace(16);
+    context.disable_update_trace();
+    {
+        let mut frame = context.lock_producer_frame().unwrap();
+        set(&mut frame, &prop, UpdateId::new(), 2);
+    }
+    context.commit().unwrap();
+    drop(context.lock_presenter_frame().unwrap());
+
+    assert_eq!(context.take_update_trace(), vec![]);
+}
@@ -0,0 +1,81 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use ngspf_core::{GroupRef, NodeRef, WeakNodeRef};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+fn new_node() -> NodeRef {
+    GroupRef::empty().into_node_ref()
+}
+
+fn hash_of<T: Hash>(x: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    x.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn upgrade() {
+    let node = new_node();
+    let weak = node.downgrade();
+    assert_eq!(weak.upgrade(), Some(node.clone()));
+    assert_eq!(weak.ptr_id(), node.ptr_id());
+
+    drop(node);
+    assert_eq!(weak.upgrade(), None);
+}
+
+#[test]
+fn eq_hash_stable_after_drop() {
+    let node1 = new_node();
+    let node2 = new_node();
+    let weak1 = node1.downgrade();
+    let weak1b = node1.downgrade();
+    let weak2 = node2.downgrade();
+
+    assert_eq!(weak1, weak1b);
+    assert_ne!(weak1, weak2);
+    assert_eq!(hash_of(&weak1), hash_of(&weak1b));
+    assert_eq!(hash_of(&weak1), hash_of(&node1));
+
+    let hash = hash_of(&weak1);
+    let ptr_id = weak1.ptr_id();
+    drop(node1);
+
+    assert_eq!(hash_of(&weak1), hash);
+    assert_eq!(weak1.ptr_id(), ptr_id);
+    assert_eq!(weak1, weak1b);
+    assert_eq!(weak1, weak1.clone());
+    assert_ne!(weak1, weak2);
+
+    // A new node never takes over the address while `weak1` is alive
+    let node3 = new_node();
+    assert_ne!(node3.downgrade(), weak1);
+}
+
+#[test]
+fn cache_pruning() {
+    let nodes: Vec<_> = (0..4).map(|_| new_node()).collect();
+
+    let mut cache: HashMap<WeakNodeRef, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.downgrade(), i))
+        .collect();
+
+    // The cache does not keep the nodes alive
+    let mut nodes = nodes;
+    nodes.remove(2);
+    nodes.remove(0);
+
+    // Prune the dead entries
+    cache.retain(|weak, _| weak.upgrade().is_some());
+
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get(&nodes[0].downgrade()), Some(&1));
+    assert_eq!(cache.get(&nodes[1].downgrade()), Some(&3));
+}
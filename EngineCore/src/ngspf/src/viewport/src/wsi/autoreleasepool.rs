@@ -24,26 +24,21 @@ mod os {
 
 #[cfg(target_os = "macos")]
 mod os {
-    use crate::metalutils::OCPtr;
-    use zangfx::backends::metal::metal;
+    use zangfx::backends::metal::autorelease::AutoreleaseScope;
+    use zangfx::base::AutoreleasePool as _;
 
     pub fn autorelease_pool_scope<T, S>(cb: T) -> S
     where
         T: FnOnce(&mut AutoreleasePool) -> S,
     {
-        let mut op = AutoreleasePool(Some(unsafe {
-            OCPtr::from_raw(metal::NSAutoreleasePool::alloc().init()).unwrap()
-        }));
-        cb(&mut op)
+        cb(&mut AutoreleasePool(AutoreleaseScope::new()))
     }
 
-    pub struct AutoreleasePool(Option<OCPtr<metal::NSAutoreleasePool>>);
+    pub struct AutoreleasePool(AutoreleaseScope);
 
     impl AutoreleasePool {
         pub fn drain(&mut self) {
-            self.0 = None;
-            self.0 =
-                Some(unsafe { OCPtr::from_raw(metal::NSAutoreleasePool::alloc().init()).unwrap() });
+            self.0.drain();
         }
     }
 }
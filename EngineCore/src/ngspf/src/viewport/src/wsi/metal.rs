@@ -349,6 +349,7 @@ impl<P: Painter> WindowManager<P> {
                     surface_props = surface_props_from_layer(&layer);
                 }
 
+                be::autorelease::check_autorelease_pool("CAMetalLayer nextDrawable");
                 if let Some(metal_drawable) = layer.next_drawable() {
                     let metal_texture = metal_drawable.texture();
                     unsafe {
@@ -19,6 +19,49 @@ pub struct DebugReport<'a> {
     pub message: &'a str,
 }
 
+impl<'a> DebugReport<'a> {
+    /// Construct a `DebugReport` of type [`DebugReportType::Error`].
+    pub fn error(message: &'a str) -> Self {
+        Self {
+            typ: DebugReportType::Error,
+            message,
+        }
+    }
+
+    /// Construct a `DebugReport` of type [`DebugReportType::Warning`].
+    pub fn warning(message: &'a str) -> Self {
+        Self {
+            typ: DebugReportType::Warning,
+            message,
+        }
+    }
+
+    /// Construct a `DebugReport` of type [`DebugReportType::Information`].
+    pub fn info(message: &'a str) -> Self {
+        Self {
+            typ: DebugReportType::Information,
+            message,
+        }
+    }
+
+    /// Construct a `DebugReport` of type
+    /// [`DebugReportType::PerformanceWarning`].
+    pub fn perf(message: &'a str) -> Self {
+        Self {
+            typ: DebugReportType::PerformanceWarning,
+            message,
+        }
+    }
+
+    /// Construct a `DebugReport` of type [`DebugReportType::Debug`].
+    pub fn debug(message: &'a str) -> Self {
+        Self {
+            typ: DebugReportType::Debug,
+            message,
+        }
+    }
+}
+
 /// Receives `DebugReport`s generated by drivers and validation layers.
 pub trait DebugReportHandler: Send + Sync {
     fn log(&self, report: &DebugReport);
@@ -128,6 +171,17 @@ impl DebugReportConduit {
         }
     }
 
+    /// Deliver a report generated by the application (not by the driver or
+    /// validation layers) to the handlers registered for its type.
+    pub fn report(&self, report: &DebugReport) {
+        for callback in self.callbacks.iter() {
+            let DebugReportCallbackData(ref handler, typ) = *callback.data;
+            if typ == report.typ {
+                handler.log(report);
+            }
+        }
+    }
+
     pub fn add_handler(&mut self, flags: DebugReportTypeFlags, handler: Arc<DebugReportHandler>) {
         for &(typ_flag, vk_typ, typ) in [
             (
@@ -215,3 +269,24 @@ impl DebugReportHandler for PrintDebugReportHandler {
         println!("{}", report.message);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_report_helpers() {
+        let cases: [(fn(&'static str) -> DebugReport<'static>, DebugReportType); 5] = [
+            (DebugReport::error, DebugReportType::Error),
+            (DebugReport::warning, DebugReportType::Warning),
+            (DebugReport::info, DebugReportType::Information),
+            (DebugReport::perf, DebugReportType::PerformanceWarning),
+            (DebugReport::debug, DebugReportType::Debug),
+        ];
+        for &(ctor, typ) in cases.iter() {
+            let report = ctor("hoge");
+            assert_eq!(report.typ, typ);
+            assert_eq!(report.message, "hoge");
+        }
+    }
+}
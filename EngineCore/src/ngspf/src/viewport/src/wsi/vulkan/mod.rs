@@ -15,7 +15,7 @@ extern crate atomic_refcell;
 
 use flags_macro::flags;
 use std::collections::HashMap;
-use std::mem::ManuallyDrop;
+use std::mem::{replace, ManuallyDrop};
 use std::sync::Arc;
 use winit::{EventsLoopProxy, Window};
 
@@ -279,7 +279,14 @@ impl<P: Painter> WindowManager<P> {
 
     pub fn update(&mut self, update_param: &P::UpdateParam) {
         for (_, phys_device) in self.phys_device_list.iter_mut() {
-            phys_device.update(update_param, &self.surface_loader, &mut self.painter);
+            phys_device.update(
+                update_param,
+                &self.entry,
+                &**self.instance,
+                &self.surface_loader,
+                (*self.report_conduit).as_ref(),
+                &mut self.painter,
+            );
         }
     }
 }
@@ -597,6 +604,8 @@ impl<P: Painter> PhysicalDevice<P> {
                 surface_data,
                 vk_props,
                 last_error: None,
+                lost: false,
+                error_reported: false,
             },
         );
     }
@@ -633,30 +642,82 @@ impl<P: Painter> PhysicalDevice<P> {
     fn update(
         &mut self,
         update_param: &P::UpdateParam,
+        entry: &ash::Entry,
+        instance: &ash::Instance,
         surface_loader: &ext::khr::Surface,
+        report_conduit: Option<&debugreport::DebugReportConduit>,
         painter: &mut P,
     ) {
+        let vk_phys_device = self.info.vk_phys_device;
+        let presentation_queue_family = self.presentation_queue_family;
+
         // Check the properties of swapchains and renew them if they are out-dated
         for (&surface_ref, surface) in self.surfaces.iter_mut() {
             // Always recreate a swapchain if we get these errors last time we
             // update the image
-            let out_dated = match surface.last_error {
+            let mut out_dated = match surface.last_error {
                 Some(PresentError::OutOfDate) | Some(PresentError::Suboptimal) => true,
                 _ => false,
             };
+            if let Some(PresentError::SurfaceLost) = surface.last_error {
+                surface.lost = true;
+            }
             surface.last_error = None;
 
+            if surface.lost {
+                // The surface itself is gone (e.g., the display was
+                // disconnected or the compositor was restarted). The swapchain
+                // must be retired before the surface is destroyed.
+                if let Some(old_swapchain) = surface.swapchain.take() {
+                    if let Some(ref cb_state_tracker) = old_swapchain.cb_state_tracker {
+                        cb_state_tracker.wait();
+                    }
+                    self.swapchain_manager.remove_swapchain(surface_ref);
+                    unsafe {
+                        self.swapchain_loader
+                            .destroy_swapchain(old_swapchain.vk_swapchain, None);
+                    }
+                }
+
+                let result = surface.recreate_vk_surface(entry, instance, surface_loader, |x| {
+                    unsafe {
+                        surface_loader.get_physical_device_surface_support(
+                            vk_phys_device,
+                            presentation_queue_family,
+                            x,
+                        )
+                    }
+                });
+                match result {
+                    Ok(()) => {
+                        surface.lost = false;
+                        surface.error_reported = false;
+                        out_dated = true;
+                    }
+                    Err(SurfaceError::SurfaceLost) => {
+                        // Try again later. The surface stays dormant until then.
+                        continue;
+                    }
+                    Err(SurfaceError::Other(x)) => {
+                        // Ditto, but report the error
+                        surface.report_error(report_conduit, || {
+                            format!("Failed to recreate a Vulkan surface.: {:?}", x)
+                        });
+                        continue;
+                    }
+                }
+            }
+
             let new_props = surface.optimal_props(
                 if out_dated {
                     None
                 } else {
                     Some(&surface.vk_props)
                 },
-                self.info.vk_phys_device,
+                vk_phys_device,
                 surface_loader,
             );
-            if new_props.is_err() {
-                // TODO: Handle surface errors
+            if let Err(e) = new_props {
                 // e.g., AMD driver seems to return ErrorInitializationFailed after the window is closed
                 if let Some(old_swapchain) = surface.swapchain.take() {
                     self.swapchain_manager.remove_swapchain(surface_ref);
@@ -666,11 +727,23 @@ impl<P: Painter> PhysicalDevice<P> {
                     }
                 }
 
+                if let SurfaceError::SurfaceLost = e {
+                    // Recreate the surface on the next update
+                    surface.lost = true;
+                }
+
                 continue;
             }
             let new_props = new_props.unwrap();
 
-            if out_dated || new_props != surface.vk_props || surface.swapchain.is_none() {
+            let transition = swapchain_transition(
+                surface.swapchain.is_some(),
+                out_dated,
+                &surface.vk_props,
+                &new_props,
+            );
+
+            if transition != SwapchainTransition::Keep {
                 // Recreate the swapchain
                 let base = surface
                     .swapchain
@@ -679,21 +752,45 @@ impl<P: Painter> PhysicalDevice<P> {
                     .unwrap_or(vk::SwapchainKHR::null());
                 let vk_create_info = new_props.to_create_info(surface.vk_surface, base);
 
-                let swapchain;
-                if let Some(vk_create_info) = vk_create_info {
-                    let vk_swapchain = match unsafe {
+                // Create a new swapchain before destroying the old one
+                // because the new one is derived from the old one
+                let vk_swapchain = if let Some(vk_create_info) = vk_create_info {
+                    match unsafe {
                         self.swapchain_loader
                             .create_swapchain(&vk_create_info, None)
                     } {
-                        Ok(x) => x,
-                        Err(x) => {
-                            // Hopefully we get a graceful error handling someday...
-                            panic!("Failed to create a swapchain.: {:?}", x);
-                        }
-                    };
-                    let vk_swapchain = UniqueSwapchainKHR(&self.swapchain_loader, vk_swapchain);
+                        Ok(x) => Some(UniqueSwapchainKHR(&self.swapchain_loader, x)),
+                        Err(x) => match SurfaceError::from(x) {
+                            SurfaceError::SurfaceLost => {
+                                // Recreate the surface on the next update
+                                surface.lost = true;
+                                None
+                            }
+                            SurfaceError::Other(x) => {
+                                // The old swapchain is retired anyway. Let
+                                // the surface go dormant, which makes the
+                                // next update try again.
+                                surface.report_error(report_conduit, || {
+                                    format!("Failed to create a swapchain.: {:?}", x)
+                                });
+                                None
+                            }
+                        },
+                    }
+                } else {
+                    None
+                };
 
+                // Retire the old swapchain
+                if let Some(old_swapchain) = surface.swapchain.take() {
                     self.swapchain_manager.remove_swapchain(surface_ref);
+                    unsafe {
+                        self.swapchain_loader
+                            .destroy_swapchain(old_swapchain.vk_swapchain, None);
+                    }
+                }
+
+                if let Some(vk_swapchain) = vk_swapchain {
                     self.swapchain_manager
                         .add_swapchain(surface_ref, *vk_swapchain)
                         .expect("Failed to setup a swapchain.");
@@ -703,7 +800,7 @@ impl<P: Painter> PhysicalDevice<P> {
                     let main_queue: &BeCmdQueue =
                         self.wm_device.main_queue.queue.query_ref().unwrap();
 
-                    swapchain = Some(
+                    surface.swapchain = Some(
                         Swapchain::new(
                             *vk_swapchain,
                             &self.swapchain_loader,
@@ -712,20 +809,13 @@ impl<P: Painter> PhysicalDevice<P> {
                         )
                         .expect("Failed to acquire images from a swapchain."),
                     );
-                    surface.vk_props = new_props.clone();
                     vk_swapchain.into_inner(); // Release
-                } else {
-                    swapchain = None;
-                }
 
-                use std::mem::replace;
-                let old_swapchain = replace(&mut surface.swapchain, swapchain);
-                if let Some(ref old_swapchain) = old_swapchain {
-                    unsafe {
-                        self.swapchain_loader
-                            .destroy_swapchain(old_swapchain.vk_swapchain, None);
-                    }
+                    surface.error_reported = false;
                 }
+                // Otherwise, the surface is dormant now
+
+                surface.vk_props = new_props.clone();
 
                 // Notify the change to the upstream
                 painter.update_surface(
@@ -806,6 +896,14 @@ struct Surface<P: Painter> {
     surface_data: P::SurfaceData,
     vk_props: VkSurfaceProps,
     last_error: Option<PresentError>,
+    /// Indicates that `vk_surface` was lost and must be recreated before
+    /// creating a new swapchain.
+    lost: bool,
+    /// Indicates that the last attempt to recreate `vk_surface` or its
+    /// swapchain failed with an error other than `SurfaceError::SurfaceLost`,
+    /// which was reported. Used to report the error only once until the
+    /// recreation succeeds.
+    error_reported: bool,
 }
 
 impl<P: Painter> crate::Debug for Surface<P>
@@ -821,6 +919,8 @@ where
             .field("surface_data", &self.surface_data)
             .field("vk_props", &self.vk_props)
             .field("last_error", &self.last_error)
+            .field("lost", &self.lost)
+            .field("error_reported", &self.error_reported)
             .finish()
     }
 }
@@ -848,6 +948,83 @@ impl<P: Painter> Surface<P> {
             surface_loader,
         )
     }
+
+    /// Destroy `vk_surface` and create a new one for the same window.
+    ///
+    /// The swapchain must have been retired beforehand. See
+    /// `replace_vk_surface` for the error handling.
+    ///
+    /// This is not exposed as a public method (e.g., `recreate_surface`)
+    /// because `PhysicalDevice::update` calls this automatically when the
+    /// surface is lost. The application doesn't have to (and can't) tell
+    /// when to do it.
+    fn recreate_vk_surface(
+        &mut self,
+        entry: &ash::Entry,
+        instance: &ash::Instance,
+        surface_loader: &ext::khr::Surface,
+        is_compatible: impl FnOnce(vk::SurfaceKHR) -> bool,
+    ) -> Result<(), SurfaceError> {
+        assert!(self.swapchain.is_none());
+
+        let (window, window_options) = (&self.window, &self.window_options);
+
+        replace_vk_surface(
+            &mut self.vk_surface,
+            |vk_surface| drop(UniqueSurfaceKHR(surface_loader, vk_surface)),
+            || vksurface::create_surface(entry, instance, window, window_options),
+            is_compatible,
+        )
+    }
+
+    /// Report an error through `report_conduit` unless an error was already
+    /// reported since the last successful recreation of `vk_surface` or its
+    /// swapchain. This prevents flooding the report as the recreation is
+    /// retried on every update.
+    fn report_error(
+        &mut self,
+        report_conduit: Option<&debugreport::DebugReportConduit>,
+        message: impl FnOnce() -> String,
+    ) {
+        if self.error_reported {
+            return;
+        }
+        self.error_reported = true;
+        if let Some(report_conduit) = report_conduit {
+            report_conduit.report(&debugreport::DebugReport::error(&message()));
+        }
+    }
+}
+
+/// Destroy a surface `vk_surface` using `destroy` and replace it with a new
+/// one created by `create`.
+///
+/// `is_compatible` is called to check if the new surface can be presented by
+/// the current physical device. Surfaces cannot be moved between physical
+/// devices because `SurfaceRef` identifies the physical device as well as the
+/// surface. Thus, if the new surface is not compatible, it is destroyed and
+/// `SurfaceError::SurfaceLost` is returned so the recreation is attempted
+/// again later. `vk_surface` is null if this function returns an error.
+fn replace_vk_surface(
+    vk_surface: &mut vk::SurfaceKHR,
+    mut destroy: impl FnMut(vk::SurfaceKHR),
+    create: impl FnOnce() -> Result<vk::SurfaceKHR, vk::Result>,
+    is_compatible: impl FnOnce(vk::SurfaceKHR) -> bool,
+) -> Result<(), SurfaceError> {
+    let old_vk_surface = replace(vk_surface, vk::SurfaceKHR::null());
+    if old_vk_surface != vk::SurfaceKHR::null() {
+        destroy(old_vk_surface);
+    }
+
+    let new_vk_surface = create()?;
+
+    if !is_compatible(new_vk_surface) {
+        destroy(new_vk_surface);
+        return Err(SurfaceError::SurfaceLost);
+    }
+
+    *vk_surface = new_vk_surface;
+    Ok(())
 }
 
 impl Swapchain {
@@ -1111,6 +1288,42 @@ impl Swapchain {
     }
 }
 
+/// Describes what `PhysicalDevice::update` does to a surface's swapchain.
+///
+/// A surface without a swapchain is dormant (e.g., the window is minimized).
+/// It stays so until the surface becomes presentable again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SwapchainTransition {
+    /// Keep the current swapchain, or stay dormant.
+    Keep,
+    /// Create a new swapchain, retiring the old one if any.
+    Recreate,
+    /// Retire the current swapchain (if any) and become dormant.
+    Suspend,
+}
+
+/// Decide the next state of a surface's swapchain.
+///
+/// `has_swapchain` indicates whether the surface currently has a swapchain.
+/// `out_dated` indicates whether the swapchain was reported to be out-dated.
+fn swapchain_transition(
+    has_swapchain: bool,
+    out_dated: bool,
+    old_props: &VkSurfaceProps,
+    new_props: &VkSurfaceProps,
+) -> SwapchainTransition {
+    let props_changed = new_props != old_props;
+    let resuming = !has_swapchain && new_props.is_presentable();
+
+    if !(out_dated || props_changed || resuming) {
+        SwapchainTransition::Keep
+    } else if new_props.is_presentable() {
+        SwapchainTransition::Recreate
+    } else {
+        SwapchainTransition::Suspend
+    }
+}
+
 /// Compute optimal surface properties for a window and its surface.
 ///
 /// If `base` is specified, only `extents` and some minimal number of fields
@@ -1278,6 +1491,14 @@ impl VkSurfaceProps {
         }
     }
 
+    /// Returns whether a swapchain can be created from these properties.
+    ///
+    /// This returns `false` if any of the extents is zero, which happens when
+    /// the window is minimized on some platforms.
+    fn is_presentable(&self) -> bool {
+        self.extents[0] != 0 && self.extents[1] != 0
+    }
+
     /// Construct a `SwapchainCreateInfoKHR`. Returns `None` if a swapchain
     /// cannot be created from these properties.
     fn to_create_info(
@@ -1285,7 +1506,7 @@ impl VkSurfaceProps {
         surface: vk::SurfaceKHR,
         old_swapchain: vk::SwapchainKHR,
     ) -> Option<vk::SwapchainCreateInfoKHR> {
-        if self.extents[0] == 0 || self.extents[1] == 0 {
+        if !self.is_presentable() {
             return None;
         }
         Some(vk::SwapchainCreateInfoKHR {
@@ -1447,3 +1668,230 @@ impl PhysicalDeviceInfo {
         return None;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn surface_error_from_vk_result() {
+        let lost = [
+            vk::Result::ERROR_SURFACE_LOST_KHR,
+            vk::Result::ERROR_INITIALIZATION_FAILED,
+        ];
+        for &x in lost.iter() {
+            match SurfaceError::from(x) {
+                SurfaceError::SurfaceLost => {}
+                e => panic!("{:?} was translated to {:?}", x, e),
+            }
+        }
+
+        match SurfaceError::from(vk::Result::ERROR_OUT_OF_HOST_MEMORY) {
+            SurfaceError::Other(_) => {}
+            e => panic!("{:?}", e),
+        }
+    }
+
+    #[test]
+    fn replace_vk_surface_replaces_handle() {
+        use ash::vk::Handle;
+        let mut vk_surface = vk::SurfaceKHR::from_raw(1);
+        let mut destroyed = Vec::new();
+
+        let result = replace_vk_surface(
+            &mut vk_surface,
+            |x| destroyed.push(x.as_raw()),
+            || Ok(vk::SurfaceKHR::from_raw(2)),
+            |x| x.as_raw() == 2,
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(destroyed, vec![1]);
+        assert_eq!(vk_surface.as_raw(), 2);
+    }
+
+    #[test]
+    fn replace_vk_surface_incompatible() {
+        use ash::vk::Handle;
+        let mut vk_surface = vk::SurfaceKHR::from_raw(1);
+        let mut destroyed = Vec::new();
+
+        let result = replace_vk_surface(
+            &mut vk_surface,
+            |x| destroyed.push(x.as_raw()),
+            || Ok(vk::SurfaceKHR::from_raw(2)),
+            |_| false,
+        );
+
+        // Both the old and new surfaces are destroyed
+        match result {
+            Err(SurfaceError::SurfaceLost) => {}
+            e => panic!("{:?}", e),
+        }
+        assert_eq!(destroyed, vec![1, 2]);
+        assert_eq!(vk_surface, vk::SurfaceKHR::null());
+    }
+
+    #[test]
+    fn replace_vk_surface_creation_failure() {
+        use ash::vk::Handle;
+        let mut vk_surface = vk::SurfaceKHR::from_raw(1);
+        let mut destroyed = Vec::new();
+
+        let result = replace_vk_surface(
+            &mut vk_surface,
+            |x| destroyed.push(x.as_raw()),
+            || Err(vk::Result::ERROR_OUT_OF_HOST_MEMORY),
+            |_| unreachable!(),
+        );
+
+        match result {
+            Err(SurfaceError::Other(_)) => {}
+            e => panic!("{:?}", e),
+        }
+        assert_eq!(destroyed, vec![1]);
+        assert_eq!(vk_surface, vk::SurfaceKHR::null());
+
+        // The null handle left by the failed attempt is not destroyed
+        let result = replace_vk_surface(
+            &mut vk_surface,
+            |x| destroyed.push(x.as_raw()),
+            || Ok(vk::SurfaceKHR::from_raw(3)),
+            |_| true,
+        );
+
+        assert!(result.is_ok());
+        assert_eq!(destroyed, vec![1]);
+        assert_eq!(vk_surface.as_raw(), 3);
+    }
+
+    fn surface_props(extents: [u32; 2]) -> VkSurfaceProps {
+        VkSurfaceProps {
+            extents,
+            format: gfx::ImageFormat::SrgbBgra8,
+            color_space: vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            min_image_count: 2,
+            pre_transform: vk::SurfaceTransformFlagsKHR::IDENTITY,
+            composite_alpha: vk::CompositeAlphaFlagsKHR::OPAQUE,
+            present_mode: vk::PresentModeKHR::FIFO,
+            pixel_ratio: 1.0,
+        }
+    }
+
+    /// Mimics the part of `PhysicalDevice::update` that manages the
+    /// swapchain of a surface.
+    #[derive(Debug)]
+    struct MockSurface {
+        has_swapchain: bool,
+        props: VkSurfaceProps,
+    }
+
+    impl MockSurface {
+        fn new(extents: [u32; 2]) -> Self {
+            let mut surface = Self {
+                has_swapchain: false,
+                props: surface_props([0, 0]),
+            };
+            surface.update(extents, false);
+            surface
+        }
+
+        fn update(&mut self, extents: [u32; 2], out_dated: bool) -> SwapchainTransition {
+            let new_props = surface_props(extents);
+            let transition =
+                swapchain_transition(self.has_swapchain, out_dated, &self.props, &new_props);
+            match transition {
+                SwapchainTransition::Keep => {}
+                SwapchainTransition::Recreate => self.has_swapchain = true,
+                SwapchainTransition::Suspend => self.has_swapchain = false,
+            }
+            if transition != SwapchainTransition::Keep {
+                self.props = new_props;
+            }
+            transition
+        }
+    }
+
+    #[test]
+    fn swapchain_transition_steady() {
+        let mut surface = MockSurface::new([640, 480]);
+        assert!(surface.has_swapchain);
+
+        assert_eq!(surface.update([640, 480], false), SwapchainTransition::Keep);
+        assert_eq!(
+            surface.update([640, 480], true),
+            SwapchainTransition::Recreate
+        );
+        assert_eq!(
+            surface.update([800, 600], false),
+            SwapchainTransition::Recreate
+        );
+        assert!(surface.has_swapchain);
+    }
+
+    #[test]
+    fn swapchain_transition_dormant_resume() {
+        let mut surface = MockSurface::new([640, 480]);
+
+        // Minimize the window
+        assert_eq!(surface.update([0, 0], false), SwapchainTransition::Suspend);
+        assert!(!surface.has_swapchain);
+
+        // Stay dormant while the window is minimized
+        assert_eq!(surface.update([0, 0], false), SwapchainTransition::Keep);
+        assert_eq!(surface.update([0, 0], true), SwapchainTransition::Suspend);
+        assert_eq!(
+            surface.update([640, 0], false),
+            SwapchainTransition::Suspend
+        );
+        assert!(!surface.has_swapchain);
+
+        // Restore the window
+        assert_eq!(
+            surface.update([640, 480], false),
+            SwapchainTransition::Recreate
+        );
+        assert!(surface.has_swapchain);
+        assert_eq!(surface.update([640, 480], false), SwapchainTransition::Keep);
+    }
+
+    #[test]
+    fn swapchain_transition_created_dormant() {
+        // The window is minimized from the beginning
+        let mut surface = MockSurface::new([0, 0]);
+        assert!(!surface.has_swapchain);
+
+        assert_eq!(surface.update([0, 0], false), SwapchainTransition::Keep);
+        assert_eq!(
+            surface.update([640, 480], false),
+            SwapchainTransition::Recreate
+        );
+        assert!(surface.has_swapchain);
+    }
+
+    #[test]
+    fn swapchain_transition_resume_same_props() {
+        // A swapchain might be lost without a change in the properties (e.g.,
+        // when the creation failed). It must be recreated anyway.
+        let mut surface = MockSurface::new([640, 480]);
+        surface.has_swapchain = false;
+
+        assert_eq!(
+            surface.update([640, 480], false),
+            SwapchainTransition::Recreate
+        );
+        assert!(surface.has_swapchain);
+    }
+
+    #[test]
+    fn swapchain_update_error_from_vk_result() {
+        match SwapchainUpdateError::from(vk::Result::ERROR_SURFACE_LOST_KHR) {
+            SwapchainUpdateError::PresentError(PresentError::SurfaceLost) => {}
+            e => panic!("{:?}", e),
+        }
+        match SwapchainUpdateError::from(vk::Result::ERROR_OUT_OF_DATE_KHR) {
+            SwapchainUpdateError::PresentError(PresentError::OutOfDate) => {}
+            e => panic!("{:?}", e),
+        }
+    }
+}
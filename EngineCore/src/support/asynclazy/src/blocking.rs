@@ -0,0 +1,142 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use parking_lot::{Condvar, Mutex};
+use std::{
+    collections::VecDeque,
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    thread,
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed-size pool of worker threads for running blocking, CPU-heavy
+/// closures.
+///
+/// Closures are executed in the FIFO order. This is used by
+/// [`Async::with_blocking_fn`] to evaluate a cell's value in programs without
+/// an asynchronous runtime.
+///
+/// When `BlockingPool` is dropped, the worker threads finish all pending
+/// closures and then exit. By default, the drop waits for them to exit. This
+/// can be changed by [`BlockingPool::set_join_on_drop`].
+///
+/// [`Async::with_blocking_fn`]: crate::Async::with_blocking_fn
+pub struct BlockingPool {
+    shared: Arc<Shared>,
+    threads: Vec<thread::JoinHandle<()>>,
+    join_on_drop: bool,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    cv: Condvar,
+}
+
+struct State {
+    queue: VecDeque<Job>,
+    shutdown: bool,
+}
+
+impl BlockingPool {
+    /// Construct a `BlockingPool` with `num_threads` worker threads.
+    ///
+    /// **Panics** if `num_threads` is zero or a worker thread could not be
+    /// created.
+    pub fn new(num_threads: usize) -> Self {
+        assert_ne!(num_threads, 0, "num_threads must not be zero");
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                shutdown: false,
+            }),
+            cv: Condvar::new(),
+        });
+
+        let threads = (0..num_threads)
+            .map(|i| {
+                let shared = Arc::clone(&shared);
+                thread::Builder::new()
+                    .name(format!("blocking pool worker {}", i))
+                    .spawn(move || shared.run_worker())
+                    .expect("failed to create a worker thread")
+            })
+            .collect();
+
+        Self {
+            shared,
+            threads,
+            join_on_drop: true,
+        }
+    }
+
+    /// Set whether dropping `BlockingPool` waits for the worker threads to
+    /// finish the pending closures. Defaults to `true`.
+    ///
+    /// Even if this is set to `false`, the pending closures are executed after
+    /// `BlockingPool` is dropped.
+    pub fn set_join_on_drop(&mut self, join_on_drop: bool) {
+        self.join_on_drop = join_on_drop;
+    }
+
+    /// Get the number of the worker threads.
+    pub fn num_threads(&self) -> usize {
+        self.threads.len()
+    }
+
+    /// Enqueue a closure to be executed by one of the worker threads.
+    pub fn execute(&self, f: impl FnOnce() + Send + 'static) {
+        let mut state = self.shared.state.lock();
+        state.queue.push_back(Box::new(f));
+        self.shared.cv.notify_one();
+    }
+}
+
+impl Shared {
+    fn run_worker(&self) {
+        let mut state = self.state.lock();
+        loop {
+            if let Some(job) = state.queue.pop_front() {
+                drop(state);
+
+                // Keep the worker alive even if the closure panics
+                let _ = panic::catch_unwind(AssertUnwindSafe(move || job()));
+
+                state = self.state.lock();
+            } else if state.shutdown {
+                return;
+            } else {
+                self.cv.wait(&mut state);
+            }
+        }
+    }
+}
+
+impl Drop for BlockingPool {
+    fn drop(&mut self) {
+        self.shared.state.lock().shutdown = true;
+        self.shared.cv.notify_all();
+
+        if self.join_on_drop {
+            for thread in self.threads.drain(..) {
+                // Propagating a panic of a closure isn't helpful here
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+impl fmt::Debug for BlockingPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BlockingPool")
+            .field("num_threads", &self.threads.len())
+            .field("num_pending", &self.shared.state.lock().queue.len())
+            .field("join_on_drop", &self.join_on_drop)
+            .finish()
+    }
+}
@@ -7,23 +7,55 @@
 //! `std::async(std::launch::async, ...)` from C++.
 #![feature(futures_api)]
 #![feature(box_patterns)]
+#![feature(unsized_locals)] // For calling boxed `FnOnce`
 use atom2::SetOnceAtom;
 use futures::{
+    future,
     prelude::*,
-    task::{Spawn, SpawnError, SpawnExt},
+    task::{Poll, Spawn, SpawnError, SpawnExt, Waker},
 };
 use parking_lot::Mutex;
-use std::sync::mpsc;
+use std::{
+    fmt,
+    sync::{mpsc, Arc},
+    time::{Duration, Instant},
+};
+
+mod blocking;
+pub use self::blocking::BlockingPool;
 
 /// An aynchronously evaluated cell.
-#[derive(Debug)]
 pub struct Async<T> {
     /// A channel for receiving an evaluted value. The value will be
     /// moved to `inner` as soon as its reception
-    initer: Mutex<mpsc::Receiver<T>>,
+    initer: Mutex<mpsc::Receiver<(T, EvalStats)>>,
     /// Stores an evaluated value.
     /// This cell only can be assigned while `initer` is locked.
-    inner: SetOnceAtom<Box<T>>,
+    inner: SetOnceAtom<Box<Evaluated<T>>>,
+    /// Wakers registered by [`Async::poll_ready`]. They are woken up when
+    /// the value is sent through `initer` and when it's moved to `inner`.
+    wakers: Arc<Mutex<Vec<Waker>>>,
+    /// A label used for debugging.
+    label: Option<String>,
+}
+
+/// An evaluated value and its associated statistics.
+struct Evaluated<T> {
+    value: T,
+    stats: Option<EvalStats>,
+}
+
+/// Statistics of the evaluation of an [`Async`].
+///
+/// [`Async`]: Async
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EvalStats {
+    /// The duration between the construction of `Async` and the point when
+    /// the future was polled for the first time.
+    pub queue_delay: Duration,
+    /// The duration between the point when the future was polled for the first
+    /// time and its completion.
+    pub eval_duration: Duration,
 }
 
 impl<T: Send + 'static> Async<T> {
@@ -37,25 +69,120 @@ impl<T: Send + 'static> Async<T> {
         value: impl Future<Output = T> + Send + 'static,
     ) -> Result<Self, SpawnError> {
         let (send, recv) = mpsc::sync_channel(1);
-
-        spawner.spawn(value.map(move |result| {
-            drop(send.send(result));
-        }))?;
+        let spawned_at = Instant::now();
+        let wakers = Arc::new(Mutex::new(Vec::new()));
+        let producer_wakers = Arc::clone(&wakers);
+
+        spawner.spawn(
+            future::lazy(|_| Instant::now()).then(move |started_at| {
+                value.map(move |result| {
+                    let stats = EvalStats {
+                        queue_delay: started_at - spawned_at,
+                        eval_duration: started_at.elapsed(),
+                    };
+                    drop(send.send((result, stats)));
+                    wake_all(&producer_wakers);
+                })
+            }),
+        )?;
 
         Ok(Self {
             initer: Mutex::new(recv),
             inner: SetOnceAtom::empty(),
+            wakers,
+            label: None,
         })
     }
+
+    /// Construct a `Async` with a label. The label is included in the output
+    /// of the `Debug` implementation.
+    ///
+    /// See [`with_future`] for other details.
+    ///
+    /// [`with_future`]: Async::with_future
+    pub fn with_future_labeled(
+        spawner: &mut (impl Spawn + ?Sized),
+        value: impl Future<Output = T> + Send + 'static,
+        label: impl Into<String>,
+    ) -> Result<Self, SpawnError> {
+        let mut this = Self::with_future(spawner, value)?;
+        this.label = Some(label.into());
+        Ok(this)
+    }
+
+    /// Construct a `Async`. A given closure is executed on a given
+    /// [`BlockingPool`] to compute the cell's value.
+    ///
+    /// Unlike [`with_future`], this does not require an asynchronous runtime
+    /// and is suitable for synchronous, CPU-heavy computation.
+    ///
+    /// If the closure panics, the methods waiting for the value panic.
+    ///
+    /// [`with_future`]: Async::with_future
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asynclazy::{Async, BlockingPool};
+    ///
+    /// let pool = BlockingPool::new(2);
+    /// let a = Async::with_blocking_fn(&pool, || (1..=10).sum::<u32>());
+    /// assert_eq!(*a.get(), 55);
+    /// ```
+    pub fn with_blocking_fn(pool: &BlockingPool, f: impl FnOnce() -> T + Send + 'static) -> Self {
+        let (send, recv) = mpsc::sync_channel(1);
+        let spawned_at = Instant::now();
+        let wakers = Arc::new(Mutex::new(Vec::new()));
+        let producer_wakers = Arc::clone(&wakers);
+
+        pool.execute(move || {
+            let started_at = Instant::now();
+            let result = f();
+            let stats = EvalStats {
+                queue_delay: started_at - spawned_at,
+                eval_duration: started_at.elapsed(),
+            };
+            drop(send.send((result, stats)));
+            wake_all(&producer_wakers);
+        });
+
+        Self {
+            initer: Mutex::new(recv),
+            inner: SetOnceAtom::empty(),
+            wakers,
+            label: None,
+        }
+    }
 }
 
 impl<T> Async<T> {
     /// Construct an initialized `Async`.
+    ///
+    /// This does not block. The value is stored in the cell right away, so the
+    /// accessors never touch the channel. The channel is still required to
+    /// fill the `initer` field; a zero-capacity one suffices because nothing is
+    /// ever sent through it, and its sending end is dropped immediately.
+    ///
+    /// `Async<T>` also implements `From<T>`, which calls this method.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use asynclazy::Async;
+    ///
+    /// let a: Async<_> = 42.into();
+    /// assert_eq!(*a.get(), 42);
+    /// ```
     pub fn with_value(x: T) -> Self {
         let (_, recv) = mpsc::sync_channel(0);
         Self {
             initer: Mutex::new(recv),
-            inner: SetOnceAtom::new(Some(Box::new(x))),
+            inner: SetOnceAtom::new(Some(Box::new(Evaluated {
+                value: x,
+                stats: None,
+            }))),
+            wakers: Arc::new(Mutex::new(Vec::new())),
+            label: None,
         }
     }
 
@@ -74,12 +201,17 @@ impl<T> Async<T> {
         }
 
         // Wait for the result
-        let result = initer.recv().expect("sending end dropped unexpectedly");
+        let (value, stats) = initer.recv().expect("sending end dropped unexpectedly");
 
-        match self.inner.store(Some(Box::new(result))) {
+        match self.inner.store(Some(Box::new(Evaluated {
+            value,
+            stats: Some(stats),
+        }))) {
             Ok(()) => {}
             Err(_) => unreachable!(),
         }
+
+        wake_all(&self.wakers);
     }
 
     fn check_nonblocking(&self) {
@@ -103,17 +235,22 @@ impl<T> Async<T> {
         }
 
         // Check the availability
-        let result = if let Ok(x) = initer.try_recv() {
+        let (value, stats) = if let Ok(x) = initer.try_recv() {
             x
         } else {
             // The result is unavailable yet
             return;
         };
 
-        match self.inner.store(Some(Box::new(result))) {
+        match self.inner.store(Some(Box::new(Evaluated {
+            value,
+            stats: Some(stats),
+        }))) {
             Ok(()) => {}
             Err(_) => unreachable!(),
         }
+
+        wake_all(&self.wakers);
     }
 
     /// Get a reference to an evaluated value. Blocks the current thread until
@@ -124,28 +261,68 @@ impl<T> Async<T> {
         //        wasn't happy about it:
         //        <https://github.com/rust-lang/rust/issues/54663>
         self.check_blocking();
-        self.inner.as_inner_ref().unwrap()
+        &self.inner.as_inner_ref().unwrap().value
     }
 
     /// Get a reference to an evaluated value. Returns `None` if the value
     /// is not available at the point when the method is called.
     pub fn try_get(&self) -> Option<&T> {
         self.check_nonblocking();
-        self.inner.as_inner_ref()
+        self.inner.as_inner_ref().map(|x| &x.value)
+    }
+
+    /// Get a reference to an evaluated value. Returns `Poll::Pending` if the
+    /// value is not available at the point when the method is called, in which
+    /// case `waker` is registered to be woken up when the value becomes
+    /// available.
+    ///
+    /// Unlike a `Future` implementation, this method only requires `&self`, so
+    /// the returned reference can outlive the polling.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(futures_api)]
+    /// use asynclazy::Async;
+    /// use futures::{executor::block_on, future::poll_fn};
+    ///
+    /// let a = Async::with_value(42);
+    /// assert_eq!(*block_on(poll_fn(|waker| a.poll_ready(waker))), 42);
+    /// ```
+    pub fn poll_ready(&self, waker: &Waker) -> Poll<&T> {
+        if let Some(x) = self.try_get() {
+            return Poll::Ready(x);
+        }
+
+        {
+            let mut wakers = self.wakers.lock();
+            if !wakers.iter().any(|w| w.will_wake(waker)) {
+                wakers.push(Waker::clone(waker));
+            }
+        }
+
+        // Check it again because the value might have become available before
+        // the waker was registered. Every path that makes the value available
+        // wakes the registered wakers afterward, so the value is either
+        // observed here or the waker is woken up later.
+        match self.try_get() {
+            Some(x) => Poll::Ready(x),
+            None => Poll::Pending,
+        }
     }
 
     /// Get a mutable reference to an evaluated value. Blocks the current thread
     /// until the value is available.
     pub fn get_mut(&mut self) -> &mut T {
         self.check_blocking();
-        self.inner.as_inner_mut().unwrap()
+        &mut self.inner.as_inner_mut().unwrap().value
     }
 
     /// Get a mutable reference to an evaluated value. Returns `None` if the
     /// value is not available at the point when the method is called.
     pub fn try_get_mut(&mut self) -> Option<&mut T> {
         self.check_nonblocking();
-        self.inner.as_inner_mut()
+        self.inner.as_inner_mut().map(|x| &mut x.value)
     }
 
     /// Consume `Self`, returning an evaluated value. Blocks the current thread
@@ -154,7 +331,7 @@ impl<T> Async<T> {
         self.check_blocking();
 
         let box x = self.inner.into_inner().unwrap();
-        x
+        x.value
     }
 
     /// Consume `Self`, returning an evaluated value. Returns `Err(self)` if the
@@ -163,22 +340,180 @@ impl<T> Async<T> {
         self.check_nonblocking();
 
         if let Some(box x) = self.inner.into_inner() {
-            Ok(x)
+            Ok(x.value)
         } else {
             Err(Self {
                 initer: self.initer,
                 inner: SetOnceAtom::empty(),
+                wakers: self.wakers,
+                label: self.label,
             })
         }
     }
+
+    /// Get the statistics of the evaluation. Returns `None` if the value is
+    /// not available at the point when the method is called, or `Self` was
+    /// constructed by [`with_value`].
+    ///
+    /// [`with_value`]: Async::with_value
+    pub fn stats(&self) -> Option<EvalStats> {
+        self.check_nonblocking();
+        self.inner.as_inner_ref().and_then(|x| x.stats)
+    }
+}
+
+fn wake_all(wakers: &Mutex<Vec<Waker>>) {
+    for waker in wakers.lock().drain(..) {
+        waker.wake();
+    }
+}
+
+impl<T> From<T> for Async<T> {
+    fn from(x: T) -> Self {
+        Self::with_value(x)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Async<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let inner = self.inner.as_inner_ref();
+        f.debug_struct("Async")
+            .field("label", &self.label)
+            .field("value", &inner.map(|x| &x.value))
+            .field("stats", &inner.and_then(|x| x.stats))
+            .finish()
+    }
+}
+
+/// A cloneable handle to an [`Async`].
+///
+/// Clones of `SharedAsync` share the ownership of a single `Async`, so the
+/// value is evaluated only once no matter how many parts of a program are
+/// interested in it.
+///
+/// [`Async`]: Async
+pub struct SharedAsync<T>(Arc<Async<T>>);
+
+impl<T: Send + 'static> SharedAsync<T> {
+    /// Construct a `SharedAsync`. A given `Future` is spawned using a given
+    /// `spawner` to compute the cell's value.
+    ///
+    /// See [`Async::with_future`] for other details.
+    ///
+    /// [`Async::with_future`]: Async::with_future
+    pub fn with_future(
+        spawner: &mut (impl Spawn + ?Sized),
+        value: impl Future<Output = T> + Send + 'static,
+    ) -> Result<Self, SpawnError> {
+        Async::with_future(spawner, value).map(Self::from)
+    }
+
+    /// Construct a `SharedAsync` with a label. The label is included in the
+    /// output of the `Debug` implementation.
+    ///
+    /// See [`Async::with_future`] for other details.
+    ///
+    /// [`Async::with_future`]: Async::with_future
+    pub fn with_future_labeled(
+        spawner: &mut (impl Spawn + ?Sized),
+        value: impl Future<Output = T> + Send + 'static,
+        label: impl Into<String>,
+    ) -> Result<Self, SpawnError> {
+        Async::with_future_labeled(spawner, value, label).map(Self::from)
+    }
+
+    /// Construct a `SharedAsync`. A given closure is executed on a given
+    /// [`BlockingPool`] to compute the cell's value.
+    ///
+    /// See [`Async::with_blocking_fn`] for other details.
+    ///
+    /// [`Async::with_blocking_fn`]: Async::with_blocking_fn
+    pub fn with_blocking_fn(pool: &BlockingPool, f: impl FnOnce() -> T + Send + 'static) -> Self {
+        Self::from(Async::with_blocking_fn(pool, f))
+    }
+}
+
+impl<T> SharedAsync<T> {
+    /// Construct an initialized `SharedAsync`.
+    pub fn with_value(x: T) -> Self {
+        Self::from(Async::with_value(x))
+    }
+
+    /// Get a reference to an evaluated value. Blocks the current thread until
+    /// the value is available.
+    pub fn get(&self) -> &T {
+        self.0.get()
+    }
+
+    /// Get a reference to an evaluated value. Returns `None` if the value
+    /// is not available at the point when the method is called.
+    pub fn try_get(&self) -> Option<&T> {
+        self.0.try_get()
+    }
+
+    /// Get a `Future` that resolves to a reference to an evaluated value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(futures_api)]
+    /// use asynclazy::SharedAsync;
+    /// use futures::executor::block_on;
+    ///
+    /// let a = SharedAsync::with_value(42);
+    /// let b = a.clone();
+    /// assert_eq!(*block_on(b.as_future()), 42);
+    /// ```
+    pub fn as_future(&self) -> impl Future<Output = &T> + '_ {
+        future::poll_fn(move |waker| self.0.poll_ready(waker))
+    }
+
+    /// Get the statistics of the evaluation.
+    ///
+    /// See [`Async::stats`] for details.
+    ///
+    /// [`Async::stats`]: Async::stats
+    pub fn stats(&self) -> Option<EvalStats> {
+        self.0.stats()
+    }
+}
+
+impl<T> From<Async<T>> for SharedAsync<T> {
+    fn from(x: Async<T>) -> Self {
+        SharedAsync(Arc::new(x))
+    }
+}
+
+impl<T> Clone for SharedAsync<T> {
+    fn clone(&self) -> Self {
+        SharedAsync(Arc::clone(&self.0))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SharedAsync<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("SharedAsync").field(&self.0).finish()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use futures::{channel::oneshot, executor::ThreadPool};
-    use std::{sync::Arc, thread, time::Duration};
+    use futures::{
+        channel::oneshot,
+        executor::{block_on, ThreadPool},
+        future::{lazy, poll_fn},
+        task::ArcWake,
+    };
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+        time::Duration,
+    };
 
     #[test]
     fn sync() {
@@ -190,6 +525,19 @@ mod tests {
         assert_eq!(Async::with_value(42).try_into_inner().unwrap(), 42);
     }
 
+    #[test]
+    fn from_value() {
+        fn get_into(x: impl Into<Async<u32>>) -> u32 {
+            x.into().into_inner()
+        }
+
+        let a: Async<_> = 42.into();
+        assert_eq!(*a.get(), 42);
+        assert_eq!(a.stats(), None);
+        assert_eq!(get_into(42), 42);
+        assert_eq!(get_into(Async::with_value(42)), 42);
+    }
+
     #[test]
     fn futures() {
         let (send, recv) = oneshot::channel();
@@ -226,4 +574,226 @@ mod tests {
 
         handle.join().unwrap();
     }
+
+    #[test]
+    fn stats_sync() {
+        let a = Async::with_value(42);
+        assert_eq!(a.stats(), None);
+    }
+
+    #[test]
+    fn stats() {
+        let pool = ThreadPool::new().unwrap();
+        let sleep = Duration::from_millis(50);
+
+        let fut = lazy(move |_| {
+            thread::sleep(sleep);
+            42
+        });
+        let a = Async::with_future_labeled(&mut &pool, fut, "meaning").unwrap();
+        assert!(format!("{:?}", a).contains("meaning"));
+
+        assert_eq!(*a.get(), 42);
+
+        let stats = a.stats().unwrap();
+        assert!(stats.eval_duration >= sleep, "{:?}", stats);
+    }
+
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl ArcWake for CountingWaker {
+        fn wake(arc_self: &Arc<Self>) {
+            arc_self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn poll_ready_sync() {
+        let counter = Arc::new(CountingWaker::default());
+        let waker = ArcWake::into_waker(Arc::clone(&counter));
+
+        let a = Async::with_value(42);
+        assert_eq!(a.poll_ready(&waker), Poll::Ready(&42));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn poll_ready_wake() {
+        let (send, recv) = oneshot::channel();
+        let pool = ThreadPool::new().unwrap();
+
+        let counter = Arc::new(CountingWaker::default());
+        let waker = ArcWake::into_waker(Arc::clone(&counter));
+
+        let a = Async::with_future(&mut &pool, recv.map(|x| x.unwrap())).unwrap();
+
+        assert_eq!(a.poll_ready(&waker), Poll::Pending);
+        assert_eq!(a.poll_ready(&waker), Poll::Pending);
+
+        // The waker must not be woken up before the value is available
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 0);
+
+        send.send(42).unwrap();
+
+        // Wait until the waker is woken up
+        while counter.0.load(Ordering::SeqCst) == 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        // The waker was registered only once
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(a.poll_ready(&waker), Poll::Ready(&42));
+    }
+
+    #[test]
+    fn poll_ready_executor() {
+        let (send, recv) = oneshot::channel();
+        let pool = ThreadPool::new().unwrap();
+
+        let a = Async::with_future(&mut &pool, recv.map(|x| x.unwrap())).unwrap();
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            send.send(42).unwrap();
+        });
+
+        assert_eq!(*block_on(poll_fn(|waker| a.poll_ready(waker))), 42);
+        handle.join().unwrap();
+    }
+    #[test]
+    fn shared_futures() {
+        let (send, recv) = oneshot::channel();
+        let pool = ThreadPool::new().unwrap();
+
+        let a = SharedAsync::with_future(&mut &pool, recv.map(|x| x.unwrap())).unwrap();
+        assert_eq!(a.try_get(), None);
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let a = a.clone();
+                thread::spawn(move || {
+                    if i % 2 == 0 {
+                        *a.get()
+                    } else {
+                        *block_on(a.as_future())
+                    }
+                })
+            })
+            .collect();
+
+        thread::sleep(Duration::from_millis(50));
+        send.send(42).unwrap();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 42);
+        }
+        assert_eq!(a.try_get(), Some(&42));
+        assert!(a.stats().is_some());
+    }
+
+    #[test]
+    fn shared_sync() {
+        let a = SharedAsync::with_value(42);
+        let b = a.clone();
+        drop(a);
+        assert_eq!(*b.get(), 42);
+        assert_eq!(*block_on(b.as_future()), 42);
+        assert_eq!(b.stats(), None);
+    }
+
+    #[test]
+    fn blocking_fn() {
+        let pool = BlockingPool::new(2);
+        let (send, recv) = mpsc::channel();
+
+        let a = Async::with_blocking_fn(&pool, move || recv.recv().unwrap());
+
+        // The result is still unevaluated
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(a.try_get(), None);
+
+        send.send(42).unwrap();
+        assert_eq!(*a.get(), 42);
+        assert_eq!(a.try_get(), Some(&42));
+        assert!(a.stats().is_some());
+    }
+
+    #[test]
+    fn blocking_fn_shared() {
+        let pool = BlockingPool::new(1);
+        let a = SharedAsync::with_blocking_fn(&pool, || 42);
+        assert_eq!(*block_on(a.clone().as_future()), 42);
+        assert_eq!(*a.get(), 42);
+    }
+
+    #[test]
+    fn blocking_pool_fifo() {
+        let pool = BlockingPool::new(1);
+        let order = Arc::new(Mutex::new(Vec::new()));
+
+        let cells: Vec<_> = (0..8)
+            .map(|i| {
+                let order = Arc::clone(&order);
+                Async::with_blocking_fn(&pool, move || {
+                    order.lock().push(i);
+                    i * 2
+                })
+            })
+            .collect();
+
+        for (i, cell) in cells.iter().enumerate() {
+            assert_eq!(*cell.get(), i * 2);
+        }
+        assert_eq!(*order.lock(), (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn blocking_pool_shutdown_while_pending() {
+        let pool = BlockingPool::new(1);
+
+        let cells: Vec<_> = (0..4)
+            .map(|i| {
+                Async::with_blocking_fn(&pool, move || {
+                    thread::sleep(Duration::from_millis(10));
+                    i
+                })
+            })
+            .collect();
+
+        // Dropping the pool waits for the pending closures to complete
+        drop(pool);
+
+        for (i, cell) in cells.iter().enumerate() {
+            assert_eq!(cell.try_get(), Some(&i));
+        }
+    }
+
+    #[test]
+    fn blocking_pool_shutdown_no_join() {
+        let mut pool = BlockingPool::new(1);
+        pool.set_join_on_drop(false);
+
+        let a = Async::with_blocking_fn(&pool, || {
+            thread::sleep(Duration::from_millis(50));
+            42
+        });
+        drop(pool);
+
+        // The pending closure is still executed
+        assert_eq!(*a.get(), 42);
+    }
+
+    #[test]
+    fn blocking_pool_panic() {
+        let pool = BlockingPool::new(1);
+
+        let a = Async::with_blocking_fn(&pool, || -> u32 { panic!("expected panic") });
+        let b = Async::with_blocking_fn(&pool, || 42);
+
+        // The worker survives the panic
+        assert_eq!(*b.get(), 42);
+        assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || *a.get())).is_err());
+    }
 }
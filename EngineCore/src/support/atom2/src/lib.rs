@@ -7,8 +7,9 @@
 //!
 //! [atom]: https://crates.io/crates/atom
 #![feature(box_into_raw_non_null)]
-use std::marker::PhantomData;
-use std::sync::atomic::{AtomicPtr, Ordering};
+use std::marker::{PhantomData, Unpin};
+use std::pin::Pin;
+use std::sync::atomic::{spin_loop_hint, AtomicPtr, Ordering};
 use std::sync::{Arc, Weak};
 use std::{
     fmt, mem,
@@ -97,6 +98,27 @@ unsafe impl<T> TypedPtrSized for Box<T> {
 unsafe impl<T> MutPtrSized for Box<T> {}
 unsafe impl<T> TrivialPtrSized for Box<T> {}
 
+unsafe impl<T> PtrSized for Pin<Box<T>> {
+    fn into_raw(this: Self) -> NonNull<()> {
+        // The pointed value is never moved out by `Atom`, so the pinning
+        // guarantee is upheld while it's stored in the raw form.
+        // `Pin` is `#[repr(transparent)]`
+        let b: Box<T> = unsafe { mem::transmute::<Pin<Box<T>>, Box<T>>(this) };
+        unsafe { mem::transmute(Box::into_raw_non_null(b)) }
+    }
+    unsafe fn from_raw(ptr: NonNull<()>) -> Self {
+        Pin::new_unchecked(Box::from_raw(ptr.as_ptr() as _))
+    }
+}
+unsafe impl<T> TypedPtrSized for Pin<Box<T>> {
+    type Target = T;
+}
+// `as_inner_mut` would allow moving the pointed value out, so this is only
+// safe if `T` doesn't care about being moved
+unsafe impl<T: Unpin> MutPtrSized for Pin<Box<T>> {}
+// `Pin` is `#[repr(transparent)]`
+unsafe impl<T> TrivialPtrSized for Pin<Box<T>> {}
+
 unsafe impl<T> PtrSized for Arc<T> {
     fn into_raw(this: Self) -> NonNull<()> {
         NonNull::new(Arc::into_raw(this) as *mut ()).expect("pointer is unexpectedly null")
@@ -109,6 +131,11 @@ unsafe impl<T> TypedPtrSized for Arc<T> {
     type Target = T;
 }
 
+// `Weak::into_raw` and `Weak::from_raw` are not available on the toolchain
+// this crate targets, so this relies on `Weak<T>` being represented by a
+// single non-null pointer to the `Arc`'s heap allocation (or a dangling
+// pointer for one created by `Weak::new`). `transmute` checks that the sizes
+// match.
 unsafe impl<T> PtrSized for Weak<T> {
     fn into_raw(this: Self) -> NonNull<()> {
         unsafe { mem::transmute(this) }
@@ -120,6 +147,23 @@ unsafe impl<T> PtrSized for Weak<T> {
 unsafe impl<T> TrivialPtrSized for Weak<T> {}
 
 /// An atomic `Option<Arc<T>>` storage that can be safely shared between threads.
+///
+/// # Memory ordering
+///
+/// Moving a value into the storage on one thread and moving it out on another
+/// thread is a handoff of the pointed object. For the receiving thread to
+/// observe the object in the state it was left in by the sending thread, the
+/// operation that puts the value must have the `Release` semantics and the one
+/// that removes the value must have the `Acquire` semantics. In particular:
+///
+///  - `swap` and `store` both put a value and remove (and return or drop) the
+///    old one, so they require `AcqRel` (or `SeqCst`).
+///  - `take` only removes a value, so it requires `Acquire` (or stronger).
+///  - `compare_and_swap` requires `AcqRel` if it might succeed.
+///
+/// Weaker orderings are only safe if the handoff is synchronized by other
+/// means. The methods suffixed with an ordering (e.g., `swap_acq_rel`) use
+/// the orderings listed above and should be preferred.
 pub struct Atom<T: PtrSized> {
     ptr: AtomicPtr<()>,
     phantom: PhantomData<T>,
@@ -155,19 +199,46 @@ impl<T: PtrSized> Atom<T> {
         unsafe { T::option_from_raw(p) }
     }
 
+    /// Store a value into the storage, returning the old value.
+    ///
+    /// See [the type-level documentation](#memory-ordering) for which
+    /// orderings are safe to use.
     pub fn swap(&self, x: Option<T>, order: Ordering) -> Option<T> {
         let new_ptr = T::option_into_raw(x);
         let old_ptr = self.ptr.swap(new_ptr as *mut (), order);
         unsafe { T::option_from_raw(old_ptr) }
     }
 
+    /// Store a value into the storage, dropping the old value.
+    ///
+    /// See [the type-level documentation](#memory-ordering) for which
+    /// orderings are safe to use.
     pub fn store(&self, x: Option<T>, order: Ordering) {
         self.swap(x, order);
     }
 
+    /// Remove the value from the storage and return it.
+    ///
+    /// See [the type-level documentation](#memory-ordering) for which
+    /// orderings are safe to use.
     pub fn take(&self, order: Ordering) -> Option<T> {
         self.swap(None, order)
     }
+
+    /// `swap` with `Ordering::AcqRel`.
+    pub fn swap_acq_rel(&self, x: Option<T>) -> Option<T> {
+        self.swap(x, Ordering::AcqRel)
+    }
+
+    /// `store` with `Ordering::AcqRel`.
+    pub fn store_acq_rel(&self, x: Option<T>) {
+        self.store(x, Ordering::AcqRel)
+    }
+
+    /// `take` with `Ordering::Acquire`.
+    pub fn take_acquire(&self) -> Option<T> {
+        self.take(Ordering::Acquire)
+    }
 }
 
 impl<T: PtrSized + Clone> Atom<T> {
@@ -221,6 +292,9 @@ impl<T: TypedPtrSized> Atom<T> {
     ///
     /// Returns the previous value with `Ok(x)` if the value was updated.
     /// `Err(new)` otherwise.
+    ///
+    /// See [the type-level documentation](#memory-ordering) for which
+    /// orderings are safe to use.
     pub fn compare_and_swap<P: AsRawPtr<T::Target>>(
         &self,
         current: &P,
@@ -241,6 +315,15 @@ impl<T: TypedPtrSized> Atom<T> {
         }
     }
 
+    /// `compare_and_swap` with `Ordering::AcqRel`.
+    pub fn compare_and_swap_acq_rel<P: AsRawPtr<T::Target>>(
+        &self,
+        current: &P,
+        new: Option<T>,
+    ) -> Result<Option<T>, Option<T>> {
+        self.compare_and_swap(current, new, Ordering::AcqRel)
+    }
+
     pub fn is_equal_to<P: AsRawPtr<T::Target>>(&self, other: &P, order: Ordering) -> bool {
         let other_ptr = other.as_raw_ptr();
         self.ptr.load(order) == other_ptr as *mut ()
@@ -259,6 +342,26 @@ impl<T: TypedPtrSized + MutPtrSized> Atom<T> {
     }
 }
 
+impl<T> Atom<Arc<T>> {
+    /// Mutably dereference the inner `Arc` without any atomic operations on
+    /// the storage.
+    ///
+    /// `&mut self` only guarantees the exclusive access to the storage, not
+    /// to the pointed value. Thus, like `Arc::get_mut`, this returns `None`
+    /// if there are other `Arc` or `Weak` pointers to the same value (as well
+    /// as when the storage is empty).
+    pub fn load_mut(&mut self) -> Option<&mut T> {
+        let raw = NonNull::new(*self.ptr.get_mut())?;
+
+        // Materialize the `Arc` without taking the ownership
+        let mut arc = mem::ManuallyDrop::new(unsafe { <Arc<T> as PtrSized>::from_raw(raw) });
+        let p: *mut T = Arc::get_mut(&mut arc)?;
+
+        // The pointed value lives as long as `self` holds the `Arc`
+        Some(unsafe { &mut *p })
+    }
+}
+
 impl<T: PtrSized> fmt::Debug for Atom<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_tuple("Atom").field(&self.ptr).finish()
@@ -277,6 +380,176 @@ impl<T: PtrSized> Default for Atom<T> {
     }
 }
 
+/// The value stored in `AtomWeak` while `AtomWeak::upgrade` is examining the
+/// stored `Weak`. Its address never collides with the raw representation of a
+/// `Weak` (see `PtrSized for Weak<T>`), which points into an `Arc`'s heap
+/// allocation (or is a dangling pointer).
+static WEAK_BORROWED: u8 = 0;
+
+fn weak_borrowed() -> *mut () {
+    &WEAK_BORROWED as *const u8 as *mut ()
+}
+
+/// Strengthen `order` so it has (at least) the `Acquire` semantics.
+fn with_acquire(order: Ordering) -> Ordering {
+    match order {
+        Ordering::Relaxed | Ordering::Acquire => Ordering::Acquire,
+        Ordering::Release | Ordering::AcqRel => Ordering::AcqRel,
+        _ => Ordering::SeqCst,
+    }
+}
+
+/// An atomic `Option<Weak<T>>` storage that can be safely shared between
+/// threads.
+///
+/// Unlike `Atom<Weak<T>>`, which can only move a `Weak` in and out of the
+/// storage, `AtomWeak` can upgrade the stored `Weak` without removing it.
+///
+/// # Concurrency
+///
+/// `upgrade` temporarily replaces the stored value with a marker while it is
+/// upgrading the `Weak`, and puts the original value back afterwards. Other
+/// operations (including `upgrade` on other threads) that encounter the marker
+/// spin until the original value is put back. This means:
+///
+///  - `upgrade` never returns `None` just because it lost a race with another
+///    operation. It returns `None` only if the storage is empty or the
+///    referenced value has been dropped.
+///  - A `store` racing with `upgrade` is never lost. It takes effect after
+///    `upgrade` puts the original value back.
+///
+/// The marker is held only for the duration of `Weak::upgrade`, which never
+/// blocks.
+///
+/// Because of this, every operation on the storage has (at least) the
+/// `Acquire` semantics regardless of the specified memory ordering.
+///
+/// # Representation
+///
+/// The stored `Weak<T>` is converted to a pointer by `PtrSized for Weak<T>`,
+/// which still transmutes `Weak<T>` instead of using `Weak::into_raw` and
+/// `Weak::from_raw`. Those functions are not available on the toolchain this
+/// crate is pinned to, so the transmute could not be removed yet.
+pub struct AtomWeak<T> {
+    ptr: AtomicPtr<()>,
+    phantom: PhantomData<Weak<T>>,
+}
+
+impl<T> AtomWeak<T> {
+    /// Construct an empty `AtomWeak`.
+    pub fn empty() -> Self {
+        Self {
+            ptr: AtomicPtr::default(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Construct an `AtomWeak` with an initial value.
+    pub fn new(x: Option<Weak<T>>) -> Self {
+        Self {
+            ptr: AtomicPtr::new(Weak::option_into_raw(x)),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Return the inner object, consuming `self`.
+    pub fn into_inner(mut self) -> Option<Weak<T>> {
+        let p = mem::replace(self.ptr.get_mut(), ptr::null_mut());
+        unsafe { Weak::option_from_raw(p) }
+    }
+
+    /// Store a value into the storage, returning the old value.
+    ///
+    /// `order` must have the `Release` semantics (i.e., `Release`, `AcqRel`,
+    /// or `SeqCst`) unless the handoff of `x` is synchronized by other means.
+    pub fn swap(&self, x: Option<Weak<T>>, order: Ordering) -> Option<Weak<T>> {
+        let new_ptr = Weak::option_into_raw(x);
+        let mut cur = self.ptr.load(Ordering::Relaxed);
+        loop {
+            if cur == weak_borrowed() {
+                // `upgrade` is in progress
+                spin_loop_hint();
+                cur = self.ptr.load(Ordering::Relaxed);
+                continue;
+            }
+
+            match self.ptr.compare_exchange_weak(
+                cur,
+                new_ptr,
+                with_acquire(order),
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => return unsafe { Weak::option_from_raw(cur) },
+                Err(x) => cur = x,
+            }
+        }
+    }
+
+    /// Store a value into the storage, dropping the old value.
+    ///
+    /// See `swap` for the requirement on `order`.
+    pub fn store(&self, x: Weak<T>, order: Ordering) {
+        self.swap(Some(x), order);
+    }
+
+    /// Remove the value from the storage and return it.
+    pub fn take(&self, order: Ordering) -> Option<Weak<T>> {
+        self.swap(None, order)
+    }
+
+    /// Attempt to upgrade the stored `Weak` to an `Arc`, leaving the stored
+    /// value intact.
+    ///
+    /// Returns `None` if the storage is empty or the value has already been
+    /// dropped. See [the type-level documentation](#concurrency) for how this
+    /// interacts with other operations.
+    pub fn upgrade(&self, order: Ordering) -> Option<Arc<T>> {
+        let p = loop {
+            let p = self.ptr.swap(weak_borrowed(), with_acquire(order));
+            if p != weak_borrowed() {
+                break p;
+            }
+
+            // Another thread is upgrading. Overwriting its marker with ours is
+            // harmless because they are identical.
+            while self.ptr.load(Ordering::Relaxed) == weak_borrowed() {
+                spin_loop_hint();
+            }
+        };
+
+        // Materialize the `Weak` without taking the ownership
+        let result = NonNull::new(p).and_then(|p| {
+            let weak = mem::ManuallyDrop::new(unsafe { <Weak<T> as PtrSized>::from_raw(p) });
+            weak.upgrade()
+        });
+
+        // Put the original value back. The `Release` ordering makes our access
+        // to the `Arc`'s control block happen-before the `Weak` is dropped by
+        // whoever takes it out next.
+        self.ptr.store(p, Ordering::Release);
+
+        result
+    }
+}
+
+impl<T> fmt::Debug for AtomWeak<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("AtomWeak").field(&self.ptr).finish()
+    }
+}
+
+impl<T> Drop for AtomWeak<T> {
+    fn drop(&mut self) {
+        unsafe { Weak::<T>::option_from_raw(*self.ptr.get_mut()) };
+    }
+}
+
+impl<T> Default for AtomWeak<T> {
+    fn default() -> Self {
+        AtomWeak::empty()
+    }
+}
+
 pub trait AsRawPtr<T> {
     fn as_raw_ptr(&self) -> *const T;
 }
@@ -370,6 +643,10 @@ impl<T: PtrSized> SetOnceAtom<T> {
     }
 
     /// Remove and return the inner object.
+    ///
+    /// This does not need any synchronization because `&mut self` guarantees
+    /// the exclusive access. `self` becomes empty after this operation, so the
+    /// subsequent calls return `None` until a new object is stored.
     pub fn take(&mut self) -> Option<T> {
         let ret = mem::replace(self.ptr.get_mut(), ptr::null_mut());
         unsafe { T::option_from_raw(ret) }
@@ -3,7 +3,9 @@
 //
 // This source code is a part of Nightingales.
 //
-use atom2::Atom;
+use atom2::{Atom, SetOnceAtom};
+use std::marker::PhantomPinned;
+use std::pin::Pin;
 use std::sync::{atomic::Ordering, Arc};
 
 #[test]
@@ -44,6 +46,56 @@ fn box_as_inner_mut_none() {
     assert!(aa.as_inner_mut().is_none());
 }
 
+#[test]
+fn pin_box_as_inner_mut_some() {
+    let mut aa = Atom::new(Some(Box::pin(1)));
+    *aa.as_inner_mut().unwrap() = 2;
+    assert_eq!(*aa.as_inner_ref().unwrap(), 2);
+    assert_eq!(*aa.into_inner().unwrap(), 2);
+}
+
+#[test]
+fn pin_box_not_unpin_swap() {
+    let value = Box::pin((42, PhantomPinned));
+    let addr = &*value as *const _;
+
+    let aa = Atom::new(Some(value));
+    let value = aa.swap(Some(Box::pin((43, PhantomPinned))), Ordering::Relaxed);
+    assert_eq!(&**value.as_ref().unwrap() as *const _, addr);
+    assert_eq!(value.unwrap().0, 42);
+
+    let value: Pin<Box<_>> = aa.take(Ordering::Relaxed).unwrap();
+    assert_eq!(value.0, 43);
+}
+
+#[test]
+fn arc_load_mut_unique() {
+    let mut aa = Atom::new(Some(Arc::new(1)));
+    *aa.load_mut().unwrap() = 2;
+    assert_eq!(*aa.into_inner().unwrap(), 2);
+}
+
+#[test]
+fn arc_load_mut_shared() {
+    let arc = Arc::new(1);
+    let mut aa = Atom::new(Some(Arc::clone(&arc)));
+    assert!(aa.load_mut().is_none());
+
+    drop(arc);
+    assert_eq!(*aa.load_mut().unwrap(), 1);
+
+    let weak = Arc::downgrade(&aa.load().unwrap());
+    assert!(aa.load_mut().is_none());
+    drop(weak);
+    assert!(aa.load_mut().is_some());
+}
+
+#[test]
+fn arc_load_mut_none() {
+    let mut aa: Atom<Arc<u32>> = Atom::empty();
+    assert!(aa.load_mut().is_none());
+}
+
 #[test]
 fn arc_load_some() {
     let mut aa = Atom::new(Some(Arc::new(1)));
@@ -81,3 +133,90 @@ fn arc_compare_and_swap2() {
     assert_eq!(*old.unwrap_err().unwrap(), 2);
     assert_eq!(*aa.into_inner().unwrap(), 1);
 }
+
+#[test]
+fn set_once_take() {
+    let value = Arc::new(1);
+    let mut aa = SetOnceAtom::empty();
+    aa.store(Some(Arc::clone(&value))).unwrap();
+    assert_eq!(Arc::strong_count(&value), 2);
+
+    let taken = aa.take().unwrap();
+    assert!(Arc::ptr_eq(&taken, &value));
+    assert!(aa.take().is_none());
+    assert!(aa.as_inner_ref().is_none());
+
+    drop(taken);
+    drop(aa);
+    assert_eq!(Arc::strong_count(&value), 1);
+}
+
+#[test]
+fn box_handoff_fixed_ordering() {
+    use std::thread;
+
+    const NUM_ITEMS: usize = 10000;
+    let atom: Arc<Atom<Box<[usize; 4]>>> = Arc::new(Atom::empty());
+
+    let producer = {
+        let atom = Arc::clone(&atom);
+        thread::spawn(move || {
+            for i in 0..NUM_ITEMS {
+                let mut item = Box::new([i; 4]);
+                loop {
+                    match atom.compare_and_swap_acq_rel(&None::<&[usize; 4]>, Some(item)) {
+                        Ok(_) => break,
+                        Err(x) => item = x.unwrap(),
+                    }
+                    thread::yield_now();
+                }
+            }
+        })
+    };
+
+    let consumer = {
+        let atom = Arc::clone(&atom);
+        thread::spawn(move || {
+            let mut next = 0;
+            while next < NUM_ITEMS {
+                if let Some(item) = atom.take_acquire() {
+                    // The contents written by the producer must be visible
+                    assert_eq!(*item, [next; 4]);
+                    next += 1;
+                } else {
+                    thread::yield_now();
+                }
+            }
+        })
+    };
+
+    producer.join().unwrap();
+    consumer.join().unwrap();
+    assert!(atom.take_acquire().is_none());
+}
+
+#[test]
+fn arc_swap_fixed_ordering() {
+    use std::thread;
+
+    let atom = Arc::new(Atom::new(Some(Arc::new(0usize))));
+
+    let threads: Vec<_> = (1..5)
+        .map(|t| {
+            let atom = Arc::clone(&atom);
+            thread::spawn(move || {
+                for i in 0..1000 {
+                    let old = atom.swap_acq_rel(Some(Arc::new(t * 10000 + i)));
+                    let old = *old.unwrap();
+                    assert!(old == 0 || (old % 10000 < 1000 && old / 10000 < 5));
+                }
+                atom.store_acq_rel(Some(Arc::new(t)));
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    assert!(atom.take_acquire().is_some());
+}
@@ -0,0 +1,106 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use atom2::{Atom, AtomWeak};
+use std::sync::{atomic::Ordering, Arc, Weak};
+use std::thread;
+
+#[test]
+fn atom_weak_roundtrip() {
+    let value = Arc::new(1);
+    let aa = Atom::new(Some(Arc::downgrade(&value)));
+    let weak = aa.take(Ordering::Relaxed).unwrap();
+    assert!(Arc::ptr_eq(&weak.upgrade().unwrap(), &value));
+    assert_eq!(Arc::weak_count(&value), 1);
+}
+
+#[test]
+fn atom_weak_dangling() {
+    let aa = Atom::new(Some(Weak::<u32>::new()));
+    assert!(aa.into_inner().unwrap().upgrade().is_none());
+}
+
+#[test]
+fn upgrade_some() {
+    let value = Arc::new(1);
+    let aa = AtomWeak::new(Some(Arc::downgrade(&value)));
+    assert!(Arc::ptr_eq(&aa.upgrade(Ordering::Relaxed).unwrap(), &value));
+
+    // The stored `Weak` is left intact
+    assert!(Arc::ptr_eq(&aa.upgrade(Ordering::Relaxed).unwrap(), &value));
+    assert_eq!(Arc::weak_count(&value), 1);
+}
+
+#[test]
+fn upgrade_empty() {
+    let aa: AtomWeak<u32> = AtomWeak::empty();
+    assert!(aa.upgrade(Ordering::Relaxed).is_none());
+
+    aa.store(Weak::new(), Ordering::Relaxed);
+    assert!(aa.upgrade(Ordering::Relaxed).is_none());
+}
+
+#[test]
+fn upgrade_after_drop() {
+    let value = Arc::new(1);
+    let aa = AtomWeak::new(Some(Arc::downgrade(&value)));
+    drop(value);
+    assert!(aa.upgrade(Ordering::Relaxed).is_none());
+    assert!(aa.take(Ordering::Relaxed).is_some());
+    assert!(aa.take(Ordering::Relaxed).is_none());
+}
+
+#[test]
+fn drop_releases_weak() {
+    let value = Arc::new(1);
+    let aa = AtomWeak::new(Some(Arc::downgrade(&value)));
+    assert_eq!(Arc::weak_count(&value), 1);
+    drop(aa);
+    assert_eq!(Arc::weak_count(&value), 0);
+}
+
+#[test]
+fn store_upgrade_race() {
+    let values: Arc<Vec<_>> = Arc::new((0..4).map(Arc::new).collect());
+    let aa = Arc::new(AtomWeak::new(Some(Arc::downgrade(&values[0]))));
+
+    let upgraders: Vec<_> = (0..4)
+        .map(|_| {
+            let aa = Arc::clone(&aa);
+            thread::spawn(move || {
+                for _ in 0..10000 {
+                    // Never spuriously fails because all values are alive
+                    aa.upgrade(Ordering::Relaxed).unwrap();
+                }
+            })
+        })
+        .collect();
+
+    let storers: Vec<_> = (0..2)
+        .map(|i| {
+            let aa = Arc::clone(&aa);
+            let values = Arc::clone(&values);
+            thread::spawn(move || {
+                for k in 0..10000 {
+                    let value = &values[(i + k) % values.len()];
+                    aa.store(Arc::downgrade(value), Ordering::Relaxed);
+                }
+            })
+        })
+        .collect();
+
+    for thread in upgraders.into_iter().chain(storers) {
+        thread.join().unwrap();
+    }
+
+    // No stores were lost or duplicated
+    let total_weak: usize = values.iter().map(Arc::weak_count).sum();
+    assert_eq!(total_weak, 1);
+
+    let aa = Arc::try_unwrap(aa).unwrap();
+    drop(aa);
+    let total_weak: usize = values.iter().map(Arc::weak_count).sum();
+    assert_eq!(total_weak, 0);
+}
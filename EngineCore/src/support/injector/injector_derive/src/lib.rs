@@ -0,0 +1,145 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+//! Procedural macros for [`injector`](../injector/index.html).
+//!
+//! # `#[derive(Key)]`
+//!
+//! Implements `injector::Key` on a unit struct. The associated type `Value` is
+//! specified by the `#[value(...)]` attribute.
+//!
+//! The supertraits of `Key` (`Clone`, `PartialEq`, `Eq`, `Hash`, and `Debug`)
+//! are implemented by the macro as well, so they must not be derived
+//! separately.
+//!
+//! The generated code refers to the items of `injector` by absolute paths,
+//! so `injector` must be a direct dependency of the crate using this macro.
+//!
+//! # Examples
+//!
+//!     use injector::{Container, Key};
+//!
+//!     #[derive(Key)]
+//!     #[value(MyService)]
+//!     struct MyServiceKey;
+//!
+//!     #[derive(Debug)]
+//!     struct MyService;
+//!
+//!     let mut container = Container::new();
+//!     container.register(MyServiceKey, MyService);
+//!
+//!     let _service: &MyService = container.get(&MyServiceKey).unwrap();
+//!
+//! The value type must be specified:
+//!
+//! ```compile_fail
+//! use injector::Key;
+//!
+//! #[derive(Key)]
+//! struct MyServiceKey;
+//! ```
+//!
+//! Only unit structs are supported:
+//!
+//! ```compile_fail
+//! use injector::Key;
+//!
+//! #[derive(Key)]
+//! #[value(u32)]
+//! struct MyServiceKey(u32);
+//! ```
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    Attribute, Data, DeriveInput, Fields, Type,
+};
+
+#[proc_macro_derive(Key, attributes(value))]
+pub fn derive_key(input: TokenStream) -> TokenStream {
+    let ast: DeriveInput = syn::parse(input).unwrap();
+
+    if ast.generics.params.len() > 0 {
+        panic!("`derive(Key)` does not support generics");
+    }
+
+    match ast.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Unit => {}
+            _ => panic!("`derive(Key)` may only be applied to unit structs"),
+        },
+        _ => panic!("`derive(Key)` may only be applied to unit structs"),
+    }
+
+    let mut value_tys = ast.attrs.iter().filter_map(value_arg);
+    let value_ty = value_tys
+        .next()
+        .expect("`derive(Key)` requires `#[value(...)]`");
+    if value_tys.next().is_some() {
+        panic!("`#[value(...)]` is specified more than once");
+    }
+
+    let ident = &ast.ident;
+    let name = ident.to_string();
+
+    let tokens = quote! {
+        impl ::injector::Key for #ident {
+            type Value = #value_ty;
+        }
+
+        impl ::std::clone::Clone for #ident {
+            fn clone(&self) -> Self {
+                #ident
+            }
+        }
+
+        impl ::std::cmp::PartialEq for #ident {
+            fn eq(&self, _: &Self) -> bool {
+                true
+            }
+        }
+
+        impl ::std::cmp::Eq for #ident {}
+
+        impl ::std::hash::Hash for #ident {
+            fn hash<H: ::std::hash::Hasher>(&self, _: &mut H) {}
+        }
+
+        impl ::std::fmt::Debug for #ident {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.write_str(#name)
+            }
+        }
+    };
+
+    tokens.into()
+}
+
+/// The argument of a `#[value(...)]` attribute.
+struct ValueArg(Type);
+
+impl Parse for ValueArg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        parenthesized!(content in input);
+        Ok(ValueArg(content.parse()?))
+    }
+}
+
+/// Extract the type specified by a `#[value(...)]` attribute.
+fn value_arg(attr: &Attribute) -> Option<Type> {
+    if attr.path.segments.len() != 1 || attr.path.segments[0].ident != "value" {
+        return None;
+    }
+
+    let arg: ValueArg = syn::parse2(attr.tts.clone())
+        .unwrap_or_else(|_| panic!("malformed `#[value(...)]` attribute"));
+
+    Some(arg.0)
+}
@@ -0,0 +1,155 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use asynclazy::SharedAsync;
+use futures::{prelude::*, task::Spawn};
+use std::{fmt, fmt::Debug, pin::Pin, sync::Arc};
+
+use crate::{BuildError, Container, SingletonExt};
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// An asynchronous factory object.
+trait AsyncFactory<T>: 'static + Send + Sync + Debug {
+    fn build(&self, container: &mut Container) -> BoxFuture<T>;
+}
+
+type AsyncFactoryRef<T> = Arc<dyn AsyncFactory<T>>;
+
+/// Wraps a closure to form an `AsyncFactory` object.
+struct AsyncFactoryImpl<T>(T);
+
+impl<T, S> AsyncFactory<T> for AsyncFactoryImpl<S>
+where
+    S: 'static + Send + Sync + Fn(&mut Container) -> BoxFuture<T>,
+{
+    fn build(&self, container: &mut Container) -> BoxFuture<T> {
+        self.0(container)
+    }
+}
+
+impl<T> Debug for AsyncFactoryImpl<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("AsyncFactoryImpl").finish()
+    }
+}
+
+/// An extension trait for [`crate::Container`] to provide means to register
+/// asynchronous factory objects and use them to instantiate singleton objects
+/// on demand.
+///
+/// This trait is only available if the `async` feature is enabled.
+///
+/// An asynchronously built instance of `T` is stored in the container as
+/// [`asynclazy::SharedAsync`]`<T>`. Since the container is only borrowed
+/// during the call to [`AsyncFactoryExt::get_singleton_or_build_async`] and
+/// not across the evaluation of the returned future, a factory consists of
+/// two stages:
+///
+///  1. The factory function is called synchronously with `&mut Container`.
+///     Dependencies must be resolved (or their `SharedAsync` handles must be
+///     obtained) in this stage.
+///  2. The future returned by the factory function is spawned on an executor.
+///     It can't access the container anymore.
+///
+/// # Examples
+///
+///     #![feature(futures_api)]
+///     use injector::{AsyncFactoryExt, Container, FactoryExt};
+///     use futures::{executor::{block_on, ThreadPool}, future};
+///
+///     #[derive(Debug)]
+///     struct Config { path: &'static str }
+///
+///     #[derive(Debug)]
+///     struct Database { contents: String }
+///
+///     let pool = ThreadPool::new().unwrap();
+///     let mut container = Container::new();
+///
+///     container.register_singleton_factory(|_| Config { path: "db.txt" });
+///     container.register_singleton_async_factory(|container| {
+///         // Resolve the dependencies synchronously
+///         let path = container.get_singleton_or_build::<Config>().unwrap().path;
+///
+///         // ... and then build the instance asynchronously
+///         future::lazy(move |_| Database {
+///             contents: format!("contents of {}", path),
+///         })
+///     });
+///
+///     let database = container
+///         .get_singleton_or_build_async::<Database>(&mut &pool)
+///         .unwrap();
+///     let database = block_on(database.as_future());
+///     assert_eq!(database.contents, "contents of db.txt");
+///
+pub trait AsyncFactoryExt {
+    /// Get a handle to an instance of `T` previously created by this method.
+    /// Create one using a factory object registered by
+    /// [`AsyncFactoryExt::register_singleton_async_factory`]`<T>` if there is
+    /// not such an object, spawning the future returned by the factory on
+    /// `spawner`.
+    ///
+    /// All handles returned for the same `T` share the same instance, so
+    /// concurrent callers waiting for it share a single construction.
+    ///
+    /// Returns `Err(BuildError::SpawnFailed)` if the future could not be
+    /// spawned. In this case, the instance is not created and the next call
+    /// will retry.
+    fn get_singleton_or_build_async<T: 'static + Send + Sync + Debug>(
+        &mut self,
+        spawner: &mut dyn Spawn,
+    ) -> Result<SharedAsync<T>, BuildError>;
+
+    /// Register a factory that can be used by
+    /// [`AsyncFactoryExt::get_singleton_or_build_async`]`<T>`.
+    ///
+    /// Replacing an existing factory is subject to the
+    /// [overwrite policy](crate::OverwritePolicy) of the container.
+    fn register_singleton_async_factory<T, F>(
+        &mut self,
+        factory: impl 'static + Send + Sync + Fn(&mut Container) -> F,
+    ) where
+        T: 'static + Send + Sync + Debug,
+        F: 'static + Send + Future<Output = T>;
+
+    /// Return `true` if a factory was registered by
+    /// [`AsyncFactoryExt::register_singleton_async_factory`]`<T>`.
+    fn has_singleton_async_factory<T: 'static + Send + Sync + Debug>(&self) -> bool;
+}
+
+impl AsyncFactoryExt for Container {
+    fn get_singleton_or_build_async<T: 'static + Send + Sync + Debug>(
+        &mut self,
+        spawner: &mut dyn Spawn,
+    ) -> Result<SharedAsync<T>, BuildError> {
+        self.get_singleton_or_try_create_with(|container| {
+            let factory: AsyncFactoryRef<T> =
+                Arc::clone(container.get_singleton().ok_or(BuildError::NoFactory)?);
+            let future = factory.build(container);
+            SharedAsync::with_future(spawner, future).map_err(|_| BuildError::SpawnFailed)
+        })
+        .map(|x: &mut SharedAsync<T>| x.clone())
+    }
+
+    fn register_singleton_async_factory<T, F>(
+        &mut self,
+        factory: impl 'static + Send + Sync + Fn(&mut Container) -> F,
+    ) where
+        T: 'static + Send + Sync + Debug,
+        F: 'static + Send + Future<Output = T>,
+    {
+        let factory_impl = AsyncFactoryImpl(move |container: &mut _| -> BoxFuture<T> {
+            Box::pin(factory(container))
+        });
+        let factory: AsyncFactoryRef<T> = Arc::new(factory_impl);
+        self.register_singleton(factory);
+    }
+
+    fn has_singleton_async_factory<T: 'static + Send + Sync + Debug>(&self) -> bool {
+        self.get_singleton::<AsyncFactoryRef<T>>().is_some()
+    }
+}
@@ -0,0 +1,165 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use std::{fmt, mem::replace};
+
+use crate::{Key, ValueBag};
+
+/// A view into a single entry in a [`crate::Container`], which may either be
+/// vacant or occupied.
+///
+/// This is constructed by [`crate::Container::entry`] and mirrors
+/// `std::collections::hash_map::Entry`.
+pub enum Entry<'a, K: Key> {
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a, K>),
+    /// A vacant entry.
+    Vacant(VacantEntry<'a, K>),
+}
+
+/// A view into an occupied entry in a [`crate::Container`]. It is part of
+/// the [`Entry`] enum.
+pub struct OccupiedEntry<'a, K: Key> {
+    key: K,
+    value: &'a mut K::Value,
+}
+
+/// A view into a vacant entry in a [`crate::Container`]. It is part of the
+/// [`Entry`] enum.
+pub struct VacantEntry<'a, K: Key> {
+    key: K,
+    value_bag: &'a mut ValueBag<K, K::Value>,
+}
+
+impl<'a, K: Key> Entry<'a, K> {
+    pub(crate) fn new(value_bag: &'a mut ValueBag<K, K::Value>, key: K) -> Self {
+        if value_bag.get(&key).is_none() {
+            Entry::Vacant(VacantEntry { key, value_bag })
+        } else {
+            // Work-around borrow check issue (see `get_or_try_create_with`)
+            let value = value_bag.get_mut(&key).unwrap();
+            Entry::Occupied(OccupiedEntry { key, value })
+        }
+    }
+
+    /// Get a reference to this entry's key.
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(e) => e.key(),
+            Entry::Vacant(e) => e.key(),
+        }
+    }
+
+    /// Ensure a value is in the entry by inserting `default` if empty, and
+    /// return a mutable reference to the value in the entry.
+    pub fn or_insert(self, default: K::Value) -> &'a mut K::Value {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default),
+        }
+    }
+
+    /// Ensure a value is in the entry by inserting the result of `default` if
+    /// empty, and return a mutable reference to the value in the entry.
+    pub fn or_insert_with(self, default: impl FnOnce() -> K::Value) -> &'a mut K::Value {
+        match self {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(default()),
+        }
+    }
+
+    /// Provide in-place mutable access to an occupied entry before any
+    /// potential inserts into the container.
+    pub fn and_modify(self, f: impl FnOnce(&mut K::Value)) -> Self {
+        match self {
+            Entry::Occupied(mut e) => {
+                f(e.get_mut());
+                Entry::Occupied(e)
+            }
+            Entry::Vacant(e) => Entry::Vacant(e),
+        }
+    }
+}
+
+impl<'a, K: Key> Entry<'a, K>
+where
+    K::Value: Default,
+{
+    /// Ensure a value is in the entry by inserting the default value if empty,
+    /// and return a mutable reference to the value in the entry.
+    pub fn or_default(self) -> &'a mut K::Value {
+        self.or_insert_with(Default::default)
+    }
+}
+
+impl<'a, K: Key> OccupiedEntry<'a, K> {
+    /// Get a reference to this entry's key.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Get a reference to the value in the entry.
+    pub fn get(&self) -> &K::Value {
+        self.value
+    }
+
+    /// Get a mutable reference to the value in the entry.
+    pub fn get_mut(&mut self) -> &mut K::Value {
+        self.value
+    }
+
+    /// Convert the entry into a mutable reference to the value in the entry
+    /// with a lifetime bound to the container itself.
+    pub fn into_mut(self) -> &'a mut K::Value {
+        self.value
+    }
+
+    /// Set the value of the entry, and return the entry's old value.
+    pub fn insert(&mut self, value: K::Value) -> K::Value {
+        replace(self.value, value)
+    }
+}
+
+impl<'a, K: Key> VacantEntry<'a, K> {
+    /// Get a reference to this entry's key.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Take ownership of the key.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Set the value of the entry with the `VacantEntry`'s key, and return a
+    /// mutable reference to it.
+    pub fn insert(self, value: K::Value) -> &'a mut K::Value {
+        self.value_bag.insert(self.key, value).0
+    }
+}
+
+impl<'a, K: Key> fmt::Debug for Entry<'a, K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Entry::Occupied(e) => f.debug_tuple("Entry").field(e).finish(),
+            Entry::Vacant(e) => f.debug_tuple("Entry").field(e).finish(),
+        }
+    }
+}
+
+impl<'a, K: Key> fmt::Debug for OccupiedEntry<'a, K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("key", &self.key)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+impl<'a, K: Key> fmt::Debug for VacantEntry<'a, K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("VacantEntry").field(&self.key).finish()
+    }
+}
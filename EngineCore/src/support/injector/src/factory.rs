@@ -3,9 +3,13 @@
 //
 // This source code is a part of Nightingales.
 //
-use std::{fmt, fmt::Debug, sync::Arc};
+use std::{
+    fmt,
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
 
-use crate::{Container, Key, SingletonExt};
+use crate::{Container, Key, SingletonExt, TeardownEntry};
 
 /// A factory object.
 ///
@@ -44,6 +48,10 @@ impl<T> Debug for FactoryImpl<T> {
 pub enum BuildError {
     /// The factory object of a specified type or key was not found.
     NoFactory,
+    /// The future returned by an asynchronous factory could not be spawned.
+    /// Only returned by `AsyncFactoryExt::get_singleton_or_build_async`.
+    #[cfg(feature = "async")]
+    SpawnFailed,
 }
 
 /// An extension trait for [`crate::Container`] to provide means to register
@@ -66,6 +74,9 @@ pub trait FactoryExt {
     ) -> Result<&mut T, BuildError>;
 
     /// Register a factory that can be used by [`FactoryExt::get_or_build`]`<K>`.
+    ///
+    /// Replacing an existing factory is subject to the
+    /// [overwrite policy](crate::OverwritePolicy) of the container.
     fn register_factory<K: Key>(
         &mut self,
         factory: impl 'static + Send + Sync + Fn(&K, &mut Container) -> K::Value,
@@ -73,10 +84,123 @@ pub trait FactoryExt {
 
     /// Register a factory that can be used by
     /// [`FactoryExt::get_singleton_or_build`]`<T>`.
+    ///
+    /// Replacing an existing factory is subject to the
+    /// [overwrite policy](crate::OverwritePolicy) of the container.
+    ///
+    /// # Examples
+    ///
+    ///     use injector::{Container, FactoryExt, OverwritePolicy};
+    ///     use std::panic::{catch_unwind, AssertUnwindSafe};
+    ///     use std::sync::{Arc, Mutex};
+    ///
+    ///     let mut container = Container::new();
+    ///     container.register_singleton_factory(|_| 1u32);
+    ///
+    ///     // `Warn` reports the replacement of the factory
+    ///     let log = Arc::new(Mutex::new(Vec::new()));
+    ///     let log2 = Arc::clone(&log);
+    ///     container.set_overwrite_policy(OverwritePolicy::Warn(Box::new(
+    ///         move |key_type, _key| log2.lock().unwrap().push(key_type),
+    ///     )));
+    ///     container.register_singleton_factory(|_| 2u32);
+    ///     assert_eq!(log.lock().unwrap().len(), 1);
+    ///
+    ///     // `Deny` rejects it
+    ///     container.set_overwrite_policy(OverwritePolicy::Deny);
+    ///     let result = catch_unwind(AssertUnwindSafe(|| {
+    ///         container.register_singleton_factory(|_| 3u32);
+    ///     }));
+    ///     assert!(result.is_err());
+    ///
+    ///     assert_eq!(*container.get_singleton_or_build::<u32>().unwrap(), 2);
+    ///
     fn register_singleton_factory<T: 'static + Send + Sync + Debug>(
         &mut self,
         factory: impl 'static + Send + Sync + Fn(&mut Container) -> T,
     );
+
+    /// Register a factory that can be used by
+    /// [`FactoryExt::get_singleton_or_build`]`<T>`, along with a teardown
+    /// function to be called on the created instance when the container is
+    /// dropped.
+    ///
+    /// Teardown functions are called in the reverse order of the creation of
+    /// their respective instances. Since a factory creates the dependencies
+    /// of its instance before the instance itself, a service is always torn
+    /// down before the services it depends on.
+    ///
+    /// The teardown function is not called if the factory was never used.
+    ///
+    /// # Examples
+    ///
+    ///     use injector::{Container, FactoryExt};
+    ///     use std::sync::{Arc, Mutex};
+    ///
+    ///     #[derive(Debug)]
+    ///     struct Database;
+    ///
+    ///     #[derive(Debug)]
+    ///     struct Cache;
+    ///
+    ///     let log = Arc::new(Mutex::new(Vec::new()));
+    ///     let mut container = Container::new();
+    ///
+    ///     let log2 = Arc::clone(&log);
+    ///     container.register_singleton_with_teardown(
+    ///         |container| {
+    ///             // `Cache` depends on `Database`
+    ///             container.get_singleton_or_build::<Database>().unwrap();
+    ///             Cache
+    ///         },
+    ///         move |_: &mut Cache| log2.lock().unwrap().push("cache"),
+    ///     );
+    ///
+    ///     let log2 = Arc::clone(&log);
+    ///     container.register_singleton_with_teardown(
+    ///         |_| Database,
+    ///         move |_: &mut Database| log2.lock().unwrap().push("database"),
+    ///     );
+    ///
+    ///     container.get_singleton_or_build::<Cache>().unwrap();
+    ///     drop(container);
+    ///
+    ///     assert_eq!(*log.lock().unwrap(), ["cache", "database"]);
+    ///
+    fn register_singleton_with_teardown<T: 'static + Send + Sync + Debug>(
+        &mut self,
+        factory: impl 'static + Send + Sync + Fn(&mut Container) -> T,
+        teardown: impl 'static + Send + Sync + FnOnce(&mut T),
+    );
+
+    /// Return `true` if a factory was registered by
+    /// [`FactoryExt::register_factory`]`<K>`.
+    ///
+    /// A key type has at most one factory since registering another one
+    /// replaces the existing one.
+    fn has_factory<K: Key>(&self) -> bool;
+
+    /// Return `true` if a factory was registered by
+    /// [`FactoryExt::register_singleton_factory`]`<T>` or
+    /// [`FactoryExt::register_singleton_with_teardown`]`<T>`.
+    ///
+    /// This is useful for validating the configuration of a container at
+    /// startup, before any objects are built.
+    ///
+    /// # Examples
+    ///
+    ///     use injector::{Container, FactoryExt};
+    ///
+    ///     #[derive(Debug)]
+    ///     struct Database;
+    ///
+    ///     let mut container = Container::new();
+    ///     assert!(!container.has_singleton_factory::<Database>());
+    ///
+    ///     container.register_singleton_factory(|_| Database);
+    ///     assert!(container.has_singleton_factory::<Database>());
+    ///
+    fn has_singleton_factory<T: 'static + Send + Sync + Debug>(&self) -> bool;
 }
 
 impl FactoryExt for Container {
@@ -115,4 +239,37 @@ impl FactoryExt for Container {
         let factory: FactoryRef<(), T> = Arc::new(factory_impl);
         self.register_singleton(factory);
     }
+
+    fn register_singleton_with_teardown<T: 'static + Send + Sync + Debug>(
+        &mut self,
+        factory: impl 'static + Send + Sync + Fn(&mut Container) -> T,
+        teardown: impl 'static + Send + Sync + FnOnce(&mut T),
+    ) {
+        let teardown = Mutex::new(Some(teardown));
+        self.register_singleton_factory(move |container| {
+            let value = factory(container);
+
+            // The instance is registered to the container after the factory
+            // returns, which happens after all of its dependencies are created
+            if let Some(teardown) = teardown.lock().unwrap().take() {
+                container.teardowns.push(TeardownEntry {
+                    has_instance: |container| container.get_singleton::<T>().is_some(),
+                    teardown: Box::new(move |container| {
+                        if let Some(value) = container.get_singleton_mut::<T>() {
+                            teardown(value);
+                        }
+                    }),
+                });
+            }
+
+            value
+        });
+    }
+    fn has_factory<K: Key>(&self) -> bool {
+        self.get_singleton::<FactoryRef<K, K::Value>>().is_some()
+    }
+
+    fn has_singleton_factory<T: 'static + Send + Sync + Debug>(&self) -> bool {
+        self.get_singleton::<FactoryRef<(), T>>().is_some()
+    }
 }
@@ -273,37 +273,186 @@
 //!         .clone()  // Get `Result<YAServiceRef, Error>`
 //!         .expect_err("The error did not propagate for some reasons");
 //!
+//! ## Modules
+//!
+//! Factories are usually registered in bundles, one for each subsystem. Such
+//! a bundle can be implemented as a [`Module`] and installed by
+//! [`Container::install`], which makes sure each module is installed only
+//! once. See the documentation of [`Module`] for an example.
+//!
+//! # Cargo features
+//!
+//!  - `async` enables `AsyncFactoryExt`, which builds singleton objects
+//!    asynchronously.
+//!
+#![feature(core_intrinsics)] // For `type_name`
 #![feature(never_type)]
+#![feature(unsized_locals)] // For calling boxed `FnOnce`
+#![cfg_attr(feature = "async", feature(futures_api))]
 use std::{
     any::{Any, TypeId},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt,
     hash::Hash,
     mem::replace,
 };
 
+#[cfg(feature = "async")]
+mod asyncfactory;
+mod entry;
 mod factory;
+mod module;
+mod shared;
 mod singleton;
 
+#[cfg(feature = "async")]
+pub use self::asyncfactory::*;
+pub use self::entry::*;
 pub use self::factory::*;
+pub use self::module::*;
+pub use self::shared::*;
 pub use self::singleton::*;
 
+pub use injector_derive::Key;
+
 /// The `injector` prelude.
 pub mod prelude {
     #[doc(no_inline)]
     pub use super::{FactoryExt, SingletonExt};
+
+    #[cfg(feature = "async")]
+    #[doc(no_inline)]
+    pub use super::AsyncFactoryExt;
 }
 
 /// A DI-like container.
 ///
 /// See [the crate documentation](index.html) for details.
-#[derive(Default, Debug)]
+#[derive(Default)]
 pub struct Container {
     /// Each element is a `ValueBag<K, K::Value>` where `K: Key`.
     key_types: HashMap<TypeId, Box<dyn ValueBagTrait>>,
+
+    /// Teardown functions to be called in the reverse order when the
+    /// container is dropped.
+    teardowns: Vec<TeardownEntry>,
+
+    /// Specifies the behavior of [`Container::register`] on an existing key.
+    overwrite_policy: OverwritePolicy,
+
+    /// The set of modules installed by [`Container::install`].
+    installed_modules: HashSet<ModuleId>,
+
+    /// The stack of modules whose [`Module::install`] is currently running.
+    installing_modules: Vec<ModuleId>,
+}
+
+type Teardown = Box<dyn FnOnce(&mut Container) + Send + Sync>;
+
+/// A teardown function registered by
+/// [`FactoryExt::register_singleton_with_teardown`].
+struct TeardownEntry {
+    /// Check if a given container has the singleton instance the teardown
+    /// function is associated with.
+    has_instance: fn(&Container) -> bool,
+    teardown: Teardown,
+}
+
+impl fmt::Debug for Container {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Container")
+            .field("key_types", &self.key_types)
+            .field("overwrite_policy", &self.overwrite_policy)
+            .field("installed_modules", &self.installed_modules)
+            .finish()
+    }
+}
+
+impl Drop for Container {
+    fn drop(&mut self) {
+        while let Some(entry) = self.teardowns.pop() {
+            (entry.teardown)(self);
+        }
+    }
+}
+
+/// Specifies which object prevails when merging containers by
+/// [`Container::extend`] and both of them have objects associated with an
+/// identical key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConflictPolicy {
+    /// Keep the object in the destination container.
+    SelfWins,
+    /// Replace the object in the destination container with the one from the
+    /// source container.
+    OtherWins,
+}
+
+/// Specifies the behavior of [`Container::register`] (and the methods built
+/// on it, such as [`SingletonExt::register_singleton`] and
+/// [`FactoryExt::register_singleton_factory`]) when an object associated with
+/// an identical key is already registered.
+///
+/// Set by [`Container::set_overwrite_policy`]. Does not affect
+/// [`Container::extend`], which takes a [`ConflictPolicy`] instead.
+pub enum OverwritePolicy {
+    /// Silently replace the existing object. This is the default.
+    Allow,
+    /// Call the supplied function with the key type name and the `Debug`
+    /// representation of the key, and then replace the existing object.
+    Warn(Box<OverwriteWarnFn>),
+    /// Keep the existing object and report an error. [`Container::register`]
+    /// panics while [`Container::try_register`] returns `Err(_)`.
+    Deny,
 }
 
+/// The callback function type of [`OverwritePolicy::Warn`], receiving the key
+/// type name and the `Debug` representation of the key.
+pub type OverwriteWarnFn = dyn Fn(&'static str, &str) + Send + Sync;
+
+impl Default for OverwritePolicy {
+    fn default() -> Self {
+        OverwritePolicy::Allow
+    }
+}
+
+impl fmt::Debug for OverwritePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OverwritePolicy::Allow => write!(f, "Allow"),
+            OverwritePolicy::Warn(_) => f.debug_tuple("Warn").field(&"..").finish(),
+            OverwritePolicy::Deny => write!(f, "Deny"),
+        }
+    }
+}
+
+/// Indicates that [`Container::try_register`] failed because an object
+/// associated with an identical key is already registered and the overwrite
+/// policy is [`OverwritePolicy::Deny`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AlreadyRegistered {
+    /// The name of the key type.
+    pub key_type: &'static str,
+    /// The `Debug` representation of the key.
+    pub key_debug: String,
+}
+
+impl fmt::Display for AlreadyRegistered {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "an object is already registered with the key {} (of type {})",
+            self.key_debug, self.key_type
+        )
+    }
+}
+
+impl std::error::Error for AlreadyRegistered {}
+
 /// Identifies an object in a [`Container`].
+///
+/// For unit structs, this trait can be implemented by
+/// [`#[derive(Key)]`](../injector_derive/index.html).
 pub trait Key: Any + Send + Sync + Hash + Eq + Clone + fmt::Debug {
     /// The type of the object to be stored in a [`Container`], associated with
     /// this (or `Eq`uivalent) `Key`.
@@ -330,6 +479,75 @@ impl Container {
         key_type_map.get(key)
     }
 
+    /// Return `true` if an object associated with a specified `key` is
+    /// registered.
+    ///
+    /// Unlike [`Container::get_or_create_with`] and the methods built on it,
+    /// this never creates an object.
+    ///
+    /// # Examples
+    ///
+    ///     use injector::{Container, Key};
+    ///
+    ///     #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    ///     struct VerbosityKey(&'static str);
+    ///
+    ///     impl Key for VerbosityKey {
+    ///         type Value = u32;
+    ///     }
+    ///
+    ///     let mut container = Container::new();
+    ///     container.register(VerbosityKey("renderer"), 3);
+    ///
+    ///     // Register a default value only if missing
+    ///     if !container.contains(&VerbosityKey("audio")) {
+    ///         container.register(VerbosityKey("audio"), 1);
+    ///     }
+    ///
+    ///     assert!(container.contains(&VerbosityKey("renderer")));
+    ///     assert_eq!(container.get(&VerbosityKey("audio")), Some(&1));
+    ///
+    pub fn contains<K: Key>(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Get an iterator over all objects associated with keys of type `K` and
+    /// previously registered by [`Container::register`], as well as their
+    /// keys.
+    ///
+    /// The iteration order is unspecified.
+    ///
+    /// # Examples
+    ///
+    ///     use injector::{Container, Key};
+    ///
+    ///     #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    ///     struct PluginKey(&'static str);
+    ///
+    ///     impl Key for PluginKey {
+    ///         type Value = u32;
+    ///     }
+    ///
+    ///     let mut container = Container::new();
+    ///     assert_eq!(container.iter_values::<PluginKey>().count(), 0);
+    ///
+    ///     container.register(PluginKey("foo"), 1);
+    ///     container.register(PluginKey("bar"), 2);
+    ///
+    ///     let mut values: Vec<_> = container.iter_values::<PluginKey>().collect();
+    ///     values.sort_by_key(|&(_, value)| *value);
+    ///     assert_eq!(values, vec![(&PluginKey("foo"), &1), (&PluginKey("bar"), &2)]);
+    ///
+    pub fn iter_values<K: Key>(&self) -> impl Iterator<Item = (&K, &K::Value)> {
+        self.key_types
+            .get(&TypeId::of::<K>())
+            .map(|key_type_map| -> &ValueBag<K, K::Value> {
+                key_type_map.as_any().downcast_ref().unwrap()
+            })
+            .into_iter()
+            .flat_map(ValueBag::iter)
+    }
+
     /// Get a mutable reference to an object associated with a specified `key`
     /// and previously registered by [`Container::register`].
     ///
@@ -356,6 +574,38 @@ impl Container {
             .unwrap()
     }
 
+    /// Get a mutable reference to an object associated with a specified `key`
+    /// and previously registered by [`Container::register`]. Create one using
+    /// `Default::default` if there is not such an object.
+    ///
+    /// # Examples
+    ///
+    ///     use injector::{Container, Key};
+    ///
+    ///     #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    ///     struct VerbosityKey(&'static str);
+    ///
+    ///     impl Key for VerbosityKey {
+    ///         type Value = u32;
+    ///     }
+    ///
+    ///     let mut container = Container::new();
+    ///     container.register(VerbosityKey("renderer"), 3);
+    ///
+    ///     assert_eq!(*container.get_or_default(&VerbosityKey("renderer")), 3);
+    ///     assert_eq!(*container.get_or_default(&VerbosityKey("audio")), 0);
+    ///
+    ///     // The default value is registered to the container
+    ///     *container.get_or_default(&VerbosityKey("audio")) += 1;
+    ///     assert_eq!(container.get(&VerbosityKey("audio")), Some(&1));
+    ///
+    pub fn get_or_default<K: Key>(&mut self, key: &K) -> &mut K::Value
+    where
+        K::Value: Default,
+    {
+        self.get_or_create_with(key, |_, _| Default::default())
+    }
+
     /// Get a mutable reference to an object associated with a specified `key`
     /// and previously registered by [`Container::register`]. Create one using
     /// `factory` if there is not such an object.
@@ -376,37 +626,291 @@ impl Container {
 
         let value = factory(key, self)?;
 
-        let key_type_map_entry = self.key_types.entry(TypeId::of::<K>());
+        Ok(self.value_bag_mut::<K>().insert(key.clone(), value).0)
+    }
 
-        let key_type_map: &mut ValueBag<K, K::Value> = key_type_map_entry
-            .or_insert_with(|| {
-                let key_type_map: ValueBag<K, K::Value> = ValueBag::new();
-                Box::new(key_type_map)
-            }).as_any_mut()
-            .downcast_mut()
-            .unwrap();
+    /// Replace an object associated with a specified `key` with the result of
+    /// applying `f` to it. This is useful for wrapping (decorating) an already
+    /// registered object.
+    ///
+    /// Returns `true` if there was such an object and it was replaced.
+    /// Returns `false` without calling `f` otherwise.
+    ///
+    /// The object is removed from the container while `f` is running. If `f`
+    /// panics, the object is dropped and the container is left without an
+    /// object associated with `key`.
+    ///
+    /// # Examples
+    ///
+    ///     use injector::{Container, Key};
+    ///
+    ///     #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    ///     struct NameKey;
+    ///
+    ///     impl Key for NameKey {
+    ///         type Value = String;
+    ///     }
+    ///
+    ///     let mut container = Container::new();
+    ///     assert!(!container.map_value(&NameKey, |name| name));
+    ///
+    ///     container.register(NameKey, "renderer".to_owned());
+    ///     assert!(container.map_value(&NameKey, |name| format!("<{}>", name)));
+    ///     assert_eq!(container.get(&NameKey).map(String::as_str), Some("<renderer>"));
+    ///
+    pub fn map_value<K: Key>(&mut self, key: &K, f: impl FnOnce(K::Value) -> K::Value) -> bool {
+        let value_bag = self.value_bag_mut::<K>();
 
-        Ok(key_type_map.insert(key.clone(), value).0)
+        // Remove the object first so the container stays consistent even if
+        // `f` panics
+        if let Some((key, value)) = value_bag.remove(key) {
+            let value = f(value);
+            value_bag.insert(key, value);
+            true
+        } else {
+            false
+        }
     }
 
     /// Register an object associated with a specified `key`.
     ///
     /// Returns the previously registered object with an identical key, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there already is such an object and the overwrite policy is
+    /// [`OverwritePolicy::Deny`]. Use [`Container::try_register`] to handle
+    /// this case gracefully.
     pub fn register<K: Key>(&mut self, key: K, value: K::Value) -> Option<K::Value> {
-        let key_type_map_entry = self.key_types.entry(TypeId::of::<K>());
+        self.try_register(key, value).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Register an object associated with a specified `key`, respecting the
+    /// overwrite policy.
+    ///
+    /// Returns the previously registered object with an identical key, if any.
+    /// Returns `Err(_)` without registering `value` if there already is such
+    /// an object and the overwrite policy is [`OverwritePolicy::Deny`].
+    ///
+    /// # Examples
+    ///
+    ///     use injector::{Container, Key, OverwritePolicy};
+    ///     use std::sync::{Arc, Mutex};
+    ///
+    ///     #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    ///     struct PluginKey(&'static str);
+    ///
+    ///     impl Key for PluginKey {
+    ///         type Value = u32;
+    ///     }
+    ///
+    ///     let mut container = Container::new();
+    ///
+    ///     // `Allow` (default)
+    ///     assert_eq!(container.try_register(PluginKey("foo"), 1), Ok(None));
+    ///     assert_eq!(container.try_register(PluginKey("foo"), 2), Ok(Some(1)));
+    ///
+    ///     // `Warn`
+    ///     let log = Arc::new(Mutex::new(Vec::new()));
+    ///     let log2 = Arc::clone(&log);
+    ///     container.set_overwrite_policy(OverwritePolicy::Warn(Box::new(
+    ///         move |_key_type, key| log2.lock().unwrap().push(key.to_owned()),
+    ///     )));
+    ///     assert_eq!(container.try_register(PluginKey("bar"), 3), Ok(None));
+    ///     assert_eq!(container.try_register(PluginKey("foo"), 4), Ok(Some(2)));
+    ///     assert_eq!(*log.lock().unwrap(), vec![r#"PluginKey("foo")"#.to_owned()]);
+    ///
+    ///     // `Deny`
+    ///     container.set_overwrite_policy(OverwritePolicy::Deny);
+    ///     let error = container.try_register(PluginKey("foo"), 5).unwrap_err();
+    ///     assert_eq!(error.key_debug, r#"PluginKey("foo")"#);
+    ///     assert_eq!(container.get(&PluginKey("foo")), Some(&4));
+    ///
+    pub fn try_register<K: Key>(
+        &mut self,
+        key: K,
+        value: K::Value,
+    ) -> Result<Option<K::Value>, AlreadyRegistered> {
+        let key_types = &mut self.key_types;
+        let value_bag = Self::value_bag_mut_inner::<K>(key_types);
+
+        if value_bag.get(&key).is_some() {
+            match &self.overwrite_policy {
+                OverwritePolicy::Allow => {}
+                OverwritePolicy::Warn(f) => {
+                    f(type_name::<K>(), &format!("{:?}", key));
+                }
+                OverwritePolicy::Deny => {
+                    return Err(AlreadyRegistered {
+                        key_type: type_name::<K>(),
+                        key_debug: format!("{:?}", key),
+                    });
+                }
+            }
+        }
+
+        Ok(value_bag.insert(key, value).1)
+    }
+
+    /// Get the overwrite policy.
+    pub fn overwrite_policy(&self) -> &OverwritePolicy {
+        &self.overwrite_policy
+    }
+
+    /// Set the overwrite policy, which specifies the behavior of
+    /// [`Container::register`] on an existing key.
+    ///
+    /// [`Container::extend`] is not affected by the overwrite policy.
+    pub fn set_overwrite_policy(&mut self, policy: OverwritePolicy) {
+        self.overwrite_policy = policy;
+    }
+
+    /// Move all objects registered to `other` to `self`.
+    ///
+    /// `policy` determines which object prevails when both containers have
+    /// objects associated with an identical key.
+    ///
+    /// The teardown functions of `other` are moved to `self` as well. They are
+    /// called before those of `self` when `self` is dropped. So are the
+    /// records of the modules installed to `other`. If both containers have
+    /// an instance of the same singleton, the teardown function of the
+    /// discarded instance is called immediately.
+    ///
+    /// # Examples
+    ///
+    ///     use injector::{Container, ConflictPolicy, Key};
+    ///
+    ///     #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    ///     struct PluginKey(&'static str);
+    ///
+    ///     impl Key for PluginKey {
+    ///         type Value = u32;
+    ///     }
+    ///
+    ///     let mut container = Container::new();
+    ///     container.register(PluginKey("foo"), 1);
+    ///     container.register(PluginKey("bar"), 2);
+    ///
+    ///     let mut plugin = Container::new();
+    ///     plugin.register(PluginKey("bar"), 3);
+    ///     plugin.register(PluginKey("baz"), 4);
+    ///
+    ///     container.extend(plugin, ConflictPolicy::SelfWins);
+    ///
+    ///     assert_eq!(container.get(&PluginKey("foo")), Some(&1));
+    ///     assert_eq!(container.get(&PluginKey("bar")), Some(&2));
+    ///     assert_eq!(container.get(&PluginKey("baz")), Some(&4));
+    ///
+    ///     let mut plugin = Container::new();
+    ///     plugin.register(PluginKey("bar"), 5);
+    ///
+    ///     container.extend(plugin, ConflictPolicy::OtherWins);
+    ///
+    ///     assert_eq!(container.get(&PluginKey("bar")), Some(&5));
+    ///
+    pub fn extend(&mut self, mut other: Container, policy: ConflictPolicy) {
+        use std::collections::hash_map::Entry;
+
+        // Tear down the singleton instances to be discarded
+        match policy {
+            ConflictPolicy::SelfWins => Self::teardown_conflicting(&mut other, self),
+            ConflictPolicy::OtherWins => Self::teardown_conflicting(self, &other),
+        }
+
+        for (type_id, value_bag) in replace(&mut other.key_types, HashMap::new()) {
+            match self.key_types.entry(type_id) {
+                Entry::Vacant(e) => {
+                    e.insert(value_bag);
+                }
+                Entry::Occupied(mut e) => {
+                    e.get_mut().merge(value_bag, policy);
+                }
+            }
+        }
+
+        self.teardowns.extend(other.teardowns.drain(..));
+        self.installed_modules.extend(other.installed_modules.drain());
+    }
+
+    /// Call and remove the teardown functions of `target` associated with
+    /// singleton instances that are also present in `other`.
+    fn teardown_conflicting(target: &mut Container, other: &Container) {
+        let (mut conflicting, kept) = replace(&mut target.teardowns, Vec::new())
+            .into_iter()
+            .partition::<Vec<_>, _>(|entry| {
+                (entry.has_instance)(target) && (entry.has_instance)(other)
+            });
+        target.teardowns = kept;
+
+        while let Some(entry) = conflicting.pop() {
+            (entry.teardown)(target);
+        }
+    }
 
-        let key_type_map: &mut ValueBag<K, K::Value> = key_type_map_entry
+    /// Get the given key's corresponding entry in the container for in-place
+    /// manipulation.
+    ///
+    /// # Examples
+    ///
+    ///     use injector::{Container, Key};
+    ///
+    ///     #[derive(Debug, PartialEq, Eq, Hash, Clone)]
+    ///     struct PluginListKey(&'static str);
+    ///
+    ///     impl Key for PluginListKey {
+    ///         type Value = Vec<u32>;
+    ///     }
+    ///
+    ///     let mut container = Container::new();
+    ///
+    ///     // Vacant
+    ///     container.entry(PluginListKey("foo")).or_default().push(1);
+    ///     assert_eq!(container.get(&PluginListKey("foo")), Some(&vec![1]));
+    ///
+    ///     // Occupied (the only entry of the key type)
+    ///     container.entry(PluginListKey("foo")).or_default().push(2);
+    ///     assert_eq!(container.get(&PluginListKey("foo")), Some(&vec![1, 2]));
+    ///
+    ///     // Occupied (one of multiple entries of the key type)
+    ///     container.entry(PluginListKey("bar")).or_insert_with(|| vec![3]);
+    ///     container
+    ///         .entry(PluginListKey("foo"))
+    ///         .and_modify(|v| v.push(4))
+    ///         .or_insert_with(Vec::new);
+    ///     assert_eq!(container.get(&PluginListKey("foo")), Some(&vec![1, 2, 4]));
+    ///     assert_eq!(container.get(&PluginListKey("bar")), Some(&vec![3]));
+    ///
+    pub fn entry<K: Key>(&mut self, key: K) -> Entry<'_, K> {
+        Entry::new(self.value_bag_mut::<K>(), key)
+    }
+
+    /// Get the `ValueBag` for a key type `K`, creating one if it does not exist
+    /// yet.
+    fn value_bag_mut<K: Key>(&mut self) -> &mut ValueBag<K, K::Value> {
+        Self::value_bag_mut_inner::<K>(&mut self.key_types)
+    }
+
+    /// `value_bag_mut` that only borrows `key_types`.
+    fn value_bag_mut_inner<K: Key>(
+        key_types: &mut HashMap<TypeId, Box<dyn ValueBagTrait>>,
+    ) -> &mut ValueBag<K, K::Value> {
+        key_types
+            .entry(TypeId::of::<K>())
             .or_insert_with(|| {
                 let key_type_map: ValueBag<K, K::Value> = ValueBag::new();
                 Box::new(key_type_map)
-            }).as_any_mut()
+            })
+            .as_any_mut()
             .downcast_mut()
-            .unwrap();
-
-        key_type_map.insert(key, value).1
+            .unwrap()
     }
 }
 
+/// Get the name of a type, used in diagnostic messages.
+pub(crate) fn type_name<T: ?Sized>() -> &'static str {
+    unsafe { std::intrinsics::type_name::<T>() }
+}
+
 enum ValueBag<K: Eq + Hash, V> {
     Empty,
     Singleton(K, V),
@@ -417,6 +921,11 @@ enum ValueBag<K: Eq + Hash, V> {
 trait ValueBagTrait: fmt::Debug + Send + Sync {
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+
+    /// Move all entries from `other` to `self`. `other` must have the same
+    /// type as `self`.
+    fn merge(&mut self, other: Box<dyn ValueBagTrait>, policy: ConflictPolicy);
 }
 
 impl<K: Eq + Hash, V> ValueBagTrait for ValueBag<K, V>
@@ -430,6 +939,26 @@ where
     fn as_any_mut(&mut self) -> &mut dyn Any {
         self
     }
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn merge(&mut self, other: Box<dyn ValueBagTrait>, policy: ConflictPolicy) {
+        let other: Box<Self> = other.into_any().downcast().unwrap();
+
+        for (key, value) in other.into_entries() {
+            match policy {
+                ConflictPolicy::SelfWins => {
+                    if self.get(&key).is_none() {
+                        self.insert(key, value);
+                    }
+                }
+                ConflictPolicy::OtherWins => {
+                    self.insert(key, value);
+                }
+            }
+        }
+    }
 }
 
 // Make `ValueBag` look as if it were a mere `HashMap`
@@ -501,6 +1030,45 @@ impl<K: Eq + Hash, V> ValueBag<K, V> {
         }
     }
 
+    fn remove(&mut self, key: &K) -> Option<(K, V)> {
+        use self::ValueBag::*;
+
+        match self {
+            Empty => None,
+            Singleton(k, _) => if k == key {
+                match replace(self, Empty) {
+                    Singleton(k, v) => Some((k, v)),
+                    _ => unreachable!(),
+                }
+            } else {
+                None
+            },
+            Generic(map) => map.remove_entry(key),
+        }
+    }
+
+    fn into_entries(self) -> impl Iterator<Item = (K, V)> {
+        use self::ValueBag::*;
+
+        let (singleton, map) = match self {
+            Empty => (None, None),
+            Singleton(k, v) => (Some((k, v)), None),
+            Generic(map) => (None, Some(map.into_iter())),
+        };
+        singleton.into_iter().chain(map.into_iter().flatten())
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        use self::ValueBag::*;
+
+        let (singleton, map) = match self {
+            Empty => (None, None),
+            Singleton(k, v) => (Some((k, v)), None),
+            Generic(map) => (None, Some(map.iter())),
+        };
+        singleton.into_iter().chain(map.into_iter().flatten())
+    }
+
     fn get_mut(&mut self, key: &K) -> Option<&mut V> {
         use self::ValueBag::*;
 
@@ -0,0 +1,236 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use std::{
+    any::TypeId,
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use crate::{type_name, Container};
+
+/// Identifies a [`Module`] in a [`Container`].
+///
+/// Two `ModuleId`s are equal iff they were created from the same type.
+#[derive(Clone, Copy)]
+pub struct ModuleId {
+    type_id: TypeId,
+    name: &'static str,
+}
+
+impl ModuleId {
+    /// Get the `ModuleId` for a specified type.
+    pub fn of<T: ?Sized + 'static>() -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            name: type_name::<T>(),
+        }
+    }
+
+    /// Get the name of the type this `ModuleId` was created from.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl fmt::Debug for ModuleId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ModuleId").field(&self.name).finish()
+    }
+}
+
+impl PartialEq for ModuleId {
+    fn eq(&self, other: &Self) -> bool {
+        self.type_id == other.type_id
+    }
+}
+
+impl Eq for ModuleId {}
+
+impl Hash for ModuleId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.type_id.hash(state);
+    }
+}
+
+/// A bundle of objects and factories to be registered to a [`Container`] by
+/// [`Container::install`].
+///
+/// A module is installed at most once per container, so subsystems can
+/// install the modules they depend on without worrying about duplicate
+/// registrations.
+///
+/// # Examples
+///
+///     use injector::{Container, FactoryExt, Module};
+///
+///     #[derive(Debug)]
+///     struct Renderer;
+///
+///     struct RendererModule;
+///
+///     impl Module for RendererModule {
+///         fn install(self, container: &mut Container) {
+///             container.register_singleton_factory(|_: &mut Container| Renderer);
+///         }
+///     }
+///
+///     let mut container = Container::new();
+///     assert!(container.install(RendererModule));
+///
+///     // The second call is a no-op
+///     assert!(!container.install(RendererModule));
+///
+///     container.assert_installed::<RendererModule>();
+///
+pub trait Module: Sized + 'static {
+    /// Get the `ModuleId` identifying this module.
+    ///
+    /// Defaults to `ModuleId::of::<Self>()`. [`Container::assert_installed`]
+    /// assumes the default implementation.
+    fn id(&self) -> ModuleId {
+        ModuleId::of::<Self>()
+    }
+
+    /// Register the contents of this module to `container`.
+    ///
+    /// This method is called by [`Container::install`]. Call
+    /// [`Container::assert_installed`] here to declare dependencies on other
+    /// modules.
+    fn install(self, container: &mut Container);
+}
+
+/// A module type or a tuple of module types, used by
+/// [`Container::assert_installed`].
+pub trait ModuleList {
+    /// Get the `ModuleId`s of the modules.
+    fn module_ids() -> Vec<ModuleId>;
+}
+
+impl<M: Module> ModuleList for M {
+    fn module_ids() -> Vec<ModuleId> {
+        vec![ModuleId::of::<M>()]
+    }
+}
+
+macro_rules! impl_module_list_for_tuple {
+    ($($t:ident),*) => {
+        impl<$($t: Module),*> ModuleList for ($($t,)*) {
+            fn module_ids() -> Vec<ModuleId> {
+                vec![$(ModuleId::of::<$t>()),*]
+            }
+        }
+    };
+}
+
+impl_module_list_for_tuple!(A);
+impl_module_list_for_tuple!(A, B);
+impl_module_list_for_tuple!(A, B, C);
+impl_module_list_for_tuple!(A, B, C, D);
+impl_module_list_for_tuple!(A, B, C, D, E);
+impl_module_list_for_tuple!(A, B, C, D, E, F);
+impl_module_list_for_tuple!(A, B, C, D, E, F, G);
+impl_module_list_for_tuple!(A, B, C, D, E, F, G, H);
+
+impl Container {
+    /// Install a module by calling [`Module::install`].
+    ///
+    /// Returns `true` if the module was installed by this call. Returns
+    /// `false` without doing anything if a module with an identical
+    /// [`ModuleId`] is already installed.
+    pub fn install<M: Module>(&mut self, module: M) -> bool {
+        let id = module.id();
+
+        // Mark the module as installed first so that a cyclic dependency does
+        // not cause an infinite recursion
+        if !self.installed_modules.insert(id) {
+            return false;
+        }
+
+        let guard = InstallingGuard::new(self, id);
+        module.install(&mut *guard.0);
+        drop(guard);
+
+        true
+    }
+
+    /// Return `true` if a module with a specified `ModuleId` is installed.
+    pub fn is_installed(&self, id: &ModuleId) -> bool {
+        self.installed_modules.contains(id)
+    }
+
+    /// Assert that the specified modules are installed.
+    ///
+    /// `M` is a module type or a tuple of module types. This method only
+    /// recognizes modules using the default implementation of [`Module::id`].
+    ///
+    /// # Panics
+    ///
+    /// Panics with a message listing the missing modules if any of them are
+    /// not installed.
+    ///
+    /// # Examples
+    ///
+    ///     use injector::{Container, Module};
+    ///
+    ///     struct AudioModule;
+    ///
+    ///     impl Module for AudioModule {
+    ///         fn install(self, _: &mut Container) {}
+    ///     }
+    ///
+    ///     struct GameModule;
+    ///
+    ///     impl Module for GameModule {
+    ///         fn install(self, container: &mut Container) {
+    ///             // `GameModule` depends on `AudioModule`
+    ///             container.assert_installed::<AudioModule>();
+    ///         }
+    ///     }
+    ///
+    ///     let mut container = Container::new();
+    ///     container.install(AudioModule);
+    ///     container.install(GameModule);
+    ///
+    pub fn assert_installed<M: ModuleList>(&self) {
+        let missing: Vec<_> = M::module_ids()
+            .into_iter()
+            .filter(|id| !self.is_installed(id))
+            .map(|id| id.name())
+            .collect();
+
+        if missing.is_empty() {
+            return;
+        }
+
+        let missing = missing.join(", ");
+        if let Some(requiring) = self.installing_modules.last() {
+            panic!(
+                "module {} requires the following modules that are not installed: {}",
+                requiring.name(),
+                missing
+            );
+        } else {
+            panic!("the following modules are not installed: {}", missing);
+        }
+    }
+}
+
+/// Pushes a `ModuleId` to `Container::installing_modules` and pops it on drop,
+/// so that the stack stays consistent even if `Module::install` panics.
+struct InstallingGuard<'a>(&'a mut Container);
+
+impl<'a> InstallingGuard<'a> {
+    fn new(container: &'a mut Container, id: ModuleId) -> Self {
+        container.installing_modules.push(id);
+        InstallingGuard(container)
+    }
+}
+
+impl Drop for InstallingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.installing_modules.pop();
+    }
+}
@@ -0,0 +1,117 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{BuildError, Container, FactoryExt, Key};
+
+/// A [`Container`] that can be shared between threads.
+///
+/// `SharedContainer` wraps a `Container` with `Arc<Mutex<_>>`. Cloning a
+/// `SharedContainer` produces another handle to the same `Container`.
+///
+/// Since the lock cannot be held across the lifetime of a returned reference,
+/// the accessor methods of `SharedContainer` return *clones* of the stored
+/// objects, and therefore require the object types to implement `Clone`. Use
+/// a cheaply clonable type such as `Arc<T>` for objects accessed this way.
+///
+/// Every method call locks the whole container for its duration. This
+/// includes the invocation of factories, so a slow factory blocks all other
+/// threads accessing the container. On the flip side, this guarantees that a
+/// factory is called only once even if multiple threads request the same
+/// object simultaneously.
+///
+/// # Examples
+///
+///     use injector::{Container, FactoryExt, SharedContainer};
+///     use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+///     use std::thread;
+///
+///     #[derive(Debug)]
+///     struct MyService;
+///
+///     static NUM_BUILDS: AtomicUsize = AtomicUsize::new(0);
+///
+///     let mut container = Container::new();
+///     container.register_singleton_factory(|_| {
+///         NUM_BUILDS.fetch_add(1, Ordering::Relaxed);
+///         Arc::new(MyService)
+///     });
+///
+///     let container = SharedContainer::new(container);
+///
+///     let threads: Vec<_> = (0..8)
+///         .map(|_| {
+///             let container = container.clone();
+///             thread::spawn(move || {
+///                 container.get_singleton_or_build::<Arc<MyService>>().unwrap()
+///             })
+///         })
+///         .collect();
+///
+///     let services: Vec<_> = threads.into_iter().map(|t| t.join().unwrap()).collect();
+///
+///     // The factory was called exactly once
+///     assert_eq!(NUM_BUILDS.load(Ordering::Relaxed), 1);
+///     assert!(services.iter().all(|s| Arc::ptr_eq(s, &services[0])));
+///
+#[derive(Debug, Clone, Default)]
+pub struct SharedContainer(Arc<Mutex<Container>>);
+
+impl SharedContainer {
+    /// Construct a `SharedContainer` by wrapping a given `Container`.
+    pub fn new(container: Container) -> Self {
+        SharedContainer(Arc::new(Mutex::new(container)))
+    }
+
+    /// Lock the container and get a mutable access to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while holding the lock.
+    pub fn lock(&self) -> MutexGuard<'_, Container> {
+        self.0.lock().expect("the container is poisoned")
+    }
+
+    /// Get a clone of an object associated with a specified `key`.
+    ///
+    /// Returns `None` if there is not such an object.
+    pub fn get<K: Key>(&self, key: &K) -> Option<K::Value>
+    where
+        K::Value: Clone,
+    {
+        self.lock().get(key).cloned()
+    }
+
+    /// Get a clone of an object associated with a specified `key`. Create one
+    /// using a factory object registered by
+    /// [`FactoryExt::register_factory`]`<K>` if there is not such an object.
+    pub fn get_or_build<K: Key>(&self, key: &K) -> Result<K::Value, BuildError>
+    where
+        K::Value: Clone,
+    {
+        self.lock().get_or_build(key).map(|x| x.clone())
+    }
+
+    /// Get a clone of an instance of `T`. Create one using a factory object
+    /// registered by [`FactoryExt::register_singleton_factory`]`<T>` if there
+    /// is not such an object.
+    ///
+    /// See [`SharedContainer`] for an example.
+    pub fn get_singleton_or_build<T: 'static + Send + Sync + Debug + Clone>(
+        &self,
+    ) -> Result<T, BuildError> {
+        self.lock().get_singleton_or_build::<T>().map(|x| x.clone())
+    }
+}
+
+impl From<Container> for SharedContainer {
+    fn from(x: Container) -> Self {
+        Self::new(x)
+    }
+}
@@ -5,9 +5,13 @@
 //
 use std::{fmt::Debug, marker::PhantomData};
 
-use crate::{Container, Key};
+use crate::{Container, Entry, Key};
 
-struct SingletonKey<T>(PhantomData<fn(T)>);
+/// The `Key` type used by [`SingletonExt`]. Every instance of `SingletonKey<T>`
+/// is equal to each other.
+///
+/// Use [`singleton_key`] to construct a value of this type.
+pub struct SingletonKey<T>(PhantomData<fn(T)>);
 
 impl<T> std::fmt::Debug for SingletonKey<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -46,7 +50,7 @@ impl<T: 'static + Send + Sync + Debug> Key for SingletonKey<T> {
 /// Get a `Key` object for a specified value type.
 ///
 /// [`SingletonExt`] uses this function to supply a `Key`.
-pub fn singleton_key<T: 'static + Send + Sync + Debug>() -> impl Key<Value = T> {
+pub fn singleton_key<T: 'static + Send + Sync + Debug>() -> SingletonKey<T> {
     SingletonKey::<T>::default()
 }
 
@@ -107,6 +111,21 @@ pub trait SingletonExt {
     ///
     /// Returns the previously registered object with an identical type, if any.
     fn register_singleton<T: 'static + Send + Sync + Debug>(&mut self, value: T) -> Option<T>;
+
+    /// Get the entry for an instance of `T` for in-place manipulation.
+    ///
+    /// # Examples
+    ///
+    ///     use injector::{Container, SingletonExt};
+    ///
+    ///     let mut container = Container::new();
+    ///
+    ///     container.singleton_entry::<Vec<u32>>().or_default().push(1);
+    ///     container.singleton_entry::<Vec<u32>>().or_default().push(2);
+    ///
+    ///     assert_eq!(container.get_singleton::<Vec<u32>>(), Some(&vec![1, 2]));
+    ///
+    fn singleton_entry<T: 'static + Send + Sync + Debug>(&mut self) -> Entry<'_, SingletonKey<T>>;
 }
 
 impl SingletonExt for Container {
@@ -135,4 +154,8 @@ impl SingletonExt for Container {
     fn register_singleton<T: 'static + Send + Sync + Debug>(&mut self, value: T) -> Option<T> {
         self.register(singleton_key::<T>(), value)
     }
+
+    fn singleton_entry<T: 'static + Send + Sync + Debug>(&mut self) -> Entry<'_, SingletonKey<T>> {
+        self.entry(singleton_key::<T>())
+    }
 }
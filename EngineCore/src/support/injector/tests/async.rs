@@ -0,0 +1,108 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+#![cfg(feature = "async")]
+#![feature(futures_api)]
+use futures::{
+    channel::oneshot,
+    executor::{block_on, ThreadPool},
+    future,
+    prelude::*,
+};
+use injector::{AsyncFactoryExt, BuildError, Container, FactoryExt};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+#[derive(Debug, PartialEq)]
+struct Service(u32);
+
+#[derive(Debug)]
+struct Config(u32);
+
+#[test]
+fn no_factory() {
+    let pool = ThreadPool::new().unwrap();
+    let mut container = Container::new();
+    assert_eq!(
+        container
+            .get_singleton_or_build_async::<Service>(&mut &pool)
+            .err(),
+        Some(BuildError::NoFactory)
+    );
+}
+
+#[test]
+fn has_factory() {
+    let mut container = Container::new();
+    assert!(!container.has_singleton_async_factory::<Service>());
+
+    container.register_singleton_async_factory(|_| future::ready(Service(1)));
+    assert!(container.has_singleton_async_factory::<Service>());
+
+    // Synchronous and asynchronous factories are distinct
+    assert!(!container.has_singleton_factory::<Service>());
+}
+
+#[test]
+fn dependency() {
+    let pool = ThreadPool::new().unwrap();
+    let mut container = Container::new();
+
+    container.register_singleton_factory(|_| Config(42));
+    container.register_singleton_async_factory(|container| {
+        let value = container.get_singleton_or_build::<Config>().unwrap().0;
+        future::lazy(move |_| Service(value))
+    });
+
+    let service = container
+        .get_singleton_or_build_async::<Service>(&mut &pool)
+        .unwrap();
+    assert_eq!(block_on(service.as_future()), &Service(42));
+}
+
+#[test]
+fn concurrent_resolutions() {
+    let pool = ThreadPool::new().unwrap();
+    let mut container = Container::new();
+
+    let (send, recv) = oneshot::channel();
+    let recv = Mutex::new(Some(recv));
+    let num_builds = Arc::new(AtomicUsize::new(0));
+
+    let num_builds2 = Arc::clone(&num_builds);
+    container.register_singleton_async_factory(move |_| {
+        num_builds2.fetch_add(1, Ordering::SeqCst);
+        let recv = recv.lock().unwrap().take().expect("called twice");
+        recv.map(|x| Service(x.unwrap()))
+    });
+
+    // Two concurrent resolutions share a single construction
+    let service1 = container
+        .get_singleton_or_build_async::<Service>(&mut &pool)
+        .unwrap();
+    let service2 = container
+        .get_singleton_or_build_async::<Service>(&mut &pool)
+        .unwrap();
+    assert_eq!(num_builds.load(Ordering::SeqCst), 1);
+
+    let handles: Vec<_> = vec![service1, service2]
+        .into_iter()
+        .map(|service| thread::spawn(move || block_on(service.as_future()).0))
+        .collect();
+
+    thread::sleep(Duration::from_millis(50));
+    send.send(42).unwrap();
+
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), 42);
+    }
+    assert_eq!(num_builds.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,50 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use injector::{Container, FactoryExt, Key, SingletonExt};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct PluginKey(&'static str);
+
+impl Key for PluginKey {
+    type Value = u32;
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct OtherKey;
+
+impl Key for OtherKey {
+    type Value = u32;
+}
+
+#[test]
+fn present_and_absent() {
+    let mut container = Container::new();
+
+    // The key type was never used
+    assert!(!container.contains(&PluginKey("foo")));
+
+    container.register(PluginKey("foo"), 1);
+    assert!(container.contains(&PluginKey("foo")));
+    assert!(!container.contains(&PluginKey("bar")));
+    assert!(!container.contains(&OtherKey));
+
+    container.register(PluginKey("bar"), 2);
+    assert!(container.contains(&PluginKey("foo")));
+    assert!(container.contains(&PluginKey("bar")));
+    assert!(!container.contains(&PluginKey("baz")));
+}
+
+#[test]
+fn does_not_build() {
+    let mut container = Container::new();
+    container.register_singleton_factory(|_: &mut Container| -> u32 {
+        panic!("the factory must not be called")
+    });
+
+    assert!(container.has_singleton_factory::<u32>());
+    assert!(!container.contains(&injector::singleton_key::<u32>()));
+    assert!(container.get_singleton::<u32>().is_none());
+}
@@ -0,0 +1,60 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use injector::{Container, Key};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+#[derive(Key)]
+#[value(u32)]
+struct CounterKey;
+
+#[derive(Key)]
+#[value(Arc<dyn Service>)]
+struct ServiceKey;
+
+trait Service: std::fmt::Debug + Send + Sync {
+    fn name(&self) -> &str;
+}
+
+#[derive(Debug)]
+struct ServiceImpl;
+
+impl Service for ServiceImpl {
+    fn name(&self) -> &str {
+        "impl"
+    }
+}
+
+#[test]
+fn supertraits() {
+    assert_eq!(CounterKey, CounterKey.clone());
+    assert_eq!(format!("{:?}", CounterKey), "CounterKey");
+
+    let hash = |key: &CounterKey| {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    };
+    assert_eq!(hash(&CounterKey), hash(&CounterKey.clone()));
+}
+
+#[test]
+fn register_get() {
+    let mut container = Container::new();
+    container.register(CounterKey, 42);
+    container.register(ServiceKey, Arc::new(ServiceImpl));
+
+    assert_eq!(container.get(&CounterKey), Some(&42));
+    assert_eq!(container.get(&ServiceKey).unwrap().name(), "impl");
+}
+
+#[test]
+fn get_or_create_with() {
+    let mut container = Container::new();
+    assert_eq!(*container.get_or_create_with(&CounterKey, |_, _| 1), 1);
+    assert_eq!(*container.get_or_create_with(&CounterKey, |_, _| 2), 1);
+}
@@ -0,0 +1,88 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use injector::{Container, Entry, Key};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct PluginKey(&'static str);
+
+impl Key for PluginKey {
+    type Value = u32;
+}
+
+#[test]
+fn vacant() {
+    let mut container = Container::new();
+
+    // The key type was never used
+    match container.entry(PluginKey("foo")) {
+        Entry::Vacant(e) => {
+            assert_eq!(e.key(), &PluginKey("foo"));
+            assert_eq!(*e.insert(1), 1);
+        }
+        Entry::Occupied(e) => panic!("{:?}", e),
+    }
+    assert_eq!(container.get(&PluginKey("foo")), Some(&1));
+
+    // The key type has one entry
+    match container.entry(PluginKey("bar")) {
+        Entry::Vacant(e) => assert_eq!(e.into_key(), PluginKey("bar")),
+        Entry::Occupied(e) => panic!("{:?}", e),
+    }
+    assert_eq!(*container.entry(PluginKey("bar")).or_insert(2), 2);
+
+    // The key type has multiple entries
+    assert_eq!(*container.entry(PluginKey("baz")).or_default(), 0);
+
+    assert_eq!(container.get(&PluginKey("foo")), Some(&1));
+    assert_eq!(container.get(&PluginKey("bar")), Some(&2));
+    assert_eq!(container.get(&PluginKey("baz")), Some(&0));
+}
+
+#[test]
+fn occupied_singleton() {
+    let mut container = Container::new();
+    container.register(PluginKey("foo"), 1);
+
+    match container.entry(PluginKey("foo")) {
+        Entry::Occupied(mut e) => {
+            assert_eq!(e.key(), &PluginKey("foo"));
+            assert_eq!(*e.get(), 1);
+            *e.get_mut() += 1;
+            assert_eq!(e.insert(3), 2);
+            assert_eq!(*e.into_mut(), 3);
+        }
+        Entry::Vacant(e) => panic!("{:?}", e),
+    }
+    assert_eq!(container.get(&PluginKey("foo")), Some(&3));
+
+    let value = container
+        .entry(PluginKey("foo"))
+        .and_modify(|x| *x += 1)
+        .or_insert_with(|| panic!("the entry must not be vacant"));
+    assert_eq!(*value, 4);
+}
+
+#[test]
+fn occupied_generic() {
+    let mut container = Container::new();
+    container.register(PluginKey("foo"), 1);
+    container.register(PluginKey("bar"), 2);
+
+    for &(name, value) in [("foo", 1), ("bar", 2)].iter() {
+        match container.entry(PluginKey(name)) {
+            Entry::Occupied(mut e) => {
+                assert_eq!(e.key(), &PluginKey(name));
+                assert_eq!(*e.get(), value);
+                assert_eq!(e.insert(value * 10), value);
+            }
+            Entry::Vacant(e) => panic!("{:?}", e),
+        }
+    }
+
+    *container.entry(PluginKey("foo")).or_insert(0) += 1;
+    assert_eq!(container.get(&PluginKey("foo")), Some(&11));
+    assert_eq!(container.get(&PluginKey("bar")), Some(&20));
+}
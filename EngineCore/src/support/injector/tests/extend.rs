@@ -0,0 +1,90 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use injector::{ConflictPolicy, Container, FactoryExt, SingletonExt};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug)]
+struct Service(&'static str);
+
+#[derive(Debug)]
+struct Other(&'static str);
+
+type Log = Arc<Mutex<Vec<&'static str>>>;
+
+/// Construct a `Container` with `Service(name)` and `Other(name)` created by
+/// factories whose teardown functions log the names of the torn down
+/// instances.
+fn new_container(log: &Log, name: &'static str, with_other: bool) -> Container {
+    let mut container = Container::new();
+
+    let log2 = Arc::clone(log);
+    container.register_singleton_with_teardown(
+        move |_| Service(name),
+        move |service: &mut Service| log2.lock().unwrap().push(service.0),
+    );
+    container.get_singleton_or_build::<Service>().unwrap();
+
+    if with_other {
+        let log2 = Arc::clone(log);
+        container.register_singleton_with_teardown(
+            move |_| Other(name),
+            move |other: &mut Other| log2.lock().unwrap().push(other.0),
+        );
+        container.get_singleton_or_build::<Other>().unwrap();
+    }
+
+    container
+}
+
+#[test]
+fn teardown_self_wins() {
+    let log = Log::default();
+    let mut container = new_container(&log, "self", false);
+    let other = new_container(&log, "other", true);
+
+    container.extend(other, ConflictPolicy::SelfWins);
+
+    // The discarded instance is torn down immediately
+    assert_eq!(*log.lock().unwrap(), ["other"]);
+    assert_eq!(container.get_singleton::<Service>().unwrap().0, "self");
+
+    log.lock().unwrap().clear();
+    drop(container);
+
+    // `Other("other")` was moved to `self`
+    assert_eq!(*log.lock().unwrap(), ["other", "self"]);
+}
+
+#[test]
+fn teardown_other_wins() {
+    let log = Log::default();
+    let mut container = new_container(&log, "self", false);
+    let other = new_container(&log, "other", true);
+
+    container.extend(other, ConflictPolicy::OtherWins);
+
+    // The discarded instance is torn down immediately
+    assert_eq!(*log.lock().unwrap(), ["self"]);
+    assert_eq!(container.get_singleton::<Service>().unwrap().0, "other");
+
+    log.lock().unwrap().clear();
+    drop(container);
+
+    assert_eq!(*log.lock().unwrap(), ["other", "other"]);
+}
+
+#[test]
+fn teardown_no_conflict() {
+    let log = Log::default();
+    let mut container = Container::new();
+    let other = new_container(&log, "other", true);
+
+    container.extend(other, ConflictPolicy::SelfWins);
+    assert!(log.lock().unwrap().is_empty());
+
+    drop(container);
+    assert_eq!(*log.lock().unwrap(), ["other", "other"]);
+}
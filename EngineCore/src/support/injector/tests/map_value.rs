@@ -0,0 +1,121 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use injector::{Container, Key};
+use std::sync::{Arc, Mutex};
+
+#[derive(Key)]
+#[value(Arc<dyn Service>)]
+struct ServiceKey;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+struct NamedServiceKey(&'static str);
+
+impl Key for NamedServiceKey {
+    type Value = Arc<dyn Service>;
+}
+
+trait Service: std::fmt::Debug + Send + Sync {
+    fn name(&self) -> String;
+}
+
+#[derive(Debug)]
+struct ServiceImpl;
+
+impl Service for ServiceImpl {
+    fn name(&self) -> String {
+        "impl".to_owned()
+    }
+}
+
+/// Decorates a `Service` with logging.
+#[derive(Debug)]
+struct LoggingService {
+    inner: Arc<dyn Service>,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Service for LoggingService {
+    fn name(&self) -> String {
+        let name = self.inner.name();
+        self.log.lock().unwrap().push(name.clone());
+        format!("logged {}", name)
+    }
+}
+
+#[test]
+fn decorate() {
+    let mut container = Container::new();
+    container.register(ServiceKey, Arc::new(ServiceImpl) as Arc<dyn Service>);
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let log2 = Arc::clone(&log);
+    assert!(container.map_value(&ServiceKey, move |inner| {
+        Arc::new(LoggingService { inner, log: log2 }) as Arc<dyn Service>
+    }));
+
+    let service = container.get(&ServiceKey).unwrap();
+    assert_eq!(service.name(), "logged impl");
+    assert_eq!(*log.lock().unwrap(), vec!["impl".to_owned()]);
+}
+
+#[test]
+fn absent() {
+    let mut container = Container::new();
+    assert!(!container.map_value(&ServiceKey, |_| unreachable!()));
+    assert!(container.get(&ServiceKey).is_none());
+
+    container.register(
+        NamedServiceKey("foo"),
+        Arc::new(ServiceImpl) as Arc<dyn Service>,
+    );
+    assert!(!container.map_value(&NamedServiceKey("bar"), |_| unreachable!()));
+    assert!(container.get(&NamedServiceKey("bar")).is_none());
+}
+
+#[test]
+fn multiple_keys() {
+    let mut container = Container::new();
+    container.register(
+        NamedServiceKey("foo"),
+        Arc::new(ServiceImpl) as Arc<dyn Service>,
+    );
+    container.register(
+        NamedServiceKey("bar"),
+        Arc::new(ServiceImpl) as Arc<dyn Service>,
+    );
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let log2 = Arc::clone(&log);
+    assert!(container.map_value(&NamedServiceKey("bar"), move |inner| {
+        Arc::new(LoggingService { inner, log: log2 }) as Arc<dyn Service>
+    }));
+
+    assert_eq!(
+        container.get(&NamedServiceKey("foo")).unwrap().name(),
+        "impl"
+    );
+    assert_eq!(
+        container.get(&NamedServiceKey("bar")).unwrap().name(),
+        "logged impl"
+    );
+    assert_eq!(container.iter_values::<NamedServiceKey>().count(), 2);
+}
+
+#[test]
+fn panicking_fn() {
+    let mut container = Container::new();
+    container.register(ServiceKey, Arc::new(ServiceImpl) as Arc<dyn Service>);
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        container.map_value(&ServiceKey, |_| panic!("expected panic"));
+    }));
+    assert!(result.is_err());
+
+    // The object is gone, but the container is still usable
+    assert!(container.get(&ServiceKey).is_none());
+    container.register(ServiceKey, Arc::new(ServiceImpl) as Arc<dyn Service>);
+    assert_eq!(container.get(&ServiceKey).unwrap().name(), "impl");
+}
@@ -0,0 +1,142 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use injector::{Container, FactoryExt, Module, ModuleId, OverwritePolicy};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+#[derive(Debug)]
+struct ServiceA;
+
+#[derive(Debug)]
+struct ServiceB(Arc<ServiceA>);
+
+struct ModuleA {
+    install_count: Arc<AtomicUsize>,
+}
+
+impl Module for ModuleA {
+    fn install(self, container: &mut Container) {
+        self.install_count.fetch_add(1, Ordering::Relaxed);
+        container.register_singleton_factory(|_: &mut Container| Arc::new(ServiceA));
+    }
+}
+
+struct ModuleB;
+
+impl Module for ModuleB {
+    fn install(self, container: &mut Container) {
+        container.assert_installed::<ModuleA>();
+        container.register_singleton_factory(|container: &mut Container| {
+            let a = Arc::clone(container.get_singleton_or_build::<Arc<ServiceA>>().unwrap());
+            ServiceB(a)
+        });
+    }
+}
+
+fn new_container() -> Container {
+    let mut container = Container::new();
+    // Catch duplicate registrations
+    container.set_overwrite_policy(OverwritePolicy::Deny);
+    container
+}
+
+#[test]
+fn dependency_satisfied() {
+    let mut container = new_container();
+    let install_count = Arc::new(AtomicUsize::new(0));
+
+    assert!(container.install(ModuleA {
+        install_count: Arc::clone(&install_count),
+    }));
+    assert!(container.install(ModuleB));
+
+    assert!(container.is_installed(&ModuleId::of::<ModuleA>()));
+    assert!(container.is_installed(&ModuleId::of::<ModuleB>()));
+    container.assert_installed::<(ModuleA, ModuleB)>();
+
+    assert!(container.get_singleton_or_build::<ServiceB>().is_ok());
+    assert_eq!(install_count.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+#[should_panic(expected = "requires the following modules that are not installed")]
+fn dependency_missing() {
+    let mut container = new_container();
+    container.install(ModuleB);
+}
+
+#[test]
+fn dependency_missing_message() {
+    let result = std::panic::catch_unwind(|| {
+        let mut container = new_container();
+        container.install(ModuleB);
+    });
+    let payload = result.unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(message.contains("ModuleB"), "{}", message);
+    assert!(message.contains("ModuleA"), "{}", message);
+}
+
+#[test]
+fn dependency_missing_unwind() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let mut container = new_container();
+    assert!(catch_unwind(AssertUnwindSafe(|| {
+        container.install(ModuleB);
+    }))
+    .is_err());
+
+    // `ModuleB` is not being installed anymore
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        container.assert_installed::<ModuleA>();
+    }));
+    let payload = result.unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(!message.contains("ModuleB"), "{}", message);
+}
+
+#[test]
+fn double_install() {
+    let mut container = new_container();
+    let install_count = Arc::new(AtomicUsize::new(0));
+
+    for &expected in &[true, false, false] {
+        let installed = container.install(ModuleA {
+            install_count: Arc::clone(&install_count),
+        });
+        assert_eq!(installed, expected);
+    }
+
+    assert_eq!(install_count.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+#[should_panic(expected = "the following modules are not installed")]
+fn assert_installed_outside_module() {
+    let container = Container::new();
+    container.assert_installed::<(ModuleA, ModuleB)>();
+}
+
+#[test]
+fn extend() {
+    let install_count = Arc::new(AtomicUsize::new(0));
+
+    let mut other = Container::new();
+    other.install(ModuleA {
+        install_count: Arc::clone(&install_count),
+    });
+
+    let mut container = Container::new();
+    container.extend(other, injector::ConflictPolicy::SelfWins);
+    container.assert_installed::<ModuleA>();
+    assert!(!container.install(ModuleA {
+        install_count: Arc::clone(&install_count),
+    }));
+    assert_eq!(install_count.load(Ordering::Relaxed), 1);
+}
@@ -18,10 +18,6 @@ use proc_macro::TokenStream;
 pub fn derive_iter_all_values(input: TokenStream) -> TokenStream {
     let ast: DeriveInput = syn::parse(input).unwrap();
 
-    if ast.generics.params.len() > 0 {
-        panic!("`derive(IterValues)` does not generics (yet)");
-    }
-
     let quote_tokens = match ast.data {
         Data::Enum(ref data) => gen_enum(&ast.ident, &ast, data),
         _ => panic!("`derive(IterValues)` may only be applied to enums (currently)"),
@@ -38,7 +34,9 @@ fn gen_enum(ident: &Ident, item: &DeriveInput, data: &DataEnum) -> Tokens {
         Fields::Unnamed(ref fields) => fields.unnamed.len() == 0,
     });
 
-    if fieldless {
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    if fieldless && item.generics.params.len() == 0 {
         // Use a static value list
         let var_exprs = data.variants.iter().map(|v| {
             let ref v_ident = v.ident;
@@ -191,18 +189,66 @@ fn gen_enum(ident: &Ident, item: &DeriveInput, data: &DataEnum) -> Tokens {
         },
     );
 
+    // An expression evaluating to the size hint of the values of each variant
+    let variant_size_hints: Vec<_> = variants_and_types
+        .iter()
+        .map(|&(_, ref value_tuple)| {
+            if value_tuple.is_none() {
+                // Field-less(-like)
+                return quote! { (1usize, Some(1usize)) };
+            }
+
+            quote! {
+                ::std::iter::Iterator::size_hint(
+                    &<#value_tuple as ::itervalues::IterValues>::iter_values())
+            }
+        })
+        .collect();
+
+    // `match` case for each state, evaluating to the size hint of the remaining
+    // values of the current variant and all subsequent variants
+    let size_hint_cases = variants_and_types.iter().enumerate().map(
+        |(i, &(variant, ref value_tuple))| {
+            let ref v_ident = variant.ident;
+            let rest = &variant_size_hints[i + 1..];
+
+            let (pattern, current) = if value_tuple.is_none() {
+                // Field-less(-like)
+                (
+                    quote! { #state_name::#v_ident },
+                    quote! { (1usize, Some(1usize)) },
+                )
+            } else {
+                (
+                    quote! { #state_name::#v_ident(ref it) },
+                    quote! { it.size_hint() },
+                )
+            };
+
+            quote! {
+                #pattern => {
+                    let hint = #current;
+                    #( let hint = add_size_hints(hint, #rest); )*
+                    hint
+                }
+            }
+        },
+    );
+
     let ref vis = item.vis;
     let ref start = state_initializers[0];
 
+    // The state type has the same generic parameters as `#ident` because it
+    // holds `T::Iterator` for each type parameter `T` used in the fields.
     quote! {
         #[doc(hidden)]
-        #vis enum #state_name {
+        #vis enum #state_name #impl_generics #where_clause {
             #(#state_variants,)*
             __IterValuesEnd
         }
 
-        impl ::std::iter::Iterator for #state_name {
-            type Item = #ident;
+        impl #impl_generics ::std::iter::Iterator for #state_name #ty_generics #where_clause {
+            type Item = #ident #ty_generics;
 
             fn next(&mut self) -> ::std::option::Option<Self::Item> {
                 loop {
@@ -226,10 +272,28 @@ fn gen_enum(ident: &Ident, item: &DeriveInput, data: &DataEnum) -> Tokens {
                     }
                 }
             }
+
+            fn size_hint(&self) -> (usize, ::std::option::Option<usize>) {
+                fn add_size_hints(
+                    (lower1, upper1): (usize, ::std::option::Option<usize>),
+                    (lower2, upper2): (usize, ::std::option::Option<usize>),
+                ) -> (usize, ::std::option::Option<usize>) {
+                    let upper = match (upper1, upper2) {
+                        (Some(x), Some(y)) => x.checked_add(y),
+                        _ => None,
+                    };
+                    (lower1.saturating_add(lower2), upper)
+                }
+
+                match *self {
+                    #(#size_hint_cases,)*
+                    #state_name::__IterValuesEnd => (0, Some(0)),
+                }
+            }
         }
 
-        impl ::itervalues::IterValues for #ident {
-            type Iterator = #state_name;
+        impl #impl_generics ::itervalues::IterValues for #ident #ty_generics #where_clause {
+            type Iterator = #state_name #ty_generics;
 
             fn iter_values() -> Self::Iterator {
                 #start
@@ -28,7 +28,11 @@
 //!
 //!     # }
 //!
+use std::cmp::Ordering;
 use std::iter::{self, ExactSizeIterator, Iterator};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::num::{NonZeroI8, NonZeroU16, NonZeroU8};
+use std::ops::RangeInclusive;
 use std::slice;
 
 /// Returns an iterator that enumerates all possible values of a type.
@@ -37,6 +41,43 @@ pub trait IterValues: Sized {
 
     /// Retrieve an iterator that enumerates all possible values of this type.
     fn iter_values() -> Self::Iterator;
+
+    /// Collect all possible values of this type into a `Vec`.
+    ///
+    /// The `Vec` is preallocated according to the iterator's `size_hint`,
+    /// which is exact for iterators implementing `ExactSizeIterator`.
+    fn to_vec() -> Vec<Self> {
+        Self::iter_values().collect()
+    }
+
+    /// Choose one of the possible values of this type deterministically based
+    /// on `seed`. Returns `None` if this type has no values.
+    ///
+    /// The value is chosen by taking `seed` modulo the number of the values.
+    /// This is intended for tests that need a representative value, and is
+    /// neither uniform nor unpredictable.
+    ///
+    /// The number of the values is obtained from the iterator's `size_hint`
+    /// if it's exact. Otherwise, this method enumerates all values to count
+    /// them.
+    ///
+    /// # Examples
+    ///
+    ///     use itervalues::IterValues;
+    ///
+    ///     assert_eq!(<bool>::random_sample(0), Some(false));
+    ///     assert_eq!(<bool>::random_sample(3), Some(true));
+    ///
+    fn random_sample(seed: u64) -> Option<Self> {
+        let count = match Self::iter_values().size_hint() {
+            (lower, Some(upper)) if lower == upper => lower,
+            _ => Self::iter_values().count(),
+        };
+        if count == 0 {
+            return None;
+        }
+        Self::iter_values().nth((seed % count as u64) as usize)
+    }
 }
 
 impl IterValues for () {
@@ -55,29 +96,166 @@ impl IterValues for bool {
     }
 }
 
+impl IterValues for Ordering {
+    type Iterator = iter::Cloned<slice::Iter<'static, Self>>;
+
+    fn iter_values() -> Self::Iterator {
+        [Ordering::Less, Ordering::Equal, Ordering::Greater]
+            .into_iter()
+            .cloned()
+    }
+}
+
+macro_rules! impl_nonzero {
+    (
+        $(#[$m:meta])*
+        $iter:ident, $nz:ident, $int:ty, $start:expr, $end:expr, |$x:ident| $map:expr
+    ) => {
+        impl IterValues for $nz {
+            type Iterator = $iter;
+
+            fn iter_values() -> Self::Iterator {
+                $iter($start..=$end)
+            }
+        }
+
+        $(#[$m])*
+        #[derive(Debug, Clone)]
+        pub struct $iter(RangeInclusive<$int>);
+
+        impl Iterator for $iter {
+            type Item = $nz;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.0.next().map(|$x| {
+                    let value = $map;
+                    debug_assert_ne!(value, 0);
+                    // The range never includes a value mapped to zero
+                    unsafe { $nz::new_unchecked(value) }
+                })
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.0.size_hint()
+            }
+        }
+
+        impl ExactSizeIterator for $iter {}
+    };
+}
+
+impl_nonzero! {
+    /// An iterator that yields every value of `NonZeroU8` in the ascending
+    /// order.
+    NonZeroU8IterValues, NonZeroU8, u8, 1, u8::max_value(), |x| x
+}
+
+impl_nonzero! {
+    /// An iterator that yields every value of `NonZeroU16` in the ascending
+    /// order.
+    NonZeroU16IterValues, NonZeroU16, u16, 1, u16::max_value(), |x| x
+}
+
+impl_nonzero! {
+    /// An iterator that yields every value of `NonZeroI8` in the ascending
+    /// order.
+    ///
+    /// This iterates over `MIN..=MAX - 1` and skips zero by incrementing
+    /// non-negative values by one.
+    NonZeroI8IterValues, NonZeroI8, i8, i8::min_value(), i8::max_value() - 1,
+    |x| if x >= 0 { x + 1 } else { x }
+}
+
+/// Returns an iterator that pairs the values of two types at the same index.
+///
+/// This is equivalent to `A::iter_values().zip(B::iter_values())`. Note that
+/// this is different from `<(A, B)>::iter_values()`, which enumerates the
+/// Cartesian product of `A` and `B`.
+///
+/// # Examples
+///
+///     use itervalues::zip_iter_values;
+///
+///     let values: Vec<(bool, Option<bool>)> = zip_iter_values().collect();
+///     assert_eq!(values.as_slice(), &[(false, None), (true, Some(false))]);
+///
+pub fn zip_iter_values<A: IterValues, B: IterValues>() -> impl Iterator<Item = (A, B)> {
+    A::iter_values().zip(B::iter_values())
+}
+
+static IP_ADDRS: [IpAddr; 6] = [
+    IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
+    IpAddr::V4(Ipv4Addr::new(255, 255, 255, 255)),
+    IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)),
+    IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)),
+    IpAddr::V6(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1)),
+    IpAddr::V6(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1)),
+];
+
+/// Enumerates a fixed, curated set of representative IP addresses.
+///
+/// **This is a testing utility, not an exhaustive enumeration.** The produced
+/// values are, in order: the IPv4 loopback address (`127.0.0.1`), the IPv4
+/// broadcast address (`255.255.255.255`), a private IPv4 address
+/// (`192.168.0.1`), the IPv6 loopback address (`::1`), a link-local IPv6
+/// address (`fe80::1`), and an IPv6 multicast address (`ff02::1`).
+impl IterValues for IpAddr {
+    type Iterator = iter::Cloned<slice::Iter<'static, Self>>;
+
+    fn iter_values() -> Self::Iterator {
+        IP_ADDRS.iter().cloned()
+    }
+}
+
 impl<T: IterValues> IterValues for Option<T> {
     type Iterator = OptionIterValues<T>;
 
     fn iter_values() -> Self::Iterator {
-        OptionIterValues(None)
+        OptionIterValues {
+            iter: T::iter_values(),
+            none_yielded: false,
+        }
     }
 }
 
-pub struct OptionIterValues<T: IterValues>(Option<T::Iterator>);
+/// An iterator that yields `None` followed by each value of `T` wrapped with
+/// `Some`.
+pub struct OptionIterValues<T: IterValues> {
+    iter: T::Iterator,
+    none_yielded: bool,
+}
 
 impl<T: IterValues> Iterator for OptionIterValues<T> {
     type Item = Option<T>;
 
     fn next(&mut self) -> Option<Option<T>> {
-        if self.0.is_none() {
-            self.0 = Some(T::iter_values());
+        if !self.none_yielded {
+            self.none_yielded = true;
             Some(None)
         } else {
-            self.0.as_mut().unwrap().next().map(Some)
+            self.iter.next().map(Some)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        if self.none_yielded {
+            (lower, upper)
+        } else {
+            (
+                lower.saturating_add(1),
+                upper.and_then(|x| x.checked_add(1)),
+            )
         }
     }
 }
 
+impl<T: IterValues> ExactSizeIterator for OptionIterValues<T>
+where
+    T::Iterator: ExactSizeIterator,
+{
+}
+
 impl<T1: IterValues> IterValues for (T1,) {
     type Iterator = WrapTuple<T1::Iterator>;
 
@@ -113,31 +291,42 @@ impl<T1: IterValues + Clone, T2: IterValues> IterValues for (T1, T2) {
 
     fn iter_values() -> Self::Iterator {
         let mut iter1 = T1::iter_values();
-        let value1 = iter1.next();
         let iter2 = T2::iter_values();
-        PairIterValues {
-            count: match (iter1.size_hint(), iter2.size_hint()) {
-                ((_, Some(len1)), (_, Some(len2))) => len1.checked_mul(len2),
+        let (lower1, upper1) = iter1.size_hint();
+        let (lower2, upper2) = iter2.size_hint();
+        let size_hint = (
+            lower1.saturating_mul(lower2),
+            match (upper1, upper2) {
+                (Some(upper1), Some(upper2)) => upper1.checked_mul(upper2),
                 _ => None,
             },
+        );
+        let value1 = iter1.next();
+        PairIterValues {
             iter1,
             value1,
             iter2,
+            size_hint,
         }
     }
 }
 
+/// An iterator that enumerates the Cartesian product of the values of two
+/// types.
+///
+/// This implements `ExactSizeIterator` if both of the inner iterators do.
+/// Note that `len` panics if the number of the values does not fit in
+/// `usize`.
 pub struct PairIterValues<T1: IterValues + Clone, T2: IterValues> {
     iter1: T1::Iterator,
     value1: Option<T1>,
     iter2: T2::Iterator,
-    count: Option<usize>,
+    /// The size hint of the remaining elements.
+    size_hint: (usize, Option<usize>),
 }
 
-impl<T1: IterValues + Clone, T2: IterValues> Iterator for PairIterValues<T1, T2> {
-    type Item = (T1, T2);
-
-    fn next(&mut self) -> Option<(T1, T2)> {
+impl<T1: IterValues + Clone, T2: IterValues> PairIterValues<T1, T2> {
+    fn next_inner(&mut self) -> Option<(T1, T2)> {
         if let Some(value1) = self.value1.clone() {
             if let Some(value2) = self.iter2.next() {
                 return Some((value1, value2));
@@ -155,12 +344,34 @@ impl<T1: IterValues + Clone, T2: IterValues> Iterator for PairIterValues<T1, T2>
         }
         None
     }
+}
+
+impl<T1: IterValues + Clone, T2: IterValues> Iterator for PairIterValues<T1, T2> {
+    type Item = (T1, T2);
+
+    fn next(&mut self) -> Option<(T1, T2)> {
+        let item = self.next_inner();
+        if item.is_some() {
+            let (lower, upper) = self.size_hint;
+            self.size_hint = (lower.saturating_sub(1), upper.map(|x| x.saturating_sub(1)));
+        } else {
+            self.size_hint = (0, Some(0));
+        }
+        item
+    }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        (0, self.count)
+        self.size_hint
     }
 }
 
+impl<T1: IterValues + Clone, T2: IterValues> ExactSizeIterator for PairIterValues<T1, T2>
+where
+    T1::Iterator: ExactSizeIterator,
+    T2::Iterator: ExactSizeIterator,
+{
+}
+
 impl<T1: IterValues + Clone, T2: IterValues + Clone, T3: IterValues> IterValues for (T1, T2, T3) {
     type Iterator = Flatten3<<(T1, (T2, T3)) as IterValues>::Iterator>;
 
@@ -26,3 +26,133 @@ fn bools2() {
     let values: Vec<_> = <(bool,)>::iter_values().collect();
     assert_eq!(values.as_slice(), &[(false,), (true,)]);
 }
+
+#[test]
+fn orderings() {
+    use std::cmp::Ordering;
+    let values: Vec<_> = <Ordering>::iter_values().collect();
+    assert_eq!(
+        values.as_slice(),
+        &[Ordering::Less, Ordering::Equal, Ordering::Greater]
+    );
+}
+
+#[test]
+fn ip_addrs() {
+    use std::net::IpAddr;
+    let values: Vec<_> = <IpAddr>::iter_values().collect();
+    assert_eq!(values.len(), 6);
+    assert!(values[0].is_loopback());
+    assert!(values[3].is_loopback());
+    assert!(values[5].is_multicast());
+    assert_eq!(values.iter().filter(|a| a.is_ipv4()).count(), 3);
+}
+
+#[test]
+fn to_vec() {
+    assert_eq!(<bool>::to_vec(), vec![false, true]);
+    assert_eq!(<Option<bool>>::to_vec(), vec![None, Some(false), Some(true)]);
+}
+
+#[test]
+fn random_sample() {
+    use std::cmp::Ordering;
+    assert_eq!(<bool>::random_sample(0), Some(false));
+    assert_eq!(<bool>::random_sample(1), Some(true));
+    assert_eq!(<bool>::random_sample(2), Some(false));
+    assert_eq!(<Ordering>::random_sample(5), Some(Ordering::Greater));
+    assert_eq!(<()>::random_sample(u64::max_value()), Some(()));
+
+    // Deterministic
+    let all_values = <(bool, Option<bool>)>::to_vec();
+    for seed in 0..16 {
+        let value = <(bool, Option<bool>)>::random_sample(seed);
+        assert_eq!(value, <(bool, Option<bool>)>::random_sample(seed));
+        assert_eq!(value, Some(all_values[seed as usize % 6]));
+    }
+
+    // A type whose iterator does not report the exact size
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Even(u8);
+    impl IterValues for Even {
+        type Iterator = Box<dyn Iterator<Item = Even>>;
+        fn iter_values() -> Self::Iterator {
+            Box::new((0..=254).filter(|x| x % 2 == 0).map(Even))
+        }
+    }
+    assert_eq!(<Even>::random_sample(130), Some(Even(4)));
+}
+
+#[test]
+fn random_sample_empty() {
+    #[derive(Debug, PartialEq)]
+    enum Never {}
+    impl IterValues for Never {
+        type Iterator = std::iter::Empty<Never>;
+        fn iter_values() -> Self::Iterator {
+            std::iter::empty()
+        }
+    }
+    assert_eq!(<Never>::random_sample(42), None);
+}
+
+#[test]
+fn zip() {
+    let values: Vec<(bool, bool)> = itervalues::zip_iter_values().collect();
+    assert_eq!(values.as_slice(), &[(false, false), (true, true)]);
+}
+
+/// Checks that `iter`'s `len` matches the number of the remaining elements
+/// at every step.
+fn check_exact_size<I: ExactSizeIterator>(mut iter: I) -> usize {
+    let count = iter.len();
+    for i in (0..count).rev() {
+        assert!(iter.next().is_some());
+        assert_eq!(iter.len(), i);
+    }
+    assert!(iter.next().is_none());
+    assert_eq!(iter.size_hint(), (0, Some(0)));
+    count
+}
+
+#[test]
+fn exact_size() {
+    assert_eq!(check_exact_size(<bool>::iter_values()), 2);
+    assert_eq!(check_exact_size(<Option<bool>>::iter_values()), 3);
+    assert_eq!(check_exact_size(<Option<Option<bool>>>::iter_values()), 4);
+    assert_eq!(check_exact_size(<(bool,)>::iter_values()), 2);
+    assert_eq!(check_exact_size(<(bool, Option<bool>)>::iter_values()), 6);
+    assert_eq!(check_exact_size(<(bool, ())>::iter_values()), 2);
+    assert_eq!(check_exact_size(<(bool, bool, bool)>::iter_values()), 8);
+    assert_eq!(
+        check_exact_size(<(bool, Option<bool>, bool, ())>::iter_values()),
+        12
+    );
+}
+
+#[test]
+fn nonzero_u8() {
+    use std::num::NonZeroU8;
+    let values: Vec<_> = <NonZeroU8>::iter_values().map(NonZeroU8::get).collect();
+    let expected: Vec<_> = (1..=255).collect();
+    assert_eq!(values, expected);
+    assert_eq!(<NonZeroU8>::iter_values().len(), 255);
+}
+
+#[test]
+fn nonzero_u16() {
+    use std::num::NonZeroU16;
+    let values: Vec<_> = <NonZeroU16>::iter_values().map(NonZeroU16::get).collect();
+    let expected: Vec<_> = (1..=65535).collect();
+    assert_eq!(values, expected);
+    assert_eq!(<NonZeroU16>::iter_values().len(), 65535);
+}
+
+#[test]
+fn nonzero_i8() {
+    use std::num::NonZeroI8;
+    let values: Vec<_> = <NonZeroI8>::iter_values().map(NonZeroI8::get).collect();
+    let expected: Vec<_> = (-128..=127).filter(|&x| x != 0).collect();
+    assert_eq!(values, expected);
+    assert_eq!(check_exact_size(<NonZeroI8>::iter_values()), 255);
+}
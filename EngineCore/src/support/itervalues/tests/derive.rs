@@ -75,3 +75,71 @@ fn nested() {
         ]
     );
 }
+
+#[test]
+fn generic() {
+    #[derive(IterValues, Copy, Clone, PartialEq, Eq, Debug)]
+    enum Test<T: IterValues + Clone> {
+        A(T),
+        B,
+    }
+
+    let values: Vec<_> = Test::<bool>::iter_values().collect();
+    assert_eq!(values.as_slice(), &[Test::A(false), Test::A(true), Test::B]);
+}
+
+#[test]
+fn generic_where_clause() {
+    #[derive(IterValues, Copy, Clone, PartialEq, Eq, Debug)]
+    enum Test<T1, T2>
+    where
+        T1: IterValues + Clone,
+        T2: IterValues + Clone,
+    {
+        A { x: T1, y: T2 },
+        B(T2),
+    }
+
+    let values: Vec<_> = Test::<bool, Option<bool>>::iter_values().collect();
+    assert_eq!(
+        values.as_slice(),
+        &[
+            Test::A { x: false, y: None },
+            Test::A { x: false, y: Some(false) },
+            Test::A { x: false, y: Some(true) },
+            Test::A { x: true, y: None },
+            Test::A { x: true, y: Some(false) },
+            Test::A { x: true, y: Some(true) },
+            Test::B(None),
+            Test::B(Some(false)),
+            Test::B(Some(true)),
+        ]
+    );
+}
+
+#[test]
+fn size_hint() {
+    #[derive(IterValues, Copy, Clone, PartialEq, Eq, Debug)]
+    enum Test1 {
+        A,
+        B,
+        C,
+    }
+
+    #[derive(IterValues, Copy, Clone, PartialEq, Eq, Debug)]
+    enum Test2 {
+        X(Test1, bool),
+        Y,
+        Z { a: Option<bool> },
+    }
+
+    assert_eq!(Test1::iter_values().len(), 3);
+
+    let mut it = Test2::iter_values();
+    for i in (0..10).rev() {
+        assert_eq!(it.size_hint(), (i + 1, Some(i + 1)));
+        assert!(it.next().is_some());
+        assert_eq!(it.size_hint(), (i, Some(i)));
+    }
+    assert!(it.next().is_none());
+}
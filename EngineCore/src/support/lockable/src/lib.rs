@@ -89,9 +89,19 @@
 //!     let mut counter = Counter(1);
 //!     let a = counter.incrementer_mut();
 //!     assert_eq!(a(), 2);
+//!
+//! # Acquiring multiple locks
+//!
+//! [`BorrowLockStack`] acquires multiple locks in a consistent order to
+//! prevent dead-locks.
+//!
+//! # Cargo features
+//!
+//!  - `spin` implements `BorrowLock` for `&spin::Mutex<_>` and smart pointers
+//!    to it, which is useful in environments without OS-backed mutexes.
 
 use std::{
-    ops::{Deref, DerefMut},
+    ops::{Deref, DerefMut, Index, IndexMut},
     pin::Pin,
     rc::Rc,
     sync::Arc,
@@ -161,6 +171,34 @@ impl_borrow_lock_lock_api_mutex!(Rc<lock_api::Mutex<R, T>>);
 impl_borrow_lock_lock_api_mutex!(Pin<Arc<lock_api::Mutex<R, T>>>);
 impl_borrow_lock_lock_api_mutex!(Pin<Rc<lock_api::Mutex<R, T>>>);
 
+// `for impl Deref<Target = spin::Mutex<_>> + !DerefMut`
+#[cfg(feature = "spin")]
+macro_rules! impl_borrow_lock_spin_mutex {
+    ($t:ty) => {
+        unsafe impl<T> BorrowLock<T> for $t {
+            fn raw_lock(&mut self) -> *mut T {
+                let mut guard = (**self).lock();
+                let ptr = (&mut *guard) as *mut _;
+                std::mem::forget(guard);
+                ptr
+            }
+            unsafe fn raw_unlock(&mut self) {
+                self.force_unlock();
+            }
+        }
+    };
+}
+#[cfg(feature = "spin")]
+impl_borrow_lock_spin_mutex!(&spin::Mutex<T>);
+#[cfg(feature = "spin")]
+impl_borrow_lock_spin_mutex!(Arc<spin::Mutex<T>>);
+#[cfg(feature = "spin")]
+impl_borrow_lock_spin_mutex!(Rc<spin::Mutex<T>>);
+#[cfg(feature = "spin")]
+impl_borrow_lock_spin_mutex!(Pin<Arc<spin::Mutex<T>>>);
+#[cfg(feature = "spin")]
+impl_borrow_lock_spin_mutex!(Pin<Rc<spin::Mutex<T>>>);
+
 // I wanted to add `impl BorrowLock` for `RefCell`, but `RefCell` doesn't have a
 // `force_unlock` equivalent...
 
@@ -193,3 +231,146 @@ impl<'a, T, L: BorrowLock<T>> DerefMut for BorrowLockGuard<'a, T, L> {
         unsafe { &mut *self.ptr }
     }
 }
+
+/// Holds multiple locks acquired in a consistent order.
+///
+/// Each lock is associated with an ordering key, which is typically the
+/// address of the underlying lock object. The locks are acquired in the
+/// ascending order of their keys, so two threads acquiring overlapping sets of
+/// locks through `BorrowLockStack` never dead-lock. The locks are released in
+/// the reverse order when `BorrowLockStack` is dropped.
+///
+/// The inner objects are accessed by the indices of the locks in the sequence
+/// passed to [`BorrowLockStack::new`] (not by the acquisition order).
+///
+/// # Examples
+///
+///     use lockable::{BorrowLock, BorrowLockStack};
+///     use parking_lot::Mutex;
+///
+///     fn transfer(from: &Mutex<u32>, to: &Mutex<u32>, amount: u32) {
+///         let (mut from_ref, mut to_ref) = (from, to);
+///         let mut stack = BorrowLockStack::new(vec![
+///             (from as *const _ as usize, &mut from_ref as &mut dyn BorrowLock<u32>),
+///             (to as *const _ as usize, &mut to_ref as &mut dyn BorrowLock<u32>),
+///         ]);
+///         stack[0] -= amount;
+///         stack[1] += amount;
+///     }
+///
+///     let (a, b) = (Mutex::new(10), Mutex::new(0));
+///     transfer(&a, &b, 3);
+///     transfer(&b, &a, 1);
+///     assert_eq!((*a.lock(), *b.lock()), (8, 2));
+///
+pub struct BorrowLockStack<'a, T> {
+    /// The locks in the original order.
+    locks: Vec<StackEntry<'a, T>>,
+    /// Indices into `locks`, sorted by the ordering keys.
+    order: Vec<usize>,
+    /// The number of locks acquired so far. The first `num_locked` elements
+    /// of `order` point the acquired locks.
+    num_locked: usize,
+}
+
+struct StackEntry<'a, T> {
+    key: usize,
+    lock: &'a mut dyn BorrowLock<T>,
+    ptr: *mut T,
+}
+
+impl<'a, T> BorrowLockStack<'a, T> {
+    /// Acquire the specified locks in the ascending order of their keys.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two locks have the same key. This check is done before
+    /// acquiring any locks, so no locks are held when it fails. If
+    /// `BorrowLock::raw_lock` panics, the locks acquired before that are
+    /// released.
+    pub fn new(locks: impl IntoIterator<Item = (usize, &'a mut dyn BorrowLock<T>)>) -> Self {
+        let locks: Vec<_> = locks
+            .into_iter()
+            .map(|(key, lock)| StackEntry {
+                key,
+                lock,
+                ptr: std::ptr::null_mut(),
+            })
+            .collect();
+
+        let mut order: Vec<usize> = (0..locks.len()).collect();
+        order.sort_by_key(|&i| locks[i].key);
+
+        for pair in order.windows(2) {
+            assert_ne!(locks[pair[0]].key, locks[pair[1]].key, "duplicate lock key");
+        }
+
+        let mut this = Self {
+            locks,
+            order,
+            num_locked: 0,
+        };
+
+        // If `raw_lock` panics, `drop` releases the locks acquired so far
+        while this.num_locked < this.order.len() {
+            let entry = &mut this.locks[this.order[this.num_locked]];
+            entry.ptr = entry.lock.raw_lock();
+            this.num_locked += 1;
+        }
+
+        this
+    }
+
+    /// Get the number of the locks.
+    pub fn len(&self) -> usize {
+        self.locks.len()
+    }
+
+    /// Return `true` if there are no locks.
+    pub fn is_empty(&self) -> bool {
+        self.locks.is_empty()
+    }
+
+    /// Get a reference to the inner object of the `i`-th lock.
+    pub fn get(&self, i: usize) -> Option<&T> {
+        self.locks.get(i).map(|entry| unsafe { &*entry.ptr })
+    }
+
+    /// Get a mutable reference to the inner object of the `i`-th lock.
+    pub fn get_mut(&mut self, i: usize) -> Option<&mut T> {
+        self.locks
+            .get_mut(i)
+            .map(|entry| unsafe { &mut *entry.ptr })
+    }
+}
+
+impl<'a, T> Drop for BorrowLockStack<'a, T> {
+    fn drop(&mut self) {
+        for &i in self.order[0..self.num_locked].iter().rev() {
+            unsafe {
+                self.locks[i].lock.raw_unlock();
+            }
+        }
+    }
+}
+
+impl<'a, T> Index<usize> for BorrowLockStack<'a, T> {
+    type Output = T;
+    fn index(&self, i: usize) -> &Self::Output {
+        self.get(i).expect("index out of bounds")
+    }
+}
+
+impl<'a, T> IndexMut<usize> for BorrowLockStack<'a, T> {
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        self.get_mut(i).expect("index out of bounds")
+    }
+}
+
+impl<'a, T: std::fmt::Debug> std::fmt::Debug for BorrowLockStack<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries((0..self.len()).map(|i| &self[i]))
+            .finish()
+    }
+}
@@ -0,0 +1,50 @@
+//
+// Copyright 2019 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+#![cfg(feature = "spin")]
+use lockable::BorrowLock;
+use spin::Mutex;
+use std::{pin::Pin, rc::Rc, sync::Arc};
+
+fn hoge<'a>(mut x: impl BorrowLock<u32> + 'a) -> impl FnOnce() -> u32 + 'a {
+    move || {
+        *x.borrow_lock() += 1;
+        *x.borrow_lock()
+    }
+}
+
+#[test]
+fn spin_mutex_ref() {
+    let counter = Mutex::new(1);
+    let a = hoge(&counter);
+    let b = hoge(&counter);
+    assert_eq!(a(), 2);
+    assert_eq!(b(), 3);
+
+    // The lock is released
+    assert!(counter.try_lock().is_some());
+}
+
+#[test]
+fn spin_mutex_arc() {
+    let (a, b);
+    {
+        let counter = Arc::new(Mutex::new(1));
+        a = hoge(counter.clone());
+        b = hoge(Pin::new(counter.clone()));
+    }
+    assert_eq!(a(), 2);
+    assert_eq!(b(), 3);
+}
+
+#[test]
+fn spin_mutex_rc() {
+    let counter = Rc::new(Mutex::new(1));
+    let a = hoge(counter.clone());
+    let b = hoge(Pin::new(counter.clone()));
+    assert_eq!(a(), 2);
+    assert_eq!(b(), 3);
+    assert!(counter.try_lock().is_some());
+}
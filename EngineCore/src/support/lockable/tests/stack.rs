@@ -0,0 +1,134 @@
+//
+// Copyright 2019 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use lockable::{BorrowLock, BorrowLockStack};
+use std::{
+    cell::RefCell,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Event {
+    Lock(u32),
+    Unlock(u32),
+}
+
+/// A `BorrowLock` that records lock operations to a log.
+struct MockLock<'a> {
+    name: u32,
+    value: u32,
+    log: &'a RefCell<Vec<Event>>,
+    fail: bool,
+}
+
+impl<'a> MockLock<'a> {
+    fn new(name: u32, log: &'a RefCell<Vec<Event>>) -> Self {
+        Self {
+            name,
+            value: name,
+            log,
+            fail: false,
+        }
+    }
+}
+
+unsafe impl<'a> BorrowLock<u32> for MockLock<'a> {
+    fn raw_lock(&mut self) -> *mut u32 {
+        if self.fail {
+            panic!("lock failure");
+        }
+        self.log.borrow_mut().push(Event::Lock(self.name));
+        &mut self.value
+    }
+
+    unsafe fn raw_unlock(&mut self) {
+        self.log.borrow_mut().push(Event::Unlock(self.name));
+    }
+}
+
+#[test]
+fn lock_order() {
+    let log = RefCell::new(Vec::new());
+    let (mut a, mut b, mut c) = (
+        MockLock::new(1, &log),
+        MockLock::new(2, &log),
+        MockLock::new(3, &log),
+    );
+
+    {
+        let mut stack = BorrowLockStack::new(vec![
+            (20, &mut b as &mut dyn BorrowLock<u32>),
+            (30, &mut c as &mut dyn BorrowLock<u32>),
+            (10, &mut a as &mut dyn BorrowLock<u32>),
+        ]);
+
+        assert_eq!(
+            *log.borrow(),
+            [Event::Lock(1), Event::Lock(2), Event::Lock(3)]
+        );
+
+        // Indexed by the original order
+        assert_eq!(stack.len(), 3);
+        assert_eq!([stack[0], stack[1], stack[2]], [2, 3, 1]);
+        stack[0] += 10;
+
+        log.borrow_mut().clear();
+    }
+
+    // Released in the reverse order
+    assert_eq!(
+        *log.borrow(),
+        [Event::Unlock(3), Event::Unlock(2), Event::Unlock(1)]
+    );
+    assert_eq!(b.value, 12);
+}
+
+#[test]
+fn duplicate_key() {
+    let log = RefCell::new(Vec::new());
+    let (mut a, mut b) = (MockLock::new(1, &log), MockLock::new(2, &log));
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        BorrowLockStack::new(vec![
+            (10, &mut a as &mut dyn BorrowLock<u32>),
+            (10, &mut b as &mut dyn BorrowLock<u32>),
+        ]);
+    }));
+
+    assert!(result.is_err());
+    // No locks were acquired
+    assert!(log.borrow().is_empty());
+}
+
+#[test]
+fn lock_failure() {
+    let log = RefCell::new(Vec::new());
+    let (mut a, mut b, mut c) = (
+        MockLock::new(1, &log),
+        MockLock::new(2, &log),
+        MockLock::new(3, &log),
+    );
+    c.fail = true;
+
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        BorrowLockStack::new(vec![
+            (30, &mut c as &mut dyn BorrowLock<u32>),
+            (10, &mut a as &mut dyn BorrowLock<u32>),
+            (20, &mut b as &mut dyn BorrowLock<u32>),
+        ]);
+    }));
+
+    assert!(result.is_err());
+    // The locks acquired before the panic are released in the reverse order
+    assert_eq!(
+        *log.borrow(),
+        [
+            Event::Lock(1),
+            Event::Lock(2),
+            Event::Unlock(2),
+            Event::Unlock(1)
+        ]
+    );
+}
@@ -5,12 +5,15 @@
 //! The producing `Future` is evaluated only once. The consuming `Future`s can
 //! be created or removed freely and dynamically while and/or after the
 //! producing `Future` is evaluated. The consuming `Future`s must be polled
-//! for the producing `Future` to be able to make a progress. `forget`ting
-//! a consuming `Future` might indefinitely stall the progress.
+//! for the producing `Future` to be able to make a progress.
 //!
 //! The result of the producing `Future` is broadcasted to the consuming
 //! `Future` by `clone`-ing the result value. Therefore, the output type must
 //! implement `Clone`.
+//! [`ConsumerInner::poll_take`] avoids the `clone` when the consumer holds the
+//! only reference to the `MultiCastInner`. [`ConsumerInner::poll_ready`] and [`ConsumerInner::ready`] wait
+//! for the result without cloning it, which then can be borrowed from
+//! [`MultiCastInner::result`].
 //!
 //! # Examples
 //!
@@ -30,13 +33,16 @@
 //! assert_eq!(block_on(consumer1.join(consumer2)), (42, 42));
 //! ```
 //!
-//! ## Do not stall consumers
+//! ## Stalled consumers
 //!
-//! Make sure all consuming `Future`s are polled simultaneously. `MultiCast`
-//! assumes that all live consumers are equally polled. The following code will
-//! deadlock:
+//! `MultiCast` assumes that all live consumers are equally polled. At any
+//! moment, exactly one of the consumers (the *leader*) is responsible for
+//! polling the producing `Future`. If the leader stops being polled without
+//! being dropped, another consumer takes over the leadership when it notices
+//! that the producing `Future` has not been polled for a while. Therefore, the
+//! following code does not deadlock:
 //!
-//! ```no_run
+//! ```
 //! # #![feature(futures_api)]
 //! # use futures::{future::lazy, executor::block_on};
 //! # use multicastfuture::MultiCast;
@@ -49,7 +55,8 @@
 //! block_on(consumer2);
 //! ```
 //!
-//! Just make sure to drop unused consumers:
+//! Detecting a stalled leader costs a few extra polls, though. Just make sure
+//! to drop unused consumers:
 //!
 //! ```
 //! # #![feature(futures_api)]
@@ -65,6 +72,86 @@
 //! block_on(consumer2);
 //! ```
 //!
+//! ## Panicking producer
+//!
+//! If the producing `Future` panics while being polled, the `MultiCast` is
+//! *poisoned*. The panic is propagated to the caller of the consumer that was
+//! polling the producing `Future` at that moment. Every other consumer (as
+//! well as every consumer created afterward) panics when polled, instead of
+//! stalling forever. The producing `Future` is never polled again.
+//!
+//! Consumers re-panic rather than returning an error because the output type
+//! is defined by the producing `Future` and has no room for one. Since the
+//! poisoned state is never observed as a value, the producing `Future` is not
+//! required to be `UnwindSafe`. Use [`MultiCastInner::is_poisoned`] to check
+//! for this state without panicking.
+//!
+//! ```
+//! # #![feature(futures_api)]
+//! # use futures::{future::lazy, executor::block_on};
+//! # use multicastfuture::MultiCast;
+//! # use std::{pin::Pin, panic::{catch_unwind, AssertUnwindSafe}};
+//! let mc = MultiCast::new(lazy(|_| -> u32 { panic!("oops") }));
+//!
+//! let consumer1 = Pin::new(&mc).subscribe();
+//! let consumer2 = Pin::new(&mc).subscribe();
+//!
+//! assert!(catch_unwind(AssertUnwindSafe(|| block_on(consumer1))).is_err());
+//! assert!(mc.is_poisoned());
+//! assert!(catch_unwind(AssertUnwindSafe(|| block_on(consumer2))).is_err());
+//! ```
+//!
+//! ## Thread safety
+//!
+//! `MultiCast<F>` is `Send` if both of `F` and `F::Output` are `Send`. It's
+//! `Sync` if both of them are `Send` and `Sync`, since the producing `Future`
+//! is polled by whichever consumer happens to be the leader at the moment.
+//! A consumer is `Send` if the pointer type `P` is `Send` and the `MultiCast`
+//! is `Sync`.
+//!
+//! ```compile_fail
+//! # #![feature(futures_api)]
+//! # use futures::future::lazy;
+//! # use multicastfuture::MultiCast;
+//! # use std::rc::Rc;
+//! fn assert_sync<T: Sync>(_: &T) {}
+//!
+//! let rc = Rc::new(());
+//! let mc = MultiCast::new(lazy(move |_| { drop(rc); 42u32 }));
+//! assert_sync(&mc);
+//! ```
+//!
+//! `Sync` isn't enough for the producing `Future`:
+//!
+//! ```compile_fail
+//! # #![feature(futures_api)]
+//! # use futures::future::lazy;
+//! # use multicastfuture::MultiCast;
+//! # use std::sync::Mutex;
+//! fn assert_sync<T: Sync>(_: &T) {}
+//!
+//! // `MutexGuard` is `Sync` but not `Send`
+//! let mutex = Mutex::new(());
+//! let guard = mutex.lock().unwrap();
+//! let mc = MultiCast::new(lazy(move |_| { drop(guard); 42u32 }));
+//! assert_sync(&mc);
+//! ```
+//!
+//! Nor is it for the output:
+//!
+//! ```compile_fail
+//! # #![feature(futures_api)]
+//! # use futures::future::lazy;
+//! # use multicastfuture::MultiCast;
+//! # use std::pin::Pin;
+//! # use std::sync::{Arc, Mutex};
+//! fn assert_send<T: Send>(_: &T) {}
+//!
+//! let mutex: &'static Mutex<()> = Box::leak(Box::new(Mutex::new(())));
+//! let mc = MultiCast::new(lazy(move |_| Arc::new(mutex.lock().unwrap())));
+//! assert_send(&Pin::new(&mc).subscribe());
+//! ```
+//!
 //! ## Unsizing
 //!
 //! `MultiCast` supports unsized coercions on the `Future` type parameter:
@@ -82,16 +169,24 @@
 #![feature(futures_api)]
 #![feature(maybe_uninit)]
 #![feature(maybe_uninit_ref)]
-use futures::{ready, task::Waker, Future, Poll};
+use futures::{
+    future::{FutureExt, Map},
+    task::Waker,
+    Future, Poll,
+};
 use parking_lot::Mutex;
 use std::{
     cell::UnsafeCell,
     fmt,
     mem::MaybeUninit,
     ops::Deref,
+    panic::{catch_unwind, resume_unwind, AssertUnwindSafe, RefUnwindSafe},
     pin::Pin,
     ptr::null_mut,
-    sync::atomic::{AtomicBool, AtomicPtr, Ordering},
+    sync::{
+        atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
 /// Broadcasts the result of a `Future` (the producing `Future`) to one or more
@@ -119,6 +214,24 @@ pub struct MultiCastInner<F: Future<Output = T> + ?Sized, T> {
     /// completed or not.
     complete: AtomicBool,
 
+    /// Indicates whether the producing `Future` has panicked during `poll`.
+    /// `complete` and `poisoned` are never both `true`.
+    poisoned: AtomicBool,
+
+    /// Indicates whether the result has been moved out by
+    /// `ConsumerInner::poll_take`. At most one of `complete`, `poisoned`, and
+    /// `taken` is `true`.
+    taken: AtomicBool,
+
+    /// Indicates whether a consumer is currently polling the producing
+    /// `Future`. This ensures that only one consumer polls it at a time even
+    /// if the leadership was taken over by another consumer in the meantime.
+    polling: AtomicBool,
+
+    /// Incremented every time the producing `Future` is polled. Used by
+    /// non-leader consumers to detect a stalled leader.
+    generation: AtomicUsize,
+
     /// The mutex for protecting the state of the consumer list.
     mutex: Mutex<()>,
 
@@ -132,12 +245,22 @@ pub struct MultiCastInner<F: Future<Output = T> + ?Sized, T> {
 /// See [the crate documentation](index.html) for details.
 pub type MultiCast<F> = MultiCastInner<F, <F as Future>::Output>;
 
+/// A [`MultiCastInner`] wrapping a type-erased, boxed `Future`.
+///
+/// See [`MultiCastInner::new_boxed`].
+pub type BoxMultiCast<'a, T> = MultiCastInner<Pin<Box<dyn Future<Output = T> + Send + 'a>>, T>;
+
 /// The consuming `Future` of [`MultiCastInner`].
 ///
 /// `T` is uniquely determined from `F` but it's defined as a type parameter
 /// to enable unsized coercions. This type has a type alias [`Consumer`] that
 /// doesn't have this redundant type parameter.
 ///
+/// A consumer is `Send` if `P` is `Send` and `MultiCastInner` is `Sync`. For
+/// example, consumers created by [`MultiCastInner::subscribe_arc`] can be
+/// spawned on a multi-threaded executor as long as the producing `Future` and
+/// its output are `Send + Sync`.
+///
 /// See [the crate documentation](index.html) for details.
 #[derive(Debug)]
 pub struct ConsumerInner<P: Deref<Target = MultiCastInner<F, T>>, F: Future<Output = T> + ?Sized, T>
@@ -163,8 +286,20 @@ struct ConsumerState {
     ///    `MultiCastInner::leader`).
     ///  - The completion of the producing `Future`.
     ///
+    /// `None` indicates that this consumer has never been polled.
     task: Mutex<Option<Waker>>,
 
+    /// The value of `MultiCastInner::generation` observed when this consumer
+    /// was polled last time. Only accessed by the owning consumer.
+    last_generation: AtomicUsize,
+
+    /// The number of consecutive polls of this consumer (as a non-leader)
+    /// that didn't observe any progress of the producing `Future`. The leader
+    /// is woken up on the first such poll, so the subsequent ones indicate
+    /// that the leader missed the wakeup. Only accessed by the owning
+    /// consumer.
+    num_stalled_polls: AtomicUsize,
+
     /// The pointers to the previous and next `ConsumerState`s in a circular
     /// linked list.
     ///
@@ -180,9 +315,86 @@ impl<F: Future<Output = T>, T> MultiCastInner<F, T> {
             result: UnsafeCell::new(MaybeUninit::uninitialized()),
             leader: AtomicPtr::default(),
             complete: AtomicBool::new(false),
+            poisoned: AtomicBool::new(false),
+            taken: AtomicBool::new(false),
+            polling: AtomicBool::new(false),
+            generation: AtomicUsize::new(0),
             mutex: Mutex::new(()),
         }
     }
+
+    /// Construct a `MultiCastInner` by wrapping a given `Future`, and place it
+    /// in a pinned `Arc`.
+    ///
+    /// The returned `Pin<Arc<Self>>` can be cloned and sent to other threads,
+    /// each of which can call [`subscribe`] on its own clone to create a
+    /// consuming `Future`. `F` must be `Sync` as well as `Send` because the
+    /// producing `Future` might be polled by whichever consumer happens to be
+    /// the leader at the moment.
+    ///
+    /// [`subscribe`]: MultiCastInner::subscribe
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(futures_api)]
+    /// use futures::{future::lazy, executor::block_on};
+    /// use multicastfuture::MultiCast;
+    /// use std::thread::spawn;
+    ///
+    /// let mc = MultiCast::new_arc(lazy(|_| 42u32));
+    ///
+    /// let consumer1 = mc.clone().subscribe();
+    /// let consumer2 = mc.clone().subscribe();
+    ///
+    /// let thread1 = spawn(move || block_on(consumer1));
+    /// let thread2 = spawn(move || block_on(consumer2));
+    ///
+    /// assert_eq!(thread1.join().unwrap(), 42);
+    /// assert_eq!(thread2.join().unwrap(), 42);
+    /// ```
+    pub fn new_arc(inner: F) -> Pin<Arc<Self>>
+    where
+        F: Send + Sync,
+        T: Send + Sync + Clone,
+    {
+        Arc::pin(Self::new(inner))
+    }
+}
+
+impl<'a, T> BoxMultiCast<'a, T> {
+    /// Construct a `MultiCastInner` by wrapping a given type-erased `Future`.
+    ///
+    /// The boxed `Future` is pinned in place; it's never moved out of the
+    /// `Box` after this call.
+    ///
+    /// The returned `MultiCastInner` is not `Sync` because the producing
+    /// `Future` is not required to be `Sync`. Use [`new_arc`] with a concrete
+    /// `Future` type if the consumers are to be sent to other threads.
+    ///
+    /// [`new_arc`]: MultiCastInner::new_arc
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(futures_api)]
+    /// use futures::{future::{lazy, FutureExt}, executor::block_on};
+    /// use multicastfuture::BoxMultiCast;
+    /// use std::pin::Pin;
+    ///
+    /// let producer: Box<dyn futures::Future<Output = u32> + Send> =
+    ///     Box::new(lazy(|_| 42u32));
+    ///
+    /// let mc = BoxMultiCast::new_boxed(producer);
+    ///
+    /// let consumer1 = Pin::new(&mc).subscribe();
+    /// let consumer2 = Pin::new(&mc).subscribe();
+    ///
+    /// assert_eq!(block_on(consumer1.join(consumer2)), (42, 42));
+    /// ```
+    pub fn new_boxed(inner: Box<dyn Future<Output = T> + Send + 'a>) -> Self {
+        Self::new(Pin::from(inner))
+    }
 }
 
 impl<F: Future<Output = T> + ?Sized, T> MultiCastInner<F, T> {
@@ -192,7 +404,10 @@ impl<F: Future<Output = T> + ?Sized, T> MultiCastInner<F, T> {
             let this = &*self;
             let _lock = this.mutex.lock();
 
-            if this.complete.load(Ordering::Relaxed) {
+            if this.complete.load(Ordering::Relaxed)
+                || this.poisoned.load(Ordering::Relaxed)
+                || this.taken.load(Ordering::Relaxed)
+            {
                 break None;
             }
 
@@ -200,6 +415,8 @@ impl<F: Future<Output = T> + ?Sized, T> MultiCastInner<F, T> {
             let mut state = Box::pin(ConsumerState::default());
             let state_ptr = (&*state) as *const _ as *mut _;
 
+            *state.last_generation.get_mut() = this.generation.load(Ordering::Relaxed);
+
             let leader = this.leader.load(Ordering::Acquire);
             if leader.is_null() {
                 this.leader
@@ -228,11 +445,73 @@ impl<F: Future<Output = T> + ?Sized, T> MultiCastInner<F, T> {
         }
     }
 
+    /// Create a consuming `Future` from a reference to `Pin<Arc<Self>>`.
+    ///
+    /// This is a shorthand for `Pin::clone(self).subscribe()`. The returned
+    /// consumer holds its own clone of the `Arc`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(futures_api)]
+    /// use futures::{future::lazy, executor::block_on};
+    /// use multicastfuture::MultiCast;
+    ///
+    /// let mc = MultiCast::new_arc(lazy(|_| 42u32));
+    ///
+    /// let consumer = mc.subscribe_arc();
+    /// assert_eq!(block_on(consumer), 42);
+    /// ```
+    pub fn subscribe_arc(self: &Pin<Arc<Self>>) -> ConsumerInner<Arc<Self>, F, T> {
+        Pin::clone(self).subscribe()
+    }
+
     /// Check if the result is ready.
+    ///
+    /// If this returns `true`, the result is visible to the calling thread,
+    /// i.e., [`result`] is guaranteed to return `Some(_)`.
+    ///
+    /// [`result`]: MultiCastInner::result
     pub fn is_complete(&self) -> bool {
+        self.complete.load(Ordering::Acquire)
+    }
+
+    /// Check if the result is ready, without synchronizing with the thread
+    /// that produced the result.
+    ///
+    /// This is a best-effort check intended for status displays and such that
+    /// can tolerate a stale value. The returned value might lag behind
+    /// [`is_complete`]. Even if this returns `true`, the result must be
+    /// accessed through [`result`] (which performs the proper
+    /// synchronization) and not be assumed to be ready based on this method.
+    ///
+    /// [`is_complete`]: MultiCastInner::is_complete
+    /// [`result`]: MultiCastInner::result
+    pub fn is_complete_relaxed(&self) -> bool {
         self.complete.load(Ordering::Relaxed)
     }
 
+    /// Check if the producing `Future` has panicked.
+    ///
+    /// Polling a consuming `Future` of a poisoned `MultiCastInner` results in
+    /// a panic. See [the crate documentation](index.html) for details.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Relaxed)
+    }
+
+    /// Check if the result has been moved out by
+    /// [`ConsumerInner::poll_take`].
+    ///
+    /// [`is_complete`] returns `false` in this case. This state is only
+    /// observable through the consumer that took the result because
+    /// `poll_take` doesn't move the result out if there are any other
+    /// references to the `MultiCastInner`.
+    ///
+    /// [`is_complete`]: MultiCastInner::is_complete
+    pub fn is_taken(&self) -> bool {
+        self.taken.load(Ordering::Relaxed)
+    }
+
     /// Get a reference to the result if it's ready.
     pub fn result(&self) -> Option<&F::Output> {
         if self.complete.load(Ordering::Acquire) {
@@ -243,6 +522,11 @@ impl<F: Future<Output = T> + ?Sized, T> MultiCastInner<F, T> {
     }
 
     /// Get a mutable reference to the result if it's ready.
+    ///
+    /// See [`map_result_in_place`] for the implications of mutating the
+    /// result.
+    ///
+    /// [`map_result_in_place`]: MultiCastInner::map_result_in_place
     pub fn result_mut(&mut self) -> Option<&mut F::Output> {
         if *self.complete.get_mut() {
             unsafe { Some((&mut *self.result.get()).get_mut()) }
@@ -251,6 +535,42 @@ impl<F: Future<Output = T> + ?Sized, T> MultiCastInner<F, T> {
         }
     }
 
+    /// Modify the result in place using a given function if it's ready.
+    /// Returns the function back without calling it if the result is not
+    /// ready yet.
+    ///
+    /// Consuming `Future`s receive a `clone` of the stored result. Therefore,
+    /// mutating the result changes what consuming `Future`s polled after this
+    /// call observe (e.g., those created by `subscribe` afterward).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(futures_api)]
+    /// use futures::{future::lazy, executor::block_on};
+    /// use multicastfuture::MultiCast;
+    /// use std::pin::Pin;
+    ///
+    /// let mut mc = MultiCast::new(lazy(|_| 42u32));
+    /// assert!(mc.map_result_in_place(|x| *x += 1).is_err());
+    ///
+    /// assert_eq!(block_on(Pin::new(&mc).subscribe()), 42);
+    ///
+    /// assert!(mc.map_result_in_place(|x| *x += 1).is_ok());
+    /// assert_eq!(block_on(Pin::new(&mc).subscribe()), 43);
+    /// ```
+    pub fn map_result_in_place<G>(&mut self, f: G) -> Result<(), G>
+    where
+        G: FnOnce(&mut F::Output),
+    {
+        if let Some(result) = self.result_mut() {
+            f(result);
+            Ok(())
+        } else {
+            Err(f)
+        }
+    }
+
     /// Attempt to get the result. Returns the original object if the result is
     /// is not ready yet.
     pub fn try_into_result(mut self) -> Result<F::Output, Self>
@@ -276,10 +596,47 @@ impl<F: Future<Output = T> + ?Sized, T> Drop for MultiCastInner<F, T> {
     }
 }
 
+// `MultiCastInner` owns the producing `Future` and the result. Both of them
+// may be dropped on whichever thread owns `MultiCastInner`.
+unsafe impl<F: Future<Output = T> + ?Sized, T> Send for MultiCastInner<F, T>
+where
+    F: Send,
+    F::Output: Send,
+{
+}
+
+// Through `&MultiCastInner`,
+//
+//  - The producing `Future` is polled by the current leader, which may be on
+//    any thread (`F: Send`). After the completion, `fmt::Debug` accesses it
+//    from any thread (`F: Sync`).
+//  - The result is written by the leader and then might be moved out or
+//    dropped elsewhere (`F::Output: Send`). It's shared by all consumers,
+//    which `clone` it simultaneously (`F::Output: Sync`).
 unsafe impl<F: Future<Output = T> + ?Sized, T> Sync for MultiCastInner<F, T>
 where
-    F: Sync,
-    F::Output: Sync,
+    F: Send + Sync,
+    F::Output: Send + Sync,
+{
+}
+
+// A panic in the producing `Future` poisons `MultiCastInner` (see the crate
+// documentation), after which neither the producing `Future` nor the result
+// is observable. Thus only the result's unwind safety matters.
+impl<F: Future<Output = T> + ?Sized, T> RefUnwindSafe for MultiCastInner<F, T> where
+    F::Output: RefUnwindSafe
+{
+}
+
+// A consumer accesses `MultiCastInner` concurrently with other consumers,
+// which can be on other threads. Require `MultiCastInner: Sync` so that an
+// exotic `P` which is `Send` but provides a shared access to the same
+// `MultiCastInner` to multiple threads can't be used to circumvent it.
+unsafe impl<P: Deref<Target = MultiCastInner<F, T>>, F: Future<Output = T> + ?Sized, T> Send
+    for ConsumerInner<P, F, T>
+where
+    P: Send,
+    MultiCastInner<F, T>: Sync,
 {
 }
 
@@ -296,8 +653,26 @@ where
                 .field("complete", &true)
                 .finish()
         } else {
+            let (poisoned, taken, has_leader, consumer_count) = {
+                let _lock = self.mutex.lock();
+                let poisoned = self.poisoned.load(Ordering::Relaxed);
+                let taken = self.taken.load(Ordering::Relaxed);
+                let leader = self.leader.load(Ordering::Relaxed);
+
+                if self.complete.load(Ordering::Relaxed) || poisoned || taken || leader.is_null() {
+                    // The consumer list is empty or not maintained anymore
+                    (poisoned, taken, false, 0)
+                } else {
+                    (poisoned, taken, true, unsafe { count_consumers(leader) })
+                }
+            };
+
             f.debug_struct("MultiCastInner")
                 .field("complete", &false)
+                .field("poisoned", &poisoned)
+                .field("taken", &taken)
+                .field("has_leader", &has_leader)
+                .field("consumer_count", &consumer_count)
                 .finish()
         }
     }
@@ -310,68 +685,391 @@ impl<P: Deref<Target = MultiCastInner<F, T>>, F: Future<Output = T> + ?Sized, T>
     pub fn multi_cast(&self) -> &Pin<P> {
         &self.producer
     }
-}
 
-impl<P: Deref<Target = MultiCastInner<F, T>>, F: Future<Output = T> + ?Sized, T> Future
-    for ConsumerInner<P, F, T>
-where
-    F::Output: Clone,
-{
-    type Output = F::Output;
+    /// Map the result of this consuming `Future` to a different type.
+    ///
+    /// This is equivalent to [`FutureExt::map`] and is provided for
+    /// discoverability.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(futures_api)]
+    /// use futures::{future::lazy, executor::block_on};
+    /// use multicastfuture::MultiCast;
+    /// use std::pin::Pin;
+    ///
+    /// let mc = MultiCast::new(lazy(|_| 21u32));
+    /// let consumer = Pin::new(&mc).subscribe().map(|x| x * 2);
+    ///
+    /// assert_eq!(block_on(consumer), 42);
+    /// ```
+    pub fn map<U, G>(self, f: G) -> Map<Self, G>
+    where
+        G: FnOnce(T) -> U,
+        T: Clone,
+    {
+        FutureExt::map(self, f)
+    }
+
+    /// Remove this consumer from the consumer list, returning `true` if it
+    /// was the leader.
+    ///
+    /// This is equivalent to dropping the consumer except that it reports
+    /// whether the leadership was transferred to another consumer (or
+    /// released because there are no other consumers). Returns `false` if the
+    /// producing `Future` has already completed or panicked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(futures_api)]
+    /// use futures::{future::lazy, executor::block_on};
+    /// use multicastfuture::MultiCast;
+    /// use std::pin::Pin;
+    ///
+    /// let mc = MultiCast::new(lazy(|_| 42u32));
+    /// let consumer1 = Pin::new(&mc).subscribe();
+    /// let consumer2 = Pin::new(&mc).subscribe();
+    ///
+    /// // The first consumer is the initial leader
+    /// assert!(consumer1.unsubscribe());
+    ///
+    /// assert_eq!(block_on(consumer2), 42);
+    /// ```
+    pub fn unsubscribe(mut self) -> bool {
+        self.unlink()
+    }
 
-    fn poll(self: Pin<&mut Self>, waker: &Waker) -> Poll<Self::Output> {
+    /// Poll this consumer for the readiness of the result without cloning it.
+    ///
+    /// This does everything `Future::poll` does (including polling the
+    /// producing `Future` as the leader) except for cloning the result. After
+    /// this returns `Poll::Ready(())`, the result can be borrowed by
+    /// `multi_cast().result().unwrap()`. Unlike `poll`, this method does not
+    /// require the output type to implement `Clone`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(futures_api)]
+    /// use futures::{future::{lazy, poll_fn}, executor::block_on};
+    /// use multicastfuture::MultiCast;
+    /// use std::pin::Pin;
+    ///
+    /// let mc = MultiCast::new(lazy(|_| vec![42u8; 1 << 20]));
+    /// let mut consumer = Pin::new(&mc).subscribe();
+    ///
+    /// block_on(poll_fn(|waker| Pin::new(&mut consumer).poll_ready(waker)));
+    /// assert_eq!(consumer.multi_cast().result().unwrap().len(), 1 << 20);
+    /// ```
+    pub fn poll_ready(self: Pin<&mut Self>, waker: &Waker) -> Poll<()> {
+        match self.poll_core(waker, None) {
+            Poll::Ready(Some(_)) => unreachable!(),
+            Poll::Ready(None) => Poll::Ready(()),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    /// Convert this consumer into a `Future` that completes when the result
+    /// is ready, without cloning the result.
+    ///
+    /// This is a wrapper of [`poll_ready`] suitable for use in `async fn`s.
+    /// The consumer is dropped along with the returned `Future`. After the
+    /// completion, the result can be borrowed through the `MultiCastInner`
+    /// the consumer was created from.
+    ///
+    /// [`poll_ready`]: ConsumerInner::poll_ready
+    pub fn ready(self) -> impl Future<Output = ()> {
+        ReadyFuture(self)
+    }
+
+    /// Remove this consumer from the consumer list. Returns `true` if it was
+    /// the leader. Does nothing if it was already removed.
+    fn unlink(&mut self) -> bool {
+        // `state` must outlive `_lock`
+        let state = if let Some(state) = self.state.take() {
+            state
+        } else {
+            return false;
+        };
+        let producer = &*self.producer;
+
+        let state_ptr: *mut ConsumerState = (&*state) as *const _ as *mut _;
+
+        let _lock = producer.mutex.lock();
+
+        if producer.complete.load(Ordering::Relaxed)
+            || producer.poisoned.load(Ordering::Relaxed)
+            || producer.taken.load(Ordering::Relaxed)
+        {
+            // The consumer list is not used anymore
+            return false;
+        }
+
+        // If this consumer is the current leader, transfer the leadership
+        // to another consumer
+        let was_leader = producer.leader.load(Ordering::Relaxed) == state_ptr;
+        if was_leader {
+            let new_leader = state.prev_next[1].load(Ordering::Relaxed);
+            if new_leader == state_ptr {
+                // The list is now empty.
+                producer.leader.store(null_mut(), Ordering::Release);
+
+                return true;
+            } else {
+                producer.leader.store(new_leader, Ordering::Release);
+
+                // Wake up the new leader so that the producing `Future`
+                // knows which `Waker` to wake up next
+                if let Some(waker) = &*(unsafe { &*new_leader }.task.lock()) {
+                    waker.wake();
+                }
+            }
+        }
+
+        // Remove this consumer from the list
+        unsafe {
+            let prev = state.prev_next[0].load(Ordering::Relaxed);
+            let next = state.prev_next[1].load(Ordering::Relaxed);
+
+            debug_assert_ne!(prev, state_ptr);
+            debug_assert_ne!(next, state_ptr);
+
+            (&*prev).prev_next[1].store(next, Ordering::Relaxed);
+            (&*next).prev_next[0].store(prev, Ordering::Relaxed);
+        }
+
+        was_leader
+    }
+
+    /// The common part of `poll_ready` and `poll_take`. Returns
+    /// `Poll::Ready(Some(_))` if the result was moved out, or
+    /// `Poll::Ready(None)` if the result is stored in `MultiCastInner`.
+    ///
+    /// `can_take` is called upon completion (while holding the lock) to
+    /// decide whether the result can be moved out. It must return `true` only
+    /// if no one but this consumer can observe `MultiCastInner` anymore.
+    fn poll_core(
+        self: Pin<&mut Self>,
+        waker: &Waker,
+        can_take: Option<fn(&Pin<P>) -> bool>,
+    ) -> Poll<Option<T>> {
         let this = &*self;
         let producer = &*this.producer;
+
         if let Some(state) = &this.state {
             let state_ptr: *mut ConsumerState = (&**state) as *const _ as *mut _;
 
-            if producer.complete.load(Ordering::Acquire) {
-                // We already have the result
-            } else if producer.leader.load(Ordering::Acquire) == state_ptr {
-                // This consumer is responsible for polling the producing `Future`.
+            // Make sure we get woken up when we receive a leadership or the
+            // result is ready
+            register_waker(state, waker);
+
+            loop {
+                if producer.poisoned.load(Ordering::Acquire) {
+                    panic!("the producing Future of MultiCast panicked");
+                }
+
+                if producer.taken.load(Ordering::Acquire) {
+                    panic!("the result of MultiCast has already been taken");
+                }
+
+                if producer.complete.load(Ordering::Acquire) {
+                    // We already have the result
+                    break;
+                }
+
+                if producer.leader.load(Ordering::SeqCst) != state_ptr {
+                    if !steal_leadership(producer, state) {
+                        return Poll::Pending;
+                    }
+                    continue;
+                }
+
+                // This consumer is responsible for polling the producing
+                // `Future`. However, the previous leader might be still
+                // polling it. In this case, the previous leader wakes us up
+                // when it's done.
+                if producer.polling.swap(true, Ordering::SeqCst) {
+                    return Poll::Pending;
+                }
+
+                // The leadership might have been taken over or the previous
+                // leader might have completed the producing `Future` before we
+                // got to this point
+                if producer.leader.load(Ordering::SeqCst) != state_ptr
+                    || producer.complete.load(Ordering::Acquire)
+                    || producer.poisoned.load(Ordering::Acquire)
+                    || producer.taken.load(Ordering::Acquire)
+                {
+                    release_polling(producer, state_ptr);
+                    continue;
+                }
+
+                let generation = producer.generation.fetch_add(1, Ordering::Relaxed) + 1;
+                state.last_generation.store(generation, Ordering::Relaxed);
+                state.num_stalled_polls.store(0, Ordering::Relaxed);
 
-                // `&mut *producer.future.get()` because this consumer is the
-                // current leader.
+                // `&mut *producer.future.get()` because this consumer holds
+                // `MultiCastInner::polling`.
                 // `Pin::new_unchecked` is safe here because we do not move the
                 // contents of `MultiCastInner::future` once `Pin<P>` started
                 // existing and `MultiCastInner` itself is pinned by `Pin<P>`.
                 let inner = unsafe { Pin::new_unchecked(&mut *producer.future.get()) };
 
-                // Poll the future
-                let value = ready!(inner.poll(waker));
+                // Poll the future. If it panics, mark `producer` as poisoned
+                // and wake up all consumers (except `self`) so that they can
+                // observe the poisoned state instead of waiting forever.
+                // `AssertUnwindSafe` is fine here because the producing
+                // `Future` is never touched again after a panic.
+                let value = match catch_unwind(AssertUnwindSafe(|| inner.poll(waker))) {
+                    Ok(Poll::Ready(value)) => value,
+                    Ok(Poll::Pending) => {
+                        release_polling(producer, state_ptr);
+                        return Poll::Pending;
+                    }
+                    Err(payload) => {
+                        let _lock = producer.mutex.lock();
+                        producer.poisoned.store(true, Ordering::Release);
+                        unsafe {
+                            wake_others(state_ptr);
+                        }
+                        drop(_lock);
+
+                        resume_unwind(payload);
+                    }
+                };
 
-                // Store the result and wake up all consumers (except `self`)
                 let _lock = producer.mutex.lock();
+
+                // If no one else can observe the result, move it out
+                if can_take.map(|f| f(&this.producer)) == Some(true) {
+                    // Every other consumer would keep `MultiCastInner`
+                    // observable. The consumer list is valid because we are
+                    // holding the lock.
+                    debug_assert_eq!(state.prev_next[1].load(Ordering::Relaxed), state_ptr);
+
+                    producer.taken.store(true, Ordering::Release);
+                    producer.polling.store(false, Ordering::SeqCst);
+                    return Poll::Ready(Some(value));
+                }
+
+                // Store the result and wake up all consumers (except `self`)
                 unsafe {
                     (&mut *producer.result.get()).set(value);
                     producer.complete.store(true, Ordering::Release);
 
-                    let mut ptr = state.prev_next[1].load(Ordering::Relaxed);
-                    while ptr != state_ptr {
-                        let other_state = &*ptr;
-                        if let Some(waker) = &*other_state.task.lock() {
-                            waker.wake();
-                        }
-                        ptr = other_state.prev_next[1].load(Ordering::Relaxed);
-                    }
+                    wake_others(state_ptr);
                 }
-            } else {
-                // Register the waker
-                let mut waker_cell = state.task.lock();
-
-                if waker_cell.as_ref().map(|w| w.will_wake(waker)) != Some(true) {
-                    *waker_cell = Some(Waker::clone(waker));
-                }
-
-                return Poll::Pending;
+                producer.polling.store(false, Ordering::SeqCst);
+                break;
             }
         } else {
+            if producer.poisoned.load(Ordering::Acquire) {
+                panic!("the producing Future of MultiCast panicked");
+            }
+
+            if producer.taken.load(Ordering::Acquire) {
+                panic!("the result of MultiCast has already been taken");
+            }
+
             // The `Future` was already complete at the point when `subscribe`
             // was called
         }
 
-        let value = unsafe { (&*producer.result.get()).get_ref().clone() };
-        Poll::Ready(value)
+        Poll::Ready(None)
+    }
+}
+
+impl<F: Future<Output = T> + ?Sized, T> ConsumerInner<Arc<MultiCastInner<F, T>>, F, T>
+where
+    F::Output: Clone,
+{
+    /// Poll this consuming `Future`, moving the result out instead of cloning
+    /// it if possible.
+    ///
+    /// The result is moved out if this consumer completes the producing
+    /// `Future` and, at that point, holds the only reference to the
+    /// `MultiCastInner`. This implies there are no other consumers, and no
+    /// consumers can be created afterward, so no one can observe the moved-out
+    /// result. The check is done while holding the internal mutex, which
+    /// `poll` acquires upon completion anyway, so this method is no more
+    /// expensive than `poll`. Otherwise (e.g., other consumers or references
+    /// exist, or the result was already available), this method falls back
+    /// to cloning the result like `poll`.
+    ///
+    /// This method is only available for consumers created from
+    /// `Pin<Arc<MultiCastInner>>` because other kinds of references can't
+    /// prove that the `MultiCastInner` won't be accessed after the consumer is
+    /// gone.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #![feature(futures_api)]
+    /// use futures::{future::{lazy, poll_fn}, executor::block_on};
+    /// use multicastfuture::MultiCast;
+    /// use std::{pin::Pin, sync::Arc};
+    ///
+    /// let mc = Arc::pin(MultiCast::new(lazy(|_| vec![42u8; 1 << 20])));
+    ///
+    /// // `consumer` holds the only reference to `mc`
+    /// let mut consumer = mc.subscribe();
+    ///
+    /// let result = block_on(poll_fn(|waker| Pin::new(&mut consumer).poll_take(waker)));
+    /// assert_eq!(result.len(), 1 << 20);
+    /// assert!(consumer.multi_cast().is_taken());
+    /// ```
+    pub fn poll_take(mut self: Pin<&mut Self>, waker: &Waker) -> Poll<T> {
+        match self.as_mut().poll_core(waker, Some(is_sole_reference)) {
+            Poll::Ready(Some(value)) => Poll::Ready(value),
+            Poll::Ready(None) => Poll::Ready(self.clone_result()),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<P: Deref<Target = MultiCastInner<F, T>>, F: Future<Output = T> + ?Sized, T>
+    ConsumerInner<P, F, T>
+where
+    F::Output: Clone,
+{
+    /// Clone the result. Must be called after `poll_core` returned
+    /// `Poll::Ready(None)`.
+    fn clone_result(&self) -> T {
+        self.producer.result().unwrap().clone()
+    }
+}
+
+impl<P: Deref<Target = MultiCastInner<F, T>>, F: Future<Output = T> + ?Sized, T> Future
+    for ConsumerInner<P, F, T>
+where
+    F::Output: Clone,
+{
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, waker: &Waker) -> Poll<Self::Output> {
+        match self.as_mut().poll_ready(waker) {
+            Poll::Ready(()) => Poll::Ready(self.clone_result()),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// The `Future` returned by [`ConsumerInner::ready`].
+struct ReadyFuture<P: Deref<Target = MultiCastInner<F, T>>, F: Future<Output = T> + ?Sized, T>(
+    ConsumerInner<P, F, T>,
+);
+
+impl<P: Deref<Target = MultiCastInner<F, T>>, F: Future<Output = T> + ?Sized, T> Future
+    for ReadyFuture<P, F, T>
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, waker: &Waker) -> Poll<()> {
+        // `ConsumerInner` doesn't rely on being pinned, but projecting
+        // `Pin` is the easiest way to reuse `poll_ready`
+        unsafe { self.map_unchecked_mut(|this| &mut this.0) }.poll_ready(waker)
     }
 }
 
@@ -379,48 +1077,158 @@ impl<P: Deref<Target = MultiCastInner<F, T>>, F: Future<Output = T> + ?Sized, T>
     for ConsumerInner<P, F, T>
 {
     fn drop(&mut self) {
-        if let Some(state) = &self.state {
-            let producer = &*self.producer;
+        self.unlink();
+    }
+}
 
-            let state_ptr: *mut ConsumerState = (&**state) as *const _ as *mut _;
+/// The number of consecutive polls of a non-leader consumer that didn't
+/// observe any progress of the producing `Future` even after the consumer woke
+/// up the leader, after which the consumer takes over the leadership.
+const STEAL_THRESHOLD: usize = 2;
 
-            let _lock = producer.mutex.lock();
+/// Store `waker` to `state` unless it's already there.
+fn register_waker(state: &ConsumerState, waker: &Waker) {
+    let mut waker_cell = state.task.lock();
 
-            if producer.complete.load(Ordering::Relaxed) {
-                return;
-            }
+    if waker_cell.as_ref().map(|w| w.will_wake(waker)) != Some(true) {
+        *waker_cell = Some(Waker::clone(waker));
+    }
+}
 
-            // If this consumer is the current leader, transfer the leadership
-            // to another consumer
-            if producer.leader.load(Ordering::Relaxed) == state_ptr {
-                let new_leader = state.prev_next[1].load(Ordering::Relaxed);
-                if new_leader == state_ptr {
-                    // The list is now empty.
-                    producer.leader.store(null_mut(), Ordering::Release);
+/// Called when a non-leader consumer is polled. Take over the leadership if
+/// the current leader appears to be stalled. Returns `true` if the calling
+/// consumer is now the leader.
+///
+/// The current leader is deemed stalled if it has never been polled, or it
+/// missed a wakeup. When the calling consumer observes that the producing
+/// `Future` hasn't been polled since its last poll, it wakes up the leader,
+/// which polls the producing `Future` unless it's stalled. If the producing
+/// `Future` still hasn't been polled after `STEAL_THRESHOLD` more polls of the
+/// calling consumer, the leader missed the wakeup.
+///
+/// A leader that is merely waiting for the producing `Future` keeps the
+/// leadership because the producing `Future` is polled every time the leader
+/// is woken up. While checking the leader, the calling consumer wakes itself
+/// up to re-check, so a stalled leader is detected even if nothing else wakes
+/// up the calling consumer.
+fn steal_leadership<F: Future<Output = T> + ?Sized, T>(
+    producer: &MultiCastInner<F, T>,
+    state: &ConsumerState,
+) -> bool {
+    let state_ptr: *mut ConsumerState = state as *const _ as *mut _;
 
-                    return;
-                } else {
-                    producer.leader.store(new_leader, Ordering::Release);
+    let generation = producer.generation.load(Ordering::Relaxed);
+    let num_stalled_polls =
+        if state.last_generation.swap(generation, Ordering::Relaxed) == generation {
+            state.num_stalled_polls.fetch_add(1, Ordering::Relaxed) + 1
+        } else {
+            state.num_stalled_polls.store(0, Ordering::Relaxed);
+            0
+        };
 
-                    // Wake up the new leader so that the producing `Future`
-                    // knows which `Waker` to wake up next
-                    if let Some(waker) = &*(unsafe { &*new_leader }.task.lock()) {
-                        waker.wake();
-                    }
-                }
+    let _lock = producer.mutex.lock();
+
+    if producer.complete.load(Ordering::Relaxed)
+        || producer.poisoned.load(Ordering::Relaxed)
+        || producer.taken.load(Ordering::Relaxed)
+    {
+        // Let the caller observe the new state
+        return true;
+    }
+
+    let leader = producer.leader.load(Ordering::Relaxed);
+    debug_assert!(!leader.is_null());
+    if leader == state_ptr {
+        return true;
+    }
+
+    // `leader` is valid because we are holding the lock
+    let leader_absent = unsafe { &*leader }.task.lock().is_none();
+
+    if leader_absent || num_stalled_polls > STEAL_THRESHOLD {
+        producer.leader.store(state_ptr, Ordering::SeqCst);
+        state.num_stalled_polls.store(0, Ordering::Relaxed);
+        true
+    } else {
+        if num_stalled_polls == 1 {
+            // Check if the leader is still responsive
+            if let Some(waker) = &*unsafe { &*leader }.task.lock() {
+                waker.wake();
+            }
+        }
+        if num_stalled_polls > 0 {
+            if let Some(waker) = &*state.task.lock() {
+                waker.wake();
             }
+        }
+        false
+    }
+}
 
-            // Remove this consumer from the list
-            unsafe {
-                let prev = state.prev_next[0].load(Ordering::Relaxed);
-                let next = state.prev_next[1].load(Ordering::Relaxed);
+/// Release `MultiCastInner::polling`. If the leadership was taken over while
+/// the calling consumer was polling the producing `Future`, wake up the new
+/// leader, which might have given up polling it because of us.
+fn release_polling<F: Future<Output = T> + ?Sized, T>(
+    producer: &MultiCastInner<F, T>,
+    state_ptr: *mut ConsumerState,
+) {
+    producer.polling.store(false, Ordering::SeqCst);
 
-                debug_assert_ne!(prev, state_ptr);
-                debug_assert_ne!(next, state_ptr);
+    if producer.leader.load(Ordering::SeqCst) != state_ptr {
+        let _lock = producer.mutex.lock();
 
-                (&*prev).prev_next[1].store(next, Ordering::Relaxed);
-                (&*next).prev_next[0].store(prev, Ordering::Relaxed);
+        if producer.complete.load(Ordering::Relaxed)
+            || producer.poisoned.load(Ordering::Relaxed)
+            || producer.taken.load(Ordering::Relaxed)
+        {
+            return;
+        }
+
+        let leader = producer.leader.load(Ordering::Relaxed);
+        if !leader.is_null() && leader != state_ptr {
+            // `leader` is valid because we are holding the lock
+            if let Some(waker) = &*unsafe { &*leader }.task.lock() {
+                waker.wake();
             }
         }
     }
 }
+
+/// Check if `producer` is the only reference to `MultiCastInner`.
+fn is_sole_reference<F: Future<Output = T> + ?Sized, T>(
+    producer: &Pin<Arc<MultiCastInner<F, T>>>,
+) -> bool {
+    // `Pin` is `#[repr(transparent)]`. Borrowing the `Arc` this way doesn't
+    // move the pointee.
+    let producer: &Arc<MultiCastInner<F, T>> = unsafe { &*(producer as *const _ as *const _) };
+    Arc::strong_count(producer) == 1
+}
+
+/// Count the consumers in the list including `state_ptr`.
+///
+/// The caller must hold the lock of `MultiCastInner::mutex`, and `state_ptr`
+/// must point to a `ConsumerState` in the list.
+unsafe fn count_consumers(state_ptr: *mut ConsumerState) -> usize {
+    let mut count = 1;
+    let mut ptr = (&*state_ptr).prev_next[1].load(Ordering::Relaxed);
+    while ptr != state_ptr {
+        count += 1;
+        ptr = (&*ptr).prev_next[1].load(Ordering::Relaxed);
+    }
+    count
+}
+
+/// Wake up all consumers in the list except `state_ptr`.
+///
+/// The caller must hold the lock of `MultiCastInner::mutex`, and `state_ptr`
+/// must point to a `ConsumerState` in the list.
+unsafe fn wake_others(state_ptr: *mut ConsumerState) {
+    let mut ptr = (&*state_ptr).prev_next[1].load(Ordering::Relaxed);
+    while ptr != state_ptr {
+        let other_state = &*ptr;
+        if let Some(waker) = &*other_state.task.lock() {
+            waker.wake();
+        }
+        ptr = other_state.prev_next[1].load(Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,74 @@
+#![feature(futures_api)]
+//! Pins the auto trait implementations of `MultiCast` and its consumers.
+//! The negative cases are covered by the `compile_fail` examples in the crate
+//! documentation.
+use futures::future::Ready;
+use multicastfuture::{Consumer, MultiCast};
+use std::{
+    panic::{RefUnwindSafe, UnwindSafe},
+    pin::Pin,
+    sync::Arc,
+};
+
+fn assert_send<T: ?Sized + Send>() {}
+fn assert_sync<T: ?Sized + Sync>() {}
+fn assert_ref_unwind_safe<T: ?Sized + RefUnwindSafe>() {}
+fn assert_unwind_safe<T: ?Sized + UnwindSafe>() {}
+
+type Mc = MultiCast<Ready<u32>>;
+
+#[test]
+fn multicast_send_sync() {
+    assert_send::<Mc>();
+    assert_sync::<Mc>();
+}
+
+#[test]
+fn multicast_unwind_safe() {
+    assert_ref_unwind_safe::<Mc>();
+    assert_unwind_safe::<Mc>();
+    assert_unwind_safe::<&Mc>();
+}
+
+#[test]
+fn consumer_send_sync() {
+    assert_send::<Consumer<&Mc, Ready<u32>>>();
+    assert_sync::<Consumer<&Mc, Ready<u32>>>();
+    assert_send::<Consumer<Arc<Mc>, Ready<u32>>>();
+    assert_sync::<Consumer<Arc<Mc>, Ready<u32>>>();
+}
+
+#[test]
+fn consumer_is_thread_movable() {
+    let mc = MultiCast::new_arc(futures::future::ready(42u32));
+    let consumer = Pin::clone(&mc).subscribe();
+    let value = std::thread::spawn(move || futures::executor::block_on(consumer))
+        .join()
+        .unwrap();
+    assert_eq!(value, 42);
+}
+
+#[test]
+fn consumers_on_thread_pool() {
+    use futures::{channel::oneshot, executor::ThreadPool, prelude::*, task::SpawnExt};
+
+    let mut pool = ThreadPool::new().unwrap();
+    let (send, recv) = oneshot::channel::<u32>();
+    let mc = MultiCast::new_arc(recv.map(|x| x.unwrap()));
+
+    let results: Vec<_> = (0..8)
+        .map(|_| {
+            let (result_send, result_recv) = oneshot::channel();
+            let consumer = mc.subscribe_arc();
+            pool.spawn(consumer.map(move |x| result_send.send(x).unwrap()))
+                .unwrap();
+            result_recv
+        })
+        .collect();
+
+    send.send(42).unwrap();
+
+    for result in results {
+        assert_eq!(futures::executor::block_on(result), Ok(42));
+    }
+}
@@ -1,7 +1,29 @@
 #![feature(futures_api)]
-use futures::{executor::block_on, future::lazy, prelude::*};
-use multicastfuture::MultiCast;
-use std::{marker::Unpin, pin::Pin};
+use futures::{
+    channel::oneshot,
+    executor::block_on,
+    future::{self, lazy},
+    prelude::*,
+    task::ArcWake,
+    Poll,
+};
+use multicastfuture::{BoxMultiCast, MultiCast};
+use std::{
+    marker::Unpin,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+struct NoopWaker;
+
+impl ArcWake for NoopWaker {
+    fn wake(_: &Arc<Self>) {}
+}
 
 #[test]
 fn consumers_one() {
@@ -19,7 +41,6 @@ fn consumers_two_1() {
     assert_eq!(block_on(con2), 42);
 }
 
-/* Will block indefinitely
 #[test]
 fn consumers_two_2() {
     let mc = MultiCast::new(lazy(|_| 42));
@@ -28,7 +49,6 @@ fn consumers_two_2() {
     assert_eq!(block_on(con2), 42);
     assert_eq!(block_on(con1), 42);
 }
-*/
 
 #[test]
 fn consumers_two_3() {
@@ -57,6 +77,124 @@ fn consumers_three() {
     assert_eq!(block_on(con3), 42);
 }
 
+#[test]
+fn stalled_leader() {
+    let (send, recv) = oneshot::channel();
+    let mc = MultiCast::new(recv.map(|x: Result<u32, _>| x.unwrap()));
+    let waker = ArcWake::into_waker(Arc::new(NoopWaker));
+
+    // `con1` becomes the leader and polls the producing `Future`, but it's
+    // never polled again
+    let mut con1 = Pin::new(&mc).subscribe();
+    assert_eq!(Pin::new(&mut con1).poll(&waker), Poll::Pending);
+
+    let con2 = Pin::new(&mc).subscribe();
+    send.send(42).unwrap();
+
+    // `con2` takes over the leadership
+    assert_eq!(block_on(con2), 42);
+    assert_eq!(mc.result(), Some(&42));
+}
+
+#[test]
+fn stalled_leader_send_later() {
+    let (send, recv) = oneshot::channel();
+    let mc = MultiCast::new_arc(recv.map(|x: Result<u32, _>| x.unwrap()));
+    let waker = ArcWake::into_waker(Arc::new(NoopWaker));
+
+    let mut con1 = mc.subscribe_arc();
+    assert_eq!(Pin::new(&mut con1).poll(&waker), Poll::Pending);
+
+    // `con2` takes over the leadership before the value is sent. The
+    // producing `Future` must wake up `con2` (not `con1`) when it's sent.
+    let con2 = mc.subscribe_arc();
+    let thread = thread::spawn(move || block_on(con2));
+
+    thread::sleep(Duration::from_millis(50));
+    send.send(42).unwrap();
+
+    assert_eq!(thread.join().unwrap(), 42);
+
+    // `con1` is not the leader anymore, but it still gets the result
+    assert_eq!(Pin::new(&mut con1).poll(&waker), Poll::Ready(42));
+}
+
+#[test]
+fn stalled_leader_many_threads() {
+    let (send, recv) = oneshot::channel();
+    let mc = MultiCast::new_arc(recv.map(|x: Result<u32, _>| x.unwrap()));
+    let waker = ArcWake::into_waker(Arc::new(NoopWaker));
+
+    let mut stalled = mc.subscribe_arc();
+    assert_eq!(Pin::new(&mut stalled).poll(&waker), Poll::Pending);
+
+    // The consumers compete for the leadership
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let consumer = mc.subscribe_arc();
+            thread::spawn(move || block_on(consumer))
+        })
+        .collect();
+
+    thread::sleep(Duration::from_millis(50));
+    send.send(42).unwrap();
+
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), 42);
+    }
+}
+
+/// A waker counting the number of times it was woken up.
+struct CountWaker(AtomicUsize);
+
+impl ArcWake for CountWaker {
+    fn wake(arc_self: &Arc<Self>) {
+        arc_self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn pending_leader() {
+    let last_waker = Arc::new(Mutex::new(None));
+    let ready = Arc::new(AtomicBool::new(false));
+    let mc = {
+        let last_waker = Arc::clone(&last_waker);
+        let ready = Arc::clone(&ready);
+        MultiCast::new(future::poll_fn(move |waker| {
+            *last_waker.lock().unwrap() = Some(waker.clone());
+            if ready.load(Ordering::Relaxed) {
+                Poll::Ready(42)
+            } else {
+                Poll::Pending
+            }
+        }))
+    };
+    let leader_wake_count = Arc::new(CountWaker(AtomicUsize::new(0)));
+    let leader_waker = ArcWake::into_waker(Arc::clone(&leader_wake_count));
+    let waker = ArcWake::into_waker(Arc::new(NoopWaker));
+
+    let mut con1 = Pin::new(&mc).subscribe();
+    let mut con2 = Pin::new(&mc).subscribe();
+    assert_eq!(Pin::new(&mut con1).poll(&leader_waker), Poll::Pending);
+
+    for _ in 0..10 {
+        assert_eq!(Pin::new(&mut con2).poll(&waker), Poll::Pending);
+
+        // `con1` is polled whenever it's woken up, so it's not stalled
+        if leader_wake_count.0.swap(0, Ordering::Relaxed) > 0 {
+            assert_eq!(Pin::new(&mut con1).poll(&leader_waker), Poll::Pending);
+        }
+    }
+
+    // `con1` is still the leader
+    let producer_waker = last_waker.lock().unwrap().take().unwrap();
+    assert!(producer_waker.will_wake(&leader_waker));
+
+    ready.store(true, Ordering::Relaxed);
+    assert_eq!(Pin::new(&mut con1).poll(&leader_waker), Poll::Ready(42));
+    assert_eq!(Pin::new(&mut con2).poll(&waker), Poll::Ready(42));
+}
+
 #[test]
 fn delete_leader() {
     let mc = MultiCast::new(lazy(|_| 42));
@@ -86,6 +224,49 @@ fn delete_all() {
     assert_eq!(block_on(con3), 42);
 }
 
+#[test]
+fn unsubscribe_leader() {
+    let (send, recv) = oneshot::channel();
+    let mc = MultiCast::new(recv.map(|x: Result<u32, _>| x.unwrap()));
+    let waker = ArcWake::into_waker(Arc::new(NoopWaker));
+
+    let mut con1 = Pin::new(&mc).subscribe();
+    let con2 = Pin::new(&mc).subscribe();
+    let con3 = Pin::new(&mc).subscribe();
+
+    // `con1` becomes the leader
+    assert_eq!(Pin::new(&mut con1).poll(&waker), Poll::Pending);
+
+    assert!(con1.unsubscribe());
+
+    // The leadership is transferred to one of the remaining consumers
+    send.send(42).unwrap();
+    assert_eq!(block_on(con2.join(con3)), (42, 42));
+}
+
+#[test]
+fn unsubscribe_nonleader() {
+    let mc = MultiCast::new(lazy(|_| 42));
+    let con1 = Pin::new(&mc).subscribe();
+    let con2 = Pin::new(&mc).subscribe();
+    assert!(!con2.unsubscribe());
+    assert_eq!(block_on(con1), 42);
+}
+
+#[test]
+fn unsubscribe_last() {
+    let mc = MultiCast::new(lazy(|_| 42));
+    let con1 = Pin::new(&mc).subscribe();
+    assert!(con1.unsubscribe());
+
+    let con2 = Pin::new(&mc).subscribe();
+    assert_eq!(block_on(con2), 42);
+
+    // The consumer list is not used after completion
+    let con3 = Pin::new(&mc).subscribe();
+    assert!(!con3.unsubscribe());
+}
+
 #[test]
 fn already_has_result() {
     let mc = MultiCast::new(lazy(|_| 42));
@@ -102,3 +283,275 @@ fn unsize() {
     let con1 = Pin::new(mc).subscribe();
     assert_eq!(block_on(con1), 42);
 }
+
+#[test]
+fn boxed() {
+    let producer: Box<dyn Future<Output = u32> + Send> = Box::new(lazy(|_| 42u32));
+    let mc = BoxMultiCast::new_boxed(producer);
+    let con1 = Pin::new(&mc).subscribe();
+    let con2 = Pin::new(&mc).subscribe();
+    assert_eq!(block_on(con1.join(con2)), (42, 42));
+}
+
+#[test]
+fn consumer_map() {
+    let mc = MultiCast::new(lazy(|_| 21));
+    let con1 = Pin::new(&mc).subscribe().map(|x| x * 2);
+    let con2 = Pin::new(&mc).subscribe().map(|x| x.to_string());
+    assert_eq!(block_on(con1.join(con2)), (42, "21".to_owned()));
+}
+
+#[test]
+fn debug_consumer_count() {
+    let mc = MultiCast::new(future::ready(42));
+    let debug = || format!("{:?}", mc);
+    assert!(debug().contains("has_leader: false"), "{}", debug());
+    assert!(debug().contains("consumer_count: 0"), "{}", debug());
+
+    let con1 = Pin::new(&mc).subscribe();
+    let con2 = Pin::new(&mc).subscribe();
+    assert!(debug().contains("has_leader: true"), "{}", debug());
+    assert!(debug().contains("consumer_count: 2"), "{}", debug());
+
+    drop(con1);
+    assert!(debug().contains("has_leader: true"), "{}", debug());
+    assert!(debug().contains("consumer_count: 1"), "{}", debug());
+
+    assert_eq!(block_on(con2), 42);
+    assert!(debug().contains("complete: true"), "{}", debug());
+}
+
+#[test]
+fn map_result_in_place() {
+    let mut mc = MultiCast::new(lazy(|_| 42));
+    assert!(mc.map_result_in_place(|x| *x = 1).is_err());
+
+    let con1 = Pin::new(&mc).subscribe();
+    assert_eq!(block_on(con1), 42);
+
+    assert!(mc.map_result_in_place(|x| *x += 1).is_ok());
+    assert_eq!(mc.result(), Some(&43));
+
+    let con2 = Pin::new(&mc).subscribe();
+    assert_eq!(block_on(con2), 43);
+}
+
+#[test]
+fn arc_two_threads() {
+    let mc = MultiCast::new_arc(lazy(|_| 42));
+    let con1 = mc.clone().subscribe();
+    let con2 = mc.clone().subscribe();
+    let thread1 = std::thread::spawn(move || block_on(con1));
+    let thread2 = std::thread::spawn(move || block_on(con2));
+    assert_eq!(thread1.join().unwrap(), 42);
+    assert_eq!(thread2.join().unwrap(), 42);
+    assert_eq!(mc.result(), Some(&42));
+}
+
+#[test]
+fn panicking_producer() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let mc = MultiCast::new(lazy(|_| -> u32 { panic!("producer panicked") }));
+    let con1 = Pin::new(&mc).subscribe();
+    let con2 = Pin::new(&mc).subscribe();
+    assert!(catch_unwind(AssertUnwindSafe(|| block_on(con1))).is_err());
+    assert!(mc.is_poisoned());
+    assert!(!mc.is_complete());
+    assert!(catch_unwind(AssertUnwindSafe(|| block_on(con2))).is_err());
+
+    // Consumers created after the panic must not stall either
+    let con3 = Pin::new(&mc).subscribe();
+    assert!(catch_unwind(AssertUnwindSafe(|| block_on(con3))).is_err());
+}
+
+#[test]
+fn is_complete_relaxed() {
+    let (send, recv) = oneshot::channel();
+    let mc = MultiCast::new_arc(recv.map(|x: Result<u32, _>| x.unwrap()));
+    assert!(!mc.is_complete_relaxed());
+
+    let con1 = mc.subscribe_arc();
+    let thread = thread::spawn(move || block_on(con1));
+    send.send(42).unwrap();
+
+    // Eventually observes the completion
+    while !mc.is_complete_relaxed() {
+        thread::yield_now();
+    }
+    assert_eq!(mc.result(), Some(&42));
+
+    assert_eq!(thread.join().unwrap(), 42);
+}
+
+#[test]
+fn arc_panicking_producer() {
+    let (send, recv) = futures::channel::oneshot::channel::<()>();
+    let mc = MultiCast::new_arc(recv.map(|_| -> u32 { panic!("producer panicked") }));
+    let con1 = mc.clone().subscribe();
+    let con2 = mc.clone().subscribe();
+    let thread1 = std::thread::spawn(move || block_on(con1));
+    let thread2 = std::thread::spawn(move || block_on(con2));
+    send.send(()).unwrap();
+    assert!(thread1.join().is_err());
+    assert!(thread2.join().is_err());
+    assert!(mc.is_poisoned());
+}
+
+#[test]
+fn arc_subscribe_arc() {
+    let mc = MultiCast::new_arc(lazy(|_| 42));
+    let con1 = mc.subscribe_arc();
+    let con2 = mc.subscribe_arc();
+    assert_eq!(block_on(con1.join(con2)), (42, 42));
+    assert_eq!(block_on(mc.subscribe_arc()), 42);
+}
+
+/// A large value counting the number of times it was cloned.
+struct CountClone(Arc<AtomicUsize>, Box<[u8]>);
+
+impl Clone for CountClone {
+    fn clone(&self) -> Self {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        CountClone(Arc::clone(&self.0), self.1.clone())
+    }
+}
+
+fn new_count_clone_mc(
+    count: &Arc<AtomicUsize>,
+) -> MultiCast<impl Future<Output = CountClone> + Unpin> {
+    let count = Arc::clone(count);
+    MultiCast::new(lazy(move |_| CountClone(count, vec![42; 1 << 20].into())))
+}
+
+#[test]
+fn poll_take_sole() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let mc = Arc::pin(new_count_clone_mc(&count));
+
+    // `con1` holds the only reference to `mc`
+    let mut con1 = mc.subscribe();
+    let value = block_on(future::poll_fn(|waker| {
+        Pin::new(&mut con1).poll_take(waker)
+    }));
+    assert_eq!(value.1[0], 42);
+    assert_eq!(count.load(Ordering::Relaxed), 0);
+
+    let mc = con1.multi_cast();
+    assert!(mc.is_taken());
+    assert!(!mc.is_complete());
+    assert!(mc.result().is_none());
+}
+
+#[test]
+fn poll_take_not_sole() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let mc = Arc::pin(new_count_clone_mc(&count));
+
+    let mut con1 = mc.subscribe_arc();
+    let con2 = mc.subscribe_arc();
+
+    // Falls back to `clone` because `con2` exists
+    let value = block_on(future::poll_fn(|waker| {
+        Pin::new(&mut con1).poll_take(waker)
+    }));
+    assert_eq!(value.1[0], 42);
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+    assert!(!mc.is_taken());
+
+    assert_eq!(block_on(con2).1[0], 42);
+    assert_eq!(count.load(Ordering::Relaxed), 2);
+    assert!(mc.result().is_some());
+}
+
+#[test]
+fn poll_take_subscribe_after() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let mc = Arc::pin(new_count_clone_mc(&count));
+
+    // Falls back to `clone` because `mc` might be used to create more
+    // consumers
+    let mut con1 = mc.subscribe_arc();
+    let value = block_on(future::poll_fn(|waker| {
+        Pin::new(&mut con1).poll_take(waker)
+    }));
+    assert_eq!(value.1[0], 42);
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+    assert!(!mc.is_taken());
+    drop(con1);
+
+    // ...which indeed happens here
+    let con2 = mc.subscribe_arc();
+    assert_eq!(block_on(con2).1[0], 42);
+    assert_eq!(count.load(Ordering::Relaxed), 2);
+    assert!(mc.result().is_some());
+}
+
+#[test]
+fn poll_take_already_complete() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let mc = Arc::pin(new_count_clone_mc(&count));
+
+    block_on(mc.subscribe_arc());
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+
+    // The result is already stored in `mc`, so it's cloned
+    let mut con2 = mc.subscribe();
+    block_on(future::poll_fn(|waker| {
+        Pin::new(&mut con2).poll_take(waker)
+    }));
+    assert_eq!(count.load(Ordering::Relaxed), 2);
+    assert!(!con2.multi_cast().is_taken());
+}
+
+#[test]
+fn poll_ready_leader() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let num_polls = Arc::new(AtomicUsize::new(0));
+    let mc = {
+        let count = Arc::clone(&count);
+        let num_polls = Arc::clone(&num_polls);
+        MultiCast::new(future::poll_fn(move |_| {
+            num_polls.fetch_add(1, Ordering::Relaxed);
+            Poll::Ready(CountClone(Arc::clone(&count), vec![42; 1 << 20].into()))
+        }))
+    };
+
+    let mut con1 = Pin::new(&mc).subscribe();
+    let con2 = Pin::new(&mc).subscribe();
+
+    // `con1` is the leader, so it polls the producing `Future`
+    block_on(future::poll_fn(|waker| {
+        Pin::new(&mut con1).poll_ready(waker)
+    }));
+    assert_eq!(num_polls.load(Ordering::Relaxed), 1);
+    assert!(mc.is_complete());
+    assert_eq!(con1.multi_cast().result().unwrap().1[0], 42);
+    assert_eq!(count.load(Ordering::Relaxed), 0);
+
+    // Dropping `con1` after readiness does not disturb `con2`
+    drop(con1);
+    assert_eq!(block_on(con2).1[0], 42);
+    assert_eq!(num_polls.load(Ordering::Relaxed), 1);
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn ready_non_clone() {
+    struct NonClone(u32);
+
+    let (send, recv) = oneshot::channel();
+    let mc = MultiCast::new(recv.map(|x| NonClone(x.unwrap())));
+
+    let con1 = Pin::new(&mc).subscribe().ready();
+    let con2 = Pin::new(&mc).subscribe().ready();
+
+    let thread = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        send.send(42).unwrap();
+    });
+    block_on(con1.join(con2));
+    thread.join().unwrap();
+
+    assert_eq!(mc.result().unwrap().0, 42);
+}
@@ -6,11 +6,18 @@
 //! Provides container types that provide implementations for `PartialEq` and
 //! `Eq` based on a referential equality.
 //!
+//! [`RefEqWeak`] is a weak counterpart of [`RefEqArc`]. It is useful for
+//! cache keys that must not keep the referent alive.
+//!
+//! [`RefEqWeak`]: struct.RefEqWeak.html
+//! [`RefEqArc`]: struct.RefEqArc.html
+//!
 //! # Nightly Rust
 //!
 //! The `nightly` feature enables the implementations of `CoerceUnsized` on
-//! `RefEqArc` and `RefEqBox`, thus allowing unsizing these containers (e.g.,
-//! `RefEqArc<u32>` to `RefEqArc<Any>`). This requires a nightly Rust compiler.
+//! `RefEqArc`, `RefEqWeak`, and `RefEqBox`, thus allowing unsizing these
+//! containers (e.g., `RefEqArc<u32>` to `RefEqArc<Any>`). This requires a
+//! nightly Rust compiler.
 #![cfg_attr(feature = "nightly", feature(unsize, coerce_unsized))]
 use std::hash::Hasher;
 #[cfg(feature = "nightly")]
@@ -18,7 +25,7 @@ use std::marker::Unsize;
 use std::ops::Deref;
 #[cfg(feature = "nightly")]
 use std::ops::CoerceUnsized;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use std::ptr;
 
 /// Compute a hash value based on the referential equality on references.
@@ -106,6 +113,75 @@ impl<T: ?Sized> RefEqArc<T> {
     pub fn into_arc(this: Self) -> Arc<T> {
         this.0
     }
+
+    /// Create a `RefEqWeak` pointing to the same object.
+    pub fn downgrade(this: &Self) -> RefEqWeak<T> {
+        RefEqWeak {
+            weak: Arc::downgrade(&this.0),
+            ptr: Self::ptr_id(this),
+        }
+    }
+
+    /// Return the address of the referent, which uniquely identifies it
+    /// while it is alive.
+    pub fn ptr_id(this: &Self) -> usize {
+        &*this.0 as *const T as *const () as usize
+    }
+}
+
+/// `Weak` wrapper that provides a referential equality.
+///
+/// The equality and hash value are based on the address of the referent
+/// captured by [`RefEqArc::downgrade`], so they stay the same even after the
+/// referent was dropped. A `RefEqWeak` compares equal to and has the same hash
+/// value as any other `RefEqWeak` created from the same object. Its hash value
+/// is also identical to that of the originating `RefEqArc`.
+///
+/// The backing allocation is not released while any `RefEqWeak` to it is
+/// alive, so two `RefEqWeak`s derived from different objects never compare
+/// equal as long as both of them exist.
+///
+/// [`RefEqArc::downgrade`]: struct.RefEqArc.html#method.downgrade
+#[derive(Debug)]
+pub struct RefEqWeak<T: ?Sized> {
+    weak: Weak<T>,
+    ptr: usize,
+}
+
+impl<T: ?Sized> RefEqWeak<T> {
+    /// Attempt to upgrade the weak pointer to a `RefEqArc`. Returns `None` if
+    /// the referent has already been dropped.
+    pub fn upgrade(&self) -> Option<RefEqArc<T>> {
+        self.weak.upgrade().map(RefEqArc)
+    }
+
+    /// Return the address of the referent captured at the time when this
+    /// `RefEqWeak` was created. Equal to `RefEqArc::ptr_id` of the originating
+    /// `RefEqArc`.
+    pub fn ptr_id(&self) -> usize {
+        self.ptr
+    }
+}
+
+impl<T: ?Sized> PartialEq for RefEqWeak<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr
+    }
+}
+impl<T: ?Sized> Eq for RefEqWeak<T> {}
+impl<T: ?Sized> ::std::hash::Hash for RefEqWeak<T> {
+    fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {
+        state.write_usize(self.ptr);
+    }
+}
+
+impl<T: ?Sized> Clone for RefEqWeak<T> {
+    fn clone(&self) -> Self {
+        RefEqWeak {
+            weak: self.weak.clone(),
+            ptr: self.ptr,
+        }
+    }
 }
 
 #[cfg(feature = "nightly")]
@@ -114,6 +190,9 @@ impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<RefEqBox<U>> for RefEqBox<T
 #[cfg(feature = "nightly")]
 impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<RefEqArc<U>> for RefEqArc<T> {}
 
+#[cfg(feature = "nightly")]
+impl<T: ?Sized + Unsize<U>, U: ?Sized> CoerceUnsized<RefEqWeak<U>> for RefEqWeak<T> {}
+
 impl<T: ?Sized> Clone for RefEqArc<T> {
     fn clone(&self) -> Self {
         RefEqArc(self.0.clone())
@@ -0,0 +1,110 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use std::fmt;
+use std::thread::{self, Thread};
+
+use StickyMutex;
+
+/// A barrier enabling multiple threads to synchronize the beginning of some
+/// computation.
+///
+/// This is functionally equivalent to `std::sync::Barrier`, but is built on
+/// top of [`StickyMutex`] and thread parking instead of a condition variable.
+/// A barrier can be reused after all threads passed through it.
+///
+///     use stickylock::StickyBarrier;
+///     use std::sync::Arc;
+///     use std::thread;
+///
+///     let barrier = Arc::new(StickyBarrier::new(4));
+///     let handles: Vec<_> = (0..4)
+///         .map(|_| {
+///             let barrier = Arc::clone(&barrier);
+///             thread::spawn(move || barrier.wait())
+///         })
+///         .collect();
+///
+///     // Exactly one thread is chosen as the leader
+///     let num_leaders = handles
+///         .into_iter()
+///         .map(|h| h.join().unwrap())
+///         .filter(|&is_leader| is_leader)
+///         .count();
+///     assert_eq!(num_leaders, 1);
+///
+/// [`StickyMutex`]: struct.StickyMutex.html
+pub struct StickyBarrier {
+    state: StickyMutex<BarrierState>,
+    num_threads: usize,
+}
+
+struct BarrierState {
+    /// The number of threads waiting on the current generation.
+    count: usize,
+    /// Incremented every time all threads passed through the barrier.
+    generation: usize,
+    /// Threads to be unparked when the current generation is complete.
+    waiters: Vec<Thread>,
+}
+
+impl fmt::Debug for StickyBarrier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StickyBarrier")
+            .field("num_threads", &self.num_threads)
+            .finish()
+    }
+}
+
+impl StickyBarrier {
+    /// Construct a `StickyBarrier` that blocks `n - 1` threads calling `wait`
+    /// and wakes up all of them when the `n`-th thread calls `wait`.
+    ///
+    /// `n = 0` is treated in the same way as `n = 1`.
+    pub fn new(n: usize) -> Self {
+        Self {
+            state: StickyMutex::new(BarrierState {
+                count: 0,
+                generation: 0,
+                waiters: Vec::with_capacity(n.saturating_sub(1)),
+            }),
+            num_threads: n,
+        }
+    }
+
+    /// Block the current thread until all threads have rendezvoused here.
+    ///
+    /// Returns `true` for exactly one thread (the last one to arrive) of each
+    /// generation, and `false` for all the others.
+    pub fn wait(&self) -> bool {
+        let generation = {
+            let mut state = self.state.lock();
+            state.count += 1;
+
+            if state.count >= self.num_threads {
+                // We are the leader. Start a new generation and wake up the
+                // other threads
+                state.count = 0;
+                state.generation = state.generation.wrapping_add(1);
+                for waiter in state.waiters.drain(..) {
+                    waiter.unpark();
+                }
+                return true;
+            }
+
+            state.waiters.push(thread::current());
+            state.generation
+        };
+
+        // `park` may return spuriously, so check the generation every time
+        loop {
+            thread::park();
+
+            if self.state.lock().generation != generation {
+                return false;
+            }
+        }
+    }
+}
@@ -6,7 +6,8 @@
 //! A mutex that adds another mode of explicit locking to satisfy a specific
 //! performance and security need.
 //!
-//! This crate provides a mutex type [`StickyMutex`].
+//! This crate provides a mutex type [`StickyMutex`], and a barrier type
+//! [`StickyBarrier`] built on top of it.
 //! In addition to the normal operation of a reentrant mutex, it supports
 //! transitioning into an intermediate state, which we call the *sticky* state.
 //! In this state, a thread has a lock acquired on it, but doesn't hold a
@@ -58,12 +59,38 @@
 //!   only up to one thread can maintain a lock (normal/sticky) on a mutex.
 //! - **Locked**. A thread owns a lock and holds a lock guard, and can access
 //!   the contained value using the lock guard.
+//! - **Shared**. One or more threads hold shared lock guards created by
+//!   `borrow_ref`, and can access the contained value immutably using the
+//!   lock guards.
 //!
 //! Sticky lock is *recursive* - you can call `stick` as many times as
 //! you want, as long as it's matched by the same number of calls to `unstick`.
 //! Furthermore, you can call `stick`/`unstick` while at the same time holding a
 //! normal lock.
 //!
+//! # Shared access
+//!
+//! `borrow_ref` provides immutable access to the contained value without
+//! blocking other threads doing the same, which is useful for read-heavy use
+//! cases. Internally, `StickyMutex` is built on a read-write lock; a normal or
+//! sticky lock corresponds to its write lock, and `borrow_ref` acquires its
+//! read lock.
+//!
+//! If the calling thread already owns a sticky lock, `borrow_ref` succeeds
+//! immediately (and fast) just like `lock`. In this case, the sticky lock is
+//! not released until all shared lock guards are dropped even if the sticky
+//! lock count reaches zero.
+//!
+//!     use stickylock::StickyMutex;
+//!     let m = StickyMutex::new(42);
+//!     {
+//!         let x = m.borrow_ref();
+//!         let y = m.borrow_ref();
+//!         assert_eq!(*x + *y, 84);
+//!     }
+//!     *m.lock() += 1;
+//!     assert_eq!(*m.borrow_ref(), 43);
+//!
 //! # Comparison with `ReentrantMutex`
 //!
 //! In some ways, the mutex provided by this crate bears some resemblance to
@@ -122,6 +149,9 @@
 //!    or might be transfered to another thread that happens to have the same
 //!    identifier (which in practice only happens on a 32-bit architecture).
 //!  - Poisoning is not implemented.
+//!  - Calling `lock` or `stick` while holding a shared lock guard which was
+//!    acquired without owning a sticky lock results in a dead-lock, just like
+//!    upgrading a read lock of a normal read-write lock.
 //!
 //! # Implementation notes
 //!
@@ -140,7 +170,9 @@ extern crate parking_lot;
 #[cfg(feature = "stable_deref_trait")]
 extern crate stable_deref_trait;
 
+mod barrier;
 mod mutex_core;
+pub use barrier::StickyBarrier;
 use mutex_core::StickyMutexCore;
 pub use mutex_core::UnstickError;
 
@@ -148,16 +180,50 @@ use std::cell::UnsafeCell;
 use std::fmt;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 /// A mutex type that supports holding a lock without holding a lock guard.
 ///
 /// See [the crate-level documentation] for details.
 ///
 /// [the crate-level documentation]: index.html
+///
+/// # Trait objects
+///
+/// `StickyMutex<T>` supports unsized coercions on `T` when it's placed behind
+/// a pointer, so a trait object can be protected by creating a
+/// `StickyMutex<T>` with a concrete type and then converting the pointer:
+///
+///     use stickylock::StickyMutex;
+///     use std::sync::Arc;
+///
+///     trait Counter: Send {
+///         fn increment(&mut self) -> u32;
+///     }
+///
+///     struct MyCounter(u32);
+///     impl Counter for MyCounter {
+///         fn increment(&mut self) -> u32 {
+///             self.0 += 1;
+///             self.0
+///         }
+///     }
+///
+///     let counter: Arc<StickyMutex<dyn Counter>> =
+///         Arc::new(StickyMutex::new(MyCounter(0)));
+///
+///     assert_eq!(counter.lock().increment(), 1);
+///     assert_eq!(counter.lock().increment(), 2);
+///
+/// There is no constructor that takes a `Box<T>` of an unsized type because
+/// the value would have to be moved into a new allocation together with the
+/// lock state, which is impossible without knowing its concrete type.
 pub struct StickyMutex<T: ?Sized> {
     core: StickyMutexCore,
     borrowed: AtomicBool,
+    /// The number of shared lock guards created while the current owner
+    /// thread owns the lock.
+    owner_shared_borrows: AtomicUsize,
     data: UnsafeCell<T>,
 }
 
@@ -170,6 +236,7 @@ impl<T> StickyMutex<T> {
         Self {
             core: StickyMutexCore::new(),
             borrowed: AtomicBool::new(false),
+            owner_shared_borrows: AtomicUsize::new(0),
             data: UnsafeCell::new(x),
         }
     }
@@ -220,7 +287,12 @@ impl<T: ?Sized> StickyMutex<T> {
     /// Decrease the sticky lock count. Release a sticky lock if the count
     /// reaches zero.
     pub fn unstick(&self) -> Result<(), UnstickError> {
-        unsafe { self.core.unstick(|| self.borrowed.load(Ordering::Relaxed)) }
+        unsafe {
+            self.core.unstick(|| {
+                self.borrowed.load(Ordering::Relaxed)
+                    || self.owner_shared_borrows.load(Ordering::Relaxed) > 0
+            })
+        }
     }
 
     /// Acquire a lock, blocking the current thread until it is able to do so.
@@ -234,7 +306,8 @@ impl<T: ?Sized> StickyMutex<T> {
     ///
     /// # Panics
     ///
-    /// Panics if it is already locked by the current thread.
+    /// Panics if it is already locked by the current thread, or the current
+    /// thread holds a shared lock guard created while it owned a sticky lock.
     pub fn lock(&self) -> StickyMutexGuard<T> {
         self.core.lock();
 
@@ -242,6 +315,9 @@ impl<T: ?Sized> StickyMutex<T> {
         if self.borrowed.load(Ordering::Relaxed) {
             panic!("already locked by the current thread");
         }
+        if self.owner_shared_borrows.load(Ordering::Relaxed) > 0 {
+            panic!("already borrowed by the current thread");
+        }
         self.borrowed.store(true, Ordering::Relaxed);
 
         StickyMutexGuard(self, PhantomData)
@@ -257,7 +333,9 @@ impl<T: ?Sized> StickyMutex<T> {
         }
 
         // Check the uniqueness of mutable reference
-        if self.borrowed.load(Ordering::Relaxed) {
+        if self.borrowed.load(Ordering::Relaxed)
+            || self.owner_shared_borrows.load(Ordering::Relaxed) > 0
+        {
             return None;
         }
         self.borrowed.store(true, Ordering::Relaxed);
@@ -265,6 +343,80 @@ impl<T: ?Sized> StickyMutex<T> {
         Some(StickyMutexGuard(self, PhantomData))
     }
 
+    /// Acquire a shared lock, blocking the current thread until it is able to
+    /// do so.
+    ///
+    ///  - If the calling thread does not hold a normal nor sticky lock, a real
+    ///    shared lock operation takes place. It will block if another thread
+    ///    holds a normal or sticky lock on it, but not if other threads only
+    ///    hold shared locks.
+    ///  - If the calling thread already holds a sticky lock but not a normal
+    ///    lock, then the lock succeeds immediately (and fast).
+    ///  - It will panic if the current thread already holds a normal lock.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it is already locked by the current thread.
+    pub fn borrow_ref(&self) -> StickyMutexReadGuard<T>
+    where
+        T: Sync,
+    {
+        let owned = self.core.lock_shared();
+        if owned {
+            self.borrow_ref_owned()
+                .expect("already locked by the current thread")
+        } else {
+            StickyMutexReadGuard {
+                mutex: self,
+                owned,
+                _phantom: PhantomData,
+            }
+        }
+    }
+
+    /// Attempt to acquire a shared lock.
+    ///
+    /// Works similarly to `borrow_ref`, but returns `None` if the lock could
+    /// not be acquired at this time.
+    pub fn try_borrow_ref(&self) -> Option<StickyMutexReadGuard<T>>
+    where
+        T: Sync,
+    {
+        let owned = self.core.try_lock_shared()?;
+        if owned {
+            self.borrow_ref_owned()
+        } else {
+            Some(StickyMutexReadGuard {
+                mutex: self,
+                owned,
+                _phantom: PhantomData,
+            })
+        }
+    }
+
+    /// Create a shared lock guard in the case where the current thread already
+    /// owns the lock.
+    fn borrow_ref_owned(&self) -> Option<StickyMutexReadGuard<T>> {
+        if self.borrowed.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        let count = self.owner_shared_borrows.load(Ordering::Relaxed);
+        if count == 0 {
+            // Retain the lock even if the sticky lock count reaches zero.
+            // (This is a no-op because we already own the lock.)
+            self.core.lock();
+        }
+        let new_count = count.checked_add(1).expect("shared lock count overflow");
+        self.owner_shared_borrows.store(new_count, Ordering::Relaxed);
+
+        Some(StickyMutexReadGuard {
+            mutex: self,
+            owned: true,
+            _phantom: PhantomData,
+        })
+    }
+
     /// Get a mutable reference to the contained data.
     pub fn get_mut(&mut self) -> &mut T {
         unsafe { &mut *self.data.get() }
@@ -300,3 +452,50 @@ impl<'a, T: ?Sized + 'a> Drop for StickyMutexGuard<'a, T> {
 
 #[cfg(feature = "stable_deref_trait")]
 unsafe impl<'a, T: ?Sized + 'a> stable_deref_trait::StableDeref for StickyMutexGuard<'a, T> {}
+
+/// An RAII shared lock guard of `StickyMutex`, created by
+/// [`StickyMutex::borrow_ref`]. The shared lock is released when this
+/// structure is dropped.
+///
+/// [`StickyMutex::borrow_ref`]: struct.StickyMutex.html#method.borrow_ref
+#[derive(Debug)]
+pub struct StickyMutexReadGuard<'a, T: ?Sized + 'a> {
+    mutex: &'a StickyMutex<T>,
+    /// `true` if this guard was created while the current thread owned the
+    /// lock.
+    owned: bool,
+    _phantom: PhantomData<*mut T>,
+}
+
+impl<'a, T: ?Sized + 'a> Deref for StickyMutexReadGuard<'a, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<'a, T: ?Sized + 'a> Drop for StickyMutexReadGuard<'a, T> {
+    fn drop(&mut self) {
+        let mutex = self.mutex;
+        if self.owned {
+            let count = mutex.owner_shared_borrows.load(Ordering::Relaxed) - 1;
+            mutex.owner_shared_borrows.store(count, Ordering::Relaxed);
+            if count == 0 {
+                // Release the lock if it's not retained by a sticky lock
+                unsafe {
+                    mutex.core.unlock();
+                }
+            }
+        } else {
+            unsafe {
+                mutex.core.unlock_shared();
+            }
+        }
+    }
+}
+
+#[cfg(feature = "stable_deref_trait")]
+unsafe impl<'a, T: ?Sized + 'a> stable_deref_trait::StableDeref
+    for StickyMutexReadGuard<'a, T>
+{
+}
@@ -4,13 +4,13 @@
 // This source code is a part of Nightingales.
 //
 
-use parking_lot::Mutex;
+use parking_lot::RwLock;
 use std::mem::forget;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[derive(Debug)]
 pub struct StickyMutexCore {
-    mutex: Mutex<()>,
+    mutex: RwLock<()>,
     owner: AtomicUsize, // Atomic<ThreadId>
     stick_count: AtomicUsize,
 }
@@ -25,7 +25,7 @@ pub enum UnstickError {
 impl StickyMutexCore {
     pub fn new() -> Self {
         Self {
-            mutex: Mutex::new(()),
+            mutex: RwLock::new(()),
             owner: AtomicUsize::new(NOBODY),
             stick_count: AtomicUsize::new(0),
         }
@@ -35,7 +35,7 @@ impl StickyMutexCore {
     pub fn lock(&self) {
         let current_thread_id = current_thread_id();
         if self.owner.load(Ordering::Relaxed) != current_thread_id {
-            forget(self.mutex.lock());
+            forget(self.mutex.write());
 
             debug_assert_eq!(self.stick_count.load(Ordering::Relaxed), 0);
             self.owner.store(current_thread_id, Ordering::Relaxed);
@@ -46,7 +46,7 @@ impl StickyMutexCore {
     pub fn try_lock(&self) -> bool {
         let current_thread_id = current_thread_id();
         if self.owner.load(Ordering::Relaxed) != current_thread_id {
-            let lock = self.mutex.try_lock();
+            let lock = self.mutex.try_write();
             if lock.is_none() {
                 return false;
             }
@@ -69,10 +69,46 @@ impl StickyMutexCore {
 
         if stick_count == 0 {
             self.owner.store(NOBODY, Ordering::Relaxed);
-            self.mutex.force_unlock();
+            self.mutex.force_unlock_write();
         }
     }
 
+    /// Acquire a shared lock.
+    ///
+    /// Returns `true` if the current thread already has a hard or sticky lock,
+    /// in which case no lock operation takes place and the caller must not
+    /// call `unlock_shared` later.
+    pub fn lock_shared(&self) -> bool {
+        if self.owner.load(Ordering::Relaxed) == current_thread_id() {
+            true
+        } else {
+            // Use a recursive read lock so a thread can own multiple shared
+            // locks without dead-locking with a waiting writer
+            forget(self.mutex.read_recursive());
+            false
+        }
+    }
+
+    /// Try to acquire a shared lock.
+    ///
+    /// Returns `None` if the lock could not be acquired at this time. The
+    /// meaning of the returned `bool` is identical to that of `lock_shared`.
+    pub fn try_lock_shared(&self) -> Option<bool> {
+        if self.owner.load(Ordering::Relaxed) == current_thread_id() {
+            Some(true)
+        } else {
+            let lock = self.mutex.try_read_recursive()?;
+            forget(lock);
+            Some(false)
+        }
+    }
+
+    /// Release a shared lock acquired by `lock_shared` or `try_lock_shared`
+    /// that returned `false`.
+    pub unsafe fn unlock_shared(&self) {
+        self.mutex.force_unlock_read();
+    }
+
     /// Increase the sticky lock count.
     pub fn stick(&self) {
         let current_thread_id = current_thread_id();
@@ -84,7 +120,7 @@ impl StickyMutexCore {
 
             self.stick_count.store(new_stick_count, Ordering::Relaxed);
         } else {
-            forget(self.mutex.lock());
+            forget(self.mutex.write());
 
             debug_assert_eq!(self.stick_count.load(Ordering::Relaxed), 0);
             self.stick_count.store(1, Ordering::Relaxed);
@@ -95,7 +131,8 @@ impl StickyMutexCore {
     /// Decrease the sticky lock count. `has_normal_lock`, which is called only
     /// if the current thread owns the mutex, must return whether the mutex
     /// is currently locked using a "hard" lock (i.e. there have been calls to
-    /// `lock` without a matching call to `unlock`).
+    /// `lock` without a matching call to `unlock`). The hard lock is
+    /// released only if this returns `false` and the count reaches zero.
     pub unsafe fn unstick<F>(&self, has_normal_lock: F) -> Result<(), UnstickError>
     where
         F: FnOnce() -> bool,
@@ -111,7 +148,7 @@ impl StickyMutexCore {
 
             if new_stick_count == 0 && !has_normal_lock() {
                 self.owner.store(NOBODY, Ordering::Relaxed);
-                self.mutex.force_unlock();
+                self.mutex.force_unlock_write();
             }
             Ok(())
         } else {
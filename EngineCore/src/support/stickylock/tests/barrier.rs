@@ -0,0 +1,69 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+extern crate stickylock;
+
+use std::sync::Arc;
+use std::thread;
+use stickylock::*;
+
+#[test]
+fn single_thread() {
+    let barrier = StickyBarrier::new(1);
+    assert!(barrier.wait());
+    assert!(barrier.wait());
+}
+
+#[test]
+fn zero_threads() {
+    let barrier = StickyBarrier::new(0);
+    assert!(barrier.wait());
+}
+
+#[test]
+fn stress() {
+    const NUM_THREADS: usize = 8;
+    const NUM_ROUNDS: usize = 200;
+    const NUM_INCREMENTS: usize = 10;
+
+    let barrier = Arc::new(StickyBarrier::new(NUM_THREADS));
+    let counter = Arc::new(StickyMutex::new(0usize));
+
+    let handles: Vec<_> = (0..NUM_THREADS)
+        .map(|_| {
+            let barrier = Arc::clone(&barrier);
+            let counter = Arc::clone(&counter);
+            thread::spawn(move || {
+                let mut num_leaders = 0;
+                for round in 0..NUM_ROUNDS {
+                    // Perform a batch of updates while holding a sticky lock
+                    counter.stick();
+                    for _ in 0..NUM_INCREMENTS {
+                        *counter.lock() += 1;
+                    }
+                    counter.unstick().unwrap();
+
+                    if barrier.wait() {
+                        num_leaders += 1;
+                    }
+
+                    // All threads finished the current round
+                    assert_eq!(
+                        *counter.lock(),
+                        (round + 1) * NUM_THREADS * NUM_INCREMENTS
+                    );
+
+                    // Make sure no thread starts the next round before every
+                    // thread checked the counter
+                    barrier.wait();
+                }
+                num_leaders
+            })
+        })
+        .collect();
+
+    let num_leaders: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+    assert_eq!(num_leaders, NUM_ROUNDS);
+}
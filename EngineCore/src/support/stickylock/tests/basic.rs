@@ -83,3 +83,112 @@ fn unstick_before_unlock() {
     let _x = k.lock();
     k.unstick().unwrap();
 }
+
+#[test]
+fn borrow_ref_success() {
+    let k = StickyMutex::new(42);
+    let x = k.borrow_ref();
+    let y = k.borrow_ref();
+    assert_eq!(*x, 42);
+    assert_eq!(*y, 42);
+}
+
+#[test]
+fn borrow_ref_then_lock() {
+    let k = StickyMutex::new(42);
+    drop(k.borrow_ref());
+    *k.lock() = 43;
+    assert_eq!(*k.borrow_ref(), 43);
+}
+
+#[test]
+#[should_panic]
+fn lock_then_borrow_ref_panic() {
+    let k = StickyMutex::new(42);
+    let _x = k.lock();
+    k.borrow_ref();
+}
+
+#[test]
+fn lock_then_try_borrow_ref_fail() {
+    let k = StickyMutex::new(42);
+    let _x = k.lock();
+    assert!(k.try_borrow_ref().is_none());
+}
+
+#[test]
+fn try_borrow_ref_success() {
+    let k = StickyMutex::new(42);
+    let _x = k.borrow_ref();
+    assert_eq!(*k.try_borrow_ref().unwrap(), 42);
+}
+
+#[test]
+fn sticky_borrow_ref() {
+    let k = StickyMutex::new(42);
+    k.stick();
+    let _x = k.borrow_ref();
+    assert!(k.try_lock().is_none());
+    k.unstick().unwrap();
+}
+
+#[test]
+#[should_panic]
+fn sticky_borrow_ref_then_lock_panic() {
+    let k = StickyMutex::new(42);
+    k.stick();
+    let _x = k.borrow_ref();
+    k.lock();
+}
+
+#[test]
+fn unstick_before_borrow_ref_drop() {
+    let k = StickyMutex::new(42);
+    k.stick();
+    let x = k.borrow_ref();
+    k.unstick().unwrap();
+    assert_eq!(*x, 42);
+    drop(x);
+    assert_eq!(k.unstick(), Err(UnstickError::NotLocked));
+    *k.lock() = 43;
+}
+
+#[test]
+fn borrow_ref_shared_between_threads() {
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    let k = Arc::new(StickyMutex::new(42));
+    let barrier = Arc::new(Barrier::new(2));
+
+    let _x = k.borrow_ref();
+
+    let k2 = Arc::clone(&k);
+    let barrier2 = Arc::clone(&barrier);
+    let handle = thread::spawn(move || {
+        // Does not block even though the main thread holds a shared lock
+        assert_eq!(*k2.borrow_ref(), 42);
+        assert!(k2.try_lock().is_none());
+        barrier2.wait();
+    });
+
+    barrier.wait();
+    handle.join().unwrap();
+}
+
+#[test]
+fn unsized_boxed() {
+    use std::fmt::Debug;
+    let k: Box<StickyMutex<dyn Debug + Send>> = Box::new(StickyMutex::new(42));
+    assert_eq!(format!("{:?}", &*k.lock()), "42");
+    k.stick();
+    assert_eq!(format!("{:?}", &*k.lock()), "42");
+    k.unstick().unwrap();
+}
+
+#[test]
+fn unsized_slice_borrow_ref() {
+    let k: Box<StickyMutex<[u32]>> = Box::new(StickyMutex::new([1, 2, 3]));
+    k.lock()[1] = 4;
+    assert_eq!(&*k.borrow_ref(), &[1, 4, 3][..]);
+}
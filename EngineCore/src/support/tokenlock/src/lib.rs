@@ -89,9 +89,182 @@
 //! let read_guard1 = lock.read(&token).unwrap();
 //! let read_guard2 = lock.read(&token).unwrap();
 //! ```
+//!
+//! The accessors returning `Option` don't tell why the access failed. The
+//! `try_` variants return a [`WrongTokenError`] instead, which includes the
+//! IDs of the token and the keyhole for diagnostics:
+//!
+//! ```
+//! # use tokenlock::*;
+//! let token1 = Token::new();
+//! let token2 = Token::new();
+//! let lock = TokenLock::new(&token1, 1);
+//!
+//! let error = lock.try_read(&token2).unwrap_err();
+//! assert_eq!(error.keyhole_id(), lock.keyhole_id());
+//! assert_eq!(error.token_id(), token2.id());
+//! ```
+//!
+//! In hot paths where the token is statically known to match, the
+//! `_unchecked` variants skip the check (except in debug builds):
+//!
+//! ```
+//! # use tokenlock::*;
+//! let token = Token::new();
+//! let lock = TokenLock::new(&token, 1);
+//!
+//! // SAFETY: `lock` was created from `token`
+//! assert_eq!(unsafe { *lock.read_unchecked(&token) }, 1);
+//! ```
+//!
+//! For single-threaded uses, [`UnsyncToken`] and [`UnsyncTokenLock`] provide
+//! the same interface without atomic operations.
+//!
+//! [`UnsyncToken`]: struct.UnsyncToken.html
+//! [`UnsyncTokenLock`]: struct.UnsyncTokenLock.html
+//! [`WrongTokenError`]: struct.WrongTokenError.html
 use std::{fmt, hash};
 use std::cell::UnsafeCell;
+use std::error::Error;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Implements the methods shared by `TokenLock` and `UnsyncTokenLock`.
+///
+/// `$lock` must have the fields `keyhole` and `data: UnsafeCell<T>`. `$token`
+/// and `$token_ref` must be newtypes of the type of `keyhole`, which must have
+/// `fn get(&self) -> u64`.
+macro_rules! impl_token_lock {
+    ($lock:ident, $token:ident, $token_ref:ident) => {
+        impl<T: ?Sized> ::std::fmt::Debug for $lock<T> {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                f.debug_struct(stringify!($lock))
+                    .field("keyhole", &self.keyhole)
+                    .finish()
+            }
+        }
+
+        impl<T> $lock<T> {
+            pub fn new<S: Into<$token_ref>>(token: S, data: T) -> Self {
+                Self {
+                    keyhole: token.into().0,
+                    data: ::std::cell::UnsafeCell::new(data),
+                }
+            }
+        }
+
+        impl<T: ?Sized> $lock<T> {
+            #[inline]
+            pub fn get_mut(&mut self) -> &mut T {
+                unsafe { &mut *self.data.get() }
+            }
+
+            #[inline]
+            pub fn read<'a>(&'a self, token: &'a $token) -> Option<&'a T> {
+                if token.0 == self.keyhole {
+                    Some(unsafe { &*self.data.get() })
+                } else {
+                    None
+                }
+            }
+
+            #[inline]
+            pub fn write<'a>(&'a self, token: &'a mut $token) -> Option<&'a mut T> {
+                if token.0 == self.keyhole {
+                    Some(unsafe { &mut *self.data.get() })
+                } else {
+                    None
+                }
+            }
+
+            /// Get a reference to the contents.
+            ///
+            /// Returns `Err(_)` describing the mismatch if `token` does not
+            /// match this lock.
+            #[inline]
+            pub fn try_read<'a>(
+                &'a self,
+                token: &'a $token,
+            ) -> Result<&'a T, $crate::WrongTokenError> {
+                if token.0 == self.keyhole {
+                    Ok(unsafe { &*self.data.get() })
+                } else {
+                    Err($crate::WrongTokenError::new(
+                        self.keyhole.get(),
+                        token.0.get(),
+                    ))
+                }
+            }
+
+            /// Get a mutable reference to the contents.
+            ///
+            /// Returns `Err(_)` describing the mismatch if `token` does not
+            /// match this lock.
+            #[inline]
+            pub fn try_write<'a>(
+                &'a self,
+                token: &'a mut $token,
+            ) -> Result<&'a mut T, $crate::WrongTokenError> {
+                if token.0 == self.keyhole {
+                    Ok(unsafe { &mut *self.data.get() })
+                } else {
+                    Err($crate::WrongTokenError::new(
+                        self.keyhole.get(),
+                        token.0.get(),
+                    ))
+                }
+            }
+
+            /// Get a reference to the contents without checking the token.
+            ///
+            /// This is a faster version of [`read`](#method.read) intended for
+            /// hot paths where `token` is statically known to match this lock.
+            /// The check is still performed in debug builds.
+            ///
+            /// # Safety
+            ///
+            /// `token` must be the token this lock is associated with. The
+            /// borrow of `token` is what prevents the returned reference from
+            /// aliasing a mutable one, so the caller must not pass a different
+            /// token.
+            #[inline]
+            pub unsafe fn read_unchecked<'a>(&'a self, token: &'a $token) -> &'a T {
+                debug_assert!(token.0 == self.keyhole, "wrong token");
+                &*self.data.get()
+            }
+
+            /// Get a mutable reference to the contents without checking the
+            /// token.
+            ///
+            /// This is a faster version of [`write`](#method.write) intended
+            /// for hot paths where `token` is statically known to match this
+            /// lock. The check is still performed in debug builds.
+            ///
+            /// # Safety
+            ///
+            /// `token` must be the token this lock is associated with. The
+            /// mutable borrow of `token` is what guarantees the exclusive access
+            /// to the contents, so the caller must not pass a different token.
+            #[inline]
+            pub unsafe fn write_unchecked<'a>(&'a self, token: &'a mut $token) -> &'a mut T {
+                debug_assert!(token.0 == self.keyhole, "wrong token");
+                &mut *self.data.get()
+            }
+
+            /// Get the ID of the token this lock is associated with, which is
+            /// intended to be used for debugging purposes. See
+            /// [`Token::id`].
+            ///
+            /// [`Token::id`]: struct.Token.html#method.id
+            pub fn keyhole_id(&self) -> u64 {
+                self.keyhole.get()
+            }
+        }
+    };
+}
+
+mod unsync;
+pub use unsync::*;
 
 /// An inforgeable token used to access the contents of a `TokenLock`.
 ///
@@ -110,6 +283,15 @@ impl Token {
     pub fn new() -> Self {
         Token(UniqueId::new())
     }
+
+    /// Get the ID of the token, which is intended to be used for debugging
+    /// purposes.
+    ///
+    /// IDs are assigned sequentially on creation. `TokenRef`s and
+    /// `TokenLock`s created from a token share the same ID.
+    pub fn id(&self) -> u64 {
+        self.0.get()
+    }
 }
 
 impl Default for Token {
@@ -150,6 +332,16 @@ impl Default for Token {
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct TokenRef(UniqueId);
 
+impl TokenRef {
+    /// Get the ID of the token, which is intended to be used for debugging
+    /// purposes. See [`Token::id`].
+    ///
+    /// [`Token::id`]: struct.Token.html#method.id
+    pub fn id(&self) -> u64 {
+        self.0.get()
+    }
+}
+
 impl<'a> From<&'a Token> for TokenRef {
     fn from(x: &'a Token) -> TokenRef {
         TokenRef(x.0.clone())
@@ -170,50 +362,93 @@ pub struct TokenLock<T: ?Sized> {
 unsafe impl<T: ?Sized + Send + Sync> Send for TokenLock<T> {}
 unsafe impl<T: ?Sized + Send + Sync> Sync for TokenLock<T> {}
 
-impl<T: ?Sized> fmt::Debug for TokenLock<T> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("TokenLock")
-            .field("keyhole", &self.keyhole)
-            .finish()
+impl_token_lock!(TokenLock, Token, TokenRef);
+
+impl<T: ?Sized> TokenLock<T> {
+    /// Get a reference to a part of the contents, projected by `f`.
+    ///
+    /// Returns `None` if `token` does not match this `TokenLock`, in which
+    /// case `f` is not called.
+    ///
+    /// ```
+    /// # use tokenlock::*;
+    /// struct Account {
+    ///     name: String,
+    ///     balance: u64,
+    /// }
+    ///
+    /// let token = Token::new();
+    /// let lock = TokenLock::new(&token, Account {
+    ///     name: "Alice".to_owned(),
+    ///     balance: 100,
+    /// });
+    ///
+    /// let balance = lock.read_map(&token, |account| &account.balance);
+    /// assert_eq!(balance, Some(&100));
+    ///
+    /// let other_token = Token::new();
+    /// assert!(lock.read_map(&other_token, |account| &account.name).is_none());
+    /// ```
+    #[inline]
+    pub fn read_map<'a, U: ?Sized, F>(&'a self, token: &'a Token, f: F) -> Option<&'a U>
+    where
+        F: FnOnce(&'a T) -> &'a U,
+    {
+        self.read(token).map(f)
     }
 }
 
-impl<T> TokenLock<T> {
-    pub fn new<S: Into<TokenRef>>(token: S, data: T) -> Self {
+/// The error type returned by [`TokenLock::try_read`] and similar methods when
+/// the supplied token does not match the lock.
+///
+/// [`TokenLock::try_read`]: struct.TokenLock.html#method.try_read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WrongTokenError {
+    keyhole_id: u64,
+    token_id: u64,
+}
+
+impl WrongTokenError {
+    fn new(keyhole_id: u64, token_id: u64) -> Self {
         Self {
-            keyhole: token.into().0,
-            data: UnsafeCell::new(data),
+            keyhole_id,
+            token_id,
         }
     }
-}
 
-impl<T: ?Sized> TokenLock<T> {
-    #[inline]
-    #[allow(dead_code)]
-    pub fn get_mut(&mut self) -> &mut T {
-        unsafe { &mut *self.data.get() }
+    /// Get the ID of the token the lock is associated with.
+    pub fn keyhole_id(&self) -> u64 {
+        self.keyhole_id
     }
 
-    #[inline]
-    #[allow(dead_code)]
-    pub fn read<'a>(&'a self, token: &'a Token) -> Option<&'a T> {
-        if token.0 == self.keyhole {
-            Some(unsafe { &*self.data.get() })
-        } else {
-            None
-        }
+    /// Get the ID of the token supplied to the accessor.
+    pub fn token_id(&self) -> u64 {
+        self.token_id
     }
+}
 
-    #[inline]
-    pub fn write<'a>(&'a self, token: &'a mut Token) -> Option<&'a mut T> {
-        if token.0 == self.keyhole {
-            Some(unsafe { &mut *self.data.get() })
-        } else {
-            None
-        }
+impl fmt::Display for WrongTokenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "token #{} does not match the keyhole #{}",
+            self.token_id, self.keyhole_id
+        )
+    }
+}
+
+impl Error for WrongTokenError {
+    fn description(&self) -> &str {
+        "token does not match the keyhole"
     }
 }
 
+/// Allocate a new ID for `UniqueId` or `UnsyncUniqueId`.
+fn new_id() -> usize {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
 #[derive(Debug, Clone)]
 struct UniqueId(Arc<usize>);
 
@@ -232,12 +467,13 @@ impl hash::Hash for UniqueId {
 
 impl UniqueId {
     pub fn new() -> Self {
-        // This guarantees consistent hash generation even if Rust would
-        // implement a moving GC in future
-        let mut arc = Arc::new(0);
-        let id = &*arc as *const usize as usize;
-        *Arc::get_mut(&mut arc).unwrap() = id;
-        UniqueId(arc)
+        // The identity is represented by the `Arc`'s address. The contained
+        // value is only used for hashing and debugging.
+        UniqueId(Arc::new(new_id()))
+    }
+
+    fn get(&self) -> u64 {
+        *self.0 as u64
     }
 }
 
@@ -258,3 +494,57 @@ fn bad_token() {
     let lock = TokenLock::new(&token1, 1);
     assert!(lock.write(&mut token2).is_none());
 }
+
+#[test]
+fn try_access() {
+    let mut token = Token::new();
+    let lock = TokenLock::new(&token, 1);
+    assert_eq!(lock.keyhole_id(), token.id());
+    assert_eq!(lock.keyhole_id(), TokenRef::from(&token).id());
+
+    assert_eq!(lock.try_read(&token), Ok(&1));
+    *lock.try_write(&mut token).unwrap() = 2;
+    assert_eq!(lock.try_read(&token), Ok(&2));
+}
+
+#[test]
+fn try_access_bad_token() {
+    let token1 = Token::new();
+    let mut token2 = Token::new();
+    let lock = TokenLock::new(&token1, 1);
+
+    let error = lock.try_write(&mut token2).unwrap_err();
+    assert_ne!(error.keyhole_id(), error.token_id());
+    assert_eq!(error.keyhole_id(), token1.id());
+    assert_eq!(error.token_id(), token2.id());
+    assert_eq!(lock.try_read(&token2), Err(error));
+}
+
+#[test]
+fn unchecked_access() {
+    let mut token = Token::new();
+    let lock = TokenLock::new(&token, 1);
+
+    unsafe {
+        assert_eq!(*lock.read_unchecked(&token), 1);
+        *lock.write_unchecked(&mut token) = 2;
+        assert_eq!(*lock.read_unchecked(&token), 2);
+    }
+    assert_eq!(lock.read(&token), Some(&2));
+
+    // Multiple immutable references can coexist
+    let (r1, r2) = unsafe { (lock.read_unchecked(&token), lock.read_unchecked(&token)) };
+    assert_eq!((*r1, *r2), (2, 2));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic]
+fn unchecked_access_bad_token() {
+    let token1 = Token::new();
+    let token2 = Token::new();
+    let lock = TokenLock::new(&token1, 1);
+    unsafe {
+        lock.read_unchecked(&token2);
+    }
+}
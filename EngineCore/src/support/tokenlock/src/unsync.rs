@@ -0,0 +1,224 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+//
+use std::hash;
+use std::cell::UnsafeCell;
+use std::rc::Rc;
+
+use new_id;
+
+/// An inforgeable token used to access the contents of an `UnsyncTokenLock`.
+///
+/// This is a single-threaded counterpart of [`Token`]. Its identity is
+/// represented by an `Rc`, so comparing it with a keyhole is a mere pointer
+/// comparison and involves no atomic operations. This type is neither `Send`
+/// nor `Sync`.
+///
+/// `UnsyncToken` and [`Token`] belong to different families. They cannot be
+/// converted to each other, and an `UnsyncToken` cannot be used to access the
+/// contents of a [`TokenLock`] and vice versa.
+///
+/// [`Token`]: struct.Token.html
+/// [`TokenLock`]: struct.TokenLock.html
+///
+/// # Examples
+///
+/// ```
+/// # use tokenlock::*;
+/// let mut token = UnsyncToken::new();
+///
+/// let lock = UnsyncTokenLock::new(&token, 1);
+/// assert_eq!(*lock.read(&token).unwrap(), 1);
+///
+/// let mut guard = lock.write(&mut token).unwrap();
+/// assert_eq!(*guard, 1);
+/// *guard = 2;
+/// ```
+///
+/// `UnsyncToken` cannot be sent to another thread:
+///
+/// ```compile_fail
+/// # use tokenlock::*;
+/// # use std::thread;
+/// let token = UnsyncToken::new();
+/// thread::spawn(move || {
+///     let _token = token; // compile error: `UnsyncToken` is not `Send`
+/// });
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct UnsyncToken(UnsyncUniqueId);
+
+impl UnsyncToken {
+    pub fn new() -> Self {
+        UnsyncToken(UnsyncUniqueId::new())
+    }
+
+    /// Get the ID of the token, which is intended to be used for debugging
+    /// purposes. See [`Token::id`].
+    ///
+    /// IDs are allocated from the same sequence as `Token`'s, so an
+    /// `UnsyncToken` never shares an ID with a `Token`.
+    ///
+    /// [`Token::id`]: struct.Token.html#method.id
+    pub fn id(&self) -> u64 {
+        self.0.get()
+    }
+}
+
+impl Default for UnsyncToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Token that cannot be used to access the contents of an `UnsyncTokenLock`,
+/// but can be used to create a new `UnsyncTokenLock`.
+///
+/// This is a single-threaded counterpart of [`TokenRef`].
+///
+/// [`TokenRef`]: struct.TokenRef.html
+///
+/// # Examples
+///
+/// ```
+/// # use tokenlock::*;
+/// let mut token = UnsyncToken::new();
+/// let token_ref = UnsyncTokenRef::from(&token);
+/// let lock1 = UnsyncTokenLock::new(token_ref.clone(), 1);
+/// let lock2 = UnsyncTokenLock::new(token_ref.clone(), 2);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct UnsyncTokenRef(UnsyncUniqueId);
+
+impl UnsyncTokenRef {
+    /// Get the ID of the token, which is intended to be used for debugging
+    /// purposes. See [`UnsyncToken::id`].
+    ///
+    /// [`UnsyncToken::id`]: struct.UnsyncToken.html#method.id
+    pub fn id(&self) -> u64 {
+        self.0.get()
+    }
+}
+
+impl<'a> From<&'a UnsyncToken> for UnsyncTokenRef {
+    fn from(x: &'a UnsyncToken) -> UnsyncTokenRef {
+        UnsyncTokenRef(x.0.clone())
+    }
+}
+
+/// A single-threaded counterpart of [`TokenLock`] that can be accessed using
+/// an [`UnsyncToken`].
+///
+/// Unlike `TokenLock`, this type is neither `Send` nor `Sync` regardless of
+/// `T`:
+///
+/// ```compile_fail
+/// # use tokenlock::*;
+/// # use std::{sync::Arc, thread};
+/// let token = UnsyncToken::new();
+/// let lock = Arc::new(UnsyncTokenLock::new(&token, 1));
+/// thread::spawn(move || {
+///     let _lock = lock; // compile error: `UnsyncTokenLock` is not `Sync`
+/// });
+/// ```
+///
+/// It can't be accessed using a [`Token`]:
+///
+/// ```compile_fail
+/// # use tokenlock::*;
+/// let token = UnsyncToken::new();
+/// let lock = UnsyncTokenLock::new(&token, 1);
+/// let token2 = Token::new();
+/// lock.read(&token2); // compile error: mismatched types
+/// ```
+///
+/// [`TokenLock`]: struct.TokenLock.html
+/// [`UnsyncToken`]: struct.UnsyncToken.html
+/// [`Token`]: struct.Token.html
+pub struct UnsyncTokenLock<T: ?Sized> {
+    keyhole: UnsyncUniqueId,
+    data: UnsafeCell<T>,
+}
+
+impl_token_lock!(UnsyncTokenLock, UnsyncToken, UnsyncTokenRef);
+
+#[derive(Debug, Clone)]
+struct UnsyncUniqueId(Rc<usize>);
+
+impl PartialEq for UnsyncUniqueId {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+impl Eq for UnsyncUniqueId {}
+
+impl hash::Hash for UnsyncUniqueId {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        (*self.0).hash(state)
+    }
+}
+
+impl UnsyncUniqueId {
+    fn new() -> Self {
+        // See `UniqueId::new`
+        UnsyncUniqueId(Rc::new(new_id()))
+    }
+
+    fn get(&self) -> u64 {
+        *self.0 as u64
+    }
+}
+
+#[test]
+fn unsync_basic() {
+    let mut token = UnsyncToken::new();
+    let lock = UnsyncTokenLock::new(&token, 1);
+    assert_eq!(*lock.read(&token).unwrap(), 1);
+
+    let guard = lock.write(&mut token).unwrap();
+    assert_eq!(*guard, 1);
+}
+
+#[test]
+fn unsync_bad_token() {
+    let token1 = UnsyncToken::new();
+    let mut token2 = UnsyncToken::new();
+    let lock = UnsyncTokenLock::new(&token1, 1);
+    assert!(lock.write(&mut token2).is_none());
+}
+
+#[test]
+fn unsync_try_access() {
+    let mut token1 = UnsyncToken::new();
+    let token2 = UnsyncToken::new();
+    let lock = UnsyncTokenLock::new(&token1, 1);
+    assert_eq!(lock.keyhole_id(), token1.id());
+
+    *lock.try_write(&mut token1).unwrap() = 2;
+    assert_eq!(lock.try_read(&token1), Ok(&2));
+
+    let error = lock.try_read(&token2).unwrap_err();
+    assert_ne!(error.keyhole_id(), error.token_id());
+    assert_eq!(error.keyhole_id(), token1.id());
+    assert_eq!(error.token_id(), token2.id());
+}
+
+#[test]
+fn unsync_unchecked_access() {
+    let mut token = UnsyncToken::new();
+    let lock = UnsyncTokenLock::new(&token, 1);
+
+    unsafe {
+        assert_eq!(*lock.read_unchecked(&token), 1);
+        *lock.write_unchecked(&mut token) = 2;
+        assert_eq!(*lock.read_unchecked(&token), 2);
+    }
+    assert_eq!(lock.read(&token), Some(&2));
+}
@@ -0,0 +1,170 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+//! Support for [`bytemuck::Pod`](../bytemuck/trait.Pod.html), enabled by the
+//! `bytemuck` feature.
+//!
+//! The traits in this module are not re-exported by the crate root so that
+//! enabling this feature does not introduce method ambiguities to the code
+//! importing everything from the crate root.
+use bytemuck::{self, Pod};
+use std::{iter::FromIterator, slice};
+
+use super::{unwrap_as_slice, Volatile};
+
+/// Extensions of the [`bytemuck::Pod`](../bytemuck/trait.Pod.html) trait for
+/// [`Volatile`]`<T>`.
+///
+/// This trait provides the same functionality as [`VolatilePod`] for types
+/// implementing `bytemuck::Pod` instead of `pod::Pod`.
+///
+/// # Precedence
+///
+/// If `T` implements both of `pod::Pod` and `bytemuck::Pod`, the inherent
+/// methods of `Volatile<T>` (`load` and `store`) take precedence over the
+/// methods of this trait. The other methods (e.g., `map`) are ambiguous if both
+/// of this trait and [`VolatilePod`] are in scope. Neither the crate root nor
+/// the [prelude] re-exports this trait, so `pod::Pod` takes precedence unless
+/// you import this trait explicitly. Use the fully qualified syntax to choose
+/// one in the case of ambiguity.
+///
+/// [`VolatilePod`]: crate::VolatilePod
+/// [prelude]: crate::prelude
+///
+/// # Examples
+///
+///     use volatile_view::Volatile;
+///     use volatile_view::bytemuck_pod::VolatileBytemuckPod;
+///
+///     let x: Volatile<f32> = Volatile::new(42.0f32);
+///     let x_bits: &Volatile<u32> = VolatileBytemuckPod::map(&x).unwrap();
+///     assert_eq!(VolatileBytemuckPod::load(x_bits), 42.0f32.to_bits());
+///
+pub trait VolatileBytemuckPod<T> {
+    /// Load a value from `self`.
+    fn load(&self) -> T;
+
+    /// Store a value to `self`.
+    fn store(&self, x: T);
+
+    /// Convert a volatile reference from one to another type of the same size.
+    ///
+    /// Returns `None` if the source and destination types are misaligned or
+    /// not the same size.
+    fn map<U: Pod>(&self) -> Option<&Volatile<U>>;
+
+    /// Split a volatile reference from one to a slice of another type.
+    ///
+    /// Returns `None` if the source and destination types are misaligned or
+    /// the source does not fit perfectly in the destination slice type.
+    fn split<U: Pod>(&self) -> Option<&[Volatile<U>]>;
+}
+
+impl<T: Pod> VolatileBytemuckPod<T> for Volatile<T> {
+    fn load(&self) -> T {
+        unsafe { self.load_unchecked() }
+    }
+
+    fn store(&self, x: T) {
+        unsafe { self.store_unchecked(x) }
+    }
+
+    fn map<U: Pod>(&self) -> Option<&Volatile<U>> {
+        unsafe {
+            bytemuck::try_cast_ref(self.as_ref())
+                .ok()
+                .map(|x| Volatile::from_ref(x))
+        }
+    }
+
+    fn split<U: Pod>(&self) -> Option<&[Volatile<U>]> {
+        unsafe {
+            bytemuck::try_cast_slice(slice::from_ref(self.as_ref()))
+                .ok()
+                .map(|x| Volatile::slice_from_ref(x))
+        }
+    }
+}
+
+/// Extensions of the [`bytemuck::Pod`](../bytemuck/trait.Pod.html) trait for
+/// `[`[`Volatile`]`<T>]`.
+///
+/// This trait provides the same functionality as [`VolatileSlicePod`] for
+/// types implementing `bytemuck::Pod` instead of `pod::Pod`. See
+/// [`VolatileBytemuckPod`] for the precedence rules.
+///
+/// [`VolatileSlicePod`]: crate::VolatileSlicePod
+pub trait VolatileSliceBytemuckPod<T> {
+    /// Convert a volatile slice reference from one to another type.
+    ///
+    /// Returns `None` if the source and destination types are misaligned or
+    /// the source does not fit perfectly in the destination slice type.
+    fn map_slice<U: Pod>(&self) -> Option<&[Volatile<U>]>;
+
+    /// Convert a volatile slice reference to another type.
+    ///
+    /// Returns `None` if the source and destination types are misaligned or
+    /// not the same size.
+    fn merge<U: Pod>(&self) -> Option<&Volatile<U>>;
+
+    /// Copy all elements to `slice`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `slice.len() != self.len()`.
+    fn copy_to_slice(&self, slice: &mut [T]);
+
+    /// Copy all elements from `slice`.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `slice.len() != self.len()`.
+    fn copy_from_slice(&self, slice: &[T]);
+
+    /// Copy all elements to a new `Vec`.
+    fn load_to_vec(&self) -> Vec<T> {
+        self.load()
+    }
+
+    /// Copy all elements to a new collection.
+    fn load<B: FromIterator<T>>(&self) -> B;
+}
+
+impl<T: Pod> VolatileSliceBytemuckPod<T> for [Volatile<T>] {
+    fn map_slice<U: Pod>(&self) -> Option<&[Volatile<U>]> {
+        unsafe {
+            bytemuck::try_cast_slice(unwrap_as_slice(self))
+                .ok()
+                .map(|x| Volatile::slice_from_ref(x))
+        }
+    }
+
+    fn merge<U: Pod>(&self) -> Option<&Volatile<U>> {
+        unsafe {
+            let bytes: &[u8] = bytemuck::cast_slice(unwrap_as_slice(self));
+            bytemuck::try_from_bytes(bytes)
+                .ok()
+                .map(|x| Volatile::from_ref(x))
+        }
+    }
+
+    fn copy_to_slice(&self, slice: &mut [T]) {
+        assert_eq!(self.len(), slice.len());
+        for (x, y) in self.iter().zip(slice) {
+            *y = VolatileBytemuckPod::load(x);
+        }
+    }
+
+    fn copy_from_slice(&self, slice: &[T]) {
+        assert_eq!(self.len(), slice.len());
+        for (x, y) in self.iter().zip(slice) {
+            VolatileBytemuckPod::store(x, *y);
+        }
+    }
+
+    fn load<B: FromIterator<T>>(&self) -> B {
+        self.iter().map(VolatileBytemuckPod::load).collect()
+    }
+}
@@ -32,10 +32,32 @@
 //!
 //!  - [`Volatile::from_raw`] convert a raw pointer of type `*mut T` into a
 //!    volatile access view of type `&Volatile<T>`. These are unsafe for
-//!    obvious reasons.
+//!    obvious reasons. [`Volatile::try_from_raw`] additionally checks the
+//!    alignment of the pointer and returns [`Misaligned`] on failure.
 //!
 //!  - [`Volatile::new`] constructs a volatile-accessed cell on the stack.
 //!
+//!  - [`VolatileBox::new`] constructs a volatile-accessed cell on the heap.
+//!
+//!  - [`VolatileStruct::from_raw`] converts a raw pointer into a reference to
+//!    a struct consisting of volatile access views, such as a hardware register
+//!    block. Such a struct can be defined by [`volatile_struct!`].
+//!
+//! A volatile slice `&[Volatile<T>]` is an ordinary slice, so the standard
+//! slice methods can be used to divide it without any volatile accesses. For
+//! example, `chunks` and `chunks_exact` can be used to process a hardware ring
+//! buffer in fixed-size blocks:
+//!
+//!     # use volatile_view::*;
+//!     let mut ring = [0u32; 8];
+//!     let ring_view: &[Volatile<u32>] = Volatile::slice_from_mut(&mut ring[..]);
+//!
+//!     for (i, block) in ring_view.chunks_exact(4).enumerate() {
+//!         block[0].store(i as u32);
+//!     }
+//!
+//!     assert_eq!(ring, [0, 0, 0, 0, 1, 0, 0, 0]);
+//!
 //! # Prior art
 //!
 //! [`volatile`], [`volatile-register`], and [`volatile_cell`] all provide
@@ -58,10 +80,33 @@
 //! [`volatile-register`]: https://crates.io/crates/volatile-register
 //! [`volatile_cell`]: https://crates.io/crates/volatile_cell
 //! [`volatile-ptr`]: https://crates.io/crates/volatile-ptr
+//!
+//! # Cargo features
+//!
+//!  - `bytemuck` enables the [`bytemuck_pod`] module, which provides the same
+//!    operations for types implementing [`bytemuck::Pod`] instead of
+//!    `pod::Pod`.
+//!
+//! [`bytemuck::Pod`]: https://docs.rs/bytemuck/1/bytemuck/trait.Pod.html
+#[cfg(feature = "bytemuck")]
+extern crate bytemuck;
 extern crate pod;
 
 use pod::Pod;
-use std::{cell::UnsafeCell, fmt, iter::FromIterator, mem::transmute};
+use std::{
+    cell::UnsafeCell,
+    error::Error,
+    fmt,
+    iter::FromIterator,
+    mem::{align_of, transmute},
+    ops::{Deref, DerefMut},
+};
+
+#[cfg(feature = "bytemuck")]
+pub mod bytemuck_pod;
+mod volatile_struct;
+
+pub use volatile_struct::*;
 
 /// A volatile access view.
 ///
@@ -132,6 +177,39 @@ impl<T> Volatile<T> {
         Self::slice_from_ref(::std::slice::from_raw_parts(x as *const _, len))
     }
 
+    /// Construct a volatile access view from a raw pointer, checking the
+    /// alignment of the pointer.
+    ///
+    /// Returns `Err(_)` if `x` is not aligned to `align_of::<T>()`. `x` must be
+    /// non-null and must point to a memory region valid for volatile accesses,
+    /// which this method cannot check.
+    ///
+    /// # Examples
+    ///
+    ///     # use volatile_view::*;
+    ///     let mut x = [0u32; 2];
+    ///     let ptr = x.as_mut_ptr();
+    ///
+    ///     assert!(unsafe { Volatile::try_from_raw(ptr) }.is_ok());
+    ///     let bad_ptr = (ptr as *mut u8).wrapping_add(1) as *mut u32;
+    ///     assert!(unsafe { Volatile::try_from_raw(bad_ptr) }.is_err());
+    ///
+    pub unsafe fn try_from_raw(x: *mut T) -> Result<&'static Self, Misaligned> {
+        Misaligned::check::<T>(x)?;
+        Ok(Self::from_raw(x))
+    }
+
+    /// Construct a slice of volatile access views from a raw pointer, checking
+    /// the alignment of the pointer.
+    ///
+    /// Returns `Err(_)` if `x` is not aligned to `align_of::<T>()`. `x` must be
+    /// non-null and must point to a memory region valid for volatile accesses,
+    /// which this method cannot check.
+    pub unsafe fn try_slice_from_raw(x: *mut T, len: usize) -> Result<&'static [Self], Misaligned> {
+        Misaligned::check::<T>(x)?;
+        Ok(Self::slice_from_raw(x, len))
+    }
+
     /// Construct a cell accessed via a volatile access view.
     pub fn new(x: T) -> Self {
         Volatile(UnsafeCell::new(x))
@@ -181,6 +259,39 @@ impl<T: Pod> Volatile<T> {
     }
 }
 
+/// The error type returned by [`Volatile::try_from_raw`] and
+/// [`Volatile::try_slice_from_raw`] when a given pointer is misaligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Misaligned {
+    /// The address of the pointer.
+    pub addr: usize,
+    /// The required alignment.
+    pub align: usize,
+}
+
+impl Misaligned {
+    fn check<T>(x: *mut T) -> Result<(), Self> {
+        let (addr, align) = (x as usize, align_of::<T>());
+        if addr % align == 0 {
+            Ok(())
+        } else {
+            Err(Misaligned { addr, align })
+        }
+    }
+}
+
+impl fmt::Display for Misaligned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "pointer 0x{:x} is not aligned to {} bytes",
+            self.addr, self.align
+        )
+    }
+}
+
+impl Error for Misaligned {}
+
 /// Extensions of the [`Pod`](../pod/trait.Pod.html) trait for [`Volatile`]`<T>`.
 pub trait VolatilePod {
     /// Convert a volatile reference from one to another type of the same size.
@@ -368,6 +479,104 @@ impl<T: Pod> Clone for Volatile<T> {
     }
 }
 
+/// Compares the contents of two `Volatile`s. This performs two volatile
+/// reads, one for each operand.
+impl<T: Pod + PartialEq> PartialEq for Volatile<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.load() == other.load()
+    }
+}
+
+/// Constructs a cell containing `T::default()`. This makes it possible to
+/// derive `Default` on structs containing `Volatile` fields.
+///
+/// # Examples
+///
+///     # use volatile_view::*;
+///     #[derive(Default)]
+///     struct Registers {
+///         control: Volatile<u32>,
+///         status: Volatile<u32>,
+///     }
+///
+///     let regs = Registers::default();
+///     assert_eq!(regs.control.load(), 0);
+///     assert_eq!(regs.status.load(), 0);
+///
+impl<T: Pod + Default> Default for Volatile<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+/// A heap-allocated cell accessed via a volatile access view.
+///
+/// # Examples
+///
+///     # use volatile_view::*;
+///     let x = VolatileBox::new(42u32);
+///     x.store(x.load() + 1);
+///
+///     // Give the cell a `'static` lifetime so it can be shared with, e.g.,
+///     // an interrupt handler
+///     let view: &'static Volatile<u32> = x.leak();
+///     assert_eq!(view.load(), 43);
+///
+pub struct VolatileBox<T>(Box<Volatile<T>>);
+
+impl<T: Pod> VolatileBox<T> {
+    /// Construct a `VolatileBox` by moving a value to the heap.
+    pub fn new(x: T) -> Self {
+        VolatileBox(Box::new(Volatile::new(x)))
+    }
+
+    /// Construct a `VolatileBox` from an existing `Box`, reusing its memory
+    /// allocation.
+    pub fn from_box(x: Box<T>) -> Self {
+        VolatileBox(unsafe { Box::from_raw(Box::into_raw(x) as *mut Volatile<T>) })
+    }
+
+    /// Unwrap the cell.
+    pub fn into_inner(self) -> T {
+        self.0.into_inner()
+    }
+
+    /// Consume and leak the `VolatileBox`, returning a volatile access view
+    /// with the `'static` lifetime.
+    pub fn leak(self) -> &'static Volatile<T>
+    where
+        T: 'static,
+    {
+        Box::leak(self.0)
+    }
+}
+
+impl<T> Deref for VolatileBox<T> {
+    type Target = Volatile<T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for VolatileBox<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T: Pod + fmt::Debug> fmt::Debug for VolatileBox<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("VolatileBox").field(&self.load()).finish()
+    }
+}
+
+impl<T: Pod> Clone for VolatileBox<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.load())
+    }
+}
+
 /// `volatile_view` prelude.
 pub mod prelude {
     #[doc(no_inline)]
@@ -0,0 +1,141 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use pod::Pod;
+use std::mem::{align_of, size_of};
+
+use super::Volatile;
+
+/// A type consisting solely of volatile access views, such as a hardware
+/// register block.
+///
+/// Types implementing this trait can be placed on an arbitrary memory region
+/// valid for volatile accesses. Use the [`volatile_struct!`] macro to define a
+/// struct implementing this trait.
+///
+/// # Safety
+///
+/// The type must not contain anything other than [`Volatile`]`<T>`s (where
+/// `T: Pod`) and other types implementing `VolatileStruct`, and must have a
+/// well-defined layout (i.e., be `#[repr(C)]` if it is a struct).
+pub unsafe trait VolatileStruct: Sized + Sync {
+    /// Construct a reference to `Self` placed at `base`, checking the size and
+    /// alignment of the memory region.
+    ///
+    /// Returns `None` if `base` is null, `base` is not aligned to
+    /// `align_of::<Self>()`, or `len` (the size of the memory region in bytes)
+    /// is less than `size_of::<Self>()`. The memory region must be valid for
+    /// volatile accesses, which this method cannot check.
+    unsafe fn from_raw(base: *mut u8, len: usize) -> Option<&'static Self> {
+        if base.is_null() || base as usize % align_of::<Self>() != 0 || len < size_of::<Self>() {
+            None
+        } else {
+            Some(&*(base as *const Self))
+        }
+    }
+}
+
+unsafe impl<T: Pod + Sync> VolatileStruct for Volatile<T> {}
+
+macro_rules! impl_volatile_struct_for_array {
+    ($($n:expr),*) => {
+        $(unsafe impl<T: VolatileStruct> VolatileStruct for [T; $n] {})*
+    };
+}
+
+impl_volatile_struct_for_array!(
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
+    26, 27, 28, 29, 30, 31, 32, 64, 128, 256, 512, 1024, 2048, 4096
+);
+
+/// Used by [`volatile_struct!`] to check the field types.
+#[doc(hidden)]
+pub fn __assert_volatile_struct<T: VolatileStruct>() {}
+
+/// Define a `#[repr(C)]` struct implementing [`VolatileStruct`].
+///
+/// The struct can only contain [`Volatile`]`<T>`s, arrays of them (of a limited
+/// set of lengths), and other types implementing `VolatileStruct`. This is
+/// checked at compile time. The struct gets an inherent method
+/// `unsafe fn from_raw(base: *mut u8, len: usize) -> Option<&'static Self>`,
+/// which is a shorthand for [`VolatileStruct::from_raw`].
+///
+/// # Examples
+///
+///     #[macro_use]
+///     extern crate volatile_view;
+///     use volatile_view::Volatile;
+///
+///     volatile_struct! {
+///         /// A UART register block.
+///         pub struct Regs {
+///             pub status: Volatile<u32>,
+///             pub ctrl: Volatile<u32>,
+///             pub fifo: [Volatile<u8>; 64],
+///         }
+///     }
+///
+///     # fn main() {
+///     let mut buffer = vec![0u32; 18];
+///     let len = buffer.len() * 4;
+///     let regs = unsafe { Regs::from_raw(buffer.as_mut_ptr() as *mut u8, len) }.unwrap();
+///
+///     regs.ctrl.store(1);
+///     regs.fifo[0].store(0x42);
+///     assert_eq!(buffer[1], 1);
+///     # }
+///
+/// Fields of other types are rejected:
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate volatile_view;
+///
+/// volatile_struct! {
+///     struct Regs {
+///         status: u32,
+///     }
+/// }
+/// # fn main() {}
+/// ```
+#[macro_export]
+macro_rules! volatile_struct {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident : $ty:ty
+            ),* $(,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr(C)]
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: $ty,
+            )*
+        }
+
+        unsafe impl $crate::VolatileStruct for $name {}
+
+        impl $name {
+            /// Construct a reference to `Self` placed at `base`, checking the
+            /// size and alignment of the memory region.
+            ///
+            /// See `VolatileStruct::from_raw` for details.
+            #[allow(dead_code)]
+            $vis unsafe fn from_raw(base: *mut u8, len: usize) -> Option<&'static Self> {
+                <Self as $crate::VolatileStruct>::from_raw(base, len)
+            }
+
+            #[allow(dead_code)]
+            fn __assert_fields() {
+                $($crate::__assert_volatile_struct::<$ty>();)*
+            }
+        }
+    };
+}
@@ -0,0 +1,58 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+extern crate volatile_view;
+
+use std::mem::align_of;
+use volatile_view::{Volatile, VolatileBox};
+
+#[test]
+fn load_store() {
+    let x = VolatileBox::new(1u64);
+    assert_eq!(x.load(), 1);
+    x.store(2);
+    assert_eq!(x.load(), 2);
+    assert_eq!(x.into_inner(), 2);
+}
+
+#[test]
+fn aligned() {
+    let x = VolatileBox::new(0u64);
+    assert_eq!(x.as_ptr() as usize % align_of::<u64>(), 0);
+}
+
+#[test]
+fn from_box() {
+    let b = Box::new(0x42424242u32);
+    let ptr = &*b as *const u32;
+
+    let x = VolatileBox::from_box(b);
+    assert_eq!(x.as_ptr() as *const u32, ptr);
+    assert_eq!(x.load(), 0x42424242);
+}
+
+#[test]
+fn get_mut() {
+    let mut x = VolatileBox::new(1u32);
+    *x.get_mut() = 2;
+    assert_eq!(x.load(), 2);
+}
+
+#[test]
+fn leak() {
+    let x = VolatileBox::new(1u16);
+    let view: &'static Volatile<u16> = x.leak();
+    view.store(2);
+    assert_eq!(view.load(), 2);
+}
+
+#[test]
+fn clone() {
+    let x = VolatileBox::new(1u32);
+    let y = x.clone();
+    x.store(2);
+    assert_eq!(y.load(), 1);
+    assert_eq!(format!("{:?}", y), "VolatileBox(1)");
+}
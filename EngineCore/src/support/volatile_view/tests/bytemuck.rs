@@ -0,0 +1,54 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+#![cfg(feature = "bytemuck")]
+#[macro_use]
+extern crate bytemuck;
+extern crate volatile_view;
+
+use volatile_view::{
+    bytemuck_pod::{VolatileBytemuckPod, VolatileSliceBytemuckPod},
+    Volatile,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+struct Pair {
+    x: u16,
+    y: u16,
+}
+
+#[test]
+fn map() {
+    let x = Volatile::new(Pair { x: 0x1234, y: 0x1234 });
+    let x_u32: &Volatile<u32> = x.map().unwrap();
+    assert_eq!(x_u32.load(), 0x12341234);
+
+    x_u32.store(0x56785678);
+    assert_eq!(x.load(), Pair { x: 0x5678, y: 0x5678 });
+
+    assert!(VolatileBytemuckPod::map::<u64>(&x).is_none());
+}
+
+#[test]
+fn split() {
+    let x = Volatile::new(Pair { x: 1, y: 2 });
+    let x_u16: &[Volatile<u16>] = x.split().unwrap();
+    assert_eq!(x_u16.load_to_vec(), vec![1, 2]);
+
+    let merged: &Volatile<Pair> = x_u16.merge().unwrap();
+    assert_eq!(merged.load(), Pair { x: 1, y: 2 });
+}
+
+#[test]
+fn map_slice() {
+    let mut x = [Pair { x: 1, y: 2 }, Pair { x: 3, y: 4 }];
+    let x_view: &[Volatile<Pair>] = Volatile::slice_from_mut(&mut x[..]);
+    let x_u16: &[Volatile<u16>] = x_view.map_slice().unwrap();
+    assert_eq!(x_u16.load_to_vec(), vec![1, 2, 3, 4]);
+
+    x_u16.copy_from_slice(&[5, 6, 7, 8]);
+    assert_eq!(x, [Pair { x: 5, y: 6 }, Pair { x: 7, y: 8 }]);
+}
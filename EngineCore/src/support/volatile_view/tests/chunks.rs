@@ -0,0 +1,40 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+extern crate volatile_view;
+
+use volatile_view::{Volatile, VolatileSlicePod};
+
+#[test]
+fn chunks() {
+    let mut x = [1u16, 2, 3, 4, 5, 6, 7];
+    let view = Volatile::slice_from_mut(&mut x[..]);
+
+    let blocks: Vec<Vec<u16>> = view.chunks(3).map(|block| block.load_to_vec()).collect();
+    assert_eq!(blocks, vec![vec![1, 2, 3], vec![4, 5, 6], vec![7]]);
+}
+
+#[test]
+fn chunks_exact() {
+    let mut x = [1u16, 2, 3, 4, 5, 6, 7];
+    let view = Volatile::slice_from_mut(&mut x[..]);
+
+    let mut iter = view.chunks_exact(3);
+    let blocks: Vec<Vec<u16>> = iter.by_ref().map(|block| block.load_to_vec()).collect();
+    assert_eq!(blocks, vec![vec![1, 2, 3], vec![4, 5, 6]]);
+    assert_eq!(iter.remainder().load_to_vec(), vec![7]);
+}
+
+#[test]
+fn chunks_store() {
+    let mut x = [0u32; 6];
+    {
+        let view = Volatile::slice_from_mut(&mut x[..]);
+        for (i, block) in view.chunks_exact(2).enumerate() {
+            block.copy_from_slice(&[i as u32, i as u32 * 10]);
+        }
+    }
+    assert_eq!(x, [0, 0, 1, 10, 2, 20]);
+}
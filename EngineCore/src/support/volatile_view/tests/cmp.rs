@@ -0,0 +1,32 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+extern crate volatile_view;
+
+use volatile_view::Volatile;
+
+#[test]
+fn eq() {
+    let a = Volatile::new(42u32);
+    let b = Volatile::new(42u32);
+    assert_eq!(a, b);
+
+    b.store(43);
+    assert_ne!(a, b);
+}
+
+#[test]
+fn eq_from_mut() {
+    let mut x = [1u16, 2, 1];
+    let views = Volatile::slice_from_mut(&mut x[..]);
+    assert_eq!(views[0], views[2]);
+    assert_ne!(views[0], views[1]);
+}
+
+#[test]
+fn eq_nan() {
+    let a = Volatile::new(std::f32::NAN);
+    assert_ne!(a, a);
+}
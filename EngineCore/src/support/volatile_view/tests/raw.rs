@@ -0,0 +1,93 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+extern crate volatile_view;
+
+use std::mem::align_of;
+use volatile_view::{Misaligned, Volatile};
+
+/// Offset `ptr` by `offset` bytes.
+fn offset_bytes<T>(ptr: *mut T, offset: usize) -> *mut T {
+    (ptr as *mut u8).wrapping_add(offset) as *mut T
+}
+
+#[test]
+fn try_from_raw_aligned() {
+    let mut x = [0u64; 2];
+    let ptr = x.as_mut_ptr();
+
+    let view = unsafe { Volatile::try_from_raw(ptr) }.unwrap();
+    view.store(42);
+    assert_eq!(x[0], 42);
+}
+
+#[test]
+fn try_from_raw_misaligned() {
+    let mut x = [0u64; 2];
+    let ptr = x.as_mut_ptr();
+
+    for offset in 1..align_of::<u64>() {
+        let bad_ptr = offset_bytes(ptr, offset);
+        let result = unsafe { Volatile::try_from_raw(bad_ptr) };
+        assert_eq!(
+            result.err(),
+            Some(Misaligned {
+                addr: bad_ptr as usize,
+                align: align_of::<u64>(),
+            })
+        );
+    }
+}
+
+#[test]
+fn try_from_raw_byte_never_misaligned() {
+    let mut x = [0u8; 4];
+    let ptr = x.as_mut_ptr();
+
+    for offset in 0..4 {
+        assert!(unsafe { Volatile::try_from_raw(ptr.wrapping_add(offset)) }.is_ok());
+    }
+}
+
+#[test]
+fn try_slice_from_raw_aligned() {
+    let mut x = [0u32; 4];
+    let ptr = x.as_mut_ptr();
+
+    let view = unsafe { Volatile::try_slice_from_raw(ptr, 4) }.unwrap();
+    assert_eq!(view.len(), 4);
+    view[3].store(42);
+    assert_eq!(x[3], 42);
+}
+
+#[test]
+fn try_slice_from_raw_misaligned() {
+    let mut x = [0u32; 4];
+    let ptr = x.as_mut_ptr();
+
+    for offset in 1..align_of::<u32>() {
+        let bad_ptr = offset_bytes(ptr, offset);
+        let result = unsafe { Volatile::try_slice_from_raw(bad_ptr, 3) };
+        assert_eq!(
+            result.err(),
+            Some(Misaligned {
+                addr: bad_ptr as usize,
+                align: align_of::<u32>(),
+            })
+        );
+    }
+}
+
+#[test]
+fn misaligned_display() {
+    let error = Misaligned {
+        addr: 0x1001,
+        align: 4,
+    };
+    assert_eq!(
+        error.to_string(),
+        "pointer 0x1001 is not aligned to 4 bytes"
+    );
+}
@@ -0,0 +1,120 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+#[macro_use]
+extern crate volatile_view;
+
+use std::mem::{align_of, size_of};
+use volatile_view::{Volatile, VolatileStruct};
+
+volatile_struct! {
+    #[allow(dead_code)]
+    struct Regs {
+        status: Volatile<u32>,
+        ctrl: Volatile<u32>,
+        fifo: [Volatile<u8>; 64],
+    }
+}
+
+volatile_struct! {
+    struct Device {
+        /// Nested register block
+        uart: Regs,
+        irq: [Volatile<u64>; 2],
+    }
+}
+
+/// Construct a zero-filled buffer large enough to hold `len` bytes, aligned to
+/// 8 bytes.
+fn new_buffer(len: usize) -> Box<[u64]> {
+    vec![0u64; (len + 7) / 8].into_boxed_slice()
+}
+
+fn bytes(buffer: &[u64]) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(buffer.as_ptr() as *const u8, buffer.len() * 8) }
+}
+
+#[test]
+fn layout() {
+    assert_eq!(size_of::<Regs>(), 72);
+    assert_eq!(align_of::<Regs>(), 4);
+    assert_eq!(size_of::<Device>(), 88);
+    assert_eq!(align_of::<Device>(), 8);
+}
+
+#[test]
+fn access_fields() {
+    let mut buffer = new_buffer(size_of::<Regs>());
+    let base = buffer.as_mut_ptr() as *mut u8;
+
+    {
+        let regs = unsafe { Regs::from_raw(base, size_of::<Regs>()) }.unwrap();
+        regs.ctrl.store(0x12345678);
+        regs.fifo[0].store(0xaa);
+        regs.fifo[63].store(0xbb);
+    }
+
+    let b = bytes(&buffer);
+    assert_eq!(&b[4..8], &0x12345678u32.to_ne_bytes());
+    assert_eq!(b[8], 0xaa);
+    assert_eq!(b[71], 0xbb);
+
+    // Writes made outside are visible through the view
+    buffer[0] = 0;
+    let regs = unsafe { Regs::from_raw(base, size_of::<Regs>()) }.unwrap();
+    assert_eq!(regs.status.load(), 0);
+    assert_eq!(regs.fifo[0].load(), 0xaa);
+}
+
+#[test]
+fn nested() {
+    let mut buffer = new_buffer(size_of::<Device>());
+    let base = buffer.as_mut_ptr() as *mut u8;
+    let device = unsafe { Device::from_raw(base, size_of::<Device>()) }.unwrap();
+
+    device.uart.status.store(1);
+    device.irq[1].store(!0);
+
+    assert_eq!(&bytes(&buffer)[0..4], &1u32.to_ne_bytes());
+    assert_eq!(buffer[10], !0);
+}
+
+#[test]
+fn too_short() {
+    let mut buffer = new_buffer(size_of::<Regs>());
+    let base = buffer.as_mut_ptr() as *mut u8;
+    assert!(unsafe { Regs::from_raw(base, size_of::<Regs>() - 1) }.is_none());
+    assert!(unsafe { Regs::from_raw(base, 0) }.is_none());
+
+    // A larger region is okay
+    assert!(unsafe { Regs::from_raw(base, buffer.len() * 8) }.is_some());
+}
+
+#[test]
+fn misaligned() {
+    let mut buffer = new_buffer(size_of::<Device>() + 8);
+    let base = buffer.as_mut_ptr() as *mut u8;
+
+    for offset in 1..8 {
+        let ptr = base.wrapping_add(offset);
+        assert!(unsafe { Device::from_raw(ptr, size_of::<Device>()) }.is_none());
+    }
+    assert!(unsafe { Regs::from_raw(base.wrapping_add(4), size_of::<Regs>()) }.is_some());
+    assert!(unsafe { Regs::from_raw(base.wrapping_add(2), size_of::<Regs>()) }.is_none());
+}
+
+#[test]
+fn null() {
+    assert!(unsafe { Regs::from_raw(std::ptr::null_mut(), size_of::<Regs>()) }.is_none());
+}
+
+#[test]
+fn trait_from_raw() {
+    let mut buffer = new_buffer(16);
+    let base = buffer.as_mut_ptr() as *mut u8;
+    let cells = unsafe { <[Volatile<u32>; 4]>::from_raw(base, 16) }.unwrap();
+    cells[2].store(42);
+    assert_eq!(&bytes(&buffer)[8..12], &42u32.to_ne_bytes());
+}
@@ -36,6 +36,31 @@ impl BiquadKernelState {
         self.0.abs().max(self.1.abs()) > 1.0e-10
     }
 
+    /// Compute the number of samples until the state decays below the
+    /// threshold used by `is_active` given a zero input signal.
+    ///
+    /// This is done by simulating the filter, so it takes time proportional
+    /// to the returned value. Returns `None` if the filter is not stable or
+    /// the state does not decay within `MAX_TAIL_SAMPLES` samples.
+    pub fn tail_samples(&self, coefs: &BiquadCoefs) -> Option<usize> {
+        if !self.is_active() {
+            return Some(0);
+        }
+
+        if !(pole_radius(coefs) < 1.0) {
+            return None;
+        }
+
+        let mut st = *self;
+        for i in 0..MAX_TAIL_SAMPLES {
+            st.apply_to_sample(0.0, coefs);
+            if !st.is_active() {
+                return Some(i + 1);
+            }
+        }
+        None
+    }
+
     pub fn skip(&mut self, num_samples: usize, coefs: &BiquadCoefs) {
         // FIXME: there should be a O(1) method for this
         for _ in 0..num_samples {
@@ -44,6 +69,20 @@ impl BiquadKernelState {
     }
 }
 
+/// The maximum value `BiquadKernelState::tail_samples` returns.
+const MAX_TAIL_SAMPLES: usize = 1 << 20;
+
+/// Compute the largest magnitude of the roots of `z^2 + a1 z + a2`.
+fn pole_radius(coefs: &BiquadCoefs) -> f64 {
+    let disc = coefs.a1 * coefs.a1 - 4.0 * coefs.a2;
+    if disc < 0.0 {
+        // Complex conjugate poles
+        coefs.a2.sqrt()
+    } else {
+        (coefs.a1.abs() + disc.sqrt()) * 0.5
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SimpleBiquadKernel {
     coefs: BiquadCoefs,
@@ -101,6 +140,14 @@ impl Filter for SimpleBiquadKernel {
         self.states.iter().any(BiquadKernelState::is_active)
     }
 
+    fn tail_samples(&self) -> Option<usize> {
+        let mut max_tail = 0;
+        for state in self.states.iter() {
+            max_tail = max_tail.max(state.tail_samples(&self.coefs)?);
+        }
+        Some(max_tail)
+    }
+
     fn num_input_channels(&self) -> Option<usize> {
         self.num_channels()
     }
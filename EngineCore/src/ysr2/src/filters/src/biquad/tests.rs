@@ -37,6 +37,51 @@ fn identity_outplace() {
     assert_num_slice_approx_eq(&signal_new, &signal, 1.0e-5);
 }
 
+#[test]
+fn tail_samples() {
+    let coefs = biquad::BiquadCoefs {
+        b0: 1.0,
+        b1: 0.0,
+        b2: 0.0,
+        a1: -0.9,
+        a2: 0.2,
+    };
+    let mut kernel = biquad::SimpleBiquadKernel::new(&coefs, 1);
+    assert_eq!(kernel.tail_samples(), Some(0));
+
+    let mut signal = vec![1.0; 16];
+    kernel.render_inplace(&mut [&mut signal], 0..16);
+
+    let tail = kernel.tail_samples().unwrap();
+    assert!(tail > 0);
+
+    let mut signal = vec![0.0; tail - 1];
+    kernel.render_inplace(&mut [&mut signal], 0..tail - 1);
+    assert!(kernel.is_active());
+    assert_eq!(kernel.tail_samples(), Some(1));
+
+    let mut signal = vec![0.0; 1];
+    kernel.render_inplace(&mut [&mut signal], 0..1);
+    assert!(!kernel.is_active());
+    assert_eq!(kernel.tail_samples(), Some(0));
+}
+
+#[test]
+fn tail_samples_unstable() {
+    let coefs = biquad::BiquadCoefs {
+        b0: 1.0,
+        b1: 0.0,
+        b2: 0.0,
+        a1: 0.0,
+        a2: -1.0,
+    };
+    let mut kernel = biquad::SimpleBiquadKernel::new(&coefs, 1);
+
+    let mut signal = vec![1.0; 4];
+    kernel.render_inplace(&mut [&mut signal], 0..4);
+    assert_eq!(kernel.tail_samples(), None);
+}
+
 #[bench]
 fn process_1000000(b: &mut Bencher) {
     let mut signal = vec![0.0; 1000000];
@@ -4,6 +4,7 @@
 // This source code is a part of Nightingales.
 //
 use yfft;
+use std::cmp::min;
 use conv::ConvSetup;
 
 /// Processed single-channel impulse response data.
@@ -13,6 +14,7 @@ use conv::ConvSetup;
 #[derive(Debug, Clone)]
 pub struct IrSpectrum {
     blocks: Vec<Vec<Vec<f32>>>,
+    len: usize,
 }
 
 impl IrSpectrum {
@@ -55,7 +57,57 @@ impl IrSpectrum {
             })
             .collect();
 
-        Self { blocks }
+        Self { blocks, len: start }
+    }
+
+    /// Retrieve the length of the impulse response represented by this
+    /// `IrSpectrum`.
+    ///
+    /// This is equal to the length of the impulse response given to
+    /// [`IrSpectrum::from_ir`], truncated to the maximum length supported by
+    /// the `ConvSetup`.
+    pub fn impulse_len(&self) -> usize {
+        self.len
+    }
+
+    /// Reconstruct the time-domain impulse response and write it to `out`.
+    ///
+    /// `out.len()` must be equal to `self.impulse_len()`. The reconstructed
+    /// impulse response is subject to the numerical error of FFT.
+    pub fn write_impulse(&self, out: &mut [f32]) {
+        assert_eq!(out.len(), self.len);
+
+        let mut buffer = Vec::new();
+        let mut start = 0;
+        for blocks in self.blocks.iter() {
+            let len = if let Some(block) = blocks.first() {
+                block.len()
+            } else {
+                continue;
+            };
+
+            // The blocks were pre-scaled by `from_ir` to cancel out the
+            // scaling factor of the inverse transform
+            let setup = yfft::Setup::new(&yfft::Options {
+                input_data_order: yfft::DataOrder::Natural,
+                output_data_order: yfft::DataOrder::Natural,
+                input_data_format: yfft::DataFormat::HalfComplex,
+                output_data_format: yfft::DataFormat::Real,
+                len,
+                inverse: true,
+            }).unwrap();
+            let mut env = yfft::Env::new(&setup);
+
+            for block in blocks.iter() {
+                buffer.clear();
+                buffer.extend_from_slice(block);
+                env.transform(&mut buffer[..]);
+
+                let count = min(len / 2, self.len - start);
+                out[start..start + count].copy_from_slice(&buffer[0..count]);
+                start += count;
+            }
+        }
     }
 
     /// Retrieve the number of blocks of the specified size.
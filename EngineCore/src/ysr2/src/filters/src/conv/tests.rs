@@ -237,6 +237,58 @@ fn conv_solo_nonuniform4() {
     });
 }
 
+fn test_impulse_with_params(params: &ConvParams) {
+    let setup = ConvSetup::new(params);
+
+    for pat in test_patterns() {
+        let ir = IrSpectrum::from_ir(&pat, &setup);
+        assert_eq!(ir.impulse_len(), pat.len());
+
+        let mut out = vec![0.0; ir.impulse_len()];
+        ir.write_impulse(&mut out);
+        assert_num_slice_approx_eq(&out, &pat, 1.0e-5);
+    }
+}
+
+#[test]
+fn conv_impulse_simple() {
+    test_impulse_with_params(&ConvParams {
+        blocks: vec![(3, 4)],
+        latency: 8,
+    });
+}
+
+#[test]
+fn conv_impulse_nonuniform() {
+    test_impulse_with_params(&ConvParams {
+        blocks: vec![
+            // (2^1) * 1
+            (1, 1),
+            // (2^2) * 1
+            (2, 1),
+            // (2^3) * 4
+            (3, 4),
+        ],
+        latency: 2,
+    });
+}
+
+#[test]
+fn conv_impulse_truncated() {
+    let setup = ConvSetup::new(&ConvParams {
+        blocks: vec![(2, 2)],
+        latency: 4,
+    });
+    let pat: Vec<f32> = (0..12).map(|x| x as f32).collect();
+
+    let ir = IrSpectrum::from_ir(&pat, &setup);
+    assert_eq!(ir.impulse_len(), 8);
+
+    let mut out = vec![0.0; 8];
+    ir.write_impulse(&mut out);
+    assert_num_slice_approx_eq(&out, &pat[0..8], 1.0e-5);
+}
+
 pub struct MyZeroGenerator;
 
 impl Generator for MyZeroGenerator {
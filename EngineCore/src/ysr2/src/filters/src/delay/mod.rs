@@ -8,3 +8,6 @@
 mod simple;
 
 pub use self::simple::*;
+
+#[cfg(test)]
+mod tests;
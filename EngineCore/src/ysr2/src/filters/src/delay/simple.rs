@@ -10,14 +10,27 @@ use {Filter, FilterNode};
 use siso::SisoFilter;
 use utils::apply_by_sample;
 
+/// Delay filter.
+///
+/// Delays the input signal by a fixed number of samples using a ring buffer
+/// for each channel. The filter remains active while the ring buffer may hold
+/// a non-zero signal, i.e., for `latency()` samples after the last non-zero
+/// input sample was fed.
 #[derive(Debug, Clone)]
 pub struct DelayFilter {
     states: Vec<Vec<f32>>,
     position: usize,
+    /// The number of samples remaining until the ring buffer is guaranteed to
+    /// be filled with zero.
     left_samples: usize,
 }
 
 impl DelayFilter {
+    /// Construct a `DelayFilter` with the specified latency (measured in
+    /// samples) and number of channels.
+    ///
+    /// `latency` can be zero, in which case the filter outputs a signal
+    /// identical to the input. `num_channels` must not be zero.
     pub fn new(latency: usize, num_channels: usize) -> Self {
         assert_ne!(num_channels, 0);
         Self {
@@ -26,6 +39,11 @@ impl DelayFilter {
             left_samples: 0,
         }
     }
+
+    /// Get the latency (measured in samples).
+    pub fn latency(&self) -> usize {
+        self.states[0].len()
+    }
 }
 
 impl SisoFilter for DelayFilter {
@@ -48,6 +66,16 @@ impl Filter for DelayFilter {
         }
         assert_eq!(self.states.len(), to.len());
 
+        if self.latency() == 0 {
+            if let Some((inputs, ref in_range)) = from {
+                assert_eq!(range.len(), in_range.len());
+                for (to, from) in to.iter_mut().zip(inputs.iter()) {
+                    to[range.clone()].copy_from_slice(&from[in_range.clone()]);
+                }
+            }
+            return;
+        }
+
         let mut max_intensity = 0f32;
 
         for i in 0..to.len() {
@@ -83,6 +111,10 @@ impl Filter for DelayFilter {
 
         if max_intensity > 1.0e-8 {
             self.left_samples = self.states[0].len();
+        } else {
+            // The ring buffer was fed with a zero signal, pushing out the
+            // remaining tail
+            self.left_samples = self.left_samples.saturating_sub(range.len());
         }
     }
 
@@ -90,6 +122,16 @@ impl Filter for DelayFilter {
         self.left_samples > 0
     }
 
+    /// Return an upper bound of the number of samples remaining until the
+    /// ring buffer is flushed, which is at most `latency()`.
+    ///
+    /// The bound is reset to `latency()` whenever a rendered block contains a
+    /// non-zero sample, so it is exact only if the last sample of the block
+    /// was non-zero.
+    fn tail_samples(&self) -> Option<usize> {
+        Some(self.left_samples)
+    }
+
     fn num_input_channels(&self) -> Option<usize> {
         self.num_channels()
     }
@@ -157,7 +199,8 @@ impl Filter for DelayFilter {
 pub struct DelayNode(FilterNode<DelayFilter>);
 
 impl DelayNode {
-    /// Constructs a `DelayNode`.
+    /// Constructs a `DelayNode` with the specified latency (measured in
+    /// samples).
     pub fn new(latency: usize) -> Self {
         DelayNode(FilterNode::new(DelayFilter::new(latency, 1), 1, 1))
     }
@@ -0,0 +1,151 @@
+//
+// Copyright 2017 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use delay::DelayFilter;
+use offline::render_filter;
+use utils::assert_num_slice_approx_eq;
+use Filter;
+
+fn signal(len: usize) -> Vec<f32> {
+    (0..len).map(|i| ((i * 7) % 13) as f32 - 6.0).collect()
+}
+
+#[test]
+fn delay_impulse() {
+    let mut input = vec![0.0; 100];
+    input[3] = 1.0;
+    let mut filter = DelayFilter::new(10, 1);
+
+    let output = render_filter(&mut filter, &[&input], 100000);
+    assert_eq!(output.len(), 1);
+
+    let mut expected = vec![0.0; 100];
+    expected[13] = 1.0;
+    assert_num_slice_approx_eq(&output[0][..100], &expected, 1.0e-5);
+}
+
+#[test]
+fn delay_tail() {
+    let input = signal(100);
+    let mut filter = DelayFilter::new(37, 2);
+
+    let output = render_filter(&mut filter, &[&input, &input], 100000);
+    assert!(!filter.is_active());
+
+    // The tail must contain the entire delayed signal
+    let mut expected = vec![0.0; 37];
+    expected.extend_from_slice(&input);
+    for ch in output.iter() {
+        assert!(ch.len() >= expected.len());
+        assert_num_slice_approx_eq(&ch[..expected.len()], &expected, 1.0e-5);
+        assert!(ch[expected.len()..].iter().all(|&x| x == 0.0));
+    }
+}
+
+#[test]
+fn delay_becomes_inactive() {
+    let input = signal(5);
+    let mut filter = DelayFilter::new(20, 1);
+    assert!(!filter.is_active());
+
+    let mut output = vec![0.0; 5];
+    filter.render(&mut [&mut output], 0..5, Some((&[&input], 0..5)));
+    assert!(filter.is_active());
+
+    // The tail is still in the buffer after 19 samples
+    let mut output = vec![0.0; 19];
+    filter.render_inplace(&mut [&mut output], 0..19);
+    assert!(filter.is_active());
+
+    let mut output = vec![0.0; 1];
+    filter.render_inplace(&mut [&mut output], 0..1);
+    assert!(!filter.is_active());
+}
+
+#[test]
+fn delay_tail_samples() {
+    let input = signal(5);
+    let mut filter = DelayFilter::new(20, 1);
+    assert_eq!(filter.tail_samples(), Some(0));
+
+    let mut output = vec![0.0; 5];
+    filter.render(&mut [&mut output], 0..5, Some((&[&input], 0..5)));
+    assert_eq!(filter.tail_samples(), Some(20));
+
+    let mut output = vec![0.0; 19];
+    filter.render_inplace(&mut [&mut output], 0..19);
+    assert_eq!(filter.tail_samples(), Some(1));
+
+    // The last non-zero sample comes out exactly at the end of the tail
+    let mut output = vec![0.0; 1];
+    filter.render_inplace(&mut [&mut output], 0..1);
+    assert_eq!(output[0], input[4]);
+    assert_eq!(filter.tail_samples(), Some(0));
+}
+
+#[test]
+fn delay_tail_samples_skip() {
+    let input = signal(30);
+    let mut filter = DelayFilter::new(20, 1);
+
+    let mut output = vec![0.0; 30];
+    filter.render(&mut [&mut output], 0..30, Some((&[&input], 0..30)));
+    assert_eq!(filter.tail_samples(), Some(20));
+
+    filter.skip(8);
+    assert_eq!(filter.tail_samples(), Some(12));
+
+    filter.skip(12);
+    assert_eq!(filter.tail_samples(), Some(0));
+
+    filter.render(&mut [&mut output], 0..30, Some((&[&input], 0..30)));
+    filter.reset();
+    assert_eq!(filter.tail_samples(), Some(0));
+}
+
+#[test]
+fn delay_skip() {
+    let input = signal(30);
+    let mut filter = DelayFilter::new(20, 1);
+
+    let mut output = vec![0.0; 30];
+    filter.render(&mut [&mut output], 0..30, Some((&[&input], 0..30)));
+
+    // Skip a part of the tail
+    filter.skip(8);
+    assert!(filter.is_active());
+
+    let mut output = vec![0.0; 12];
+    filter.render_inplace(&mut [&mut output], 0..12);
+    assert_num_slice_approx_eq(&output, &input[18..], 1.0e-5);
+    assert!(!filter.is_active());
+}
+
+#[test]
+fn delay_reset() {
+    let input = signal(30);
+    let mut filter = DelayFilter::new(20, 1);
+
+    let mut output = vec![0.0; 30];
+    filter.render(&mut [&mut output], 0..30, Some((&[&input], 0..30)));
+
+    filter.reset();
+    assert!(!filter.is_active());
+
+    let mut output = vec![1.0; 20];
+    filter.render_inplace(&mut [&mut output], 0..20);
+    assert_num_slice_approx_eq(&output, &[0.0; 20], 1.0e-5);
+}
+
+#[test]
+fn delay_zero_latency() {
+    let input = signal(30);
+    let mut filter = DelayFilter::new(0, 1);
+
+    let output = render_filter(&mut filter, &[&input], 100000);
+    assert_num_slice_approx_eq(&output[0], &input, 1.0e-5);
+    assert!(!filter.is_active());
+    assert_eq!(filter.tail_samples(), Some(0));
+}
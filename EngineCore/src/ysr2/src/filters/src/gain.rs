@@ -13,6 +13,9 @@ use siso::SisoFilter;
 use utils::apply_by_sample;
 
 /// Gain filter.
+///
+/// Multiplies every sample by the gain value. Accepts any number of channels.
+/// The gain can be changed smoothly via `gain_mut()`.
 #[derive(Debug, Clone)]
 pub struct GainFilter {
     gain: DynamicValue,
@@ -28,6 +31,16 @@ impl GainFilter {
     pub fn with_gain(gain: f64) -> Self {
         Self { gain: DynamicValue::new(gain) }
     }
+
+    /// Get a reference to the gain value.
+    pub fn gain(&self) -> &DynamicValue {
+        &self.gain
+    }
+
+    /// Get a mutable reference to the gain value.
+    pub fn gain_mut(&mut self) -> &mut DynamicValue {
+        &mut self.gain
+    }
 }
 
 impl SisoFilter for GainFilter {
@@ -76,6 +89,10 @@ impl Filter for GainFilter {
         false
     }
 
+    fn tail_samples(&self) -> Option<usize> {
+        Some(0)
+    }
+
     fn num_input_channels(&self) -> Option<usize> {
         self.num_channels()
     }
@@ -142,3 +159,40 @@ impl Node for GainNode {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use utils::assert_num_slice_approx_eq;
+
+    #[test]
+    fn gain_outplace() {
+        let signal: Vec<_> = (1..256).map(|x| x as f32).collect();
+        let mut filter = GainFilter::with_gain(0.5);
+
+        let mut output = vec![0.0; signal.len()];
+        let len = signal.len();
+        filter.render(&mut [&mut output], 0..len, Some((&[&signal], 0..len)));
+
+        let expected: Vec<_> = signal.iter().map(|x| x * 0.5).collect();
+        assert_num_slice_approx_eq(&output, &expected, 1.0e-5);
+        assert!(!filter.is_active());
+    }
+
+    #[test]
+    fn gain_inplace_multichannel() {
+        let signal1: Vec<_> = (1..256).map(|x| x as f32).collect();
+        let signal2: Vec<_> = (1..256).map(|x| -x as f32).collect();
+        let mut filter = GainFilter::with_gain(-2.0);
+
+        let mut output1 = signal1.clone();
+        let mut output2 = signal2.clone();
+        let len = signal1.len();
+        filter.render_inplace(&mut [&mut output1, &mut output2], 0..len);
+
+        let expected1: Vec<_> = signal1.iter().map(|x| x * -2.0).collect();
+        let expected2: Vec<_> = signal2.iter().map(|x| x * -2.0).collect();
+        assert_num_slice_approx_eq(&output1, &expected1, 1.0e-5);
+        assert_num_slice_approx_eq(&output2, &expected2, 1.0e-5);
+    }
+}
@@ -20,6 +20,7 @@ pub mod conv;
 pub mod delay;
 pub mod gain;
 pub mod mixer;
+pub mod offline;
 pub mod reverb;
 pub mod siso;
 mod utils;
@@ -68,6 +69,18 @@ pub trait Filter {
     /// zero input signal.
     fn is_active(&self) -> bool;
 
+    /// Return an upper bound of the number of samples for which the filter
+    /// keeps generating a non-zero (in the same sense as `is_active`) signal
+    /// if it is fed with a zero input signal from now on.
+    ///
+    /// `None` indicates the value is unbounded or unknown. `Some(0)` implies
+    /// `is_active()` returns `false`.
+    ///
+    /// The default implementation returns `None`.
+    fn tail_samples(&self) -> Option<usize> {
+        None
+    }
+
     /// Feed `num_samples` samples with zero values and discard the output.
     fn skip(&mut self, num_samples: usize);
 
@@ -0,0 +1,143 @@
+//
+// Copyright 2017 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+//! Offline rendering helpers.
+//!
+//! These functions process an entire signal at once, which is mostly useful
+//! for tests and for bouncing a node graph to a file.
+use ysr2_common::nodes::{Context, ContextError, NodeId, OutputNode};
+use Filter;
+
+#[cfg(test)]
+mod tests;
+
+/// The number of samples rendered at once while rendering the tail of a filter
+/// in [`render_filter`].
+///
+/// [`render_filter`]: fn.render_filter.html
+const TAIL_BLOCK_SIZE: usize = 256;
+
+/// Render `num_samples` samples from a node graph, pulling them through the
+/// `OutputNode` specified by `output` in `block_size`-sample chunks.
+///
+/// Returns the rendered signal, one `Vec` for each input of the `OutputNode`.
+/// The last chunk is shortened if `num_samples` is not a multiple of
+/// `block_size`.
+///
+/// Nodes are rendered in the usual way, so inactive nodes are skipped as they
+/// would be during real-time rendering.
+///
+/// Panics if `output` does not refer to an `OutputNode` or `block_size` is
+/// zero.
+///
+/// # Examples
+///
+///     # extern crate ysr2_common;
+///     # extern crate ysr2_filters;
+///     # use ysr2_common::nodes::{Context, OutputNode, ZeroNode};
+///     # use ysr2_filters::offline::render_graph;
+///     # fn main() {
+///     let mut context = Context::new();
+///     let source_id = context.insert(ZeroNode);
+///
+///     let mut sink = OutputNode::new(1);
+///     *sink.input_source_mut(0).unwrap() = Some((source_id, 0));
+///     let sink_id = context.insert(sink);
+///
+///     let output = render_graph(&mut context, &sink_id, 1000, 256).unwrap();
+///     assert_eq!(output, vec![vec![0.0; 1000]]);
+///     # }
+///
+pub fn render_graph(
+    context: &mut Context,
+    output: &NodeId,
+    num_samples: usize,
+    block_size: usize,
+) -> Result<Vec<Vec<f32>>, ContextError> {
+    assert_ne!(block_size, 0);
+
+    let num_channels = context
+        .get_as::<OutputNode>(output)
+        .expect("output is not an OutputNode")
+        .num_inputs();
+    let mut result: Vec<_> = (0..num_channels)
+        .map(|_| Vec::with_capacity(num_samples))
+        .collect();
+
+    let mut remaining = num_samples;
+    while remaining > 0 {
+        let block_len = remaining.min(block_size);
+
+        context
+            .get_mut_as::<OutputNode>(output)
+            .unwrap()
+            .request_frame(block_len);
+
+        context.render()?;
+
+        let sink = context.get_as::<OutputNode>(output).unwrap();
+        for (i, channel) in result.iter_mut().enumerate() {
+            channel.extend_from_slice(sink.get_samples(i).unwrap());
+        }
+
+        remaining -= block_len;
+    }
+
+    Ok(result)
+}
+
+/// Apply a filter to a given signal, followed by the tail of the filter's
+/// response.
+///
+/// After `input` is processed, the filter is fed with a zero signal until
+/// `is_active()` returns `false`, or the tail reaches `max_tail_len` samples.
+/// The latter acts as a safety cap for filters whose response never decays.
+///
+/// Returns the output signal, one `Vec` for each output channel. The number of
+/// output channels is `num_output_channels()`, or `input.len()` if the filter
+/// does not restrict it. All channels of `input` must have the same length.
+pub fn render_filter<T: Filter + ?Sized>(
+    filter: &mut T,
+    input: &[&[f32]],
+    max_tail_len: usize,
+) -> Vec<Vec<f32>> {
+    let num_samples = input.first().map(|ch| ch.len()).unwrap_or(0);
+    for ch in input.iter() {
+        assert_eq!(ch.len(), num_samples);
+    }
+
+    let num_channels = filter.num_output_channels().unwrap_or(input.len());
+    let mut result = vec![vec![0.0; num_samples]; num_channels];
+
+    {
+        let mut to: Vec<_> = result.iter_mut().map(Vec::as_mut_slice).collect();
+        filter.render(&mut to, 0..num_samples, Some((input, 0..num_samples)));
+    }
+
+    let zero = vec![0.0; TAIL_BLOCK_SIZE];
+    let zero_input = vec![&zero[..]; input.len()];
+
+    let mut tail_len = 0;
+    while tail_len < max_tail_len && filter.is_active() {
+        let block_len = (max_tail_len - tail_len).min(TAIL_BLOCK_SIZE);
+
+        for ch in result.iter_mut() {
+            let new_len = ch.len() + block_len;
+            ch.resize(new_len, 0.0);
+        }
+
+        let start = num_samples + tail_len;
+        let mut to: Vec<_> = result.iter_mut().map(Vec::as_mut_slice).collect();
+        filter.render(
+            &mut to,
+            start..start + block_len,
+            Some((&zero_input[..], 0..block_len)),
+        );
+
+        tail_len += block_len;
+    }
+
+    result
+}
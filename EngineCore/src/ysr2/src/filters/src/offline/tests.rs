@@ -0,0 +1,108 @@
+//
+// Copyright 2017 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use std::ops::Range;
+use ysr2_common::nodes::{Context, OutputNode};
+use ysr2_common::stream::{Generator, GeneratorNode};
+
+use biquad::{eq, BiquadNode, SimpleBiquadKernel};
+use offline::{render_filter, render_graph};
+use siso::IdentityFilter;
+use utils::assert_num_slice_approx_eq;
+
+/// Plays back a given signal once.
+#[derive(Debug)]
+struct SignalGenerator {
+    signal: Vec<f32>,
+    position: usize,
+}
+
+impl Generator for SignalGenerator {
+    fn render(&mut self, to: &mut [&mut [f32]], range: Range<usize>) {
+        let range_len = range.len();
+        for (i, x) in to[0][range].iter_mut().enumerate() {
+            *x = self.signal.get(self.position + i).cloned().unwrap_or(0.0);
+        }
+        self.position += range_len;
+    }
+
+    fn skip(&mut self, num_samples: usize) {
+        self.position += num_samples;
+    }
+
+    fn is_active(&self) -> bool {
+        self.position < self.signal.len()
+    }
+}
+
+fn impulse(len: usize) -> Vec<f32> {
+    let mut signal = vec![0.0; len];
+    signal[0] = 1.0;
+    signal
+}
+
+#[test]
+fn biquad_graph_matches_filter() {
+    let coefs = eq::low_pass_filter(0.1, 0.7);
+    let signal: Vec<f32> = (0..1000).map(|i| ((i * 7) % 13) as f32 - 6.0).collect();
+
+    let direct = render_filter(&mut SimpleBiquadKernel::new(&coefs, 1), &[&signal], 100000);
+    assert_eq!(direct.len(), 1);
+    assert!(direct[0].len() > signal.len());
+
+    let mut context = Context::new();
+    let source_id = context.insert(GeneratorNode::new(
+        SignalGenerator {
+            signal: signal.clone(),
+            position: 0,
+        },
+        1,
+    ));
+
+    let mut biquad = BiquadNode::new(&coefs);
+    *biquad.input_source_mut() = Some((source_id, 0));
+    let biquad_id = context.insert(biquad);
+
+    let mut sink = OutputNode::new(1);
+    *sink.input_source_mut(0).unwrap() = Some((biquad_id, 0));
+    let sink_id = context.insert(sink);
+
+    // Use a block size that doesn't divide the total length to exercise the
+    // final partial block
+    let num_samples = direct[0].len() + 550;
+    let graph = render_graph(&mut context, &sink_id, num_samples, 100).unwrap();
+    assert_eq!(graph.len(), 1);
+    assert_eq!(graph[0].len(), num_samples);
+
+    // Both process the input signal in exactly the same way
+    assert_eq!(&graph[0][..signal.len()], &direct[0][..signal.len()]);
+
+    // The tails might be cut off at different points because `is_active` is
+    // checked at block boundaries, but the difference must be negligible
+    assert_num_slice_approx_eq(&graph[0][..direct[0].len()], &direct[0], 1.0e-6);
+    assert!(graph[0][direct[0].len()..].iter().all(|x| x.abs() < 1.0e-6));
+}
+
+#[test]
+fn filter_inactive_has_no_tail() {
+    let signal = impulse(10);
+    let output = render_filter(&mut IdentityFilter, &[&signal, &signal], 1000);
+    assert_eq!(output, vec![signal.clone(), signal.clone()]);
+}
+
+#[test]
+fn filter_tail_is_capped() {
+    // A marginally stable filter whose response never decays
+    let coefs = ::biquad::BiquadCoefs {
+        b0: 1.0,
+        b1: 0.0,
+        b2: 0.0,
+        a1: 0.0,
+        a2: 1.0,
+    };
+    let signal = impulse(10);
+    let output = render_filter(&mut SimpleBiquadKernel::new(&coefs, 1), &[&signal], 1000);
+    assert_eq!(output[0].len(), 1010);
+}
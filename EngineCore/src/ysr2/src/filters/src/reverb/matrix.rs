@@ -284,6 +284,10 @@ impl Filter for MatrixReverb {
         self.tail_remaining > 0.0
     }
 
+    fn tail_samples(&self) -> Option<usize> {
+        Some(self.tail_remaining.max(0.0).ceil() as usize)
+    }
+
     fn skip(&mut self, num_samples: usize) {
         let num_processed_samples = if num_samples as f64 >= self.tail_remaining {
             num_samples
@@ -54,6 +54,10 @@ impl Filter for IdentityFilter {
         false
     }
 
+    fn tail_samples(&self) -> Option<usize> {
+        Some(0)
+    }
+
     fn num_input_channels(&self) -> Option<usize> {
         None
     }
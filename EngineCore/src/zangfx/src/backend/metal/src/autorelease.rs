@@ -0,0 +1,210 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+//! Autorelease pool management.
+//!
+//! Some Metal operations (e.g., creating a command buffer) return autoreleased
+//! objects. They are never released if there is no autorelease pool on the
+//! calling thread, resulting in a silent memory growth. In a debug build, such
+//! operations check whether they are called inside an autorelease pool
+//! created by this crate (either by [`AutoreleaseScope`] or
+//! `Device::autorelease_pool_scope_core`) and generate a warning through
+//! [`zangfx_base::debug::report_debug`] if they are not. The warning is
+//! rate-limited per thread and delivered to the handlers registered by
+//! [`zangfx_base::debug::add_debug_report_handler`].
+//!
+//! Autorelease pools created by other means (e.g., the one created by
+//! `NSApplication` for each iteration of the main event loop) are not
+//! recognized by the check.
+use std::{
+    cell::{Cell, RefCell},
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+use zangfx_base::{debug, device};
+use zangfx_metal_rs as metal;
+
+use crate::utils::OCPtr;
+
+/// The minimum interval between warnings generated on a single thread.
+const WARNING_INTERVAL: Duration = Duration::from_secs(5);
+
+thread_local! {
+    /// The number of autorelease pools created by this crate that are active
+    /// on the current thread.
+    static POOL_DEPTH: Cell<usize> = Cell::new(0);
+
+    static WARNING_LIMITER: RefCell<WarningLimiter> = RefCell::new(WarningLimiter::new());
+}
+
+/// An RAII autorelease pool.
+///
+/// An autorelease pool is created on construction and drained when
+/// `AutoreleaseScope` is dropped. This is an alternative to
+/// `Device::autorelease_pool_scope_core` for code that can't be restructured
+/// around a callback.
+///
+/// Autorelease pools are bound to a thread and must be dropped in the reverse
+/// order of creation. For this reason, `AutoreleaseScope` is neither `Send`
+/// nor `Sync`.
+///
+/// # Examples
+///
+///     use zangfx_metal::autorelease::AutoreleaseScope;
+///
+///     loop {
+///         let _scope = AutoreleaseScope::new();
+///
+///         // Autoreleased objects created here are released at the end of
+///         // each iteration
+///         # break;
+///     }
+///
+#[derive(Debug)]
+pub struct AutoreleaseScope {
+    pool: Option<OCPtr<metal::NSAutoreleasePool>>,
+    _no_send_sync: PhantomData<*mut ()>,
+}
+
+impl AutoreleaseScope {
+    /// Create an autorelease pool.
+    pub fn new() -> Self {
+        POOL_DEPTH.with(|depth| depth.set(depth.get() + 1));
+        Self {
+            pool: Some(new_pool()),
+            _no_send_sync: PhantomData,
+        }
+    }
+}
+
+impl Default for AutoreleaseScope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl device::AutoreleasePool for AutoreleaseScope {
+    fn drain(&mut self) {
+        self.pool = None;
+        self.pool = Some(new_pool());
+    }
+}
+
+impl Drop for AutoreleaseScope {
+    fn drop(&mut self) {
+        self.pool = None;
+        POOL_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+fn new_pool() -> OCPtr<metal::NSAutoreleasePool> {
+    unsafe { OCPtr::from_raw(metal::NSAutoreleasePool::alloc().init()).unwrap() }
+}
+
+/// Report a warning if the current thread is not inside an autorelease pool
+/// created by this crate. `operation` is the name of an operation that
+/// autoreleases objects.
+///
+/// This is a no-op in a release build.
+pub fn check_autorelease_pool(operation: &str) {
+    if !cfg!(debug_assertions) {
+        return;
+    }
+
+    let depth = POOL_DEPTH.with(Cell::get);
+    let message = WARNING_LIMITER
+        .with(|limiter| check_pool_depth(depth, &mut limiter.borrow_mut(), Instant::now()));
+
+    if let Some(num_suppressed) = message {
+        let message = format!(
+            "zangfx_metal: `{}` was called outside an autorelease pool. \
+             Autoreleased objects will never be released. Use \
+             `Device::autorelease_pool_scope` or `AutoreleaseScope`. \
+             ({} similar warning(s) suppressed)",
+            operation, num_suppressed
+        );
+        debug::report_debug(&debug::DebugReport::warning(&message));
+    }
+}
+
+/// Decide whether a warning should be generated. Returns the number of the
+/// warnings suppressed since the last one if it should.
+fn check_pool_depth(depth: usize, limiter: &mut WarningLimiter, now: Instant) -> Option<usize> {
+    if depth > 0 {
+        None
+    } else {
+        limiter.trigger(now)
+    }
+}
+
+/// Rate-limits warnings.
+#[derive(Debug)]
+struct WarningLimiter {
+    last_warned_at: Option<Instant>,
+    num_suppressed: usize,
+}
+
+impl WarningLimiter {
+    fn new() -> Self {
+        Self {
+            last_warned_at: None,
+            num_suppressed: 0,
+        }
+    }
+
+    /// Returns `Some(num_suppressed)` if a warning should be generated.
+    fn trigger(&mut self, now: Instant) -> Option<usize> {
+        match self.last_warned_at {
+            Some(last) if now.duration_since(last) < WARNING_INTERVAL => {
+                self.num_suppressed += 1;
+                None
+            }
+            _ => {
+                self.last_warned_at = Some(now);
+                Some(std::mem::replace(&mut self.num_suppressed, 0))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_warning_inside_pool() {
+        let mut limiter = WarningLimiter::new();
+        let now = Instant::now();
+        assert_eq!(check_pool_depth(1, &mut limiter, now), None);
+        assert_eq!(check_pool_depth(2, &mut limiter, now), None);
+        assert_eq!(limiter.num_suppressed, 0);
+    }
+
+    #[test]
+    fn warning_outside_pool() {
+        let mut limiter = WarningLimiter::new();
+        assert_eq!(check_pool_depth(0, &mut limiter, Instant::now()), Some(0));
+    }
+
+    #[test]
+    fn warning_rate_limited() {
+        let mut limiter = WarningLimiter::new();
+        let t0 = Instant::now();
+        assert_eq!(check_pool_depth(0, &mut limiter, t0), Some(0));
+
+        // Suppressed until `WARNING_INTERVAL` elapses
+        let t1 = t0 + WARNING_INTERVAL / 2;
+        assert_eq!(check_pool_depth(0, &mut limiter, t1), None);
+        assert_eq!(check_pool_depth(0, &mut limiter, t1), None);
+
+        // Calls inside a pool are not counted
+        assert_eq!(check_pool_depth(1, &mut limiter, t1), None);
+
+        let t2 = t0 + WARNING_INTERVAL;
+        assert_eq!(check_pool_depth(0, &mut limiter, t2), Some(2));
+        assert_eq!(check_pool_depth(0, &mut limiter, t2), None);
+    }
+}
@@ -63,6 +63,11 @@ impl resources::BufferBuilder for BufferBuilder {
         self
     }
 
+    fn relocatable(&mut self) -> &mut dyn resources::BufferBuilder {
+        // Relocation is not supported by this backend
+        self
+    }
+
     fn build(&mut self) -> Result<base::BufferRef> {
         let size = self.size.expect("size");
         Ok(Buffer::new(*self.metal_device, size, self.label.clone(), self.usage).into())
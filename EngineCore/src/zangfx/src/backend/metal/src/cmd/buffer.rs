@@ -10,6 +10,7 @@ use std::mem::replace;
 use std::sync::Arc;
 use zangfx_metal_rs::{MTLCommandBuffer, MTLCommandBufferStatus, MTLCommandQueue};
 
+use crate::autorelease::check_autorelease_pool;
 use crate::renderpass::RenderTargetTable;
 use crate::utils::{nil_error, OCPtr};
 use zangfx_base::{self as base, command, zangfx_impl_object};
@@ -64,6 +65,8 @@ impl CmdBuffer {
         metal_queue: MTLCommandQueue,
         scheduler: Arc<Scheduler>,
     ) -> Result<Self> {
+        check_autorelease_pool("MTLCommandQueue newCommandBuffer");
+
         let metal_buffer = metal_queue.new_command_buffer();
         if metal_buffer.is_null() {
             return Err(nil_error("MTLCommandQueue newCommandBuffer"));
@@ -189,6 +192,7 @@ impl command::CmdBuffer for CmdBuffer {
                 metal_encoder,
                 replace(&mut uncommited.fence_set, Default::default()),
                 our_rt_table.extents(),
+                our_rt_table.visibility_heap().cloned(),
             )
         };
         uncommited.encoder = Some(Encoder::Render(encoder));
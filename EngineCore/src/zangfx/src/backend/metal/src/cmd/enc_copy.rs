@@ -14,6 +14,7 @@ use crate::buffer::Buffer;
 use crate::cmd::enc::{CmdBufferFenceSet, DebugCommands};
 use crate::cmd::fence::Fence;
 use crate::image::Image;
+use crate::query::QueryPool;
 use crate::utils::OCPtr;
 
 #[derive(Debug)]
@@ -279,4 +280,71 @@ impl base::CopyCmdEncoder for CopyEncoder {
             );
         }
     }
+
+    fn reset_queries(&mut self, pool: &base::QueryPoolRef, range: Range<base::QueryIndex>) {
+        use zangfx_base::QueryPool as _;
+
+        let our_pool: &QueryPool = pool.downcast_ref().expect("bad query pool type");
+        assert!(
+            range.start <= range.end && range.end <= our_pool.len(),
+            "range out of bounds"
+        );
+        if range.start == range.end {
+            return;
+        }
+
+        let (metal_buffer, start) = our_pool.metal_buffer_and_offset(range.start);
+        let (_, end) = our_pool.metal_buffer_and_offset(range.end);
+        self.metal_encoder
+            .fill_buffer(metal_buffer, NSRange::new(start, end - start), 0);
+    }
+
+    fn copy_query_results(
+        &mut self,
+        pool: &base::QueryPoolRef,
+        range: Range<base::QueryIndex>,
+        dst: &base::BufferRef,
+        dst_offset: DeviceSize,
+        dst_stride: DeviceSize,
+    ) {
+        use zangfx_base::QueryPool as _;
+
+        let our_pool: &QueryPool = pool.downcast_ref().expect("bad query pool type");
+        let my_dst: &Buffer = dst.downcast_ref().expect("bad destination buffer type");
+        assert!(
+            range.start <= range.end && range.end <= our_pool.len(),
+            "range out of bounds"
+        );
+
+        let (dst_metal_buffer, dst_buffer_offset) = my_dst.metal_buffer_and_offset().unwrap();
+
+        // The visibility result buffer is hazard-tracked, so this waits for
+        // preceding render commands writing to it
+        if dst_stride == 8 {
+            // The results are tightly packed — a single copy suffices
+            let (src_metal_buffer, start) = our_pool.metal_buffer_and_offset(range.start);
+            let (_, end) = our_pool.metal_buffer_and_offset(range.end);
+            if start < end {
+                self.metal_encoder.copy_from_buffer_to_buffer(
+                    src_metal_buffer,
+                    start,
+                    dst_metal_buffer,
+                    dst_offset + dst_buffer_offset,
+                    end - start,
+                );
+            }
+            return;
+        }
+
+        for (i, index) in range.enumerate() {
+            let (src_metal_buffer, src_offset) = our_pool.metal_buffer_and_offset(index);
+            self.metal_encoder.copy_from_buffer_to_buffer(
+                src_metal_buffer,
+                src_offset,
+                dst_metal_buffer,
+                dst_offset + dst_buffer_offset + dst_stride * i as DeviceSize,
+                8,
+            );
+        }
+    }
 }
@@ -4,12 +4,14 @@
 // This source code is a part of Nightingales.
 //
 use std::ops::Range;
+use std::sync::Arc;
 use zangfx_base::{self as base, command, heap, zangfx_impl_object};
 use zangfx_common::Rect2D;
-use zangfx_metal_rs::MTLRenderCommandEncoder;
+use zangfx_metal_rs::{MTLRenderCommandEncoder, MTLVisibilityResultMode};
 
 use crate::cmd::enc::{CmdBufferFenceSet, DebugCommands, UseResources};
 use crate::cmd::fence::Fence;
+use crate::query::{QueryPool, VisibilityHeap};
 use crate::renderpipeline::RenderStateManager;
 use crate::utils::{translate_render_stage, OCPtr};
 
@@ -18,6 +20,10 @@ crate struct RenderEncoder {
     metal_encoder: OCPtr<MTLRenderCommandEncoder>,
     fence_set: CmdBufferFenceSet,
     state: RenderStateManager,
+    visibility_heap: Option<Arc<VisibilityHeap>>,
+
+    /// The query started by `begin_query`.
+    active_query: Option<(QueryPool, base::QueryIndex)>,
 }
 
 zangfx_impl_object! { RenderEncoder:
@@ -31,15 +37,23 @@ impl RenderEncoder {
         metal_encoder: MTLRenderCommandEncoder,
         fence_set: CmdBufferFenceSet,
         extents: [u32; 2],
+        visibility_heap: Option<Arc<VisibilityHeap>>,
     ) -> Self {
         Self {
             metal_encoder: OCPtr::new(metal_encoder).unwrap(),
             fence_set,
             state: RenderStateManager::new(metal_encoder, extents),
+            visibility_heap,
+            active_query: None,
         }
     }
 
     pub(super) fn finish(self) -> CmdBufferFenceSet {
+        // This method is also called while a command buffer is dropped, in
+        // which case a panic would abort the program
+        if !std::thread::panicking() {
+            assert!(self.active_query.is_none(), "query is still active");
+        }
         self.metal_encoder.end_encoding();
         self.fence_set
     }
@@ -172,4 +186,40 @@ impl command::RenderCmdEncoder for RenderEncoder {
     fn draw_indexed_indirect(&mut self, buffer: &base::BufferRef, offset: base::DeviceSize) {
         self.state.draw_indexed_indirect(buffer, offset);
     }
+
+    fn begin_query(&mut self, pool: &base::QueryPoolRef, index: base::QueryIndex) {
+        use zangfx_base::QueryPool as _;
+
+        let our_pool: &QueryPool = pool.downcast_ref().expect("bad query pool type");
+        assert!(index < our_pool.len(), "index out of bounds");
+        assert!(self.active_query.is_none(), "queries cannot be nested");
+
+        let visibility_heap = self
+            .visibility_heap
+            .as_ref()
+            .expect("render target table does not have a visibility result buffer");
+        assert!(
+            Arc::ptr_eq(visibility_heap, our_pool.heap()),
+            "query pool was created by a different device"
+        );
+
+        let (_, offset) = our_pool.metal_buffer_and_offset(index);
+        self.metal_encoder
+            .set_visibility_result_mode(offset, MTLVisibilityResultMode::Counting);
+
+        self.active_query = Some((our_pool.clone(), index));
+    }
+
+    fn end_query(&mut self, pool: &base::QueryPoolRef, index: base::QueryIndex) {
+        let our_pool: &QueryPool = pool.downcast_ref().expect("bad query pool type");
+        match self.active_query {
+            Some((ref active_pool, active_index))
+                if active_pool.is_same(our_pool) && active_index == index => {}
+            _ => panic!("the query is not active"),
+        }
+        self.active_query = None;
+
+        self.metal_encoder
+            .set_visibility_result_mode(0, MTLVisibilityResultMode::Disabled);
+    }
 }
@@ -49,8 +49,14 @@ impl base::SetLabel for CmdQueueBuilder {
 }
 
 impl command::CmdQueueBuilder for CmdQueueBuilder {
-    fn queue_family(&mut self, _: QueueFamily) -> &mut dyn command::CmdQueueBuilder {
+    fn queue_family(&mut self, v: QueueFamily) -> &mut dyn command::CmdQueueBuilder {
         // Ignore it since we know we only have exactly one queue family
+        assert_eq!(v, 0, "invalid queue family index");
+        self
+    }
+
+    fn priority(&mut self, _: f32) -> &mut dyn command::CmdQueueBuilder {
+        // Metal does not support queue priorities
         self
     }
 
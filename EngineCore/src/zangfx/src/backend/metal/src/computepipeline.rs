@@ -20,6 +20,7 @@ pub struct ComputePipelineBuilder {
     metal_device: OCPtr<metal::MTLDevice>,
 
     compute_shader: Option<(Library, String)>,
+    spec_constants: base::SpecConstants,
     root_sig: Option<RootSig>,
 
     label: Option<String>,
@@ -39,6 +40,7 @@ impl ComputePipelineBuilder {
         Self {
             metal_device: OCPtr::new(metal_device).expect("nil device"),
             compute_shader: None,
+            spec_constants: base::SpecConstants::new(),
             root_sig: None,
             label: None,
         }
@@ -68,6 +70,15 @@ impl pipeline::ComputePipelineBuilder for ComputePipelineBuilder {
         self
     }
 
+    fn spec_constant(
+        &mut self,
+        id: base::SpecConstantId,
+        value: base::SpecValue,
+    ) -> &mut dyn pipeline::ComputePipelineBuilder {
+        self.spec_constants.insert(id, value);
+        self
+    }
+
     fn build(&mut self) -> Result<base::ComputePipelineRef> {
         let compute_shader = self.compute_shader.as_ref().expect("compute_shader");
         let root_sig = self.root_sig.as_ref().expect("root_sig");
@@ -81,6 +92,7 @@ impl pipeline::ComputePipelineBuilder for ComputePipelineBuilder {
             &compute_shader.1,
             shader::ShaderStageFlags::COMPUTE,
             root_sig,
+            &self.spec_constants,
             ::std::iter::empty(),
             *self.metal_device,
             &self.label,
@@ -9,10 +9,12 @@ use std::sync::Arc;
 use zangfx_base::{self as base, device, zangfx_impl_object, Result};
 use zangfx_metal_rs as metal;
 
+use crate::autorelease::AutoreleaseScope;
 use crate::limits::DeviceCaps;
 use crate::utils::{translate_storage_mode, OCPtr};
 use crate::{
-    arg, buffer, cmd, computepipeline, heap, image, renderpass, renderpipeline, sampler, shader,
+    arg, buffer, cmd, computepipeline, heap, image, query, renderpass, renderpipeline, sampler,
+    shader,
 };
 
 /// Implementation of `Device` for Metal.
@@ -22,6 +24,7 @@ pub struct Device {
     caps: DeviceCaps,
     arg_layout_info: arg::table::ArgLayoutInfo,
     global_heaps: Vec<base::HeapRef>,
+    visibility_heap: Arc<query::VisibilityHeap>,
 }
 
 zangfx_impl_object! { Device: dyn device::Device, dyn crate::Debug }
@@ -44,6 +47,7 @@ impl Device {
                     Arc::new(heap::GlobalHeap::new(metal_device, storage_mode))
                 })
                 .collect(),
+            visibility_heap: Arc::new(query::VisibilityHeap::new(metal_device)?),
         })
     }
 
@@ -125,11 +129,9 @@ impl device::Device for Device {
     }
 
     fn build_render_target_table(&self) -> base::pass::RenderTargetTableBuilderRef {
-        unsafe {
-            Box::new(renderpass::RenderTargetTableBuilder::new(
-                self.metal_device(),
-            ))
-        }
+        let mut builder = unsafe { renderpass::RenderTargetTableBuilder::new(self.metal_device()) };
+        builder.set_visibility_heap(Some(self.visibility_heap.clone()));
+        Box::new(builder)
     }
 
     fn build_render_pipeline(&self) -> base::pipeline::RenderPipelineBuilderRef {
@@ -148,6 +150,10 @@ impl device::Device for Device {
         }
     }
 
+    fn build_query_pool(&self) -> base::query::QueryPoolBuilderRef {
+        Box::new(query::QueryPoolBuilder::new(self.visibility_heap.clone()))
+    }
+
     fn update_arg_tables(
         &self,
         arg_table_sig: &base::ArgTableSigRef,
@@ -163,20 +169,6 @@ impl device::Device for Device {
     }
 
     fn autorelease_pool_scope_core(&self, cb: &mut dyn FnMut(&mut dyn device::AutoreleasePool)) {
-        struct AutoreleasePool(Option<OCPtr<metal::NSAutoreleasePool>>);
-
-        impl device::AutoreleasePool for AutoreleasePool {
-            fn drain(&mut self) {
-                self.0 = None;
-                self.0 = Some(unsafe {
-                    OCPtr::from_raw(metal::NSAutoreleasePool::alloc().init()).unwrap()
-                });
-            }
-        }
-
-        let mut op = AutoreleasePool(Some(unsafe {
-            OCPtr::from_raw(metal::NSAutoreleasePool::alloc().init()).unwrap()
-        }));
-        cb(&mut op)
+        cb(&mut AutoreleaseScope::new())
     }
 }
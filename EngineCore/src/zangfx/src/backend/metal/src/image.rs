@@ -32,6 +32,7 @@ pub struct ImageBuilder {
     num_mip_levels: u32,
     format: Option<base::ImageFormat>,
     usage: base::ImageUsageFlags,
+    sample_count: u32,
     label: Option<String>,
 }
 
@@ -60,6 +61,7 @@ impl ImageBuilder {
             num_mip_levels: 1,
             format: None,
             usage: base::ImageUsageFlags::default(),
+            sample_count: 1,
             label: None,
         }
     }
@@ -101,6 +103,11 @@ impl base::ImageBuilder for ImageBuilder {
         self
     }
 
+    fn sample_count(&mut self, v: u32) -> &mut dyn base::ImageBuilder {
+        self.sample_count = v;
+        self
+    }
+
     fn format(&mut self, v: base::ImageFormat) -> &mut dyn base::ImageBuilder {
         self.format = Some(v);
         self
@@ -119,8 +126,11 @@ impl base::ImageBuilder for ImageBuilder {
         let metal_desc =
             unsafe { OCPtr::from_raw(metal::MTLTextureDescriptor::alloc().init()).unwrap() };
 
-        use zangfx_metal_rs::MTLTextureType::{Cube, CubeArray, D1Array, D2Array, D1, D2, D3};
+        use zangfx_metal_rs::MTLTextureType::{
+            Cube, CubeArray, D1Array, D2Array, D2Multisample, D1, D2, D3,
+        };
         let (ty, dims) = match (extents, self.num_layers) {
+            (ImageExtents::TwoD(x, y), None) if self.sample_count > 1 => (D2Multisample, [x, y, 1]),
             (ImageExtents::OneD(x), None) => (D1, [x, 1, 1]),
             (ImageExtents::OneD(x), Some(_)) => (D1Array, [x, 1, 1]),
             (ImageExtents::TwoD(x, y), None) => (D2, [x, y, 1]),
@@ -168,7 +178,7 @@ impl base::ImageBuilder for ImageBuilder {
         metal_desc.set_depth(dims[2] as u64);
 
         metal_desc.set_mipmap_level_count(self.num_mip_levels as u64);
-        metal_desc.set_sample_count(1);
+        metal_desc.set_sample_count(self.sample_count as u64);
         metal_desc.set_array_length(self.num_layers.unwrap_or(1) as u64);
 
         let num_bytes_per_pixel = format.size_class().num_bytes_per_pixel();
@@ -23,6 +23,13 @@
 //!  - Conversely, methods named `from_raw` do not increase the reference count.
 //!  - No method increases the reference count when returning an object.
 //!
+//! ## Queries
+//!
+//! Only occlusion queries are supported. Every render target table created
+//! via `Device::build_render_target_table` shares a single visibility result
+//! buffer, from which query pools are suballocated. See the [`query`] module
+//! for details.
+//!
 //! # Debugging
 //!
 //! Setting labels is supported by the following objects: `ArgPoolBuilder`,
@@ -37,6 +44,12 @@
 //! `MTLCommandEncoder`'s methods and they are visible via Xcode's GPU Frame
 //! Capture.
 //!
+//! In a debug build, operations that autorelease objects generate a warning
+//! through [`zangfx_base::debug::report_debug`] if they are called outside an
+//! autorelease pool. See the [`autorelease`] module for details.
+//!
+//! [`autorelease`]: autorelease
+//!
 //! # Limitations
 //!
 //! ## Implementation Limits
@@ -58,6 +71,7 @@
 pub use zangfx_metal_rs as metal;
 
 pub mod arg;
+pub mod autorelease;
 pub mod buffer;
 pub mod cmd;
 pub mod computepipeline;
@@ -66,6 +80,7 @@ pub mod formats;
 pub mod heap;
 pub mod image;
 pub mod limits;
+pub mod query;
 pub mod renderpass;
 pub mod renderpipeline;
 pub mod sampler;
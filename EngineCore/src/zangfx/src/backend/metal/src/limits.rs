@@ -50,6 +50,10 @@ impl DeviceCaps {
             supports_depth_clamp: true,
             supports_fill_mode_non_solid: true,
             supports_independent_blend: true,
+            supports_sampler_anisotropy: true,
+            supports_timestamp_query: false,
+            supports_pipeline_stats_query: false,
+            timestamp_period: 0.0,
             max_image_extent_1d: 16384,
             max_image_extent_2d: 16384,
             max_image_extent_3d: 2048,
@@ -7,12 +7,15 @@
 
 use objc::runtime::{Class, NO, YES};
 use objc_foundation::{INSString, NSString};
+use std::ffi::CStr;
 use std::mem::transmute_copy;
 
-use super::{id, NSArray, NSObjectProtocol, NSObjectPrototype};
+use super::{id, nil, NSArray, NSObjectProtocol, NSObjectPrototype};
 
 use argument::MTLDataType;
 
+use libc;
+
 pub enum MTLVertexAttributePrototype {}
 pub type MTLVertexAttribute = id<(MTLVertexAttributePrototype, (NSObjectPrototype, ()))>;
 
@@ -91,6 +94,43 @@ pub enum MTLLanguageVersion {
     V2_0 = (2 << 16),
 }
 
+pub enum MTLFunctionConstantValuesPrototype {}
+pub type MTLFunctionConstantValues =
+    id<(MTLFunctionConstantValuesPrototype, (NSObjectPrototype, ()))>;
+
+impl MTLFunctionConstantValues {
+    pub fn new() -> Self {
+        unsafe { msg_send![Self::class(), new] }
+    }
+
+    pub fn alloc() -> Self {
+        unsafe { msg_send![Self::class(), alloc] }
+    }
+
+    pub fn init(&self) -> Self {
+        unsafe { msg_send![self.0, init] }
+    }
+
+    pub unsafe fn set_constant_value_at_index(
+        &self,
+        value: *const libc::c_void,
+        ty: MTLDataType,
+        index: u64,
+    ) {
+        msg_send![self.0, setConstantValue:value type:ty atIndex:index]
+    }
+
+    pub fn reset(&self) {
+        unsafe { msg_send![self.0, reset] }
+    }
+}
+
+impl NSObjectProtocol for MTLFunctionConstantValues {
+    unsafe fn class() -> &'static Class {
+        Class::get("MTLFunctionConstantValues").unwrap()
+    }
+}
+
 pub enum MTLCompileOptionsPrototype {}
 pub type MTLCompileOptions = id<(MTLCompileOptionsPrototype, (NSObjectPrototype, ()))>;
 
@@ -192,6 +232,33 @@ impl<'a> MTLLibrary {
         }
     }
 
+    pub fn get_function_with_constants(
+        &self,
+        name: &str,
+        constants: MTLFunctionConstantValues,
+    ) -> Result<MTLFunction, String> {
+        use cocoa::base::nil as cocoa_nil;
+        use cocoa::foundation::NSString as cocoa_NSString;
+
+        unsafe {
+            let nsname = cocoa_NSString::alloc(cocoa_nil).init_str(name);
+            let mut err = nil;
+
+            let func: MTLFunction = msg_send![self.0, newFunctionWithName:nsname
+                                                           constantValues:constants
+                                                                    error:&mut err];
+
+            match func.is_null() {
+                false => Ok(func),
+                true => {
+                    let desc: id = msg_send![err.0, localizedDescription];
+                    let error: *const libc::c_char = msg_send![desc.0, UTF8String];
+                    Err(CStr::from_ptr(error).to_string_lossy().into_owned())
+                }
+            }
+        }
+    }
+
     pub fn function_names(&self) -> NSArray<NSString> {
         unsafe { msg_send![self.0, functionNames] }
     }
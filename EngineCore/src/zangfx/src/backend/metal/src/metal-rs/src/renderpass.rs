@@ -28,6 +28,7 @@ pub enum MTLStoreAction {
     DontCare = 0,
     Store = 1,
     MultisampleResolve = 2,
+    StoreAndMultisampleResolve = 3,
 }
 
 #[repr(C)]
@@ -296,6 +297,10 @@ impl MTLRenderPassDescriptor {
         unsafe { msg_send![self.0, visibilityResultBuffer] }
     }
 
+    pub fn set_visibility_result_buffer(&self, buffer: MTLBuffer) {
+        unsafe { msg_send![self.0, setVisibilityResultBuffer:buffer.0] }
+    }
+
     pub fn set_render_target_array_length(&self, render_target_array_length: u64) {
         unsafe {
             msg_send![
@@ -0,0 +1,203 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+//! Implementation of `QueryPool` for Metal.
+//!
+//! Only occlusion queries are supported.
+//!
+//! Metal requires the visibility result buffer to be specified when a render
+//! command encoder is created, while ZanGFX allows any query pool to be used
+//! in any render pass. To bridge this gap, every `Device` maintains a single
+//! visibility result buffer ([`VisibilityHeap`]) attached to every render
+//! target table created from it. Each query pool occupies a portion of the
+//! buffer. The size of the buffer is fixed to [`VISIBILITY_HEAP_SIZE`] bytes,
+//! which limits the total number of occlusion queries that can exist at the
+//! same time in a device.
+use parking_lot::Mutex;
+use std::ops::Range;
+use std::sync::Arc;
+use xalloc::{SysTlsf, SysTlsfRegion};
+
+use zangfx_base::{self as base, zangfx_impl_handle, zangfx_impl_object, Result};
+use zangfx_metal_rs as metal;
+
+use crate::utils::{nil_error, OCPtr};
+
+/// The size of [`VisibilityHeap`] in bytes. This is the maximum visibility
+/// result buffer size permitted by Metal.
+pub const VISIBILITY_HEAP_SIZE: u64 = 65536;
+
+/// The size of a single occlusion query result in bytes.
+const RESULT_SIZE: u64 = 8;
+
+/// A visibility result buffer shared by all occlusion query pools created from
+/// a single device.
+#[derive(Debug)]
+crate struct VisibilityHeap {
+    metal_buffer: OCPtr<metal::MTLBuffer>,
+    tlsf: Mutex<SysTlsf<u32>>,
+}
+
+unsafe impl Send for VisibilityHeap {}
+unsafe impl Sync for VisibilityHeap {}
+
+impl VisibilityHeap {
+    /// Construct a `VisibilityHeap`.
+    ///
+    /// It's up to the caller to make sure `metal_device` is valid.
+    crate unsafe fn new(metal_device: metal::MTLDevice) -> Result<Self> {
+        let options = metal::MTLResourceStorageModeShared;
+        let metal_buffer = OCPtr::from_raw(metal_device.new_buffer(VISIBILITY_HEAP_SIZE, options))
+            .ok_or(nil_error("MTLDevice newBufferWithLength:options:"))?;
+
+        Ok(Self {
+            metal_buffer,
+            tlsf: Mutex::new(SysTlsf::new(VISIBILITY_HEAP_SIZE as u32)),
+        })
+    }
+
+    crate fn metal_buffer(&self) -> metal::MTLBuffer {
+        *self.metal_buffer
+    }
+}
+
+/// Implementation of `QueryPoolBuilder` for Metal.
+#[derive(Debug)]
+pub struct QueryPoolBuilder {
+    heap: Arc<VisibilityHeap>,
+    query_type: Option<base::QueryType>,
+    len: Option<base::QueryIndex>,
+}
+
+zangfx_impl_object! { QueryPoolBuilder: dyn base::QueryPoolBuilder, dyn crate::Debug }
+
+impl QueryPoolBuilder {
+    crate fn new(heap: Arc<VisibilityHeap>) -> Self {
+        Self {
+            heap,
+            query_type: None,
+            len: None,
+        }
+    }
+}
+
+impl base::QueryPoolBuilder for QueryPoolBuilder {
+    fn query_type(&mut self, v: base::QueryType) -> &mut dyn base::QueryPoolBuilder {
+        self.query_type = Some(v);
+        self
+    }
+
+    fn len(&mut self, v: base::QueryIndex) -> &mut dyn base::QueryPoolBuilder {
+        self.len = Some(v);
+        self
+    }
+
+    fn build(&mut self) -> Result<base::QueryPoolRef> {
+        let query_type = self.query_type.expect("query_type");
+        let len = self.len.expect("len");
+        assert_ne!(len, 0, "len must not be zero");
+
+        if query_type != base::QueryType::Occlusion {
+            panic!("not supported by this backend");
+        }
+
+        let size = len as u64 * RESULT_SIZE;
+        if size > VISIBILITY_HEAP_SIZE {
+            return Err(base::Error::new(base::ErrorKind::OutOfDeviceMemory));
+        }
+
+        let (region, offset) = self
+            .heap
+            .tlsf
+            .lock()
+            .alloc_aligned(size as u32, RESULT_SIZE as u32)
+            .ok_or_else(|| base::Error::new(base::ErrorKind::OutOfDeviceMemory))?;
+
+        Ok(QueryPool {
+            data: Arc::new(QueryPoolData {
+                heap: self.heap.clone(),
+                region: Some(region),
+                offset: offset as u64,
+                len,
+            }),
+        }
+        .into())
+    }
+}
+
+/// Implementation of `QueryPool` for Metal.
+#[derive(Debug, Clone)]
+pub struct QueryPool {
+    data: Arc<QueryPoolData>,
+}
+
+zangfx_impl_handle! { QueryPool, base::QueryPoolRef }
+
+#[derive(Debug)]
+struct QueryPoolData {
+    heap: Arc<VisibilityHeap>,
+    region: Option<SysTlsfRegion>,
+    /// The offset of the first query's result in the visibility result buffer.
+    offset: u64,
+    len: base::QueryIndex,
+}
+
+unsafe impl Send for QueryPoolData {}
+unsafe impl Sync for QueryPoolData {}
+
+impl QueryPool {
+    crate fn heap(&self) -> &Arc<VisibilityHeap> {
+        &self.data.heap
+    }
+
+    crate fn is_same(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.data, &other.data)
+    }
+
+    /// Get the visibility result buffer and the offset of the result of the
+    /// specified query.
+    crate fn metal_buffer_and_offset(&self, index: base::QueryIndex) -> (metal::MTLBuffer, u64) {
+        (
+            self.data.heap.metal_buffer(),
+            self.data.offset + index as u64 * RESULT_SIZE,
+        )
+    }
+}
+
+impl base::QueryPool for QueryPool {
+    fn query_type(&self) -> base::QueryType {
+        base::QueryType::Occlusion
+    }
+
+    fn len(&self) -> base::QueryIndex {
+        self.data.len
+    }
+
+    fn get_results(&self, range: Range<base::QueryIndex>, out: &mut [u64]) -> Result<()> {
+        assert!(
+            range.start <= range.end && range.end <= self.data.len,
+            "range out of bounds"
+        );
+        assert_eq!(out.len(), range.end - range.start);
+
+        let (metal_buffer, offset) = self.metal_buffer_and_offset(range.start);
+        unsafe {
+            let src = (metal_buffer.contents() as *const u8).offset(offset as isize);
+            std::ptr::copy_nonoverlapping(src as *const u64, out.as_mut_ptr(), out.len());
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for QueryPoolData {
+    fn drop(&mut self) {
+        if let Some(region) = self.region.take() {
+            unsafe {
+                self.heap.tlsf.lock().dealloc_unchecked(region);
+            }
+        }
+    }
+}
@@ -12,6 +12,7 @@ use zangfx_base::{zangfx_impl_handle, zangfx_impl_object};
 
 use crate::formats::translate_image_format;
 use crate::image::Image;
+use crate::query::VisibilityHeap;
 use crate::utils::{nil_error, OCPtr};
 
 /// Implementation of `RenderPassBuilder` for Metal.
@@ -20,6 +21,7 @@ pub struct RenderPassBuilder {
     targets: Vec<Option<RenderPassTargetBuilder>>,
     subpass_color_targets: Vec<Option<usize>>,
     subpass_ds_target: Option<usize>,
+    subpass_resolve_targets: Vec<Option<usize>>,
 }
 
 zangfx_impl_object! { RenderPassBuilder: dyn base::RenderPassBuilder, dyn crate::Debug }
@@ -31,6 +33,7 @@ impl RenderPassBuilder {
             targets: Vec::new(),
             subpass_color_targets: Vec::new(),
             subpass_ds_target: None,
+            subpass_resolve_targets: Vec::new(),
         }
     }
 }
@@ -63,6 +66,10 @@ impl base::RenderPassBuilder for RenderPassBuilder {
         self.subpass_ds_target = target;
     }
 
+    fn subpass_resolve_targets(&mut self, targets: &[Option<base::RenderPassTargetIndex>]) {
+        self.subpass_resolve_targets = targets.iter().cloned().collect();
+    }
+
     fn build(&mut self) -> Result<base::RenderPassRef> {
         let ref targets = self.targets;
 
@@ -74,18 +81,56 @@ impl base::RenderPassBuilder for RenderPassBuilder {
             }
         }
 
+        if !self.subpass_resolve_targets.is_empty() {
+            let target_infos: Vec<_> = targets
+                .iter()
+                .map(|target| {
+                    let target = target
+                        .as_ref()
+                        .expect("render target bindings must be tightly arranged");
+                    base::RenderPassTargetInfo {
+                        format: target.format.unwrap(),
+                        sample_count: target.sample_count,
+                    }
+                })
+                .collect();
+
+            base::validate_resolve_targets(
+                &target_infos,
+                &self.subpass_color_targets,
+                &self.subpass_resolve_targets,
+            )
+            .unwrap_or_else(|e| panic!("{}", e));
+        }
+
         let colors = self
             .subpass_color_targets
             .iter()
-            .map(|i_or_none| {
+            .enumerate()
+            .map(|(color_i, i_or_none)| {
                 i_or_none.map(|i| {
                     let target = targets[i].as_ref().unwrap();
+                    let resolve = self
+                        .subpass_resolve_targets
+                        .get(color_i)
+                        .cloned()
+                        .unwrap_or(None);
+                    let store = match (target.store_op, resolve) {
+                        (_, None) => translate_store_op(target.store_op),
+                        (base::StoreOp::Store, Some(_)) => {
+                            metal::MTLStoreAction::StoreAndMultisampleResolve
+                        }
+                        (base::StoreOp::DontCare, Some(_)) => {
+                            metal::MTLStoreAction::MultisampleResolve
+                        }
+                    };
                     PassTarget {
                         index: i,
                         format: translate_image_format(target.format.unwrap())
                             .expect("unsupported image format"),
                         load: translate_load_op(target.load_op),
-                        store: translate_store_op(target.store_op),
+                        store,
+                        resolve,
                     }
                 })
             })
@@ -99,6 +144,7 @@ impl base::RenderPassBuilder for RenderPassBuilder {
                     .expect("unsupported image format"),
                 load: translate_load_op(target.load_op),
                 store: translate_store_op(target.store_op),
+                resolve: None,
             }
         });
         let stencil = self.subpass_ds_target.map(|i| {
@@ -109,6 +155,7 @@ impl base::RenderPassBuilder for RenderPassBuilder {
                     .expect("unsupported image format"),
                 load: translate_load_op(target.stencil_load_op),
                 store: translate_store_op(target.stencil_store_op),
+                resolve: None,
             }
         });
 
@@ -148,6 +195,7 @@ struct RenderPassTargetBuilder {
     store_op: base::StoreOp,
     stencil_load_op: base::LoadOp,
     stencil_store_op: base::StoreOp,
+    sample_count: u32,
 }
 
 zangfx_impl_object! { RenderPassTargetBuilder: dyn base::RenderPassTarget, dyn crate::Debug }
@@ -163,6 +211,7 @@ impl RenderPassTargetBuilder {
             store_op: base::StoreOp::DontCare,
             stencil_load_op: base::LoadOp::DontCare,
             stencil_store_op: base::StoreOp::DontCare,
+            sample_count: 1,
         }
     }
 }
@@ -191,6 +240,11 @@ impl base::RenderPassTarget for RenderPassTargetBuilder {
         self.stencil_store_op = v;
         self
     }
+
+    fn set_sample_count(&mut self, v: u32) -> &mut dyn base::RenderPassTarget {
+        self.sample_count = v;
+        self
+    }
 }
 
 /// Implementation of `RenderPass` for Metal.
@@ -214,6 +268,8 @@ struct PassTarget {
     format: metal::MTLPixelFormat,
     load: metal::MTLLoadAction,
     store: metal::MTLStoreAction,
+    /// The resolve target. Only valid for color targets.
+    resolve: Option<base::RenderPassTargetIndex>,
 }
 
 impl RenderPass {
@@ -259,6 +315,7 @@ pub struct RenderTargetTableBuilder {
     extents: Option<[u32; 2]>,
     num_layers: u32,
     targets: Vec<Option<Target>>,
+    visibility_heap: Option<Arc<VisibilityHeap>>,
 }
 
 zangfx_impl_object! { RenderTargetTableBuilder: dyn base::RenderTargetTableBuilder, dyn crate::Debug }
@@ -292,8 +349,18 @@ impl RenderTargetTableBuilder {
             extents: None,
             num_layers: 1,
             targets: Vec::new(),
+            visibility_heap: None,
         }
     }
+
+    /// Set the visibility result buffer used by occlusion queries.
+    ///
+    /// Render target tables created without a visibility result buffer cannot
+    /// be used with occlusion queries.
+    crate fn set_visibility_heap(&mut self, v: Option<Arc<VisibilityHeap>>) -> &mut Self {
+        self.visibility_heap = v;
+        self
+    }
 }
 
 impl base::RenderTargetTableBuilder for RenderTargetTableBuilder {
@@ -369,6 +436,18 @@ impl base::RenderTargetTableBuilder for RenderTargetTableBuilder {
 
                 let target = populate_attachment_descriptor(*metal_att_desc, pass_color_target);
                 metal_att_desc.set_clear_color(target.clear_color);
+
+                if let Some(resolve_index) = pass_color_target.resolve {
+                    let resolve_target: &Target = self.targets[resolve_index].as_ref().unwrap();
+
+                    debug_assert!(
+                        !resolve_target.image.metal_texture().is_null(),
+                        "image is not bound to memory"
+                    );
+                    metal_att_desc.set_resolve_texture(resolve_target.image.metal_texture());
+                    metal_att_desc.set_resolve_level(resolve_target.mip_level as u64);
+                    metal_att_desc.set_resolve_slice(resolve_target.layer as u64);
+                }
             }
         }
 
@@ -390,9 +469,14 @@ impl base::RenderTargetTableBuilder for RenderTargetTableBuilder {
             metal_desc.set_render_target_array_length(self.num_layers as u64);
         }
 
+        if let Some(ref visibility_heap) = self.visibility_heap {
+            metal_desc.set_visibility_result_buffer(visibility_heap.metal_buffer());
+        }
+
         Ok(RenderTargetTable {
             metal_render_pass: metal_desc,
             extents,
+            visibility_heap: self.visibility_heap.clone(),
         }
         .into())
     }
@@ -442,6 +526,7 @@ impl base::RenderTarget for Target {
 pub struct RenderTargetTable {
     metal_render_pass: OCPtr<metal::MTLRenderPassDescriptor>,
     extents: [u32; 2],
+    visibility_heap: Option<Arc<VisibilityHeap>>,
 }
 
 zangfx_impl_handle! { RenderTargetTable, base::RenderTargetTableRef }
@@ -457,4 +542,8 @@ impl RenderTargetTable {
     pub(crate) fn extents(&self) -> [u32; 2] {
         self.extents
     }
+
+    pub(crate) fn visibility_heap(&self) -> Option<&Arc<VisibilityHeap>> {
+        self.visibility_heap.as_ref()
+    }
 }
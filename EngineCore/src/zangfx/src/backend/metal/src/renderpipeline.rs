@@ -31,6 +31,8 @@ pub struct RenderPipelineBuilder {
 
     vertex_shader: Option<(Library, String)>,
     fragment_shader: Option<(Library, String)>,
+    vertex_spec_constants: base::SpecConstants,
+    fragment_spec_constants: base::SpecConstants,
     root_sig: Option<RootSig>,
     render_pass: Option<(RenderPass, base::SubpassIndex)>,
     topology: Option<base::PrimitiveTopology>,
@@ -55,6 +57,8 @@ impl RenderPipelineBuilder {
             metal_device: OCPtr::new(metal_device).expect("nil device"),
             vertex_shader: None,
             fragment_shader: None,
+            vertex_spec_constants: base::SpecConstants::new(),
+            fragment_spec_constants: base::SpecConstants::new(),
             root_sig: None,
             render_pass: None,
             topology: None,
@@ -99,6 +103,23 @@ impl base::RenderPipelineBuilder for RenderPipelineBuilder {
         self
     }
 
+    fn spec_constant(
+        &mut self,
+        stage: base::ShaderStageFlags,
+        id: base::SpecConstantId,
+        value: base::SpecValue,
+    ) -> &mut dyn base::RenderPipelineBuilder {
+        let spec_constants = if stage == base::ShaderStageFlags::VERTEX {
+            &mut self.vertex_spec_constants
+        } else if stage == base::ShaderStageFlags::FRAGMENT {
+            &mut self.fragment_spec_constants
+        } else {
+            panic!("invalid shader stage: {:?}", stage);
+        };
+        spec_constants.insert(id, value);
+        self
+    }
+
     fn render_pass(
         &mut self,
         v: &base::RenderPassRef,
@@ -221,6 +242,7 @@ impl base::RenderPipelineBuilder for RenderPipelineBuilder {
             &vertex_shader.1,
             base::ShaderStageFlags::VERTEX,
             root_sig,
+            &self.vertex_spec_constants,
             shader_va_infos,
             *self.metal_device,
             &self.label,
@@ -237,6 +259,7 @@ impl base::RenderPipelineBuilder for RenderPipelineBuilder {
                 &fragment_shader.1,
                 base::ShaderStageFlags::FRAGMENT,
                 root_sig,
+                &self.fragment_spec_constants,
                 ::std::iter::empty(),
                 *self.metal_device,
                 &self.label,
@@ -126,6 +126,7 @@ impl Library {
         entry_point: &str,
         stage: shader::ShaderStageFlags,
         root_sig: &RootSig,
+        spec_constants: &base::SpecConstants,
         vertex_attrs: T,
         metal_device: metal::MTLDevice,
         pipeline_name: &Option<String>,
@@ -211,8 +212,50 @@ impl Library {
             entry_point
         };
 
-        OCPtr::new(lib.get_function(fn_name))
-            .ok_or_else(|| nil_error("MTLLibrary newFunctionWithName:"))
+        if spec_constants.is_empty() {
+            return OCPtr::new(lib.get_function(fn_name))
+                .ok_or_else(|| nil_error("MTLLibrary newFunctionWithName:"));
+        }
+
+        spec_constants.report_unknown_ids(
+            Some(&base::spirv_spec_constant_ids(self.spirv_code())),
+            entry_point,
+        );
+
+        // SPIRV-Cross translates a specialization constant into a function
+        // constant whose index is the value of its `SpecId` decoration
+        let constant_values =
+            unsafe { OCPtr::from_raw(metal::MTLFunctionConstantValues::alloc().init()) }
+                .ok_or_else(|| nil_error("MTLFunctionConstantValues alloc"))?;
+        for (id, value) in spec_constants.iter() {
+            let ty = match value {
+                base::SpecValue::Bool(_) => metal::MTLDataType::Bool,
+                base::SpecValue::U32(_) => metal::MTLDataType::UInt,
+                base::SpecValue::I32(_) => metal::MTLDataType::Int,
+                base::SpecValue::F32(_) => metal::MTLDataType::Float,
+            };
+            let bits = value.to_bits();
+            // `bool` is one byte wide in MSL. The least significant byte of
+            // `bits` comes first on every platform Metal supports.
+            unsafe {
+                constant_values.set_constant_value_at_index(
+                    &bits as *const u32 as *const _,
+                    ty,
+                    id as u64,
+                );
+            }
+        }
+
+        let metal_fn = lib
+            .get_function_with_constants(fn_name, *constant_values)
+            .map_err(|e| {
+                Error::with_detail(
+                    ErrorKind::Other,
+                    ShaderCompilationFailed { reason: e, code },
+                )
+            })?;
+
+        Ok(OCPtr::new(metal_fn).unwrap())
     }
 }
 
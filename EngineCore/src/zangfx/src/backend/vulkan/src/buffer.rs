@@ -5,8 +5,12 @@
 //
 //! Implementation of `Buffer` for Vulkan.
 use ash::version::*;
-use ash::{prelude::VkResult, vk};
-use std::sync::Arc;
+use ash::{prelude::VkResult, vk, vk::Handle};
+use iterpool::PoolPtr;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Weak,
+};
 
 use crate::device::DeviceRef;
 use zangfx_base as base;
@@ -25,9 +29,13 @@ pub struct BufferBuilder {
     queue_id: QueueIdBuilder,
     size: Option<base::DeviceSize>,
     usage: base::BufferUsageFlags,
+    relocatable: bool,
+    label: Option<String>,
 }
 
-zangfx_impl_object! { BufferBuilder: dyn base::BufferBuilder, dyn (crate::Debug) }
+zangfx_impl_object! {
+    BufferBuilder: dyn base::BufferBuilder, dyn (crate::Debug), dyn base::SetLabel
+}
 
 impl BufferBuilder {
     crate fn new(device: DeviceRef) -> Self {
@@ -36,10 +44,18 @@ impl BufferBuilder {
             queue_id: QueueIdBuilder::new(),
             size: None,
             usage: base::BufferUsageFlags::default(),
+            relocatable: false,
+            label: None,
         }
     }
 }
 
+impl base::SetLabel for BufferBuilder {
+    fn set_label(&mut self, label: &str) {
+        self.label = Some(label.to_owned());
+    }
+}
+
 impl base::BufferBuilder for BufferBuilder {
     fn queue(&mut self, queue: &base::CmdQueueRef) -> &mut dyn base::BufferBuilder {
         self.queue_id.set(queue);
@@ -56,6 +72,11 @@ impl base::BufferBuilder for BufferBuilder {
         self
     }
 
+    fn relocatable(&mut self) -> &mut dyn base::BufferBuilder {
+        self.relocatable = true;
+        self
+    }
+
     fn build(&mut self) -> Result<base::BufferRef> {
         let size = self.size.expect("size");
 
@@ -81,37 +102,24 @@ impl base::BufferBuilder for BufferBuilder {
         if self.usage.contains(base::BufferUsageFlags::INDIRECT_DRAW) {
             usage |= vk::BufferUsageFlags::INDIRECT_BUFFER;
         }
-
-        let info = vk::BufferCreateInfo {
-            s_type: vk::StructureType::BUFFER_CREATE_INFO,
-            p_next: crate::null(),
-            flags: vk::BufferCreateFlags::empty(),
-            size: size,
-            usage,
-            sharing_mode: vk::SharingMode::EXCLUSIVE,
-            queue_family_index_count: 0, // ignored for `SharingMode::EXCLUSIVE`
-            p_queue_family_indices: crate::null(),
-        };
-
-        let device = self.device.clone();
-        let vk_buffer = unsafe {
-            let vk_device = device.vk_device();
-            vk_device.create_buffer(&info, None)
+        if self.relocatable {
+            // Moving a buffer involves copying its contents to a new buffer
+            usage |= vk::BufferUsageFlags::TRANSFER_SRC | vk::BufferUsageFlags::TRANSFER_DST;
         }
-        .map_err(translate_generic_error_unwrap)?;
 
-        let vulkan_buffer = Arc::new(VulkanBuffer {
-            device,
-            vk_buffer,
-            len: size,
-            binding_info: heap::HeapBindingInfo::new(),
-        });
+        let vulkan_buffer = VulkanBuffer::new(
+            self.device.clone(),
+            size,
+            usage,
+            self.relocatable,
+            self.label.clone(),
+        )?;
 
         let queue_id = self.queue_id.get(&vulkan_buffer.device);
         let tracked_state = Arc::new(resstate::TrackedState::new(queue_id, ()));
 
         Ok(Buffer {
-            vulkan_buffer,
+            vulkan_buffer: Arc::new(vulkan_buffer),
             tracked_state,
         }
         .into())
@@ -129,12 +137,24 @@ pub struct Buffer {
 
 zangfx_impl_handle! { Buffer, base::BufferRef }
 
+/// A weak reference to a `Buffer`.
+#[derive(Debug, Clone)]
+crate struct WeakBuffer {
+    vulkan_buffer: Weak<VulkanBuffer>,
+    tracked_state: Weak<resstate::TrackedState<BufferState>>,
+}
+
 #[derive(Debug)]
 struct VulkanBuffer {
     device: DeviceRef,
-    vk_buffer: vk::Buffer,
+    /// The raw handle of `vk::Buffer`. This may change if the buffer is
+    /// relocatable.
+    vk_buffer: AtomicU64,
     len: base::DeviceSize,
+    usage: vk::BufferUsageFlags,
+    relocatable: bool,
     binding_info: heap::HeapBindingInfo,
+    live_object: PoolPtr,
 }
 
 type BufferState = ();
@@ -143,8 +163,9 @@ impl Drop for VulkanBuffer {
     fn drop(&mut self) {
         unsafe {
             let vk_device = self.device.vk_device();
-            vk_device.destroy_buffer(self.vk_buffer, None);
+            vk_device.destroy_buffer(self.vk_buffer(), None);
         }
+        self.device.unregister_live_object(self.live_object);
     }
 }
 
@@ -155,14 +176,114 @@ impl Buffer {
     } */
 
     pub fn vk_buffer(&self) -> vk::Buffer {
-        self.vulkan_buffer.vk_buffer
+        self.vulkan_buffer.vk_buffer()
+    }
+
+    /// Return `true` if the buffer was created as relocatable.
+    crate fn is_relocatable(&self) -> bool {
+        self.vulkan_buffer.relocatable
+    }
+
+    crate fn downgrade(&self) -> WeakBuffer {
+        WeakBuffer {
+            vulkan_buffer: Arc::downgrade(&self.vulkan_buffer),
+            tracked_state: Arc::downgrade(&self.tracked_state),
+        }
+    }
+
+    /// Create an unbound buffer with the same properties as `self`, associated
+    /// with the same queue. Used as the destination of a move.
+    crate fn new_relocated(&self) -> Result<Self> {
+        let old = &self.vulkan_buffer;
+        let vulkan_buffer = VulkanBuffer::new(old.device.clone(), old.len, old.usage, false, None)?;
+
+        let queue_id = self.tracked_state.queue_id();
+        let tracked_state = Arc::new(resstate::TrackedState::new(queue_id, ()));
+
+        Ok(Self {
+            vulkan_buffer: Arc::new(vulkan_buffer),
+            tracked_state,
+        })
+    }
+
+    /// Swap the underlying `vk::Buffer`s of two buffers.
+    ///
+    /// The caller must ensure neither of the buffers is in use by the device
+    /// or other threads.
+    crate unsafe fn swap_vk_buffer(&self, other: &Self) {
+        let x = self.vulkan_buffer.vk_buffer.load(Ordering::Relaxed);
+        let y = other.vulkan_buffer.vk_buffer.load(Ordering::Relaxed);
+        self.vulkan_buffer.vk_buffer.store(y, Ordering::Relaxed);
+        other.vulkan_buffer.vk_buffer.store(x, Ordering::Relaxed);
+    }
+}
+
+impl WeakBuffer {
+    crate fn upgrade(&self) -> Option<Buffer> {
+        Some(Buffer {
+            vulkan_buffer: self.vulkan_buffer.upgrade()?,
+            tracked_state: self.tracked_state.upgrade()?,
+        })
+    }
+
+    /// Return `true` if `self` points to the same buffer as `buffer`.
+    crate fn ptr_eq(&self, buffer: &Buffer) -> bool {
+        self.vulkan_buffer
+            .upgrade()
+            .map(|x| Arc::ptr_eq(&x, &buffer.vulkan_buffer))
+            .unwrap_or(false)
     }
 }
 
 impl VulkanBuffer {
+    fn new(
+        device: DeviceRef,
+        size: base::DeviceSize,
+        usage: vk::BufferUsageFlags,
+        relocatable: bool,
+        label: Option<String>,
+    ) -> Result<Self> {
+        let info = vk::BufferCreateInfo {
+            s_type: vk::StructureType::BUFFER_CREATE_INFO,
+            p_next: crate::null(),
+            flags: vk::BufferCreateFlags::empty(),
+            size: size,
+            usage,
+            sharing_mode: vk::SharingMode::EXCLUSIVE,
+            queue_family_index_count: 0, // ignored for `SharingMode::EXCLUSIVE`
+            p_queue_family_indices: crate::null(),
+        };
+
+        let vk_buffer = unsafe {
+            let vk_device = device.vk_device();
+            vk_device.create_buffer(&info, None)
+        }
+        .map_err(translate_generic_error_unwrap)?;
+
+        let live_object = device.register_live_object(base::LiveObject {
+            kind: base::LiveObjectKind::Buffer,
+            label,
+            approx_size: Some(size),
+        });
+
+        Ok(Self {
+            device,
+            vk_buffer: AtomicU64::new(vk_buffer.as_raw()),
+            len: size,
+            usage,
+            relocatable,
+            binding_info: heap::HeapBindingInfo::new(),
+            live_object,
+        })
+    }
+
+    fn vk_buffer(&self) -> vk::Buffer {
+        vk::Buffer::from_raw(self.vk_buffer.load(Ordering::Relaxed))
+    }
+
     fn memory_req(&self) -> base::MemoryReq {
         let vk_device = self.device.vk_device();
-        translate_memory_req(&unsafe { vk_device.get_buffer_memory_requirements(self.vk_buffer) })
+        translate_memory_req(&unsafe { vk_device.get_buffer_memory_requirements(self.vk_buffer()) })
     }
 }
 
@@ -220,4 +341,12 @@ impl heap::Bindable for Buffer {
         let vk_device = self.vulkan_buffer.device.vk_device();
         vk_device.bind_buffer_memory(self.vk_buffer(), vk_device_memory, offset)
     }
+
+    fn as_relocatable(&self) -> Option<&Buffer> {
+        if self.is_relocatable() {
+            Some(self)
+        } else {
+            None
+        }
+    }
 }
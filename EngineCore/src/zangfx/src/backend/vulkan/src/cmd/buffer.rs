@@ -85,6 +85,9 @@ crate struct CmdBufferData {
     /// A list of fences to be signaled after the current render pass is done.
     /// (`vkCmdSetEvent` is invalid inside a render pass.)
     deferred_signal_fences: Vec<(usize, base::AccessTypeFlags)>,
+
+    /// The query started by `begin_query` in the current render pass.
+    active_query: Option<(vk::QueryPool, base::QueryIndex)>,
 }
 
 zangfx_impl_object! {
@@ -240,6 +243,7 @@ impl CmdBufferData {
             state: EncodingState::None,
             desc_set_binding_table: DescSetBindingTable::new(),
             deferred_signal_fences: Vec::new(),
+            active_query: None,
             temp: Default::default(),
         })
     }
@@ -249,6 +253,9 @@ impl CmdBufferData {
     }
 
     crate fn reset_all_but_completion_callbacks(&mut self) {
+        // The command buffer is being discarded, so an unterminated query
+        // does not matter anymore
+        self.active_query = None;
         self.end_pass();
         self.deferred_signal_fences.clear();
         self.desc_set_binding_table.reset();
@@ -277,6 +284,14 @@ impl CmdBufferData {
         self.reset_all_but_completion_callbacks();
         self.reset_completion_callbacks();
     }
+
+    /// Panic if the queue family does not support the specified capabilities.
+    fn check_queue_family_caps(&self, caps: base::QueueFamilyCapsFlags, name: &str) {
+        let qf_caps = self.device.caps().info.queue_families[self.queue_family as usize].caps;
+        if !qf_caps.contains(caps) {
+            panic!("the queue family does not support {} passes", name);
+        }
+    }
 }
 
 impl CbPoolContent for CmdBufferData {
@@ -327,6 +342,8 @@ impl base::CmdBuffer for CmdBuffer {
             .as_mut()
             .expect("command buffer is already commited");
 
+        uncommited.check_queue_family_caps(base::QueueFamilyCapsFlags::RENDER, "render");
+
         uncommited.begin_pass();
         uncommited.begin_render_pass(rtt);
 
@@ -338,6 +355,8 @@ impl base::CmdBuffer for CmdBuffer {
             .as_mut()
             .expect("command buffer is already commited");
 
+        uncommited.check_queue_family_caps(base::QueueFamilyCapsFlags::COMPUTE, "compute");
+
         uncommited.begin_pass();
 
         &mut ***uncommited
@@ -348,6 +367,8 @@ impl base::CmdBuffer for CmdBuffer {
             .as_mut()
             .expect("command buffer is already commited");
 
+        uncommited.check_queue_family_caps(base::QueueFamilyCapsFlags::COPY, "copy");
+
         uncommited.begin_pass();
 
         &mut ***uncommited
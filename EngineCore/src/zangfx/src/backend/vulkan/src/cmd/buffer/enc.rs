@@ -22,6 +22,7 @@ use crate::device::DeviceRef;
 use crate::image::{Image, ImageStateAddresser, ImageView};
 use crate::limits::DeviceTraitFlags;
 use crate::pipeline::{ComputePipeline, RenderPipeline};
+use crate::query::QueryPool;
 use crate::renderpass::RenderTargetTable;
 use crate::resstate::{CmdBuffer, RefTable};
 use crate::utils::{translate_access_type_flags, translate_pipeline_stage_flags};
@@ -91,6 +92,7 @@ crate struct RefTableSet {
     compute_pipelines: HashSet<ComputePipeline>,
     render_pipelines: HashSet<RenderPipeline>,
     render_target_tables: HashSet<RenderTargetTable>,
+    query_pools: HashSet<QueryPool>,
 
     crate fences: RefTable<Fence, FenceOp>,
     crate arg_pools: RefTable<ArgPoolDataRef, ()>,
@@ -136,6 +138,7 @@ impl RefTableSet {
             compute_pipelines: Default::default(),
             render_pipelines: Default::default(),
             render_target_tables: Default::default(),
+            query_pools: Default::default(),
             arg_pools: Default::default(),
             buffers: Default::default(),
             images: Default::default(),
@@ -152,28 +155,13 @@ impl RefTableSet {
         self.compute_pipelines.clear();
         self.render_pipelines.clear();
         self.render_target_tables.clear();
+        self.query_pools.clear();
     }
 
-    crate fn insert_compute_pipeline(&mut self, obj: &ComputePipeline) {
-        self.compute_pipelines.insert(obj.clone());
-    }
-
-    crate fn insert_render_pipeline(&mut self, obj: &RenderPipeline) {
-        self.render_pipelines.insert(obj.clone());
-    }
-
-    crate fn insert_render_target_table(&mut self, obj: &RenderTargetTable) {
-        self.render_target_tables.insert(obj.clone());
-    }
-
-    crate fn insert_arg_pool(&mut self, obj: &ArgPool) {
-        self.arg_pools
-            .get_index_for_resource(&mut self.cmd_buffer, obj.data());
-    }
-
-    crate fn insert_buffer(&mut self, obj: &Buffer) {
-        self.buffers
-            .get_index_for_resource(&mut self.cmd_buffer, obj);
+    /// Add an object to the reference table that tracks objects of its type,
+    /// keeping it alive until the command buffer completes execution.
+    crate fn insert<T: RefTableInsert + ?Sized>(&mut self, obj: &T) {
+        obj.insert_into(self);
     }
 
     crate fn insert_image(&mut self, obj: &Image) -> (usize, &mut ImageOp) {
@@ -211,6 +199,56 @@ impl RefTableSet {
     }
 }
 
+/// An object that can be added to `RefTableSet` by `RefTableSet::insert`.
+///
+/// Each implementation knows which reference table tracks the object, so
+/// the callers don't have to. Images are not included because inserting them
+/// produces an `ImageOp`, which the callers must update (see
+/// `RefTableSet::insert_image`).
+crate trait RefTableInsert {
+    fn insert_into(&self, ref_table: &mut RefTableSet);
+}
+
+impl RefTableInsert for ComputePipeline {
+    fn insert_into(&self, ref_table: &mut RefTableSet) {
+        ref_table.compute_pipelines.insert(self.clone());
+    }
+}
+
+impl RefTableInsert for RenderPipeline {
+    fn insert_into(&self, ref_table: &mut RefTableSet) {
+        ref_table.render_pipelines.insert(self.clone());
+    }
+}
+
+impl RefTableInsert for RenderTargetTable {
+    fn insert_into(&self, ref_table: &mut RefTableSet) {
+        ref_table.render_target_tables.insert(self.clone());
+    }
+}
+
+impl RefTableInsert for QueryPool {
+    fn insert_into(&self, ref_table: &mut RefTableSet) {
+        ref_table.query_pools.insert(self.clone());
+    }
+}
+
+impl RefTableInsert for ArgPool {
+    fn insert_into(&self, ref_table: &mut RefTableSet) {
+        ref_table
+            .arg_pools
+            .get_index_for_resource(&mut ref_table.cmd_buffer, self.data());
+    }
+}
+
+impl RefTableInsert for Buffer {
+    fn insert_into(&self, ref_table: &mut RefTableSet) {
+        ref_table
+            .buffers
+            .get_index_for_resource(&mut ref_table.cmd_buffer, self);
+    }
+}
+
 #[derive(Debug)]
 crate struct DescSetBindingTable {
     /// The first arugment table index that needs rebinding.
@@ -258,7 +296,7 @@ impl DescSetBindingTable {
 
             // Add the pool to the reference table
             let my_pool: &ArgPool = pool.query_ref().expect("bad argument pool type");
-            ref_table.insert_arg_pool(my_pool);
+            ref_table.insert(my_pool);
         }
 
         self.start_dirty = min(self.start_dirty, index);
@@ -391,7 +429,7 @@ impl CmdBufferData {
 
         for (_, buffer) in buffers.iter() {
             let buffer: &Buffer = buffer.downcast_ref().expect("bad buffer type");
-            self.ref_table.insert_buffer(buffer);
+            self.ref_table.insert(buffer);
         }
 
         let vk_device = self.device.vk_device();
@@ -545,7 +583,7 @@ impl CmdBufferData {
                 }
                 QueueOwnershipTransfer::Buffer { buffer, .. } => {
                     let buffer: &Buffer = buffer.downcast_ref().expect("bad buffer type");
-                    self.ref_table.insert_buffer(buffer);
+                    self.ref_table.insert(buffer);
                 }
             }
         }
@@ -709,10 +747,30 @@ impl base::CmdEncoder for CmdBufferData {
         // TODO: debug commands
     }
 
+    fn write_timestamp(&mut self, pool: &base::QueryPoolRef, index: base::QueryIndex) {
+        use zangfx_base::QueryPool as _;
+
+        let my_pool: &QueryPool = pool.downcast_ref().expect("bad query pool type");
+        assert_eq!(my_pool.query_type(), base::QueryType::Timestamp);
+        assert!(index < my_pool.len(), "index out of bounds");
+
+        self.ref_table.insert(my_pool);
+
+        let vk_device = self.device.vk_device();
+        unsafe {
+            vk_device.fp_v1_0().cmd_write_timestamp(
+                self.vk_cmd_buffer(),
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                my_pool.vk_query_pool(),
+                index as u32,
+            );
+        }
+    }
+
     fn use_resource_core(&mut self, usage: base::ResourceUsageFlags, objs: base::ResourceSet<'_>) {
         for buffer in objs.buffers() {
             let buffer: &Buffer = buffer.downcast_ref().expect("bad buffer type");
-            self.ref_table.insert_buffer(buffer);
+            self.ref_table.insert(buffer);
         }
 
         // TODO: Add "access type" to the base API
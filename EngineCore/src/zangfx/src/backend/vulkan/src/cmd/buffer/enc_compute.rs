@@ -29,7 +29,7 @@ impl base::ComputeCmdEncoder for CmdBufferData {
         self.desc_set_binding_table
             .bind_root_sig(my_pipeline.root_sig());
 
-        self.ref_table.insert_compute_pipeline(my_pipeline);
+        self.ref_table.insert(my_pipeline);
     }
 
     fn bind_arg_table(
@@ -66,7 +66,7 @@ impl base::ComputeCmdEncoder for CmdBufferData {
         let vk_cmd_buffer = self.vk_cmd_buffer();
         let buffer: &Buffer = buffer.downcast_ref().expect("bad buffer type");
 
-        self.ref_table.insert_buffer(buffer);
+        self.ref_table.insert(buffer);
 
         self.desc_set_binding_table.flush(
             &self.device,
@@ -13,6 +13,7 @@ use zangfx_common::IntoWithPad;
 
 use crate::buffer::Buffer;
 use crate::image::{Image, ImageStateAddresser};
+use crate::query::QueryPool;
 use crate::utils::{translate_image_aspect, translate_image_subresource_range};
 
 use super::enc::ImageUnitOp;
@@ -134,7 +135,7 @@ impl base::CopyCmdEncoder for CmdBufferData {
         let my_buffer: &Buffer = buffer.downcast_ref().expect("bad buffer type");
         let vk_device = self.device.vk_device();
 
-        self.ref_table.insert_buffer(my_buffer);
+        self.ref_table.insert(my_buffer);
 
         let data = (value as u32) * 0x1010101;
 
@@ -161,8 +162,8 @@ impl base::CopyCmdEncoder for CmdBufferData {
         let my_dst: &Buffer = dst.downcast_ref().expect("bad buffer type");
         let vk_device = self.device.vk_device();
 
-        self.ref_table.insert_buffer(my_src);
-        self.ref_table.insert_buffer(my_dst);
+        self.ref_table.insert(my_src);
+        self.ref_table.insert(my_dst);
 
         unsafe {
             vk_device.cmd_copy_buffer(
@@ -193,7 +194,7 @@ impl base::CopyCmdEncoder for CmdBufferData {
         let my_src: &Buffer = src.downcast_ref().expect("bad source buffer type");
         let my_dst: &Image = dst.downcast_ref().expect("bad destination image type");
 
-        self.ref_table.insert_buffer(my_src);
+        self.ref_table.insert(my_src);
         self.use_image_for_copy(
             my_dst.translate_layout(base::ImageLayout::CopyWrite),
             vk::AccessFlags::TRANSFER_WRITE,
@@ -248,7 +249,7 @@ impl base::CopyCmdEncoder for CmdBufferData {
         let my_src: &Image = src.downcast_ref().expect("bad source image type");
         let my_dst: &Buffer = dst.downcast_ref().expect("bad destination buffer type");
 
-        self.ref_table.insert_buffer(my_dst);
+        self.ref_table.insert(my_dst);
         self.use_image_for_copy(
             my_src.translate_layout(base::ImageLayout::CopyRead),
             vk::AccessFlags::TRANSFER_READ,
@@ -379,4 +380,67 @@ impl base::CopyCmdEncoder for CmdBufferData {
             );
         }
     }
+
+    fn reset_queries(&mut self, pool: &base::QueryPoolRef, range: Range<base::QueryIndex>) {
+        use zangfx_base::QueryPool as _;
+
+        let my_pool: &QueryPool = pool.downcast_ref().expect("bad query pool type");
+        assert!(
+            range.start <= range.end && range.end <= my_pool.len(),
+            "range out of bounds"
+        );
+        if range.start == range.end {
+            return;
+        }
+
+        self.ref_table.insert(my_pool);
+
+        let vk_device = self.device.vk_device();
+        unsafe {
+            vk_device.fp_v1_0().cmd_reset_query_pool(
+                self.vk_cmd_buffer(),
+                my_pool.vk_query_pool(),
+                range.start as u32,
+                (range.end - range.start) as u32,
+            );
+        }
+    }
+
+    fn copy_query_results(
+        &mut self,
+        pool: &base::QueryPoolRef,
+        range: Range<base::QueryIndex>,
+        dst: &base::BufferRef,
+        dst_offset: base::DeviceSize,
+        dst_stride: base::DeviceSize,
+    ) {
+        use zangfx_base::QueryPool as _;
+
+        let my_pool: &QueryPool = pool.downcast_ref().expect("bad query pool type");
+        let my_dst: &Buffer = dst.downcast_ref().expect("bad buffer type");
+        assert!(
+            range.start <= range.end && range.end <= my_pool.len(),
+            "range out of bounds"
+        );
+        if range.start == range.end {
+            return;
+        }
+
+        self.ref_table.insert(my_pool);
+        self.ref_table.insert(my_dst);
+
+        let vk_device = self.device.vk_device();
+        unsafe {
+            vk_device.fp_v1_0().cmd_copy_query_pool_results(
+                self.vk_cmd_buffer(),
+                my_pool.vk_query_pool(),
+                range.start as u32,
+                (range.end - range.start) as u32,
+                my_dst.vk_buffer(),
+                dst_offset,
+                dst_stride,
+                vk::QueryResultFlags::TYPE_64 | vk::QueryResultFlags::WAIT,
+            );
+        }
+    }
 }
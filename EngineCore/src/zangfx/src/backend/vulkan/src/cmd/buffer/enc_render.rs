@@ -16,6 +16,7 @@ use super::{CmdBufferData, EncodingState};
 
 use crate::buffer::Buffer;
 use crate::pipeline::RenderPipeline;
+use crate::query::QueryPool;
 use crate::renderpass::RenderTargetTable;
 use crate::utils::{clip_rect2d_u31, translate_rect2d_u32};
 
@@ -44,11 +45,12 @@ impl CmdBufferData {
             );
         }
 
-        self.ref_table.insert_render_target_table(rtt);
+        self.ref_table.insert(rtt);
     }
 
     crate fn end_render_pass(&mut self) {
         assert_eq!(self.state, EncodingState::Render);
+        assert!(self.active_query.is_none(), "query is still active");
 
         unsafe {
             let vk_device = self.device.vk_device();
@@ -92,7 +94,7 @@ impl base::RenderCmdEncoder for CmdBufferData {
         self.desc_set_binding_table
             .bind_root_sig(my_pipeline.root_sig());
 
-        self.ref_table.insert_render_pipeline(my_pipeline);
+        self.ref_table.insert(my_pipeline);
     }
 
     fn set_blend_constant(&mut self, value: &[f32]) {
@@ -208,7 +210,7 @@ impl base::RenderCmdEncoder for CmdBufferData {
 
         for (buffer, _) in buffers.iter() {
             let buffer: &Buffer = buffer.downcast_ref().expect("bad buffer type");
-            self.ref_table.insert_buffer(buffer);
+            self.ref_table.insert(buffer);
         }
 
         for items in buffers.chunks(32) {
@@ -241,7 +243,7 @@ impl base::RenderCmdEncoder for CmdBufferData {
         let vk_device = self.device.vk_device();
         let buffer: &Buffer = buffer.downcast_ref().expect("bad buffer type");
 
-        self.ref_table.insert_buffer(buffer);
+        self.ref_table.insert(buffer);
 
         unsafe {
             vk_device.cmd_bind_index_buffer(
@@ -316,7 +318,7 @@ impl base::RenderCmdEncoder for CmdBufferData {
         let vk_device = self.device.vk_device();
         let buffer: &Buffer = buffer.downcast_ref().expect("bad buffer type");
 
-        self.ref_table.insert_buffer(buffer);
+        self.ref_table.insert(buffer);
 
         unsafe {
             vk_device.cmd_draw_indirect(vk_cmd_buffer, buffer.vk_buffer(), offset, 1, 0);
@@ -335,10 +337,54 @@ impl base::RenderCmdEncoder for CmdBufferData {
         let vk_device = self.device.vk_device();
         let buffer: &Buffer = buffer.downcast_ref().expect("bad buffer type");
 
-        self.ref_table.insert_buffer(buffer);
+        self.ref_table.insert(buffer);
 
         unsafe {
             vk_device.cmd_draw_indexed_indirect(vk_cmd_buffer, buffer.vk_buffer(), offset, 1, 0);
         }
     }
+
+    fn begin_query(&mut self, pool: &base::QueryPoolRef, index: base::QueryIndex) {
+        use zangfx_base::QueryPool as _;
+
+        let my_pool: &QueryPool = pool.downcast_ref().expect("bad query pool type");
+        match my_pool.query_type() {
+            base::QueryType::Occlusion | base::QueryType::PipelineStats(_) => {}
+            base::QueryType::Timestamp => panic!("bad query type"),
+        }
+        assert!(index < my_pool.len(), "index out of bounds");
+        assert!(self.active_query.is_none(), "queries cannot be nested");
+
+        self.ref_table.insert(my_pool);
+        self.active_query = Some((my_pool.vk_query_pool(), index));
+
+        let vk_device = self.device.vk_device();
+        unsafe {
+            vk_device.fp_v1_0().cmd_begin_query(
+                self.vk_cmd_buffer(),
+                my_pool.vk_query_pool(),
+                index as u32,
+                vk::QueryControlFlags::empty(),
+            );
+        }
+    }
+
+    fn end_query(&mut self, pool: &base::QueryPoolRef, index: base::QueryIndex) {
+        let my_pool: &QueryPool = pool.downcast_ref().expect("bad query pool type");
+        assert_eq!(
+            self.active_query,
+            Some((my_pool.vk_query_pool(), index)),
+            "the query is not active"
+        );
+        self.active_query = None;
+
+        let vk_device = self.device.vk_device();
+        unsafe {
+            vk_device.fp_v1_0().cmd_end_query(
+                self.vk_cmd_buffer(),
+                my_pool.vk_query_pool(),
+                index as u32,
+            );
+        }
+    }
 }
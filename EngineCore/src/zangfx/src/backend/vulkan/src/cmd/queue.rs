@@ -24,7 +24,8 @@ use crate::resstate;
 
 #[derive(Debug)]
 pub(crate) struct QueuePool {
-    pools: Mutex<Vec<Vec<u32>>>,
+    /// Free queues (queue index and priority) for each queue family.
+    pools: Mutex<Vec<Vec<(u32, f32)>>>,
 }
 
 impl QueuePool {
@@ -34,8 +35,8 @@ impl QueuePool {
         let num_qf = queues.iter().map(|&(qf, _)| qf + 1).max().unwrap_or(0);
 
         let mut pools = vec![Vec::new(); num_qf as usize];
-        for &(qf, i) in queues.iter().rev() {
-            pools[qf as usize].push(i);
+        for (&(qf, i), &priority) in queues.iter().zip(config.queue_priorities.iter()).rev() {
+            pools[qf as usize].push((i, priority));
         }
 
         Self {
@@ -43,10 +44,26 @@ impl QueuePool {
         }
     }
 
-    crate fn allocate_queue(&self, queue_family: base::QueueFamily) -> u32 {
-        self.pools.lock()[queue_family as usize]
-            .pop()
-            .expect("out of queues")
+    /// Allocate a queue from a given queue family. Chooses the one with the
+    /// closest priority to `priority`.
+    crate fn allocate_queue(&self, queue_family: base::QueueFamily, priority: f32) -> u32 {
+        let mut pools = self.pools.lock();
+        let pool = pools.get_mut(queue_family as usize).expect("out of queues");
+
+        // `pool` is sorted in the descending order of queue indices. Prefer
+        // lower queue indices if there are multiple candidates.
+        let (i, _) = pool
+            .iter()
+            .enumerate()
+            .rev()
+            .min_by(|(_, x), (_, y)| {
+                let dx = (x.1 - priority).abs();
+                let dy = (y.1 - priority).abs();
+                dx.partial_cmp(&dy).unwrap()
+            })
+            .expect("out of queues");
+
+        pool.remove(i).0
     }
 }
 
@@ -58,6 +75,7 @@ pub struct CmdQueueBuilder {
 
     max_num_outstanding_batches: usize,
     queue_family: Option<base::QueueFamily>,
+    priority: f32,
 }
 
 zangfx_impl_object! { CmdQueueBuilder: dyn base::CmdQueueBuilder, dyn (crate::Debug) }
@@ -69,6 +87,7 @@ impl CmdQueueBuilder {
             queue_pool,
             max_num_outstanding_batches: 8,
             queue_family: None,
+            priority: 0.5,
         }
     }
 
@@ -87,14 +106,26 @@ impl base::CmdQueueBuilder for CmdQueueBuilder {
         self
     }
 
+    fn priority(&mut self, v: f32) -> &mut dyn base::CmdQueueBuilder {
+        self.priority = v;
+        self
+    }
+
     fn build(&mut self) -> Result<base::CmdQueueRef> {
         if self.max_num_outstanding_batches < 1 {
             panic!("max_num_outstanding_batches");
         }
 
         let queue_family = self.queue_family.expect("queue_family");
+        if queue_family as usize >= self.device.caps().info.queue_families.len() {
+            panic!("invalid queue family index");
+        }
+
+        if !(self.priority >= 0.0 && self.priority <= 1.0) {
+            panic!("priority out of range");
+        }
 
-        let index = self.queue_pool.allocate_queue(queue_family);
+        let index = self.queue_pool.allocate_queue(queue_family, self.priority);
 
         let vk_device = self.device.vk_device();
         let vk_queue = unsafe { vk_device.get_device_queue(queue_family, index) };
@@ -196,6 +227,15 @@ impl base::CmdQueue for CmdQueue {
             .lock()
             .flush(&self.monitor, &self.device, self.vk_queue);
     }
+
+    fn wait_idle(&self) -> Result<()> {
+        // `vkQueueWaitIdle` requires the queue to be externally synchronized.
+        // Queue submissions are done while the scheduler is locked.
+        let _scheduler_data = self.scheduler().data.lock();
+
+        let vk_device = self.device.vk_device();
+        unsafe { vk_device.queue_wait_idle(self.vk_queue) }.map_err(translate_generic_error_unwrap)
+    }
 }
 
 #[derive(Debug)]
@@ -5,15 +5,20 @@
 //
 //! Implementation of `Device` for Vulkan.
 use arrayvec::ArrayVec;
-use parking_lot::RwLock;
-use std::sync::Arc;
+use iterpool::{IterablePool, PoolPtr};
+use parking_lot::{Mutex, RwLock};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::thread;
 
 use ash::version::*;
 use ash::vk;
 
 use crate::AshDevice;
 use crate::{
-    arg, buffer, cmd, heap, image, limits, pipeline, renderpass, resstate, sampler, shader,
+    arg, buffer, cmd, heap, image, limits, pipeline, query, renderpass, resstate, sampler, shader,
 };
 use zangfx_base::Result;
 use zangfx_base::{self as base, zangfx_impl_object};
@@ -26,6 +31,9 @@ crate struct DeviceInfo {
     /// The default queue identifier (for resource state tracking) used during
     /// object creation.
     default_resstate_queue: RwLock<Option<resstate::QueueId>>,
+
+    /// Objects to be included in `live_objects_report`.
+    live_objects: Mutex<IterablePool<base::LiveObject>>,
 }
 
 crate type DeviceRef = Arc<DeviceInfo>;
@@ -60,6 +68,21 @@ impl DeviceInfo {
             *cell = Some(queue_id);
         }
     }
+
+    /// Register an object to be included in `live_objects_report`. The
+    /// returned `PoolPtr` must be passed to `unregister_live_object` when the
+    /// object is destroyed.
+    crate fn register_live_object(&self, object: base::LiveObject) -> PoolPtr {
+        self.live_objects.lock().allocate(object)
+    }
+
+    crate fn unregister_live_object(&self, ptr: PoolPtr) {
+        self.live_objects.lock().deallocate(ptr);
+    }
+
+    crate fn live_objects_report(&self) -> Vec<base::LiveObject> {
+        self.live_objects.lock().iter().cloned().collect()
+    }
 }
 
 impl Drop for DeviceInfo {
@@ -74,6 +97,7 @@ pub struct Device {
     device_ref: Option<DeviceRef>,
     queue_pool: Arc<cmd::queue::QueuePool>,
     global_heaps: Vec<base::HeapRef>,
+    assert_no_leaks_on_drop: AtomicBool,
 }
 
 zangfx_impl_object! { Device: dyn base::Device, dyn (crate::Debug) }
@@ -103,6 +127,7 @@ impl Device {
             caps,
             sampler_pool,
             default_resstate_queue: RwLock::new(None),
+            live_objects: Mutex::new(IterablePool::new()),
         });
 
         let global_heaps = (device_ref.caps.config.heap_strategies)
@@ -119,6 +144,7 @@ impl Device {
             device_ref: Some(device_ref),
             queue_pool: Arc::new(queue_pool),
             global_heaps,
+            assert_no_leaks_on_drop: AtomicBool::new(false),
         })
     }
 
@@ -145,9 +171,12 @@ impl Device {
     ///
     /// This method will panic if the aforementioned guarantee cannot be made,
     /// for example, because there are remaining references to some child
-    /// objects somewhere in the application.
+    /// objects somewhere in the application. If
+    /// `debug_assert_no_leaks_on_drop(true)` was called, the panic message
+    /// includes the list of the remaining objects.
     pub fn teardown(&mut self) {
         self.global_heaps.clear();
+        self.check_leaks();
         if let Some(device_ref) = self.device_ref.take() {
             if let Err(x) = Arc::try_unwrap(device_ref) {
                 self.device_ref = Some(x);
@@ -169,6 +198,20 @@ impl Device {
         my_device.teardown();
     }
 
+    /// Panic if `assert_no_leaks_on_drop` is set and there are remaining live
+    /// objects.
+    fn check_leaks(&self) {
+        if !self.assert_no_leaks_on_drop.load(Ordering::Relaxed) || thread::panicking() {
+            return;
+        }
+        if let Some(device_ref) = &self.device_ref {
+            let report = device_ref.live_objects_report();
+            if !report.is_empty() {
+                panic!("some objects were leaked: {:?}", report);
+            }
+        }
+    }
+
     crate fn device_ref(&self) -> &DeviceRef {
         self.device_ref
             .as_ref()
@@ -176,6 +219,12 @@ impl Device {
     }
 }
 
+impl Drop for Device {
+    fn drop(&mut self) {
+        self.check_leaks();
+    }
+}
+
 use std::fmt;
 impl fmt::Debug for DeviceInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -274,6 +323,18 @@ impl base::Device for Device {
         ))
     }
 
+    fn build_query_pool(&self) -> base::QueryPoolBuilderRef {
+        Box::new(query::QueryPoolBuilder::new(self.device_ref().clone()))
+    }
+
+    fn live_objects_report(&self) -> Vec<base::LiveObject> {
+        self.device_ref().live_objects_report()
+    }
+
+    fn debug_assert_no_leaks_on_drop(&self, enable: bool) {
+        self.assert_no_leaks_on_drop.store(enable, Ordering::Relaxed);
+    }
+
     fn update_arg_tables(
         &self,
         arg_table_sig: &base::ArgTableSigRef,
@@ -8,6 +8,7 @@ use ash::version::*;
 use ash::{prelude::VkResult, vk};
 use iterpool::{intrusive_list, Pool, PoolPtr};
 use parking_lot::Mutex;
+use std::collections::BTreeMap;
 use std::sync::{
     atomic::{AtomicPtr, Ordering},
     Arc,
@@ -24,6 +25,9 @@ use crate::limits::HeapStrategy;
 use crate::utils::{translate_generic_error_unwrap, translate_map_memory_error_unwrap};
 use crate::{buffer, image};
 
+mod defrag;
+pub use self::defrag::{DefragBudget, DefragReport};
+
 /// Implementation of `DynamicHeapBuilder` for Vulkan.
 #[derive(Debug)]
 pub struct DynamicHeapBuilder {
@@ -149,15 +153,15 @@ impl base::DedicatedHeapBuilder for DedicatedHeapBuilder {
             heap_size += req.size;
         }
 
-        let mut heap = Heap::new(self.device.clone(), heap_size, memory_type, heap_size)?;
+        let heap = Heap::new(self.device.clone(), heap_size, memory_type, heap_size)?;
 
         // Bind resources
-        for resource in allocs.iter() {
-            let success = heap
-                .state
-                .get_mut()
-                .bind(&heap.vulkan_memory, resource.bindable())?;
-            assert!(success, "allocation has unexpectecdly failed");
+        {
+            let mut state = heap.state.lock();
+            for resource in allocs.iter() {
+                let success = state.bind(&heap.vulkan_memory, resource.bindable())?;
+                assert!(success, "allocation has unexpectecdly failed");
+            }
         }
 
         Ok(Arc::new(heap))
@@ -165,10 +169,24 @@ impl base::DedicatedHeapBuilder for DedicatedHeapBuilder {
 }
 
 /// Implementation of `Heap` for Vulkan.
+///
+/// # Defragmentation
+///
+/// Buffers created as [relocatable] can be moved to lower addresses to
+/// coalesce the free space of the heap. This is a two-phase operation:
+///
+///  1. [`Heap::plan_defragment`] decides which buffers to move, allocates
+///     their new regions, and encodes the copy commands into a
+///     `CopyCmdEncoder` supplied by the caller.
+///  2. After the command buffer has completed execution, the new locations
+///     are committed by [`DefragPlan::apply`], which also releases the old
+///     regions.
+///
+/// [relocatable]: zangfx_base::BufferBuilder::relocatable
 #[derive(Debug)]
 pub struct Heap {
     vulkan_memory: Arc<VulkanMemory>,
-    state: Mutex<HeapState>,
+    state: Arc<Mutex<HeapState>>,
 }
 
 zangfx_impl_object! { Heap: dyn base::Heap, dyn (crate::Debug) }
@@ -179,6 +197,18 @@ struct HeapState {
 
     /// The token used to take an ownership of `HeapBindingInfo::binding`.
     token: Token,
+
+    /// Relocatable buffers bound to this heap, indexed by their offsets.
+    relocatables: BTreeMap<base::DeviceSize, RelocatableEntry>,
+}
+
+#[derive(Debug)]
+struct RelocatableEntry {
+    buffer: buffer::WeakBuffer,
+    size: base::DeviceSize,
+    align: base::DeviceSize,
+    /// `true` if the buffer is being moved by a `DefragPlan`.
+    moving: bool,
 }
 
 /// A (kind of) smart pointer of `vk::DeviceMemory`.
@@ -209,6 +239,10 @@ enum HeapBinding {
     Heap {
         vulkan_memory: Arc<VulkanMemory>,
         region: Option<SysTlsfRegion>,
+        offset: base::DeviceSize,
+        /// Indicates whether the resource is registered to
+        /// `HeapState::relocatables`.
+        relocatable: bool,
     },
     GlobalHeap {
         global_heap: Arc<Mutex<GlobalHeapState>>,
@@ -229,6 +263,11 @@ crate trait Bindable {
         vk_device_memory: vk::DeviceMemory,
         offset: vk::DeviceSize,
     ) -> VkResult<()>;
+
+    /// Get the buffer if the resource is a relocatable buffer.
+    fn as_relocatable(&self) -> Option<&buffer::Buffer> {
+        None
+    }
 }
 
 impl VulkanMemory {
@@ -326,10 +365,11 @@ impl Heap {
         ty: base::MemoryType,
         arena_size: base::DeviceSize,
     ) -> Result<Self> {
-        let state = Mutex::new(HeapState {
+        let state = Arc::new(Mutex::new(HeapState {
             allocator: SysTlsf::new(arena_size),
             token: Token::new(),
-        });
+            relocatables: BTreeMap::new(),
+        }));
 
         let vulkan_memory = VulkanMemory::new(device, size, ty)?;
 
@@ -344,6 +384,255 @@ impl Heap {
     pub fn vk_device_memory(&self) -> vk::DeviceMemory {
         self.vulkan_memory.vk_device_memory()
     }
+
+    /// Plan a defragmentation pass and encode the copy commands into
+    /// `encoder`.
+    ///
+    /// Only buffers created as [relocatable] are moved. The amount of work is
+    /// limited by `budget`. The returned [`DefragPlan`] must be applied by
+    /// calling [`DefragPlan::apply`] after the command buffer has completed
+    /// execution.
+    ///
+    /// # Valid Usage
+    ///
+    ///  - `encoder` must belong to the queue the relocatable buffers are
+    ///    associated with.
+    ///  - The relocatable buffers bound to this heap must not be accessed by
+    ///    the device or the host until the plan is applied or dropped.
+    ///  - After the plan is applied, arg tables referencing the relocatable
+    ///    buffers bound to this heap must be rewritten by
+    ///    `Device::update_arg_tables` before they are used again.
+    ///    [`DefragPlan::apply`] destroys the old `vk::Buffer`s of the moved
+    ///    buffers, which are still referred to by such arg tables.
+    ///
+    /// [relocatable]: zangfx_base::BufferBuilder::relocatable
+    pub fn plan_defragment(
+        &self,
+        budget: &DefragBudget,
+        encoder: &mut dyn base::CopyCmdEncoder,
+    ) -> Result<DefragPlan> {
+        let mut state = self.state.lock();
+        let ref mut state = *state; // enable split borrow
+
+        // Collect the candidates, forgetting buffers that no longer exist
+        let mut candidates = Vec::new();
+        let mut dead_offsets = Vec::new();
+        for (&offset, entry) in state.relocatables.iter() {
+            if entry.moving {
+                continue;
+            }
+            match entry.buffer.upgrade() {
+                Some(buffer) => candidates.push(defrag::Candidate {
+                    key: buffer,
+                    offset,
+                    size: entry.size,
+                    align: entry.align,
+                }),
+                None => dead_offsets.push(offset),
+            }
+        }
+        for offset in dead_offsets {
+            state.relocatables.remove(&offset);
+        }
+
+        let (moves, report) = defrag::plan(candidates, &mut state.allocator, budget);
+
+        // Create and bind the destination buffers
+        let mut pending = Vec::with_capacity(moves.len());
+        let mut error = None;
+        for mv in moves {
+            if error.is_none() {
+                match self.new_relocated_buffer(&mv.key, mv.dst_offset) {
+                    Ok(staging) => {
+                        pending.push(PendingMove {
+                            buffer: mv.key,
+                            staging,
+                            src_offset: mv.src_offset,
+                            dst_offset: mv.dst_offset,
+                            dst_region: Some(mv.dst_region),
+                        });
+                        continue;
+                    }
+                    Err(e) => error = Some(e),
+                }
+            }
+            unsafe { state.allocator.dealloc_unchecked(mv.dst_region) };
+        }
+
+        if let Some(error) = error {
+            // Undo the allocations
+            for mut mv in pending {
+                let region = mv.dst_region.take().unwrap();
+                unsafe { state.allocator.dealloc_unchecked(region) };
+            }
+            return Err(error);
+        }
+
+        for mv in pending.iter() {
+            state.relocatables.get_mut(&mv.src_offset).unwrap().moving = true;
+        }
+
+        // Encode the copy commands
+        for mv in pending.iter() {
+            let src: base::BufferRef = mv.buffer.clone().into();
+            let dst: base::BufferRef = mv.staging.clone().into();
+            // `vkCmdCopyBuffer` does not require `size` to be a multiple of 4
+            encoder.copy_buffer(&src, 0, &dst, 0, base::Buffer::len(&mv.buffer));
+        }
+
+        Ok(DefragPlan {
+            state: Arc::clone(&self.state),
+            vulkan_memory: Arc::clone(&self.vulkan_memory),
+            moves: pending,
+            report,
+        })
+    }
+
+    /// Create a buffer identical to `buffer` and bind it at `offset`.
+    fn new_relocated_buffer(
+        &self,
+        buffer: &buffer::Buffer,
+        offset: base::DeviceSize,
+    ) -> Result<buffer::Buffer> {
+        let staging = buffer.new_relocated()?;
+        unsafe { staging.bind(self.vulkan_memory.vk_device_memory(), offset) }
+            .map_err(translate_map_memory_error_unwrap)?;
+        Ok(staging)
+    }
+}
+
+/// A defragmentation pass planned by [`Heap::plan_defragment`].
+///
+/// Dropping a `DefragPlan` without applying it cancels the pass. In either
+/// case, this must not happen until the command buffer containing the copy
+/// commands has completed execution.
+///
+/// While the plan is pending, the destination buffer of each move is a
+/// separate object, which appears as an unlabelled buffer in
+/// `Device::live_objects_report`.
+#[derive(Debug)]
+pub struct DefragPlan {
+    state: Arc<Mutex<HeapState>>,
+    vulkan_memory: Arc<VulkanMemory>,
+    moves: Vec<PendingMove>,
+    report: DefragReport,
+}
+
+#[derive(Debug)]
+struct PendingMove {
+    /// The buffer being moved.
+    buffer: buffer::Buffer,
+    /// A buffer bound to the destination region. Its `vk::Buffer` is swapped
+    /// with that of `buffer` when the move is committed.
+    staging: buffer::Buffer,
+    src_offset: base::DeviceSize,
+    dst_offset: base::DeviceSize,
+    dst_region: Option<SysTlsfRegion>,
+}
+
+impl DefragPlan {
+    /// Get the summary of the planned moves.
+    pub fn report(&self) -> &DefragReport {
+        &self.report
+    }
+
+    /// Commit the new locations of the moved buffers and release their old
+    /// regions.
+    ///
+    /// This must be called after the command buffer containing the copy
+    /// commands has completed execution (e.g., from a completion handler
+    /// registered by `CmdBuffer::on_complete`).
+    ///
+    /// The old `vk::Buffer`s of the moved buffers are destroyed by this
+    /// method. Arg tables referencing them must be rewritten before they are
+    /// used again (see [`Heap::plan_defragment`]).
+    pub fn apply(mut self) {
+        use std::mem::replace;
+
+        let moves = replace(&mut self.moves, Vec::new());
+
+        let mut state = self.state.lock();
+        let ref mut state = *state; // enable split borrow
+
+        for mut mv in moves {
+            let dst_region = mv.dst_region.take().unwrap();
+
+            let is_live = state
+                .relocatables
+                .get(&mv.src_offset)
+                .map(|entry| entry.moving && entry.buffer.ptr_eq(&mv.buffer))
+                .unwrap_or(false);
+            if !is_live {
+                // The buffer was made aliasable in the meantime
+                unsafe { state.allocator.dealloc_unchecked(dst_region) };
+                continue;
+            }
+
+            let mut entry = state.relocatables.remove(&mv.src_offset).unwrap();
+            entry.moving = false;
+            state.relocatables.insert(mv.dst_offset, entry);
+
+            let binding_info = mv.buffer.binding_info();
+            let mut binding = binding_info
+                .binding
+                .borrow(&mut state.token)
+                .expect("resource is not bound to this heap");
+
+            match binding.as_mut().unwrap() {
+                HeapBinding::Heap { region, offset, .. } => {
+                    let old_region = replace(region, Some(dst_region)).unwrap();
+                    unsafe { state.allocator.dealloc_unchecked(old_region) };
+                    *offset = mv.dst_offset;
+                }
+                _ => unreachable!(),
+            }
+
+            // `staging` takes the old `vk::Buffer` and destroys it when dropped
+            unsafe { mv.buffer.swap_vk_buffer(&mv.staging) };
+
+            let memory_ptr = self.vulkan_memory.ptr;
+            if !memory_ptr.is_null() {
+                let ptr = memory_ptr.wrapping_offset(mv.dst_offset as isize);
+                binding_info.ptr.store(ptr, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl Drop for DefragPlan {
+    fn drop(&mut self) {
+        if self.moves.is_empty() {
+            return;
+        }
+
+        // Cancel the pass
+        let mut state = self.state.lock();
+        for mut mv in self.moves.drain(..) {
+            if let Some(entry) = state.relocatables.get_mut(&mv.src_offset) {
+                if entry.buffer.ptr_eq(&mv.buffer) {
+                    entry.moving = false;
+                }
+            }
+            let region = mv.dst_region.take().unwrap();
+            unsafe { state.allocator.dealloc_unchecked(region) };
+        }
+    }
+}
+
+impl defrag::RegionAllocator for SysTlsf<base::DeviceSize> {
+    type Region = SysTlsfRegion;
+
+    fn alloc(
+        &mut self,
+        size: base::DeviceSize,
+        align: base::DeviceSize,
+    ) -> Option<(SysTlsfRegion, base::DeviceSize)> {
+        self.alloc_aligned(size, align)
+    }
+
+    fn dealloc(&mut self, region: SysTlsfRegion) {
+        unsafe { self.dealloc_unchecked(region) };
+    }
 }
 
 fn bindable_from_resource_ref(obj: base::ResourceRef<'_>) -> &dyn Bindable {
@@ -443,6 +732,9 @@ impl HeapState {
             region: Option<SysTlsfRegion>,
             offset: base::DeviceSize,
             allocator: &'a mut SysTlsf<base::DeviceSize>,
+            relocatables: &'a mut BTreeMap<base::DeviceSize, RelocatableEntry>,
+            /// Set if the resource should be registered to `relocatables`.
+            relocatable: Option<RelocatableEntry>,
         }
 
         impl<'a> AllocationInfo for Alloc<'a> {
@@ -455,9 +747,18 @@ impl HeapState {
             }
 
             fn heap_binding(mut self) -> HeapBinding {
+                let relocatable = if let Some(entry) = self.relocatable.take() {
+                    self.relocatables.insert(self.offset, entry);
+                    true
+                } else {
+                    false
+                };
+
                 HeapBinding::Heap {
                     vulkan_memory: Arc::clone(self.vulkan_memory),
                     region: Some(self.region.take().unwrap()),
+                    offset: self.offset,
+                    relocatable,
                 }
             }
         }
@@ -472,6 +773,8 @@ impl HeapState {
         }
 
         let ref mut allocator = self.allocator;
+        let ref mut relocatables = self.relocatables;
+        let relocatable_buffer = bindable.as_relocatable().map(buffer::Buffer::downgrade);
 
         bind(&mut self.token, bindable, move |req| {
             let (region, offset) = match allocator.alloc_aligned(req.size, req.align) {
@@ -479,11 +782,20 @@ impl HeapState {
                 None => return Ok(None),
             };
 
+            let relocatable = relocatable_buffer.map(|buffer| RelocatableEntry {
+                buffer,
+                size: req.size,
+                align: req.align,
+                moving: false,
+            });
+
             Ok(Some(Alloc {
                 vulkan_memory,
                 region: Some(region),
                 offset,
                 allocator,
+                relocatables,
+                relocatable,
             }))
         })
     }
@@ -497,12 +809,22 @@ impl HeapState {
             .expect("resource is not bound to this heap");
 
         match binding_maybe.as_mut().unwrap() {
-            HeapBinding::Heap { region, .. } => {
+            HeapBinding::Heap {
+                region,
+                offset,
+                relocatable,
+                ..
+            } => {
                 if let Some(region) = region.take() {
                     unsafe {
                         self.allocator.dealloc_unchecked(region);
                     }
                 }
+                if *relocatable {
+                    // The buffer can't be moved anymore
+                    self.relocatables.remove(&*offset);
+                    *relocatable = false;
+                }
             }
             _ => unreachable!(),
         };
@@ -0,0 +1,543 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+//! Planning algorithm for heap defragmentation.
+//!
+//! This module does not touch any Vulkan objects. The planner operates on an
+//! abstract allocator ([`RegionAllocator`]) and a list of movable allocations
+//! ([`Candidate`]), which makes it possible to test it with synthetic
+//! fragmentation patterns.
+use zangfx_base as base;
+
+/// Limits the amount of work done by a single defragmentation pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DefragBudget {
+    /// The maximum total number of bytes to move.
+    pub max_bytes: base::DeviceSize,
+    /// The maximum number of allocations to move.
+    pub max_moves: usize,
+}
+
+impl Default for DefragBudget {
+    /// Get a `DefragBudget` that does not limit the amount of work.
+    fn default() -> Self {
+        Self {
+            max_bytes: base::DeviceSize::max_value(),
+            max_moves: usize::max_value(),
+        }
+    }
+}
+
+/// Describes the moves planned by a defragmentation pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct DefragReport {
+    /// The number of allocations to be moved.
+    pub num_moves: usize,
+    /// The total number of bytes to be moved.
+    pub bytes_moved: base::DeviceSize,
+    /// The number of allocations that could have been moved but were not
+    /// because of the budget.
+    pub num_deferred: usize,
+}
+
+/// An allocator used by the planner to find the destination of a move.
+crate trait RegionAllocator {
+    type Region;
+
+    fn alloc(
+        &mut self,
+        size: base::DeviceSize,
+        align: base::DeviceSize,
+    ) -> Option<(Self::Region, base::DeviceSize)>;
+
+    fn dealloc(&mut self, region: Self::Region);
+}
+
+/// A movable allocation.
+#[derive(Debug, Clone)]
+crate struct Candidate<K> {
+    crate key: K,
+    crate offset: base::DeviceSize,
+    crate size: base::DeviceSize,
+    crate align: base::DeviceSize,
+}
+
+/// A planned move of an allocation.
+#[derive(Debug)]
+crate struct Move<K, R> {
+    crate key: K,
+    crate src_offset: base::DeviceSize,
+    crate dst_offset: base::DeviceSize,
+    crate size: base::DeviceSize,
+    /// The region allocated for the destination. The source region is still
+    /// allocated; it's up to the caller to release it after the move is
+    /// complete.
+    crate dst_region: R,
+}
+
+/// Plan moves of allocations toward lower addresses.
+///
+/// Candidates are processed in the descending order of their offsets. For
+/// each candidate, a new region is allocated from `allocator`. The move is
+/// accepted only if the new region is located below the current one, so that
+/// the allocations are packed toward the beginning of the heap and the free
+/// space coalesces at the end of the heap.
+///
+/// The destination regions of accepted moves remain allocated. The
+/// allocator's state is otherwise left intact.
+crate fn plan<K, A: RegionAllocator>(
+    mut candidates: Vec<Candidate<K>>,
+    allocator: &mut A,
+    budget: &DefragBudget,
+) -> (Vec<Move<K, A::Region>>, DefragReport) {
+    candidates.sort_by(|a, b| b.offset.cmp(&a.offset));
+
+    let mut moves = Vec::new();
+    let mut report = DefragReport::default();
+
+    for candidate in candidates {
+        let (region, dst_offset) = match allocator.alloc(candidate.size, candidate.align) {
+            Some(allocation) => allocation,
+            None => continue,
+        };
+
+        if dst_offset >= candidate.offset {
+            // Moving this allocation does not help
+            allocator.dealloc(region);
+            continue;
+        }
+
+        let over_budget = report.num_moves >= budget.max_moves
+            || report.bytes_moved + candidate.size > budget.max_bytes;
+        if over_budget {
+            allocator.dealloc(region);
+            report.num_deferred += 1;
+            continue;
+        }
+
+        report.num_moves += 1;
+        report.bytes_moved += candidate.size;
+
+        moves.push(Move {
+            key: candidate.key,
+            src_offset: candidate.offset,
+            dst_offset,
+            size: candidate.size,
+            dst_region: region,
+        });
+    }
+
+    (moves, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Range;
+
+    type DeviceSize = base::DeviceSize;
+
+    /// A first-fit allocator that always returns the lowest suitable address.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct FirstFit {
+        /// Sorted and non-adjacent free ranges.
+        free: Vec<Range<DeviceSize>>,
+    }
+
+    impl FirstFit {
+        fn new(size: DeviceSize) -> Self {
+            Self {
+                free: vec![0..size],
+            }
+        }
+
+        fn largest_free_block(&self) -> DeviceSize {
+            self.free.iter().map(|r| r.end - r.start).max().unwrap_or(0)
+        }
+
+        fn free_bytes(&self) -> DeviceSize {
+            self.free.iter().map(|r| r.end - r.start).sum()
+        }
+    }
+
+    impl RegionAllocator for FirstFit {
+        type Region = Range<DeviceSize>;
+
+        fn alloc(
+            &mut self,
+            size: DeviceSize,
+            align: DeviceSize,
+        ) -> Option<(Range<DeviceSize>, DeviceSize)> {
+            for i in 0..self.free.len() {
+                let block = self.free[i].clone();
+                let start = (block.start + align - 1) / align * align;
+                if start + size > block.end {
+                    continue;
+                }
+
+                let mut remaining = Vec::new();
+                if block.start < start {
+                    remaining.push(block.start..start);
+                }
+                if start + size < block.end {
+                    remaining.push(start + size..block.end);
+                }
+                self.free.splice(i..i + 1, remaining);
+
+                return Some((start..start + size, start));
+            }
+            None
+        }
+
+        fn dealloc(&mut self, region: Range<DeviceSize>) {
+            assert!(region.start < region.end);
+            let i = self
+                .free
+                .iter()
+                .position(|r| r.start >= region.end)
+                .unwrap_or(self.free.len());
+            if i > 0 {
+                assert!(self.free[i - 1].end <= region.start, "double free");
+            }
+            self.free.insert(i, region);
+
+            // Coalesce adjacent free ranges
+            let mut merged: Vec<Range<DeviceSize>> = Vec::with_capacity(self.free.len());
+            for r in self.free.drain(..) {
+                match merged.last_mut() {
+                    Some(last) if last.end == r.start => last.end = r.end,
+                    _ => merged.push(r),
+                }
+            }
+            self.free = merged;
+        }
+    }
+
+    /// A synthetic heap state.
+    struct TestHeap {
+        allocator: FirstFit,
+        /// Live allocations: `(region, movable)`.
+        allocs: Vec<(Range<DeviceSize>, bool)>,
+    }
+
+    impl TestHeap {
+        /// Allocate blocks of the specified sizes in order and then free the
+        /// blocks for which `keep` returns `false`.
+        fn new(
+            size: DeviceSize,
+            block_sizes: &[DeviceSize],
+            mut keep: impl FnMut(usize) -> bool,
+        ) -> Self {
+            let mut allocator = FirstFit::new(size);
+            let regions: Vec<_> = block_sizes
+                .iter()
+                .map(|&size| allocator.alloc(size, 1).unwrap().0)
+                .collect();
+
+            let mut allocs = Vec::new();
+            for (i, region) in regions.into_iter().enumerate() {
+                if keep(i) {
+                    allocs.push((region, true));
+                } else {
+                    allocator.dealloc(region);
+                }
+            }
+
+            Self { allocator, allocs }
+        }
+
+        fn candidates(&self) -> Vec<Candidate<usize>> {
+            self.candidates_aligned(1)
+        }
+
+        fn candidates_aligned(&self, align: DeviceSize) -> Vec<Candidate<usize>> {
+            self.allocs
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, movable))| *movable)
+                .map(|(i, (region, _))| Candidate {
+                    key: i,
+                    offset: region.start,
+                    size: region.end - region.start,
+                    align,
+                })
+                .collect()
+        }
+
+        /// Run the planner and then apply the moves.
+        fn defragment(&mut self, budget: &DefragBudget) -> DefragReport {
+            let candidates = self.candidates();
+            self.defragment_with(candidates, budget)
+        }
+
+        fn defragment_with(
+            &mut self,
+            candidates: Vec<Candidate<usize>>,
+            budget: &DefragBudget,
+        ) -> DefragReport {
+            let (moves, report) = plan(candidates, &mut self.allocator, budget);
+            self.check_moves(&moves);
+            self.apply(moves);
+            report
+        }
+
+        /// Check that the destinations of `moves` don't overlap with each
+        /// other or with any live allocations.
+        fn check_moves(&self, moves: &[Move<usize, Range<DeviceSize>>]) {
+            for (i, m) in moves.iter().enumerate() {
+                assert_eq!(m.dst_region, m.dst_offset..m.dst_offset + m.size);
+                assert!(m.dst_offset < m.src_offset);
+                assert_eq!(self.allocs[m.key].0.start, m.src_offset);
+
+                for (region, _) in self.allocs.iter() {
+                    assert!(!overlaps(&m.dst_region, region), "{:?}", m);
+                }
+                for m2 in moves[..i].iter() {
+                    assert!(!overlaps(&m.dst_region, &m2.dst_region), "{:?}", m);
+                    assert_ne!(m.key, m2.key);
+                }
+            }
+        }
+
+        fn apply(&mut self, moves: Vec<Move<usize, Range<DeviceSize>>>) {
+            for m in moves {
+                let old_region = std::mem::replace(&mut self.allocs[m.key].0, m.dst_region);
+                self.allocator.dealloc(old_region);
+            }
+        }
+
+        fn live_bytes(&self) -> DeviceSize {
+            self.allocs.iter().map(|(r, _)| r.end - r.start).sum()
+        }
+    }
+
+    fn overlaps(a: &Range<DeviceSize>, b: &Range<DeviceSize>) -> bool {
+        a.start < b.end && b.start < a.end
+    }
+
+    #[test]
+    fn empty() {
+        let mut allocator = FirstFit::new(256);
+        let (moves, report) = plan::<usize, _>(vec![], &mut allocator, &Default::default());
+        assert!(moves.is_empty());
+        assert_eq!(report, DefragReport::default());
+        assert_eq!(allocator, FirstFit::new(256));
+    }
+
+    #[test]
+    fn checkerboard() {
+        // Every other block of 16 bytes is free
+        let mut heap = TestHeap::new(256, &[16; 16], |i| i % 2 == 1);
+        assert_eq!(heap.allocator.largest_free_block(), 16);
+
+        let report = heap.defragment(&Default::default());
+        assert_eq!(report.num_moves, 4);
+        assert_eq!(report.bytes_moved, 64);
+        assert_eq!(report.num_deferred, 0);
+
+        // All allocations are packed into the first half
+        assert_eq!(heap.allocator.free, vec![128..256]);
+        assert_eq!(heap.live_bytes(), 128);
+    }
+
+    #[test]
+    fn already_compact() {
+        let mut heap = TestHeap::new(256, &[16; 8], |_| true);
+        let before = heap.allocator.clone();
+
+        let report = heap.defragment(&Default::default());
+        assert_eq!(report, DefragReport::default());
+
+        // Trial allocations must be undone
+        assert_eq!(heap.allocator, before);
+    }
+
+    #[test]
+    fn full_heap() {
+        let mut heap = TestHeap::new(256, &[32; 8], |_| true);
+        let report = heap.defragment(&Default::default());
+        assert_eq!(report, DefragReport::default());
+        assert!(heap.allocator.free.is_empty());
+    }
+
+    #[test]
+    fn idempotent() {
+        let mut heap = TestHeap::new(1024, &[16; 64], |i| i % 3 != 0);
+        heap.defragment(&Default::default());
+        let after_first = heap.allocator.clone();
+
+        let report = heap.defragment(&Default::default());
+        assert_eq!(report, DefragReport::default());
+        assert_eq!(heap.allocator, after_first);
+    }
+
+    #[test]
+    fn pinned_allocations_stay() {
+        // Free every other block, and pin the last block
+        let mut heap = TestHeap::new(256, &[16; 16], |i| i % 2 == 1);
+        heap.allocs.last_mut().unwrap().1 = false;
+        let pinned = heap.allocs.last().unwrap().0.clone();
+        assert_eq!(pinned, 240..256);
+
+        let report = heap.defragment(&Default::default());
+        assert_eq!(report.num_moves, 4);
+        assert_eq!(heap.allocs.last().unwrap().0, pinned);
+        assert_eq!(heap.allocator.free, vec![112..240]);
+    }
+
+    #[test]
+    fn all_pinned() {
+        let mut heap = TestHeap::new(256, &[16; 16], |i| i % 2 == 1);
+        for alloc in heap.allocs.iter_mut() {
+            alloc.1 = false;
+        }
+        let before = heap.allocator.clone();
+
+        let report = heap.defragment(&Default::default());
+        assert_eq!(report, DefragReport::default());
+        assert_eq!(heap.allocator, before);
+    }
+
+    #[test]
+    fn budget_max_moves() {
+        let mut heap = TestHeap::new(256, &[16; 16], |i| i % 2 == 1);
+        let budget = DefragBudget {
+            max_moves: 2,
+            ..Default::default()
+        };
+
+        let report = heap.defragment(&budget);
+        assert_eq!(report.num_moves, 2);
+        assert_eq!(report.bytes_moved, 32);
+        assert!(report.num_deferred > 0);
+
+        // The highest allocations are moved first
+        assert_eq!(heap.allocator.free[heap.allocator.free.len() - 1], 192..256);
+
+        // The remaining work can be done by subsequent passes
+        let report = heap.defragment(&budget);
+        assert_eq!(report.num_moves, 2);
+        assert_eq!(heap.allocator.free, vec![128..256]);
+
+        let report = heap.defragment(&budget);
+        assert_eq!(report, DefragReport::default());
+    }
+
+    #[test]
+    fn budget_max_bytes() {
+        let mut heap = TestHeap::new(256, &[16; 16], |i| i % 2 == 1);
+        let budget = DefragBudget {
+            max_bytes: 40,
+            ..Default::default()
+        };
+
+        let report = heap.defragment(&budget);
+        assert_eq!(report.num_moves, 2);
+        assert_eq!(report.bytes_moved, 32);
+        assert!(report.num_deferred > 0);
+    }
+
+    #[test]
+    fn budget_skips_large_allocation() {
+        // [A:64][free:128][B:8][C:64][D:8]
+        let mut heap = TestHeap::new(272, &[64, 128, 8, 64, 8], |i| i != 1);
+        let budget = DefragBudget {
+            max_bytes: 16,
+            ..Default::default()
+        };
+
+        // `C` does not fit in the budget, but the smaller ones are moved
+        let report = heap.defragment(&budget);
+        assert_eq!(report.num_moves, 2);
+        assert_eq!(report.bytes_moved, 16);
+        assert_eq!(report.num_deferred, 1);
+        assert_eq!(heap.allocs[0].0, 0..64);
+        assert_eq!(heap.allocs[1].0, 72..80);
+        assert_eq!(heap.allocs[2].0, 200..264);
+        assert_eq!(heap.allocs[3].0, 64..72);
+    }
+
+    #[test]
+    fn zero_budget() {
+        let mut heap = TestHeap::new(256, &[16; 16], |i| i % 2 == 1);
+        let before = heap.allocator.clone();
+        let budget = DefragBudget {
+            max_bytes: 0,
+            max_moves: 0,
+        };
+
+        let report = heap.defragment(&budget);
+        assert_eq!(report.num_moves, 0);
+        assert_eq!(report.num_deferred, 8);
+        assert_eq!(heap.allocator, before);
+    }
+
+    #[test]
+    fn alignment() {
+        let mut heap = TestHeap::new(1024, &[24; 40], |i| i % 4 == 0);
+        let before: Vec<_> = heap.allocs.iter().map(|(r, _)| r.clone()).collect();
+
+        let candidates = heap.candidates_aligned(64);
+        let report = heap.defragment_with(candidates, &Default::default());
+        assert!(report.num_moves > 0);
+
+        for ((region, _), old_region) in heap.allocs.iter().zip(before.iter()) {
+            if region != old_region {
+                assert_eq!(region.start % 64, 0, "{:?}", region);
+            }
+        }
+    }
+
+    #[test]
+    fn large_allocation_succeeds_after_defragmentation() {
+        let sizes: Vec<DeviceSize> = (0..64).map(|i| [8, 16, 24, 32][i % 4]).collect();
+        let total: DeviceSize = sizes.iter().sum();
+        let mut heap = TestHeap::new(total, &sizes, |i| i % 3 == 0);
+
+        let free = heap.allocator.free_bytes();
+        let want = free * 3 / 4;
+        assert!(heap.allocator.clone().alloc(want, 1).is_none());
+
+        heap.defragment(&Default::default());
+        assert_eq!(heap.allocator.free_bytes(), free);
+        assert!(heap.allocator.alloc(want, 1).is_some());
+    }
+
+    #[test]
+    fn pseudo_random_patterns() {
+        let mut seed = 0x12345678u32;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed
+        };
+
+        for _ in 0..50 {
+            let sizes: Vec<DeviceSize> =
+                (0..100).map(|_| (next() % 64 + 1) as DeviceSize).collect();
+            let total: DeviceSize = sizes.iter().sum();
+            let keep: Vec<bool> = (0..100).map(|_| next() % 2 == 0).collect();
+            let pinned: Vec<bool> = (0..100).map(|_| next() % 8 == 0).collect();
+
+            let mut heap = TestHeap::new(total + 100, &sizes, |i| keep[i]);
+            for (alloc, &pinned) in heap.allocs.iter_mut().zip(pinned.iter()) {
+                alloc.1 = !pinned;
+            }
+
+            let live = heap.live_bytes();
+            let free = heap.allocator.free_bytes();
+
+            let report = heap.defragment(&Default::default());
+
+            // Moves neither leak nor duplicate memory
+            assert_eq!(heap.live_bytes(), live);
+            assert_eq!(heap.allocator.free_bytes(), free);
+            assert_eq!(live + free, total + 100);
+
+            assert!(report.bytes_moved <= live);
+        }
+    }
+}
@@ -7,6 +7,7 @@
 use ash::version::*;
 use ash::{prelude::VkResult, vk};
 use flags_macro::flags;
+use iterpool::PoolPtr;
 use smallvec::{smallvec, SmallVec};
 use std::ops;
 use std::sync::Arc;
@@ -20,7 +21,8 @@ use crate::device::DeviceRef;
 use crate::formats::translate_image_format;
 use crate::utils::{
     offset_range, queue_id_from_queue, translate_generic_error_unwrap,
-    translate_image_subresource_range, translate_memory_req, QueueIdBuilder,
+    translate_image_subresource_range, translate_memory_req, translate_sample_count,
+    QueueIdBuilder,
 };
 use crate::{heap, resstate};
 
@@ -34,6 +36,8 @@ pub struct ImageBuilder {
     num_mip_levels: u32,
     format: Option<base::ImageFormat>,
     usage: base::ImageUsageFlags,
+    sample_count: u32,
+    label: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -44,7 +48,9 @@ enum ImageExtents {
     Cube(u32),
 }
 
-zangfx_impl_object! { ImageBuilder: dyn base::ImageBuilder, dyn (crate::Debug) }
+zangfx_impl_object! {
+    ImageBuilder: dyn base::ImageBuilder, dyn (crate::Debug), dyn base::SetLabel
+}
 
 impl ImageBuilder {
     crate fn new(device: DeviceRef) -> Self {
@@ -56,10 +62,18 @@ impl ImageBuilder {
             num_mip_levels: 1,
             format: None,
             usage: base::ImageUsageFlags::default(),
+            sample_count: 1,
+            label: None,
         }
     }
 }
 
+impl base::SetLabel for ImageBuilder {
+    fn set_label(&mut self, label: &str) {
+        self.label = Some(label.to_owned());
+    }
+}
+
 impl base::ImageBuilder for ImageBuilder {
     fn queue(&mut self, queue: &base::CmdQueueRef) -> &mut dyn base::ImageBuilder {
         self.queue_id.set(queue);
@@ -91,6 +105,11 @@ impl base::ImageBuilder for ImageBuilder {
         self
     }
 
+    fn sample_count(&mut self, v: u32) -> &mut dyn base::ImageBuilder {
+        self.sample_count = v;
+        self
+    }
+
     fn format(&mut self, v: base::ImageFormat) -> &mut dyn base::ImageBuilder {
         self.format = Some(v);
         self
@@ -176,7 +195,7 @@ impl base::ImageBuilder for ImageBuilder {
             },
             mip_levels: self.num_mip_levels,
             array_layers,
-            samples: vk::SampleCountFlags::TYPE_1,
+            samples: translate_sample_count(self.sample_count),
             tiling: vk::ImageTiling::OPTIMAL,
             usage,
             sharing_mode: vk::SharingMode::EXCLUSIVE,
@@ -192,6 +211,14 @@ impl base::ImageBuilder for ImageBuilder {
         }
         .map_err(translate_generic_error_unwrap)?;
 
+        let approx_size =
+            unsafe { device.vk_device().get_image_memory_requirements(vk_image) }.size;
+        let live_object = device.register_live_object(base::LiveObject {
+            kind: base::LiveObjectKind::Image,
+            label: self.label.clone(),
+            approx_size: Some(approx_size),
+        });
+
         let vulkan_image = Arc::new(VulkanImage {
             device,
             vk_image,
@@ -201,6 +228,7 @@ impl base::ImageBuilder for ImageBuilder {
             aspects: aspect,
             binding_info: heap::HeapBindingInfo::new(),
             destroy_manually: false,
+            live_object: Some(live_object),
         });
 
         let state = ImageState::new(&vulkan_image, true);
@@ -254,6 +282,7 @@ impl ImportImage {
             aspects: self.aspects,
             binding_info: heap::HeapBindingInfo::new(),
             destroy_manually: self.destroy_manually,
+            live_object: None,
         });
 
         let state = ImageState::new(&vulkan_image, true);
@@ -338,6 +367,9 @@ struct VulkanImage {
     aspects: vk::ImageAspectFlags,
     binding_info: heap::HeapBindingInfo,
     destroy_manually: bool,
+    /// The entry in the device's live object list. Imported images are not
+    /// tracked.
+    live_object: Option<PoolPtr>,
 }
 
 impl Drop for VulkanImage {
@@ -348,6 +380,9 @@ impl Drop for VulkanImage {
                 vk_device.destroy_image(self.vk_image, None);
             }
         }
+        if let Some(live_object) = self.live_object {
+            self.device.unregister_live_object(live_object);
+        }
     }
 }
 
@@ -73,6 +73,7 @@ pub mod heap;
 pub mod image;
 pub mod limits;
 pub mod pipeline;
+pub mod query;
 pub mod renderpass;
 mod resstate;
 pub mod sampler;
@@ -94,6 +94,10 @@ impl DeviceInfo {
             storage_buffer_align: dev_limits.min_storage_buffer_offset_alignment as _,
             supports_semaphore: true,
             supports_independent_blend: enabled_features.independent_blend != FALSE,
+            supports_sampler_anisotropy: enabled_features.sampler_anisotropy != FALSE,
+            supports_timestamp_query: dev_limits.timestamp_compute_and_graphics != FALSE,
+            supports_pipeline_stats_query: enabled_features.pipeline_statistics_query != FALSE,
+            timestamp_period: dev_limits.timestamp_period,
         };
 
         let queue_families =
@@ -239,6 +243,14 @@ pub struct DeviceConfig {
     /// allocated for ZanGFX.
     pub queues: Vec<(u32, u32)>,
 
+    /// Optionally specifies the priority of each queue in `queues`.
+    ///
+    /// The values must match the ones specified at the device creation
+    /// time by `VkDeviceQueueCreateInfo::pQueuePriorities`. They are used to
+    /// choose a queue that best matches `CmdQueueBuilder::priority`. Missing
+    /// entries default to `0.5`.
+    pub queue_priorities: Vec<f32>,
+
     /// Optionally specifies a `HeapStrategy` for each memory type.
     pub heap_strategies: Vec<Option<HeapStrategy>>,
 }
@@ -271,13 +283,36 @@ impl DeviceConfig {
             }
         }
 
+        if self.queue_priorities.len() > self.queues.len() {
+            panic!("queue_priorities: too many entries");
+        }
+        if self
+            .queue_priorities
+            .iter()
+            .any(|&p| !(p >= 0.0 && p <= 1.0))
+        {
+            panic!("queue_priorities: out of range");
+        }
+        self.queue_priorities.resize(self.queues.len(), 0.5);
+
+        // Sort `queues` and `queue_priorities` together
+        let mut queues: Vec<_> = (self.queues.iter().cloned())
+            .zip(self.queue_priorities.iter().cloned())
+            .collect();
+        queues.sort_by_key(|&(q, _)| q);
+
         // Check duplicates
-        let queues = self.queues.as_mut_slice();
-        queues.sort();
-        if queues.iter().zip(queues[1..].iter()).any(|(x, y)| x == y) {
+        if queues
+            .iter()
+            .zip(queues[1..].iter())
+            .any(|(x, y)| x.0 == y.0)
+        {
             panic!("queues: duplicate entry");
         }
 
+        self.queues = queues.iter().map(|&(q, _)| q).collect();
+        self.queue_priorities = queues.iter().map(|&(_, p)| p).collect();
+
         // Check the `Vec` of `HeapStrategy`s
         for (i, heap_strategy) in self.heap_strategies.iter().enumerate() {
             if heap_strategy.is_some() && i >= device_info.memory_types.len() {
@@ -30,18 +30,31 @@ use crate::utils::{
 ///
 /// `stage` must specify exactly one shader stage.
 ///
-/// Returns a created `vk::PipelineShaderStageCreateInfo` and `CString`.
-/// The returned `CString` should live at least as long as the
-/// `vk::PipelineShaderStageCreateInfo` is used.
+/// Returns a created `vk::PipelineShaderStageCreateInfo`, `CString`, and
+/// `SpecializationInfo`. The returned `CString` and `SpecializationInfo` should
+/// live at least as long as the `vk::PipelineShaderStageCreateInfo` is used.
 fn new_shader_stage_description(
     stage: base::ShaderStageFlags,
     library: &Library,
     entry_point_name: &str,
-) -> (vk::PipelineShaderStageCreateInfo, ffi::CString) {
+    spec_constants: &base::SpecConstants,
+) -> (
+    vk::PipelineShaderStageCreateInfo,
+    ffi::CString,
+    Option<Box<SpecializationInfo>>,
+) {
     let stage = translate_shader_stage_flags(stage);
 
     let name = ffi::CString::new(entry_point_name).unwrap();
 
+    spec_constants.report_unknown_ids(library.spec_constant_ids(), entry_point_name);
+
+    let spec_info = if spec_constants.is_empty() {
+        None
+    } else {
+        Some(Box::new(SpecializationInfo::new(spec_constants)))
+    };
+
     (
         vk::PipelineShaderStageCreateInfo {
             s_type: vk::StructureType::PIPELINE_SHADER_STAGE_CREATE_INFO,
@@ -50,12 +63,59 @@ fn new_shader_stage_description(
             stage,
             module: library.vk_shader_module(),
             p_name: name.as_ptr(),
-            p_specialization_info: crate::null(),
+            p_specialization_info: spec_info
+                .as_ref()
+                .map(|i| &i.vk_info as *const _)
+                .unwrap_or(crate::null()),
         },
         name,
+        spec_info,
     )
 }
 
+/// Owns `vk::SpecializationInfo` and the memory regions referenced by it.
+#[derive(Debug)]
+struct SpecializationInfo {
+    vk_info: vk::SpecializationInfo,
+    // Referenced by `vk_info`
+    #[allow(dead_code)]
+    map_entries: Vec<vk::SpecializationMapEntry>,
+    #[allow(dead_code)]
+    data: Vec<u32>,
+}
+
+impl SpecializationInfo {
+    fn new(spec_constants: &base::SpecConstants) -> Self {
+        // Every supported type is 32 bits wide, so each value is stored in a
+        // separate `u32` element
+        let map_entries: Vec<_> = spec_constants
+            .iter()
+            .enumerate()
+            .map(|(i, (id, _))| vk::SpecializationMapEntry {
+                constant_id: id,
+                offset: (i * 4) as u32,
+                size: 4,
+            })
+            .collect();
+
+        let data: Vec<_> = spec_constants
+            .iter()
+            .map(|(_, value)| value.to_bits())
+            .collect();
+
+        Self {
+            vk_info: vk::SpecializationInfo {
+                map_entry_count: map_entries.len() as u32,
+                p_map_entries: map_entries.as_ptr(),
+                data_size: data.len() * 4,
+                p_data: data.as_ptr() as *const _,
+            },
+            map_entries,
+            data,
+        }
+    }
+}
+
 fn translate_pipeline_creation_error_unwrap(
     device: &DeviceRef,
     (pipelines, error): (Vec<vk::Pipeline>, vk::Result),
@@ -78,6 +138,7 @@ fn translate_pipeline_creation_error_unwrap(
 pub struct ComputePipelineBuilder {
     device: DeviceRef,
     compute_shader: Option<(Library, String)>,
+    spec_constants: base::SpecConstants,
     root_sig: Option<RootSig>,
 }
 
@@ -88,6 +149,7 @@ impl ComputePipelineBuilder {
         Self {
             device,
             compute_shader: None,
+            spec_constants: base::SpecConstants::new(),
             root_sig: None,
         }
     }
@@ -110,6 +172,15 @@ impl base::ComputePipelineBuilder for ComputePipelineBuilder {
         self
     }
 
+    fn spec_constant(
+        &mut self,
+        id: base::SpecConstantId,
+        value: base::SpecValue,
+    ) -> &mut dyn base::ComputePipelineBuilder {
+        self.spec_constants.insert(id, value);
+        self
+    }
+
     fn build(&mut self) -> Result<base::ComputePipelineRef> {
         let compute_shader = self.compute_shader.as_ref().expect("compute_shader");
         let root_sig = self.root_sig.as_ref().expect("root_sig");
@@ -118,6 +189,7 @@ impl base::ComputePipelineBuilder for ComputePipelineBuilder {
             base::ShaderStageFlags::COMPUTE,
             &compute_shader.0,
             &compute_shader.1,
+            &self.spec_constants,
         );
 
         let info = vk::ComputePipelineCreateInfo {
@@ -199,6 +271,8 @@ pub struct RenderPipelineBuilder {
     device: DeviceRef,
     vertex_shader: Option<(Library, String)>,
     fragment_shader: Option<(Library, String)>,
+    vertex_spec_constants: base::SpecConstants,
+    fragment_spec_constants: base::SpecConstants,
     root_sig: Option<RootSig>,
     render_pass: Option<(RenderPass, base::SubpassIndex)>,
     vertex_buffers: Vec<Option<VertexBufferBindingBuilder>>,
@@ -215,6 +289,8 @@ impl RenderPipelineBuilder {
             device,
             vertex_shader: None,
             fragment_shader: None,
+            vertex_spec_constants: base::SpecConstants::new(),
+            fragment_spec_constants: base::SpecConstants::new(),
             root_sig: None,
             render_pass: None,
             vertex_buffers: Vec::new(),
@@ -253,6 +329,23 @@ impl base::RenderPipelineBuilder for RenderPipelineBuilder {
         self
     }
 
+    fn spec_constant(
+        &mut self,
+        stage: base::ShaderStageFlags,
+        id: base::SpecConstantId,
+        value: base::SpecValue,
+    ) -> &mut dyn base::RenderPipelineBuilder {
+        let spec_constants = if stage == base::ShaderStageFlags::VERTEX {
+            &mut self.vertex_spec_constants
+        } else if stage == base::ShaderStageFlags::FRAGMENT {
+            &mut self.fragment_spec_constants
+        } else {
+            panic!("invalid shader stage: {:?}", stage);
+        };
+        spec_constants.insert(id, value);
+        self
+    }
+
     fn render_pass(
         &mut self,
         v: &base::RenderPassRef,
@@ -318,15 +411,23 @@ impl base::RenderPipelineBuilder for RenderPipelineBuilder {
 
         let mut dyn_states = Vec::new();
 
-        let vertex_stage = self
-            .vertex_shader
-            .as_ref()
-            .map(|s| new_shader_stage_description(base::ShaderStageFlags::VERTEX, &s.0, &s.1));
+        let vertex_stage = self.vertex_shader.as_ref().map(|s| {
+            new_shader_stage_description(
+                base::ShaderStageFlags::VERTEX,
+                &s.0,
+                &s.1,
+                &self.vertex_spec_constants,
+            )
+        });
 
-        let fragment_stage = self
-            .fragment_shader
-            .as_ref()
-            .map(|s| new_shader_stage_description(base::ShaderStageFlags::FRAGMENT, &s.0, &s.1));
+        let fragment_stage = self.fragment_shader.as_ref().map(|s| {
+            new_shader_stage_description(
+                base::ShaderStageFlags::FRAGMENT,
+                &s.0,
+                &s.1,
+                &self.fragment_spec_constants,
+            )
+        });
 
         let stages: Vec<vk::PipelineShaderStageCreateInfo> = [&vertex_stage, &fragment_stage]
             .iter()
@@ -589,7 +690,11 @@ impl<'a> LlRasterizer<'a> {
             s_type: vk::StructureType::PIPELINE_DEPTH_STENCIL_STATE_CREATE_INFO,
             p_next: crate::null(),
             flags: vk::PipelineDepthStencilStateCreateFlags::empty(),
-            depth_test_enable: translate_bool(builder.depth_test_enable),
+            // A depth test that always passes can be disabled unless depth
+            // writes are enabled (which require the depth test to be enabled)
+            depth_test_enable: translate_bool(
+                builder.depth_test.is_trivial() != Some(true) || builder.depth_write_enable,
+            ),
             depth_write_enable: translate_bool(builder.depth_write_enable),
             depth_compare_op: builder.depth_compare_op,
             depth_bounds_test_enable: translate_bool(builder.depth_bounds.is_some()),
@@ -708,7 +813,7 @@ struct RasterizerBuilder {
     rasterization_samples: vk::SampleCountFlags,
     depth_bias: Option<base::StaticOrDynamic<base::DepthBias>>,
     depth_write_enable: bool,
-    depth_test_enable: bool,
+    depth_test: base::CmpFn,
     depth_compare_op: vk::CompareOp,
     depth_bounds: Option<base::StaticOrDynamic<Range<f32>>>,
     stencil_ops: [vk::StencilOpState; 2],
@@ -730,7 +835,7 @@ impl RasterizerBuilder {
             rasterization_samples: vk::SampleCountFlags::TYPE_1,
             depth_bias: None,
             depth_write_enable: false,
-            depth_test_enable: false,
+            depth_test: base::CmpFn::Always,
             depth_compare_op: vk::CompareOp::ALWAYS,
             depth_bounds: None,
             stencil_ops: [vk::StencilOpState {
@@ -825,7 +930,7 @@ impl base::Rasterizer for RasterizerBuilder {
     }
 
     fn set_depth_test(&mut self, v: base::CmpFn) -> &mut dyn base::Rasterizer {
-        self.depth_test_enable = v != base::CmpFn::Always;
+        self.depth_test = v;
         self.depth_compare_op = translate_compare_op(v);
         self
     }
@@ -1057,3 +1162,27 @@ impl Drop for RenderPipelineData {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn specialization_info_layout() {
+        let mut spec_constants = base::SpecConstants::new();
+        spec_constants.insert(3, base::SpecValue::Bool(true));
+        spec_constants.insert(1, base::SpecValue::F32(2.0));
+
+        let info = SpecializationInfo::new(&spec_constants);
+        assert_eq!(info.vk_info.map_entry_count, 2);
+        assert_eq!(info.vk_info.data_size, 8);
+
+        let entries: Vec<_> = info
+            .map_entries
+            .iter()
+            .map(|e| (e.constant_id, e.offset, e.size))
+            .collect();
+        assert_eq!(entries, vec![(3, 0, 4), (1, 4, 4)]);
+        assert_eq!(info.data, vec![1, 2.0f32.to_bits()]);
+    }
+}
@@ -0,0 +1,178 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+//! Implementation of `QueryPool` for Vulkan.
+//!
+//! ZanGFX query pools are mapped directly to Vulkan's query pools.
+//!
+use ash::version::*;
+use ash::vk;
+use refeq::RefEqArc;
+use std::ops::Range;
+
+use crate::device::DeviceRef;
+use zangfx_base as base;
+use zangfx_base::Result;
+use zangfx_base::{zangfx_impl_handle, zangfx_impl_object};
+
+use crate::utils::{translate_generic_error_unwrap, translate_pipeline_stats_flags};
+
+/// Implementation of `QueryPoolBuilder` for Vulkan.
+#[derive(Debug)]
+pub struct QueryPoolBuilder {
+    device: DeviceRef,
+    query_type: Option<base::QueryType>,
+    len: Option<base::QueryIndex>,
+}
+
+zangfx_impl_object! { QueryPoolBuilder: dyn base::QueryPoolBuilder, dyn (crate::Debug) }
+
+impl QueryPoolBuilder {
+    crate fn new(device: DeviceRef) -> Self {
+        Self {
+            device,
+            query_type: None,
+            len: None,
+        }
+    }
+}
+
+impl base::QueryPoolBuilder for QueryPoolBuilder {
+    fn query_type(&mut self, v: base::QueryType) -> &mut dyn base::QueryPoolBuilder {
+        self.query_type = Some(v);
+        self
+    }
+
+    fn len(&mut self, v: base::QueryIndex) -> &mut dyn base::QueryPoolBuilder {
+        self.len = Some(v);
+        self
+    }
+
+    fn build(&mut self) -> Result<base::QueryPoolRef> {
+        let query_type = self.query_type.expect("query_type");
+        let len = self.len.expect("len");
+        assert_ne!(len, 0, "len must not be zero");
+
+        let (vk_query_type, pipeline_statistics) = match query_type {
+            base::QueryType::Occlusion => (
+                vk::QueryType::OCCLUSION,
+                vk::QueryPipelineStatisticFlags::empty(),
+            ),
+            base::QueryType::Timestamp => (
+                vk::QueryType::TIMESTAMP,
+                vk::QueryPipelineStatisticFlags::empty(),
+            ),
+            base::QueryType::PipelineStats(flags) => (
+                vk::QueryType::PIPELINE_STATISTICS,
+                translate_pipeline_stats_flags(flags),
+            ),
+        };
+
+        let info = vk::QueryPoolCreateInfo {
+            s_type: vk::StructureType::QUERY_POOL_CREATE_INFO,
+            p_next: crate::null(),
+            flags: vk::QueryPoolCreateFlags::empty(),
+            query_type: vk_query_type,
+            query_count: len as u32,
+            pipeline_statistics,
+        };
+
+        let vk_query_pool = unsafe {
+            let vk_device: &crate::AshDevice = self.device.vk_device();
+            vk_device.create_query_pool(&info, None)
+        }
+        .map_err(translate_generic_error_unwrap)?;
+
+        Ok(QueryPool {
+            data: RefEqArc::new(QueryPoolData {
+                device: self.device.clone(),
+                vk_query_pool,
+                query_type,
+                len,
+            }),
+        }
+        .into())
+    }
+}
+
+/// Implementation of `QueryPool` for Vulkan.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryPool {
+    data: RefEqArc<QueryPoolData>,
+}
+
+zangfx_impl_handle! { QueryPool, base::QueryPoolRef }
+
+#[derive(Debug)]
+struct QueryPoolData {
+    device: DeviceRef,
+    vk_query_pool: vk::QueryPool,
+    query_type: base::QueryType,
+    len: base::QueryIndex,
+}
+
+impl QueryPool {
+    pub fn vk_query_pool(&self) -> vk::QueryPool {
+        self.data.vk_query_pool
+    }
+
+    /// Get the size of each query's result in bytes.
+    crate fn result_size(&self) -> base::DeviceSize {
+        self.data.query_type.num_values() as base::DeviceSize * 8
+    }
+}
+
+impl base::QueryPool for QueryPool {
+    fn query_type(&self) -> base::QueryType {
+        self.data.query_type
+    }
+
+    fn len(&self) -> base::QueryIndex {
+        self.data.len
+    }
+
+    fn get_results(&self, range: Range<base::QueryIndex>, out: &mut [u64]) -> Result<()> {
+        assert!(
+            range.start <= range.end && range.end <= self.data.len,
+            "range out of bounds"
+        );
+        let num_values = self.data.query_type.num_values();
+        assert_eq!(out.len(), (range.end - range.start) * num_values);
+
+        if range.start == range.end {
+            return Ok(());
+        }
+
+        let vk_device: &crate::AshDevice = self.data.device.vk_device();
+        let result = unsafe {
+            vk_device.fp_v1_0().get_query_pool_results(
+                vk_device.handle(),
+                self.data.vk_query_pool,
+                range.start as u32,
+                (range.end - range.start) as u32,
+                out.len() * 8,
+                out.as_mut_ptr() as *mut _,
+                self.result_size(),
+                vk::QueryResultFlags::TYPE_64,
+            )
+        };
+
+        match result {
+            // `NOT_READY` is returned if some results are not available yet.
+            // Such results are undefined as per the API definition.
+            vk::Result::SUCCESS | vk::Result::NOT_READY => Ok(()),
+            e => Err(translate_generic_error_unwrap(e)),
+        }
+    }
+}
+
+impl Drop for QueryPoolData {
+    fn drop(&mut self) {
+        let vk_device: &crate::AshDevice = self.device.vk_device();
+        unsafe {
+            vk_device.destroy_query_pool(self.vk_query_pool, None);
+        }
+    }
+}
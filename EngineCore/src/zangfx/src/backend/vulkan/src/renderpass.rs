@@ -18,6 +18,7 @@ use zangfx_common::IntoWithPad;
 
 use crate::utils::{
     translate_access_type_flags, translate_generic_error_unwrap, translate_pipeline_stage_flags,
+    translate_sample_count,
 };
 
 /// Implementation of `RenderPassBuilder` for Vulkan.
@@ -34,6 +35,8 @@ pub struct RenderPassBuilder {
     color_attachments: Vec<vk::AttachmentReference>,
     /// The depth/stencil attachment for subpass 0.
     depth_stencil_attachment: Option<vk::AttachmentReference>,
+    /// The resolve targets for subpass 0.
+    resolve_targets: Vec<Option<base::RenderPassTargetIndex>>,
 }
 
 zangfx_impl_object! { RenderPassBuilder: dyn base::RenderPassBuilder, dyn (crate::Debug) }
@@ -47,6 +50,7 @@ impl RenderPassBuilder {
             dependencies: Vec::new(),
             color_attachments: Vec::new(),
             depth_stencil_attachment: None,
+            resolve_targets: Vec::new(),
         }
     }
 }
@@ -119,9 +123,66 @@ impl base::RenderPassBuilder for RenderPassBuilder {
         });
     }
 
+    fn subpass_resolve_targets(&mut self, targets: &[Option<base::RenderPassTargetIndex>]) {
+        assert_eq!(self.subpass, 0);
+
+        self.resolve_targets.clear();
+        self.resolve_targets.extend(targets.iter().cloned());
+    }
+
     fn build(&mut self) -> Result<base::RenderPassRef> {
         let vk_device = self.device.vk_device();
 
+        let target_infos: Vec<_> = self
+            .targets
+            .iter()
+            .map(|target| {
+                let target = target
+                    .as_ref()
+                    .expect("render target bindings must be tightly arranged");
+                base::RenderPassTargetInfo {
+                    format: target.format,
+                    sample_count: target.vk_desc.samples.as_raw(),
+                }
+            })
+            .collect();
+
+        let color_targets: Vec<_> = self
+            .color_attachments
+            .iter()
+            .map(|vk_ref| {
+                if vk_ref.attachment == vk::ATTACHMENT_UNUSED {
+                    None
+                } else {
+                    Some(vk_ref.attachment as base::RenderPassTargetIndex)
+                }
+            })
+            .collect();
+
+        base::validate_resolve_targets(&target_infos, &color_targets, &self.resolve_targets)
+            .unwrap_or_else(|e| panic!("{}", e));
+
+        // `pResolveAttachments` must have `colorAttachmentCount` elements
+        let resolve_attachments: Vec<_> = if self.resolve_targets.is_empty() {
+            Vec::new()
+        } else {
+            (0..self.color_attachments.len())
+                .map(|i| {
+                    if let Some(&Some(target)) = self.resolve_targets.get(i) {
+                        vk::AttachmentReference {
+                            attachment: target as u32,
+                            layout: IMAGE_LAYOUT_COLOR_ATTACHMENT,
+                        }
+                    } else {
+                        vk::AttachmentReference {
+                            attachment: vk::ATTACHMENT_UNUSED,
+                            layout: vk::ImageLayout::UNDEFINED,
+                        }
+                    }
+                })
+                .collect()
+        };
+
         let vk_subpass = vk::SubpassDescription {
             flags: vk::SubpassDescriptionFlags::empty(),
             pipeline_bind_point: vk::PipelineBindPoint::GRAPHICS,
@@ -129,7 +190,11 @@ impl base::RenderPassBuilder for RenderPassBuilder {
             p_input_attachments: crate::null(),
             color_attachment_count: self.color_attachments.len() as u32,
             p_color_attachments: self.color_attachments.as_ptr(),
-            p_resolve_attachments: crate::null(),
+            p_resolve_attachments: if resolve_attachments.is_empty() {
+                crate::null()
+            } else {
+                resolve_attachments.as_ptr()
+            },
             p_depth_stencil_attachment: self
                 .depth_stencil_attachment
                 .as_ref()
@@ -238,6 +303,7 @@ impl RenderPassTargetBuilder {
 impl base::RenderPassTarget for RenderPassTargetBuilder {
     fn set_format(&mut self, v: base::ImageFormat) -> &mut dyn base::RenderPassTarget {
         self.vk_desc.format = translate_image_format(v).expect("unsupported format");
+        self.format = v;
         self
     }
 
@@ -259,6 +325,11 @@ impl base::RenderPassTarget for RenderPassTargetBuilder {
         self.vk_desc.stencil_store_op = translate_store_op(v);
         self
     }
+
+    fn set_sample_count(&mut self, v: u32) -> &mut dyn base::RenderPassTarget {
+        self.vk_desc.samples = translate_sample_count(v);
+        self
+    }
 }
 
 fn translate_load_op(load_op: base::LoadOp) -> vk::AttachmentLoadOp {
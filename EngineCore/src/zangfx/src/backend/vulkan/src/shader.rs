@@ -57,7 +57,10 @@ impl base::LibraryBuilder for LibraryBuilder {
         let vk_device = self.device.vk_device();
         let vk_shader_mod = unsafe { vk_device.create_shader_module(&info, None) }
             .map_err(translate_generic_error_unwrap)?;
-        Ok(unsafe { Library::from_raw(self.device.clone(), vk_shader_mod) }.into())
+        let library = unsafe { Library::from_raw(self.device.clone(), vk_shader_mod) };
+        Ok(library
+            .with_spec_constant_ids(base::spirv_spec_constant_ids(&spirv_code))
+            .into())
     }
 }
 
@@ -73,6 +76,8 @@ zangfx_impl_handle! { Library, base::LibraryRef }
 struct LibraryData {
     device: DeviceRef,
     vk_shader_mod: vk::ShaderModule,
+    /// `None` if the shader module was imported by `from_raw`.
+    spec_constant_ids: Option<Vec<base::SpecConstantId>>,
 }
 
 impl Library {
@@ -81,13 +86,28 @@ impl Library {
             data: Arc::new(LibraryData {
                 device,
                 vk_shader_mod,
+                spec_constant_ids: None,
             }),
         }
     }
 
+    fn with_spec_constant_ids(mut self, ids: Vec<base::SpecConstantId>) -> Self {
+        Arc::get_mut(&mut self.data).unwrap().spec_constant_ids = Some(ids);
+        self
+    }
+
     pub fn vk_shader_module(&self) -> vk::ShaderModule {
         self.data.vk_shader_mod
     }
+
+    /// Get the IDs of the specialization constants declared by the shader
+    /// module.
+    ///
+    /// Returns `None` if the `Library` was created by `from_raw`, in which
+    /// case the declared IDs are unknown.
+    pub fn spec_constant_ids(&self) -> Option<&[base::SpecConstantId]> {
+        self.data.spec_constant_ids.as_ref().map(Vec::as_slice)
+    }
 }
 
 impl Drop for LibraryData {
@@ -153,6 +153,38 @@ crate fn translate_pipeline_stage_flags(value: base::StageFlags) -> vk::Pipeline
     ret
 }
 
+/// Translate `PipelineStatsFlags`.
+///
+/// The relative order of the corresponding bits is preserved, so the results
+/// are laid out in the order defined by the base API.
+crate fn translate_pipeline_stats_flags(
+    value: base::PipelineStatsFlags,
+) -> vk::QueryPipelineStatisticFlags {
+    let mut ret = vk::QueryPipelineStatisticFlags::empty();
+    if value.contains(base::PipelineStatsFlags::INPUT_VERTICES) {
+        ret |= vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_VERTICES;
+    }
+    if value.contains(base::PipelineStatsFlags::INPUT_PRIMITIVES) {
+        ret |= vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_PRIMITIVES;
+    }
+    if value.contains(base::PipelineStatsFlags::VERTEX_INVOCATIONS) {
+        ret |= vk::QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS;
+    }
+    if value.contains(base::PipelineStatsFlags::CLIPPING_INVOCATIONS) {
+        ret |= vk::QueryPipelineStatisticFlags::CLIPPING_INVOCATIONS;
+    }
+    if value.contains(base::PipelineStatsFlags::CLIPPING_PRIMITIVES) {
+        ret |= vk::QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES;
+    }
+    if value.contains(base::PipelineStatsFlags::FRAGMENT_INVOCATIONS) {
+        ret |= vk::QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS;
+    }
+    if value.contains(base::PipelineStatsFlags::COMPUTE_INVOCATIONS) {
+        ret |= vk::QueryPipelineStatisticFlags::COMPUTE_SHADER_INVOCATIONS;
+    }
+    ret
+}
+
 crate fn translate_image_subresource_range(
     value: &base::ImageSubRange,
     aspect_mask: vk::ImageAspectFlags,
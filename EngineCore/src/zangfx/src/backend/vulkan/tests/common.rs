@@ -97,6 +97,10 @@ impl Drop for DebugReportScope {
 }
 
 impl zangfx_test::backend_tests::TestDriver for TestDriver {
+    fn has_live_objects_report(&self) -> bool {
+        true
+    }
+
     fn for_each_device(&self, runner: &mut dyn FnMut(&base::DeviceRef)) {
         unsafe {
             let entry = match ash::Entry::new() {
@@ -191,6 +195,7 @@ impl zangfx_test::backend_tests::TestDriver for TestDriver {
 
                 let enabled_features = ash::vk::PhysicalDeviceFeatures {
                     robust_buffer_access: available_features.robust_buffer_access,
+                    pipeline_statistics_query: available_features.pipeline_statistics_query,
                     ..Default::default()
                 };
 
@@ -250,6 +255,7 @@ impl zangfx_test::backend_tests::TestDriver for TestDriver {
                         .expect("Failed to create a ZanGFX device.");
 
                 let gfx_device_ref: base::DeviceRef = Arc::new(gfx_device);
+                gfx_device_ref.debug_assert_no_leaks_on_drop(true);
 
                 runner(&gfx_device_ref);
 
@@ -11,10 +11,10 @@ use std::sync::Arc;
 
 use crate::formats::IndexFormat;
 use crate::resources::{BufferRef, ImageLayout, ImageRef, ImageSubRange};
-use crate::{arg, heap, pass, pipeline, resources, sync};
+use crate::{arg, heap, pass, pipeline, query, resources, sync};
 use crate::{
-    AccessTypeFlags, ArgTableIndex, DeviceSize, QueueFamily, StageFlags, VertexBufferIndex,
-    Viewport, ViewportIndex,
+    AccessTypeFlags, ArgTableIndex, DeviceSize, QueryIndex, QueueFamily, StageFlags,
+    VertexBufferIndex, Viewport, ViewportIndex,
 };
 use crate::{Object, Result};
 use zangfx_common::Rect2D;
@@ -34,12 +34,52 @@ pub type CmdQueueBuilderRef = Box<dyn CmdQueueBuilder>;
 ///         .expect("Failed to create a command queue.");
 ///     # }
 ///
+/// The queue family can be chosen based on [`DeviceCaps::queue_families`].
+/// For example, a queue family only supporting copy commands can be used to
+/// stream assets without contending with rendering:
+///
+///     # use zangfx_base::*;
+///     # fn test(device: &Device) {
+///     let copy_queue_family = device.caps().queue_families()
+///         .iter()
+///         .position(|qf| qf.caps == QueueFamilyCapsFlags::COPY)
+///         .unwrap_or(0);
+///
+///     let cmd_queue = device.build_cmd_queue()
+///         .queue_family(copy_queue_family as QueueFamily)
+///         .priority(0.0)
+///         .build()
+///         .expect("Failed to create a command queue.");
+///     # }
+///
+/// [`DeviceCaps::queue_families`]: crate::DeviceCaps::queue_families
 pub trait CmdQueueBuilder: Object {
     /// Set the queue family index.
     ///
     /// This property is mandatory.
+    ///
+    /// # Valid Usage
+    ///
+    /// - `v` must be less than the number of the queue families returned by
+    ///   [`DeviceCaps::queue_families`].
+    ///
+    /// [`DeviceCaps::queue_families`]: crate::DeviceCaps::queue_families
     fn queue_family(&mut self, v: QueueFamily) -> &mut dyn CmdQueueBuilder;
 
+    /// Set the priority of the command queue.
+    ///
+    /// The priority is a hint to the backend, which may use it to allocate
+    /// more processing time to queues with higher priorities. Backends that
+    /// do not support queue priorities ignore this property.
+    ///
+    /// Defaults to `0.5`.
+    ///
+    /// # Valid Usage
+    ///
+    /// - `v` must be in the range `[0, 1]`.
+    ///
+    fn priority(&mut self, v: f32) -> &mut dyn CmdQueueBuilder;
+
     /// Build a `CmdQueue`.
     ///
     /// # Valid Usage
@@ -67,7 +107,8 @@ pub type CmdQueueRef = Arc<dyn CmdQueue>;
 /// # Valid Usage
 ///
 ///  - `CmdQueue` must not be dropped until the queue is idle. (i.e. There
-///    exists no command buffer being executed)
+///    exists no command buffer being executed) [`CmdQueue::wait_idle`] can be
+///    used to ensure this.
 ///
 pub trait CmdQueue: Object {
     /// Allocate a new command buffer.
@@ -81,6 +122,57 @@ pub trait CmdQueue: Object {
 
     /// Schedule pending commited command buffers for execution.
     fn flush(&self);
+
+    /// Block the current thread until all command buffers scheduled for
+    /// execution on this queue complete execution.
+    ///
+    /// Command buffers that are not scheduled yet — those not flushed by
+    /// [`flush`] or waiting on fences not signaled by any scheduled command
+    /// buffers — are not waited for.
+    ///
+    /// Completion handlers of the waited command buffers are not guaranteed to
+    /// have been called when this method returns.
+    ///
+    /// The default implementation commits an empty command buffer and waits
+    /// until its completion handler is called. This relies on that command
+    /// buffers complete in the order they are scheduled. Backends that do not
+    /// provide this guarantee must override this method.
+    ///
+    /// [`flush`]: CmdQueue::flush
+    ///
+    /// # Examples
+    ///
+    ///     # use zangfx_base::*;
+    ///     # fn test(queue: &CmdQueueRef) {
+    ///     queue.flush();
+    ///     queue.wait_idle().unwrap();
+    ///     # }
+    ///
+    fn wait_idle(&self) -> Result<()> {
+        use std::sync::{Condvar, Mutex};
+
+        let state = Arc::new((Mutex::new(None), Condvar::new()));
+
+        let mut buffer = self.new_cmd_buffer()?;
+        {
+            let state = Arc::clone(&state);
+            buffer.on_complete(Box::new(move |result| {
+                *state.0.lock().unwrap() = Some(result);
+                state.1.notify_all();
+            }));
+        }
+        buffer.commit()?;
+        drop(buffer);
+        self.flush();
+
+        let mut result = state.0.lock().unwrap();
+        loop {
+            if let Some(result) = result.take() {
+                return result;
+            }
+            result = state.1.wait(result).unwrap();
+        }
+    }
 }
 
 /// A command buffer.
@@ -114,14 +206,25 @@ pub trait CmdBuffer: Object {
     ///
     /// - All images in `render_target_table` must be associated with the queue
     ///   to which this command buffer belongs.
+    /// - The queue family of the queue must support `RENDER`.
     ///
     fn encode_render(
         &mut self,
         render_target_table: &pass::RenderTargetTableRef,
     ) -> &mut dyn RenderCmdEncoder;
     /// Begin encoding a compute pass.
+    ///
+    /// # Valid Usage
+    ///
+    /// - The queue family of the queue must support `COMPUTE`.
+    ///
     fn encode_compute(&mut self) -> &mut dyn ComputeCmdEncoder;
     /// Begin encoding a copy pass.
+    ///
+    /// # Valid Usage
+    ///
+    /// - The queue family of the queue must support `COPY`.
+    ///
     fn encode_copy(&mut self) -> &mut dyn CopyCmdEncoder;
 
     /// Register a completion handler.
@@ -401,6 +504,37 @@ pub trait RenderCmdEncoder: Object + CmdEncoder {
     ///
     /// [`DrawIndexedIndirectArgs`]: DrawIndexedIndirectArgs
     fn draw_indexed_indirect(&mut self, buffer: &resources::BufferRef, offset: DeviceSize);
+
+    /// Begin a query.
+    ///
+    /// See [the module-level documentation of `query`](../query/index.html)
+    /// for the lifecycle of queries.
+    ///
+    /// # Valid Usage
+    ///
+    ///  - The query type of `pool` must be `Occlusion` or `PipelineStats(_)`.
+    ///  - `index` must be less than `pool.len()`.
+    ///  - The query must have been reset by
+    ///    [`CopyCmdEncoder::reset_queries`] and must not have been written
+    ///    since then.
+    ///  - There must be no active query in this encoder. (Queries cannot be
+    ///    nested.)
+    ///  - The query must be ended by [`end_query`] in the same encoder.
+    ///  - `pool` must be associated with the queue to which this command
+    ///    buffer belongs.
+    ///
+    /// [`end_query`]: RenderCmdEncoder::end_query
+    fn begin_query(&mut self, pool: &query::QueryPoolRef, index: QueryIndex);
+
+    /// End a query.
+    ///
+    /// # Valid Usage
+    ///
+    ///  - `pool` and `index` must match the currently active query started by
+    ///    [`begin_query`] in this encoder.
+    ///
+    /// [`begin_query`]: RenderCmdEncoder::begin_query
+    fn end_query(&mut self, pool: &query::QueryPoolRef, index: QueryIndex);
 }
 
 /// The data layout for indirect draw calls.
@@ -605,6 +739,45 @@ pub trait CopyCmdEncoder: Object + CmdEncoder {
         dst_origin: &[u32],
         size: &[u32],
     );
+
+    /// Reset queries, making them ready to be written.
+    ///
+    /// # Valid Usage
+    ///
+    ///  - `range` must be a subrange of `0..pool.len()`.
+    ///  - The queries in `range` must not be active.
+    ///  - `pool` must be associated with the queue to which this command
+    ///    buffer belongs.
+    ///
+    fn reset_queries(&mut self, pool: &query::QueryPoolRef, range: Range<QueryIndex>);
+
+    /// Copy the results of queries to a buffer.
+    ///
+    /// The results of the `i`-th query in `range` are written as
+    /// `pool.query_type().num_values()` consecutive `u64` values starting at
+    /// `dst_offset + dst_stride * i`.
+    ///
+    /// This command waits until the results of the queries written by the
+    /// preceding commands in the same queue become available.
+    ///
+    /// # Valid Usage
+    ///
+    ///  - `range` must be a subrange of `0..pool.len()`.
+    ///  - `dst_offset` and `dst_stride` must be a multiple of 8.
+    ///  - The copied region must lie within `dst`.
+    ///  - The queries in `range` must have been written after they were
+    ///    reset for the last time.
+    ///  - `pool` and `dst` must be associated with the queue to which this
+    ///    command buffer belongs.
+    ///
+    fn copy_query_results(
+        &mut self,
+        pool: &query::QueryPoolRef,
+        range: Range<QueryIndex>,
+        dst: &resources::BufferRef,
+        dst_offset: DeviceSize,
+        dst_stride: DeviceSize,
+    );
 }
 
 pub trait CmdEncoder: Object {
@@ -644,6 +817,25 @@ pub trait CmdEncoder: Object {
     ///
     fn debug_marker(&mut self, _label: &str) {}
 
+    /// Write the device's timestamp to a query after all preceding commands
+    /// have completed execution.
+    ///
+    /// The default implementation panics.
+    ///
+    /// # Valid Usage
+    ///
+    ///  - The query type of `pool` must be `Timestamp`.
+    ///  - `index` must be less than `pool.len()`.
+    ///  - The query must have been reset by
+    ///    [`CopyCmdEncoder::reset_queries`] and must not have been written
+    ///    since then.
+    ///  - `pool` must be associated with the queue to which this command
+    ///    buffer belongs.
+    ///
+    fn write_timestamp(&mut self, _pool: &query::QueryPoolRef, _index: QueryIndex) {
+        panic!("not supported by this backend")
+    }
+
     /// Declare that the specified resources are referenced by the descriptor
     /// sets used on this command encoder.
     ///
@@ -4,6 +4,11 @@
 // This source code is a part of Nightingales.
 //
 //! Debug utiliites.
+use bitflags::bitflags;
+use lazy_static::lazy_static;
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::DeviceSize;
 
 /// Trait for setting a debug label.
 ///
@@ -35,3 +40,282 @@ pub trait SetLabel {
 pub trait Label {
     fn label(&mut self, label: &str) -> &mut Self;
 }
+
+/// Describes a live object, as reported by [`Device::live_objects_report`].
+///
+/// [`Device::live_objects_report`]: crate::device::Device::live_objects_report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LiveObject {
+    pub kind: LiveObjectKind,
+    /// The debug label assigned via [`SetLabel`](SetLabel), if any.
+    pub label: Option<String>,
+    /// The approximate number of bytes consumed by the object, if known.
+    pub approx_size: Option<DeviceSize>,
+}
+
+/// The kind of a [`LiveObject`](LiveObject).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LiveObjectKind {
+    Buffer,
+    Image,
+}
+
+/// A diagnostic message generated by ZanGFX.
+///
+/// This mirrors `DebugReport` of `ngspf_viewport`, which carries reports from
+/// Vulkan validation layers.
+#[derive(Debug, Clone)]
+pub struct DebugReport<'a> {
+    pub typ: DebugReportType,
+    pub message: &'a str,
+}
+
+impl<'a> DebugReport<'a> {
+    /// Construct a `DebugReport` of type [`DebugReportType::Error`].
+    pub fn error(message: &'a str) -> Self {
+        Self {
+            typ: DebugReportType::Error,
+            message,
+        }
+    }
+
+    /// Construct a `DebugReport` of type [`DebugReportType::Warning`].
+    pub fn warning(message: &'a str) -> Self {
+        Self {
+            typ: DebugReportType::Warning,
+            message,
+        }
+    }
+
+    /// Construct a `DebugReport` of type [`DebugReportType::Information`].
+    pub fn info(message: &'a str) -> Self {
+        Self {
+            typ: DebugReportType::Information,
+            message,
+        }
+    }
+
+    /// Construct a `DebugReport` of type
+    /// [`DebugReportType::PerformanceWarning`].
+    pub fn perf(message: &'a str) -> Self {
+        Self {
+            typ: DebugReportType::PerformanceWarning,
+            message,
+        }
+    }
+
+    /// Construct a `DebugReport` of type [`DebugReportType::Debug`].
+    pub fn debug(message: &'a str) -> Self {
+        Self {
+            typ: DebugReportType::Debug,
+            message,
+        }
+    }
+}
+
+/// Receives `DebugReport`s generated by ZanGFX.
+///
+/// See [`add_debug_report_handler`] for how to install a handler.
+///
+/// [`add_debug_report_handler`]: add_debug_report_handler
+pub trait DebugReportHandler: Send + Sync {
+    fn log(&self, report: &DebugReport<'_>);
+}
+
+bitflags! {
+    pub struct DebugReportTypeFlags: u32 {
+        /// Informational messages that may be handy when debugging an
+        /// application.
+        const INFORMATION = 0b00001;
+
+        /// Reports for potentially wrong, but not immediately harmful API usages.
+        const WARNING = 0b00010;
+
+        /// Reports for non-optimal API usages.
+        const PERFORMANCE_WARNING = 0b00100;
+
+        /// Reports for usages that may cause undefined results.
+        const ERROR = 0b01000;
+
+        /// Diagnostic informations.
+        const DEBUG = 0b10000;
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum DebugReportType {
+    /// Informational messages that may be handy when debugging an
+    /// application.
+    Information,
+
+    /// Reports for potentially wrong, but not immediately harmful API usages.
+    Warning,
+
+    /// Reports for non-optimal API usages.
+    PerformanceWarning,
+
+    /// Reports for usages that may cause undefined results.
+    Error,
+
+    /// Diagnostic informations.
+    Debug,
+}
+
+impl DebugReportType {
+    fn flag(self) -> DebugReportTypeFlags {
+        match self {
+            DebugReportType::Information => DebugReportTypeFlags::INFORMATION,
+            DebugReportType::Warning => DebugReportTypeFlags::WARNING,
+            DebugReportType::PerformanceWarning => DebugReportTypeFlags::PERFORMANCE_WARNING,
+            DebugReportType::Error => DebugReportTypeFlags::ERROR,
+            DebugReportType::Debug => DebugReportTypeFlags::DEBUG,
+        }
+    }
+}
+
+/// The debug report handler that outputs messages using `print`.
+#[derive(Debug, Default)]
+pub struct PrintDebugReportHandler(Mutex<()>);
+
+impl PrintDebugReportHandler {
+    pub fn new() -> Self {
+        PrintDebugReportHandler(Mutex::new(()))
+    }
+}
+
+impl DebugReportHandler for PrintDebugReportHandler {
+    fn log(&self, report: &DebugReport<'_>) {
+        let _lock = self.0.lock().unwrap();
+        match report.typ {
+            DebugReportType::Debug => {
+                print!("DEBUG ");
+            }
+            DebugReportType::Information => {
+                print!("INFO  ");
+            }
+            DebugReportType::Warning => {
+                print!("WARN  ");
+            }
+            DebugReportType::PerformanceWarning => {
+                print!("PERF  ");
+            }
+            DebugReportType::Error => {
+                print!("ERROR ");
+            }
+        }
+        println!("{}", report.message);
+    }
+}
+
+type HandlerList = Vec<(DebugReportTypeFlags, Arc<dyn DebugReportHandler>)>;
+
+lazy_static! {
+    static ref DEBUG_REPORT_HANDLERS: RwLock<HandlerList> = RwLock::new(Vec::new());
+    static ref DEFAULT_DEBUG_REPORT_HANDLER: PrintDebugReportHandler =
+        PrintDebugReportHandler::new();
+}
+
+/// Register a process-wide [`DebugReportHandler`] for the report types
+/// specified by `flags`.
+///
+/// Until the first handler is registered, warnings and errors are outputted
+/// by [`PrintDebugReportHandler`].
+///
+/// [`DebugReportHandler`]: DebugReportHandler
+/// [`PrintDebugReportHandler`]: PrintDebugReportHandler
+///
+/// # Examples
+///
+///     use std::sync::Arc;
+///     use zangfx_base::debug::{
+///         add_debug_report_handler, DebugReport, DebugReportHandler,
+///         DebugReportTypeFlags,
+///     };
+///
+///     struct MyHandler;
+///
+///     impl DebugReportHandler for MyHandler {
+///         fn log(&self, report: &DebugReport<'_>) {
+///             // Forward the report to the application's logger
+///             # let _ = report;
+///         }
+///     }
+///
+///     add_debug_report_handler(
+///         DebugReportTypeFlags::WARNING | DebugReportTypeFlags::ERROR,
+///         Arc::new(MyHandler),
+///     );
+///
+pub fn add_debug_report_handler(flags: DebugReportTypeFlags, handler: Arc<dyn DebugReportHandler>) {
+    DEBUG_REPORT_HANDLERS
+        .write()
+        .unwrap()
+        .push((flags, handler));
+}
+
+/// Deliver a [`DebugReport`] to the handlers registered for its type.
+///
+/// This is meant to be called by backend implementations.
+///
+/// [`DebugReport`]: DebugReport
+pub fn report_debug(report: &DebugReport<'_>) {
+    // Release the lock before calling the handlers so that they can call
+    // `add_debug_report_handler`
+    let handlers: HandlerList = DEBUG_REPORT_HANDLERS.read().unwrap().clone();
+
+    if handlers.is_empty() {
+        if (DebugReportTypeFlags::WARNING | DebugReportTypeFlags::ERROR).contains(report.typ.flag())
+        {
+            DEFAULT_DEBUG_REPORT_HANDLER.log(report);
+        }
+        return;
+    }
+
+    for (flags, handler) in handlers.iter() {
+        if flags.contains(report.typ.flag()) {
+            handler.log(report);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct RecordingHandler(Mutex<Vec<(DebugReportType, String)>>);
+
+    impl DebugReportHandler for RecordingHandler {
+        fn log(&self, report: &DebugReport<'_>) {
+            let mut reports = self.0.lock().unwrap();
+            reports.push((report.typ, report.message.to_owned()));
+        }
+    }
+
+    #[test]
+    fn custom_handler_receives_reports() {
+        let handler = Arc::new(RecordingHandler(Mutex::new(Vec::new())));
+        add_debug_report_handler(DebugReportTypeFlags::WARNING, handler.clone());
+
+        report_debug(&DebugReport::warning(
+            "custom_handler_receives_reports: hoge",
+        ));
+        report_debug(&DebugReport::info("custom_handler_receives_reports: piyo"));
+
+        // Other tests might be generating reports at the same time
+        let reports: Vec<_> = handler
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, message)| message.starts_with("custom_handler_receives_reports: "))
+            .cloned()
+            .collect();
+        assert_eq!(
+            reports,
+            vec![(
+                DebugReportType::Warning,
+                "custom_handler_receives_reports: hoge".to_owned()
+            )]
+        );
+    }
+}
@@ -6,7 +6,9 @@
 //! Device object.
 use std::sync::Arc;
 
-use crate::{arg, command, heap, limits, pass, pipeline, resources, sampler, shader, sync};
+use crate::{
+    arg, command, debug, heap, limits, pass, pipeline, query, resources, sampler, shader, sync,
+};
 use crate::{ArgArrayIndex, ArgIndex, MemoryType};
 use crate::{Object, Result};
 
@@ -84,6 +86,15 @@ pub trait Device: Object {
     /// Create a `ComputePipelineBuilder` associated with this device.
     fn build_compute_pipeline(&self) -> pipeline::ComputePipelineBuilderRef;
 
+    /// Create a `QueryPoolBuilder` associated with this device.
+    ///
+    /// The default implementation returns a [`NotSupportedQueryPoolBuilder`].
+    ///
+    /// [`NotSupportedQueryPoolBuilder`]: crate::query::NotSupportedQueryPoolBuilder
+    fn build_query_pool(&self) -> query::QueryPoolBuilderRef {
+        Box::new(query::NotSupportedQueryPoolBuilder)
+    }
+
     /// Update given argument tables.
     ///
     /// # Examples
@@ -201,6 +212,36 @@ pub trait Device: Object {
     fn autorelease_pool_scope_core(&self, cb: &mut dyn FnMut(&mut dyn AutoreleasePool)) {
         cb(&mut NullAutoreleasePool);
     }
+
+    /// Retrieve a list of the objects created from this device and still
+    /// alive.
+    ///
+    /// This is intended to be used for tracking down leaked objects. Backends
+    /// without an object tracking mechanism return an empty list.
+    ///
+    /// # Examples
+    ///
+    ///     # use zangfx_base::device::Device;
+    ///     # fn test(device: &Device) {
+    ///     for object in device.live_objects_report() {
+    ///         println!("{:?} {:?} ({:?} bytes)",
+    ///             object.kind, object.label, object.approx_size);
+    ///     }
+    ///     # }
+    ///
+    fn live_objects_report(&self) -> Vec<debug::LiveObject> {
+        Vec::new()
+    }
+
+    /// Set whether the device should panic if there are remaining live objects
+    /// (as reported by [`live_objects_report`]) at the point of its
+    /// destruction.
+    ///
+    /// This is intended to be used by tests. This is a no-op on backends that
+    /// do not support [`live_objects_report`].
+    ///
+    /// [`live_objects_report`]: Device::live_objects_report
+    fn debug_assert_no_leaks_on_drop(&self, _enable: bool) {}
 }
 
 /// Utilies for [`Device`](Device).
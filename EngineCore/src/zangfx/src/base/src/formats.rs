@@ -18,6 +18,8 @@ use lazy_static::lazy_static;
 use std::ops;
 use {itervalues::IterValues, itervalues_derive::IterValues};
 
+pub mod convert;
+
 /// Image format.
 ///
 /// See [`IntAsImageFormat`] and [`FloatAsImageFormat`] for a convenient way
@@ -0,0 +1,696 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+//! CPU-side pixel conversion between [`ImageFormat`]s.
+//!
+//! This module is useful for preparing image data to be uploaded to a device
+//! that does not support the image's original format (e.g., converting
+//! `SrgbBgra8` into `SrgbRgba8`, or `R8` into `Rgba8`).
+//!
+//! The following formats are supported as both the source and destination:
+//!
+//!  - Integer formats with 8, 16, or 32 bits per channel, both signed and
+//!    unsigned, both normalized and unnormalized.
+//!  - Floating point formats with 16 or 32 bits per channel.
+//!  - sRGB formats.
+//!
+//! Packed formats (`Rgb10A2`) and depth/stencil formats are not supported.
+//! All multi-byte values are read and written in the native byte order.
+//!
+//! [`ImageFormat`]: crate::formats::ImageFormat
+use std::{error::Error as StdError, fmt};
+
+use crate::formats::{ImageFormat, Normalizedness, Signedness};
+
+/// An error returned by [`convert_pixels`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ConvertError {
+    /// The specified format is not supported by the conversion routine.
+    Unsupported(ImageFormat),
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ConvertError::Unsupported(format) => {
+                write!(fmt, "pixel conversion is not supported for {:?}", format)
+            }
+        }
+    }
+}
+
+impl StdError for ConvertError {}
+
+/// Convert pixels from one format to another.
+///
+/// `src` and `dst` contain `height` rows, each of which contains `width`
+/// pixels. The rows are located at the intervals of `src_row_pitch` and
+/// `dst_row_pitch` bytes, respectively. The bytes in `dst` between the rows
+/// are left untouched.
+///
+/// The conversion follows the rules below:
+///
+///  - Normalized integer values are mapped to the range `[0, 1]` (unsigned)
+///    or `[-1, 1]` (signed). Unnormalized integer values and floating point
+///    values are used as they are.
+///  - sRGB-encoded color channels are decoded to linear values. Linear values
+///    are sRGB-encoded when the destination is an sRGB format. The alpha
+///    channel is always linear.
+///  - Missing green and blue channels are filled with `0`. A missing alpha
+///    channel is filled with `1`.
+///  - Values are clamped to the representable range of the destination
+///    integer format and rounded to the nearest integer.
+///
+/// When `src_format` is equal to `dst_format`, the rows are simply copied.
+///
+/// # Panics
+///
+/// Panics if a row pitch is smaller than the size of a row, or a buffer is too
+/// small to contain `height` rows.
+///
+/// # Examples
+///
+///     use zangfx_base::formats::{ImageFormat, Signedness, Normalizedness};
+///     use zangfx_base::formats::convert::convert_pixels;
+///
+///     let unorm8 = (Signedness::Unsigned, Normalizedness::Normalized);
+///     let src = [1u8, 2, 3, 4];
+///     let mut dst = [0u8; 4];
+///     convert_pixels(
+///         &src, ImageFormat::Rgba8(unorm8.0, unorm8.1),
+///         &mut dst, ImageFormat::Bgra8(unorm8.0, unorm8.1),
+///         1, 1, 4, 4,
+///     ).unwrap();
+///     assert_eq!(dst, [3, 2, 1, 4]);
+///
+#[allow(clippy::too_many_arguments)]
+pub fn convert_pixels(
+    src: &[u8],
+    src_format: ImageFormat,
+    dst: &mut [u8],
+    dst_format: ImageFormat,
+    width: usize,
+    height: usize,
+    src_row_pitch: usize,
+    dst_row_pitch: usize,
+) -> Result<(), ConvertError> {
+    let src_layout = PixelLayout::from_format(src_format)?;
+    let dst_layout = PixelLayout::from_format(dst_format)?;
+
+    let src_row_size = src_layout.num_bytes_per_pixel() * width;
+    let dst_row_size = dst_layout.num_bytes_per_pixel() * width;
+
+    if height == 0 || width == 0 {
+        return Ok(());
+    }
+
+    assert!(src_row_pitch >= src_row_size, "src_row_pitch is too small");
+    assert!(dst_row_pitch >= dst_row_size, "dst_row_pitch is too small");
+    assert!(
+        src.len() >= src_row_pitch * (height - 1) + src_row_size,
+        "src is too small"
+    );
+    assert!(
+        dst.len() >= dst_row_pitch * (height - 1) + dst_row_size,
+        "dst is too small"
+    );
+
+    for y in 0..height {
+        let src_row = &src[src_row_pitch * y..][..src_row_size];
+        let dst_row = &mut dst[dst_row_pitch * y..][..dst_row_size];
+
+        if src_format == dst_format {
+            // Fast path
+            dst_row.copy_from_slice(src_row);
+            continue;
+        }
+
+        let src_pixels = src_row.chunks(src_layout.num_bytes_per_pixel());
+        let dst_pixels = dst_row.chunks_mut(dst_layout.num_bytes_per_pixel());
+        for (src_pixel, dst_pixel) in src_pixels.zip(dst_pixels) {
+            let rgba = src_layout.decode(src_pixel);
+            dst_layout.encode(rgba, dst_pixel);
+        }
+    }
+
+    Ok(())
+}
+
+/// The type of each channel value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ComponentType {
+    Int(Signedness, Normalizedness),
+    Float,
+    Srgb,
+}
+
+/// Describes the memory layout of a pixel.
+#[derive(Debug, Clone, Copy)]
+struct PixelLayout {
+    ty: ComponentType,
+    /// The number of bytes per channel.
+    size: usize,
+    /// The RGBA channel index of each channel in memory.
+    channels: &'static [usize],
+}
+
+const CHANNELS_R: &[usize] = &[0];
+const CHANNELS_RG: &[usize] = &[0, 1];
+const CHANNELS_RGBA: &[usize] = &[0, 1, 2, 3];
+const CHANNELS_BGRA: &[usize] = &[2, 1, 0, 3];
+
+impl PixelLayout {
+    fn from_format(format: ImageFormat) -> Result<Self, ConvertError> {
+        use self::ComponentType::*;
+        use crate::formats::ImageFormat::*;
+
+        let (ty, size, channels) = match format {
+            R8(s, n) => (Int(s, n), 1, CHANNELS_R),
+            Rg8(s, n) => (Int(s, n), 1, CHANNELS_RG),
+            Rgba8(s, n) => (Int(s, n), 1, CHANNELS_RGBA),
+            Bgra8(s, n) => (Int(s, n), 1, CHANNELS_BGRA),
+            R16(s, n) => (Int(s, n), 2, CHANNELS_R),
+            Rg16(s, n) => (Int(s, n), 2, CHANNELS_RG),
+            Rgba16(s, n) => (Int(s, n), 2, CHANNELS_RGBA),
+            R32(s, n) => (Int(s, n), 4, CHANNELS_R),
+            Rg32(s, n) => (Int(s, n), 4, CHANNELS_RG),
+            Rgba32(s, n) => (Int(s, n), 4, CHANNELS_RGBA),
+            RFloat16 => (Float, 2, CHANNELS_R),
+            RgFloat16 => (Float, 2, CHANNELS_RG),
+            RgbaFloat16 => (Float, 2, CHANNELS_RGBA),
+            RFloat32 => (Float, 4, CHANNELS_R),
+            RgFloat32 => (Float, 4, CHANNELS_RG),
+            RgbaFloat32 => (Float, 4, CHANNELS_RGBA),
+            SrgbR8 => (Srgb, 1, CHANNELS_R),
+            SrgbRg8 => (Srgb, 1, CHANNELS_RG),
+            SrgbRgba8 => (Srgb, 1, CHANNELS_RGBA),
+            SrgbBgra8 => (Srgb, 1, CHANNELS_BGRA),
+            Rgb10A2(_, _)
+            | Depth16
+            | Depth24
+            | DepthFloat32
+            | Depth24Stencil8
+            | DepthFloat32Stencil8 => return Err(ConvertError::Unsupported(format)),
+        };
+
+        Ok(Self { ty, size, channels })
+    }
+
+    fn num_bytes_per_pixel(&self) -> usize {
+        self.size * self.channels.len()
+    }
+
+    fn decode(&self, pixel: &[u8]) -> [f64; 4] {
+        let mut rgba = [0.0, 0.0, 0.0, 1.0];
+        for (bytes, &ch) in pixel.chunks(self.size).zip(self.channels.iter()) {
+            rgba[ch] = self.decode_component(bytes, ch == 3);
+        }
+        rgba
+    }
+
+    fn encode(&self, rgba: [f64; 4], pixel: &mut [u8]) {
+        for (bytes, &ch) in pixel.chunks_mut(self.size).zip(self.channels.iter()) {
+            self.encode_component(rgba[ch], bytes, ch == 3);
+        }
+    }
+
+    fn decode_component(&self, bytes: &[u8], is_alpha: bool) -> f64 {
+        match (self.ty, self.size) {
+            (ComponentType::Int(Signedness::Unsigned, n), size) => {
+                let value = read_uint(bytes) as f64;
+                if n == Normalizedness::Normalized {
+                    value / uint_max(size) as f64
+                } else {
+                    value
+                }
+            }
+            (ComponentType::Int(Signedness::Signed, n), size) => {
+                let value = read_sint(bytes) as f64;
+                if n == Normalizedness::Normalized {
+                    (value / sint_max(size) as f64).max(-1.0)
+                } else {
+                    value
+                }
+            }
+            (ComponentType::Float, 2) => f16_to_f32(read_uint(bytes) as u16) as f64,
+            (ComponentType::Float, 4) => f32::from_bits(read_uint(bytes) as u32) as f64,
+            (ComponentType::Srgb, 1) => {
+                let value = bytes[0] as f64 / 255.0;
+                if is_alpha {
+                    value
+                } else {
+                    srgb_to_linear(value)
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn encode_component(&self, value: f64, bytes: &mut [u8], is_alpha: bool) {
+        match (self.ty, self.size) {
+            (ComponentType::Int(Signedness::Unsigned, n), size) => {
+                let max = uint_max(size) as f64;
+                let value = if n == Normalizedness::Normalized {
+                    value * max
+                } else {
+                    value
+                };
+                write_uint(bytes, clamp_round(value, 0.0, max) as u64);
+            }
+            (ComponentType::Int(Signedness::Signed, n), size) => {
+                let max = sint_max(size) as f64;
+                let value = if n == Normalizedness::Normalized {
+                    value.max(-1.0) * max
+                } else {
+                    value
+                };
+                write_sint(bytes, clamp_round(value, -max - 1.0, max) as i64);
+            }
+            (ComponentType::Float, 2) => write_uint(bytes, f32_to_f16(value as f32) as u64),
+            (ComponentType::Float, 4) => write_uint(bytes, (value as f32).to_bits() as u64),
+            (ComponentType::Srgb, 1) => {
+                let value = if is_alpha {
+                    value
+                } else {
+                    linear_to_srgb(value)
+                };
+                bytes[0] = clamp_round(value * 255.0, 0.0, 255.0) as u8;
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+fn uint_max(size: usize) -> u64 {
+    (1u64 << (size * 8)) - 1
+}
+
+fn sint_max(size: usize) -> i64 {
+    (1i64 << (size * 8 - 1)) - 1
+}
+
+/// Round a value to the nearest integer and clamp it to a given range.
+/// `NaN` is mapped to `0`.
+fn clamp_round(value: f64, min: f64, max: f64) -> f64 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value.round().max(min).min(max)
+    }
+}
+
+fn read_uint(bytes: &[u8]) -> u64 {
+    match bytes.len() {
+        1 => bytes[0] as u64,
+        2 => u16::from_ne_bytes([bytes[0], bytes[1]]) as u64,
+        4 => u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64,
+        _ => unreachable!(),
+    }
+}
+
+fn read_sint(bytes: &[u8]) -> i64 {
+    match bytes.len() {
+        1 => bytes[0] as i8 as i64,
+        2 => u16::from_ne_bytes([bytes[0], bytes[1]]) as i16 as i64,
+        4 => u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i32 as i64,
+        _ => unreachable!(),
+    }
+}
+
+fn write_uint(bytes: &mut [u8], value: u64) {
+    match bytes.len() {
+        1 => bytes[0] = value as u8,
+        2 => bytes.copy_from_slice(&(value as u16).to_ne_bytes()),
+        4 => bytes.copy_from_slice(&(value as u32).to_ne_bytes()),
+        _ => unreachable!(),
+    }
+}
+
+fn write_sint(bytes: &mut [u8], value: i64) {
+    // Two's complement representation is the same for both
+    write_uint(bytes, value as u64);
+}
+
+fn srgb_to_linear(x: f64) -> f64 {
+    if x <= 0.04045 {
+        x / 12.92
+    } else {
+        ((x + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(x: f64) -> f64 {
+    if x <= 0.0031308 {
+        x * 12.92
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn f16_to_f32(x: u16) -> f32 {
+    let sign = ((x as u32) & 0x8000) << 16;
+    let exp = ((x >> 10) & 0x1f) as u32;
+    let mantissa = (x & 0x3ff) as u32;
+
+    if exp == 0 {
+        // Zero or subnormal
+        let value = mantissa as f32 * (1.0 / 16777216.0);
+        f32::from_bits(value.to_bits() | sign)
+    } else if exp == 31 {
+        // Infinity or NaN
+        f32::from_bits(sign | 0x7f80_0000 | (mantissa << 13))
+    } else {
+        f32::from_bits(sign | ((exp + 112) << 23) | (mantissa << 13))
+    }
+}
+
+/// Convert a single precision floating point value to a half precision one,
+/// rounding to nearest, ties to even.
+fn f32_to_f16(x: f32) -> u16 {
+    let bits = x.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exp == 255 {
+        // Infinity or NaN
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+
+    let exp = exp - 127 + 15;
+    if exp >= 31 {
+        // Overflow
+        return sign | 0x7c00;
+    }
+
+    if exp <= 0 {
+        // Subnormal or zero
+        if exp < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_0000;
+        let shift = (14 - exp) as u32;
+        let halfway = 1 << (shift - 1);
+        let rem = mantissa & ((1 << shift) - 1);
+        let mut value = (mantissa >> shift) as u16;
+        if rem > halfway || (rem == halfway && (value & 1) != 0) {
+            value += 1;
+        }
+        return sign | value;
+    }
+
+    let mut value = ((exp as u32) << 10 | (mantissa >> 13)) as u16;
+    let rem = mantissa & 0x1fff;
+    if rem > 0x1000 || (rem == 0x1000 && (value & 1) != 0) {
+        // This might carry into the exponent, which is the correct behavior
+        value += 1;
+    }
+    sign | value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::ImageFormat::*;
+
+    const UNORM: (Signedness, Normalizedness) = (Signedness::Unsigned, Normalizedness::Normalized);
+    const SNORM: (Signedness, Normalizedness) = (Signedness::Signed, Normalizedness::Normalized);
+    const UINT: (Signedness, Normalizedness) = (Signedness::Unsigned, Normalizedness::Unnormalized);
+    const SINT: (Signedness, Normalizedness) = (Signedness::Signed, Normalizedness::Unnormalized);
+
+    fn f32s(values: &[f32]) -> Vec<u8> {
+        values.iter().flat_map(|x| x.to_ne_bytes().to_vec()).collect()
+    }
+
+    fn u16s(values: &[u16]) -> Vec<u8> {
+        values.iter().flat_map(|x| x.to_ne_bytes().to_vec()).collect()
+    }
+
+    fn u32s(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|x| x.to_ne_bytes().to_vec()).collect()
+    }
+
+    /// Convert a single row of pixels.
+    fn convert_row(src: &[u8], src_format: ImageFormat, dst_format: ImageFormat) -> Vec<u8> {
+        let src_bpp = PixelLayout::from_format(src_format).unwrap().num_bytes_per_pixel();
+        let dst_bpp = PixelLayout::from_format(dst_format).unwrap().num_bytes_per_pixel();
+        let width = src.len() / src_bpp;
+        let mut dst = vec![0xcc; width * dst_bpp];
+        convert_pixels(
+            src,
+            src_format,
+            &mut dst,
+            dst_format,
+            width,
+            1,
+            src.len(),
+            width * dst_bpp,
+        )
+        .unwrap();
+        dst
+    }
+
+    #[test]
+    fn identity() {
+        let src = [1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(
+            convert_row(&src, Rgba8(UNORM.0, UNORM.1), Rgba8(UNORM.0, UNORM.1)),
+            src.to_vec()
+        );
+    }
+
+    #[test]
+    fn reorder() {
+        let src = [1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(
+            convert_row(&src, Rgba8(UNORM.0, UNORM.1), Bgra8(UNORM.0, UNORM.1)),
+            vec![3, 2, 1, 4, 7, 6, 5, 8]
+        );
+        assert_eq!(
+            convert_row(&src, SrgbBgra8, SrgbRgba8),
+            vec![3, 2, 1, 4, 7, 6, 5, 8]
+        );
+    }
+
+    #[test]
+    fn add_channels() {
+        assert_eq!(
+            convert_row(&[10, 20], R8(UNORM.0, UNORM.1), Rgba8(UNORM.0, UNORM.1)),
+            vec![10, 0, 0, 255, 20, 0, 0, 255]
+        );
+        assert_eq!(
+            convert_row(&[10, 20], Rg8(UINT.0, UINT.1), Rgba8(UINT.0, UINT.1)),
+            vec![10, 20, 0, 1]
+        );
+        assert_eq!(
+            convert_row(&[10, 20], Rg8(UNORM.0, UNORM.1), Bgra8(UNORM.0, UNORM.1)),
+            vec![0, 20, 10, 255]
+        );
+    }
+
+    #[test]
+    fn drop_channels() {
+        assert_eq!(
+            convert_row(&[1, 2, 3, 4], Rgba8(UNORM.0, UNORM.1), R8(UNORM.0, UNORM.1)),
+            vec![1]
+        );
+        assert_eq!(
+            convert_row(&[1, 2, 3, 4], Bgra8(UNORM.0, UNORM.1), Rg8(UNORM.0, UNORM.1)),
+            vec![3, 2]
+        );
+    }
+
+    #[test]
+    fn unorm_to_float() {
+        assert_eq!(
+            convert_row(&[0, 51, 255, 255], Rgba8(UNORM.0, UNORM.1), RgbaFloat32),
+            f32s(&[0.0, 0.2, 1.0, 1.0])
+        );
+        assert_eq!(
+            convert_row(&u16s(&[0, 65535]), Rg16(UNORM.0, UNORM.1), RgFloat32),
+            f32s(&[0.0, 1.0])
+        );
+        assert_eq!(
+            convert_row(&[0, 255], Rg8(UNORM.0, UNORM.1), RgFloat16),
+            u16s(&[0x0000, 0x3c00])
+        );
+    }
+
+    #[test]
+    fn float_to_unorm() {
+        assert_eq!(
+            convert_row(&f32s(&[0.0, 0.5, 1.0, 2.0]), RgbaFloat32, Rgba8(UNORM.0, UNORM.1)),
+            vec![0, 128, 255, 255]
+        );
+        assert_eq!(
+            convert_row(&f32s(&[-1.0, std::f32::NAN]), RgFloat32, Rg8(UNORM.0, UNORM.1)),
+            vec![0, 0]
+        );
+        assert_eq!(
+            convert_row(&u16s(&[0x3800, 0x3c00]), RgFloat16, Rg16(UNORM.0, UNORM.1)),
+            u16s(&[32768, 65535])
+        );
+    }
+
+    #[test]
+    fn snorm() {
+        assert_eq!(
+            convert_row(&[0x80, 0x81, 0x00, 0x7f], Rgba8(SNORM.0, SNORM.1), RgbaFloat32),
+            f32s(&[-1.0, -1.0, 0.0, 1.0])
+        );
+        assert_eq!(
+            convert_row(&f32s(&[-2.0, -0.5, 0.5, 1.0]), RgbaFloat32, Rgba8(SNORM.0, SNORM.1)),
+            vec![0x81, 0xc0, 0x40, 0x7f]
+        );
+    }
+
+    #[test]
+    fn unnormalized() {
+        assert_eq!(
+            convert_row(&[200], R8(UINT.0, UINT.1), RFloat32),
+            f32s(&[200.0])
+        );
+        assert_eq!(
+            convert_row(&f32s(&[300.0, -5.0]), RgFloat32, Rg8(UINT.0, UINT.1)),
+            vec![255, 0]
+        );
+        assert_eq!(
+            convert_row(&f32s(&[300.0, -200.0]), RgFloat32, Rg8(SINT.0, SINT.1)),
+            vec![0x7f, 0x80]
+        );
+        assert_eq!(
+            convert_row(&u32s(&[0xffff_ffff]), R32(UINT.0, UINT.1), R16(UINT.0, UINT.1)),
+            u16s(&[0xffff])
+        );
+        assert_eq!(
+            convert_row(&u32s(&[0xffff_fffe]), R32(SINT.0, SINT.1), R8(SINT.0, SINT.1)),
+            vec![0xfe]
+        );
+    }
+
+    #[test]
+    fn bit_depth() {
+        assert_eq!(
+            convert_row(&u16s(&[0, 32768, 65535]), R16(UNORM.0, UNORM.1), R8(UNORM.0, UNORM.1)),
+            vec![0, 128, 255]
+        );
+        assert_eq!(
+            convert_row(&[0, 128, 255], R8(UNORM.0, UNORM.1), R16(UNORM.0, UNORM.1)),
+            u16s(&[0, 32896, 65535])
+        );
+        assert_eq!(
+            convert_row(&[0, 255], R8(UNORM.0, UNORM.1), R32(UNORM.0, UNORM.1)),
+            u32s(&[0, 0xffff_ffff])
+        );
+    }
+
+    #[test]
+    fn srgb() {
+        // sRGB 128 = linear 0.2158605
+        assert_eq!(
+            convert_row(&[0, 128, 255, 128], SrgbRgba8, Rgba8(UNORM.0, UNORM.1)),
+            vec![0, 55, 255, 128]
+        );
+        // linear 0.5 = sRGB 0.7353569
+        assert_eq!(
+            convert_row(&f32s(&[0.0, 0.5, 1.0, 0.5]), RgbaFloat32, SrgbRgba8),
+            vec![0, 188, 255, 128]
+        );
+        assert_eq!(
+            convert_row(&[0, 55, 255, 128], Rgba8(UNORM.0, UNORM.1), SrgbBgra8),
+            vec![255, 128, 0, 128]
+        );
+        assert_eq!(convert_row(&[10], SrgbR8, SrgbRgba8), vec![10, 0, 0, 255]);
+    }
+
+    #[test]
+    fn half_float() {
+        assert_eq!(
+            convert_row(&u16s(&[0x3c00, 0x3800, 0xc000, 0x0001]), RgbaFloat16, RgbaFloat32),
+            f32s(&[1.0, 0.5, -2.0, 1.0 / 16777216.0])
+        );
+        assert_eq!(
+            convert_row(
+                &f32s(&[1.0, 65504.0, 1.0e6, 1.0 / 16777216.0]),
+                RgbaFloat32,
+                RgbaFloat16
+            ),
+            u16s(&[0x3c00, 0x7bff, 0x7c00, 0x0001])
+        );
+        // Ties to even
+        assert_eq!(
+            convert_row(&f32s(&[1.0 + 1.0 / 2048.0]), RFloat32, RFloat16),
+            u16s(&[0x3c00])
+        );
+        assert_eq!(
+            convert_row(&f32s(&[1.0 + 3.0 / 2048.0]), RFloat32, RFloat16),
+            u16s(&[0x3c02])
+        );
+    }
+
+    #[test]
+    fn row_pitch() {
+        let src = [1, 2, 0xff, 0xff, 3, 4, 0xff, 0xff];
+        let mut dst = [0xcc; 12];
+        convert_pixels(
+            &src,
+            R8(UNORM.0, UNORM.1),
+            &mut dst,
+            Rg8(UNORM.0, UNORM.1),
+            2,
+            2,
+            4,
+            6,
+        )
+        .unwrap();
+        assert_eq!(dst, [1, 0, 2, 0, 0xcc, 0xcc, 3, 0, 4, 0, 0xcc, 0xcc]);
+
+        // The fast path
+        let mut dst = [0xcc; 6];
+        convert_pixels(
+            &src,
+            R8(UNORM.0, UNORM.1),
+            &mut dst,
+            R8(UNORM.0, UNORM.1),
+            2,
+            2,
+            4,
+            3,
+        )
+        .unwrap();
+        assert_eq!(dst, [1, 2, 0xcc, 3, 4, 0xcc]);
+    }
+
+    #[test]
+    fn unsupported() {
+        let mut dst = [0; 16];
+        for &format in &[Rgb10A2(UNORM.0, UNORM.1), Depth16, DepthFloat32Stencil8] {
+            assert_eq!(
+                convert_pixels(&[0; 16], format, &mut dst, RgbaFloat32, 1, 1, 16, 16),
+                Err(ConvertError::Unsupported(format))
+            );
+            assert_eq!(
+                convert_pixels(&[0; 16], RgbaFloat32, &mut dst, format, 1, 1, 16, 16),
+                Err(ConvertError::Unsupported(format))
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_small_pitch() {
+        let mut dst = [0; 16];
+        let _ = convert_pixels(
+            &[0; 16],
+            Rg8(UNORM.0, UNORM.1),
+            &mut dst,
+            Rg8(UNORM.0, UNORM.1),
+            2,
+            2,
+            2,
+            4,
+        );
+    }
+}
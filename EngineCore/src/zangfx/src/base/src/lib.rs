@@ -15,6 +15,8 @@ pub use zangfx_common as common;
 #[doc(no_inline)]
 pub use query_interface::{interfaces, mopo, vtable_for};
 
+use itervalues_derive::IterValues;
+
 // `handles` defines a macro
 #[macro_use]
 pub mod handles;
@@ -34,6 +36,7 @@ pub mod heap;
 pub mod limits;
 pub mod pass;
 pub mod pipeline;
+pub mod query;
 pub mod resources;
 pub mod sampler;
 pub mod shader;
@@ -76,9 +79,12 @@ pub type RenderSubpassColorTargetIndex = usize;
 /// Specifies a viewport in a render pipeline.
 pub type ViewportIndex = usize;
 
+/// Represents a query in a query pool.
+pub type QueryIndex = usize;
+
 /// Specifies a predicate (boolean-valued function) on two numeric values
 /// used during various kinds of tests (e.g., depth test).
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, IterValues)]
 pub enum CmpFn {
     Never,
     Less,
@@ -90,6 +96,28 @@ pub enum CmpFn {
     Always,
 }
 
+impl CmpFn {
+    /// Get the result of the predicate if it does not depend on the operands.
+    ///
+    /// Returns `Some(true)` for `Always`, `Some(false)` for `Never`, and `None`
+    /// otherwise. Backends can use this to elide tests that have no effect.
+    ///
+    /// # Examples
+    ///
+    ///     # use zangfx_base::CmpFn;
+    ///     assert_eq!(CmpFn::Always.is_trivial(), Some(true));
+    ///     assert_eq!(CmpFn::Never.is_trivial(), Some(false));
+    ///     assert_eq!(CmpFn::Less.is_trivial(), None);
+    ///
+    pub fn is_trivial(self) -> Option<bool> {
+        match self {
+            CmpFn::Always => Some(true),
+            CmpFn::Never => Some(false),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Viewport {
     /// The X coordinate of the viewport's upper left corner.
@@ -106,6 +134,55 @@ pub struct Viewport {
     pub max_depth: f32,
 }
 
+impl Viewport {
+    /// Check if all fields of two `Viewport`s are equal within a tolerance of
+    /// `eps`.
+    ///
+    /// # Examples
+    ///
+    ///     # use zangfx_base::Viewport;
+    ///     let vp = Viewport {
+    ///         x: 0.0,
+    ///         y: 0.0,
+    ///         width: 640.0,
+    ///         height: 480.0,
+    ///         min_depth: 0.0,
+    ///         max_depth: 1.0,
+    ///     };
+    ///     let vp2 = Viewport { width: 640.0001, ..vp };
+    ///     assert!(vp.approx_eq(&vp2, 0.001));
+    ///     assert!(!vp.approx_eq(&vp2, 0.00001));
+    ///
+    pub fn approx_eq(&self, other: &Self, eps: f32) -> bool {
+        (self.x - other.x).abs() <= eps
+            && (self.y - other.y).abs() <= eps
+            && (self.width - other.width).abs() <= eps
+            && (self.height - other.height).abs() <= eps
+            && (self.min_depth - other.min_depth).abs() <= eps
+            && (self.max_depth - other.max_depth).abs() <= eps
+    }
+
+    /// Check if a given point is inside the viewport's rectangle. The depth
+    /// range is ignored.
+    ///
+    /// The upper left edges are inclusive and the lower right edges are
+    /// exclusive, so adjacent viewports never contain the same point. A
+    /// negative `width` or `height` (used to flip the viewport) is handled by
+    /// swapping the edges.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        fn contains_1d(start: f32, size: f32, p: f32) -> bool {
+            let (min, max) = if size < 0.0 {
+                (start + size, start)
+            } else {
+                (start, start + size)
+            };
+            p >= min && p < max
+        }
+
+        contains_1d(self.x, self.width, x) && contains_1d(self.y, self.height, y)
+    }
+}
+
 define_object! { dyn ArgTableSigBuilder }
 define_object! { dyn ArgSig }
 define_object! { dyn RootSigBuilder }
@@ -131,6 +208,7 @@ define_object! { dyn ImageBuilder }
 define_object! { dyn BufferBuilder }
 define_object! { dyn SamplerBuilder }
 define_object! { dyn LibraryBuilder }
+define_object! { dyn QueryPoolBuilder }
 
 /// The `zangfx_base` prelude.
 pub mod prelude {
@@ -152,8 +230,136 @@ pub mod prelude {
 #[doc(no_inline)]
 pub use crate::{
     arg::*, command::*, debug::*, device::*, error::*, formats::*, handles::*, heap::*, limits::*,
-    objects::*, pass::*, pipeline::*, resources::*, sampler::*, shader::*, sync::*,
+    objects::*, pass::*, pipeline::*, query::*, resources::*, sampler::*, shader::*, sync::*,
 };
 
 #[doc(no_inline)]
 pub use zangfx_common::Rect2D;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use itervalues::IterValues;
+
+    #[test]
+    fn cmp_fn_is_trivial() {
+        for cmp_fn in CmpFn::iter_values() {
+            let expected = match cmp_fn {
+                CmpFn::Always => Some(true),
+                CmpFn::Never => Some(false),
+                _ => None,
+            };
+            assert_eq!(cmp_fn.is_trivial(), expected, "{:?}", cmp_fn);
+        }
+        assert_eq!(
+            CmpFn::iter_values()
+                .filter(|cmp_fn| cmp_fn.is_trivial().is_some())
+                .count(),
+            2
+        );
+    }
+
+    fn viewport() -> Viewport {
+        Viewport {
+            x: 10.0,
+            y: 20.0,
+            width: 100.0,
+            height: 50.0,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }
+    }
+
+    #[test]
+    fn viewport_approx_eq() {
+        let vp = viewport();
+        assert!(vp.approx_eq(&vp, 0.0));
+        assert!(vp.approx_eq(&Viewport { x: 10.05, ..vp }, 0.1));
+        assert!(!vp.approx_eq(&Viewport { x: 10.2, ..vp }, 0.1));
+        assert!(!vp.approx_eq(&Viewport { max_depth: 0.5, ..vp }, 0.1));
+    }
+
+    #[test]
+    fn viewport_contains() {
+        let vp = viewport();
+        assert!(vp.contains(50.0, 40.0));
+
+        // Upper left boundaries are inclusive
+        assert!(vp.contains(10.0, 20.0));
+        assert!(vp.contains(10.0, 69.9));
+
+        // Lower right boundaries are exclusive
+        assert!(!vp.contains(110.0, 40.0));
+        assert!(!vp.contains(50.0, 70.0));
+
+        // Just outside
+        assert!(!vp.contains(9.99, 40.0));
+        assert!(!vp.contains(50.0, 19.99));
+        assert!(!vp.contains(110.01, 40.0));
+        assert!(!vp.contains(50.0, 70.01));
+    }
+
+    fn device_limits(supports: bool) -> DeviceLimits {
+        DeviceLimits {
+            supports_heap_aliasing: supports,
+            supports_semaphore: supports,
+            supports_depth_bounds: supports,
+            supports_depth_clamp: supports,
+            supports_fill_mode_non_solid: supports,
+            supports_cube_array: supports,
+            supports_independent_blend: supports,
+            supports_sampler_anisotropy: supports,
+            supports_timestamp_query: supports,
+            supports_pipeline_stats_query: supports,
+            timestamp_period: 1.0,
+            max_image_extent_1d: 4096,
+            max_image_extent_2d: 4096,
+            max_image_extent_3d: 256,
+            max_image_num_array_layers: 256,
+            max_render_target_extent: 4096,
+            max_render_target_num_layers: 256,
+            max_num_viewports: if supports { 16 } else { 1 },
+            max_compute_workgroup_size: [128, 128, 64],
+            max_num_compute_workgroup_invocations: 128,
+            max_compute_workgroup_count: [65535; 3],
+            uniform_buffer_align: 256,
+            storage_buffer_align: 256,
+        }
+    }
+
+    #[test]
+    fn device_limits_supports() {
+        let none = device_limits(false);
+        let all = device_limits(true);
+
+        // Every feature must be derived from some limit
+        for feature in DeviceFeature::iter_values() {
+            assert!(!none.supports(feature), "{:?}", feature);
+            assert!(all.supports(feature), "{:?}", feature);
+        }
+    }
+
+    #[test]
+    fn missing_features_display() {
+        let error = MissingFeatures {
+            features: vec![DeviceFeature::DepthBounds, DeviceFeature::SamplerAnisotropy],
+        };
+        assert_eq!(
+            error.to_string(),
+            "the device does not support the following features: DepthBounds, SamplerAnisotropy"
+        );
+    }
+
+    #[test]
+    fn viewport_contains_flipped() {
+        let vp = Viewport {
+            y: 70.0,
+            height: -50.0,
+            ..viewport()
+        };
+        assert!(vp.contains(50.0, 20.0));
+        assert!(vp.contains(50.0, 69.9));
+        assert!(!vp.contains(50.0, 70.0));
+        assert!(!vp.contains(50.0, 19.99));
+    }
+}
@@ -5,10 +5,12 @@
 //
 //! Provides an information regarding a device's capabilities and limits.
 use bitflags::bitflags;
+use itervalues_derive::IterValues;
+use std::{error::Error, fmt};
 
 use crate::formats::{ImageFormat, VertexFormat};
 use crate::Object;
-use crate::{DeviceSize, MemoryRegionIndex};
+use crate::{DeviceSize, MemoryRegionIndex, QueueFamily};
 
 #[derive(Debug, Clone, Copy)]
 pub struct DeviceLimits {
@@ -37,6 +39,23 @@ pub struct DeviceLimits {
 
     pub supports_independent_blend: bool,
 
+    /// Indicates whether `SamplerBuilder::max_anisotropy` can have values other
+    /// than `1`.
+    pub supports_sampler_anisotropy: bool,
+
+    /// Indicates whether `QueryType::Timestamp` is supported or not.
+    ///
+    /// `QueryType::Occlusion` is supported by every backend.
+    pub supports_timestamp_query: bool,
+
+    /// Indicates whether `QueryType::PipelineStats` is supported or not.
+    pub supports_pipeline_stats_query: bool,
+
+    /// The number of nanoseconds per tick of timestamps written by
+    /// `CmdEncoder::write_timestamp`. Meaningless if `supports_timestamp_query`
+    /// is `false`.
+    pub timestamp_period: f32,
+
     pub max_image_extent_1d: u32,
     pub max_image_extent_2d: u32,
     pub max_image_extent_3d: u32,
@@ -72,6 +91,80 @@ pub struct DeviceLimits {
     // TODO: expose more limits
 }
 
+impl DeviceLimits {
+    /// Return whether a given optional feature is supported or not.
+    pub fn supports(&self, feature: DeviceFeature) -> bool {
+        match feature {
+            DeviceFeature::HeapAliasing => self.supports_heap_aliasing,
+            DeviceFeature::Semaphore => self.supports_semaphore,
+            DeviceFeature::DepthBounds => self.supports_depth_bounds,
+            DeviceFeature::DepthClamp => self.supports_depth_clamp,
+            DeviceFeature::FillModeNonSolid => self.supports_fill_mode_non_solid,
+            DeviceFeature::CubeArray => self.supports_cube_array,
+            DeviceFeature::IndependentBlend => self.supports_independent_blend,
+            DeviceFeature::SamplerAnisotropy => self.supports_sampler_anisotropy,
+            DeviceFeature::TimestampQuery => self.supports_timestamp_query,
+            DeviceFeature::PipelineStatsQuery => self.supports_pipeline_stats_query,
+            DeviceFeature::MultiViewport => self.max_num_viewports > 1,
+        }
+    }
+}
+
+/// An optional feature of a device.
+///
+/// Use [`DeviceCaps::supports`] or [`DeviceCaps::require_features`] to check
+/// the support of features. Most of them correspond to a `supports_*` field of
+/// [`DeviceLimits`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, IterValues)]
+pub enum DeviceFeature {
+    /// See [`DeviceLimits::supports_heap_aliasing`].
+    HeapAliasing,
+    /// See [`DeviceLimits::supports_semaphore`].
+    Semaphore,
+    /// See [`DeviceLimits::supports_depth_bounds`].
+    DepthBounds,
+    /// See [`DeviceLimits::supports_depth_clamp`].
+    DepthClamp,
+    /// See [`DeviceLimits::supports_fill_mode_non_solid`].
+    FillModeNonSolid,
+    /// See [`DeviceLimits::supports_cube_array`].
+    CubeArray,
+    /// See [`DeviceLimits::supports_independent_blend`].
+    IndependentBlend,
+    /// See [`DeviceLimits::supports_sampler_anisotropy`].
+    SamplerAnisotropy,
+    /// See [`DeviceLimits::supports_timestamp_query`].
+    TimestampQuery,
+    /// See [`DeviceLimits::supports_pipeline_stats_query`].
+    PipelineStatsQuery,
+    /// Indicates whether [`DeviceLimits::max_num_viewports`] is greater than
+    /// `1`.
+    MultiViewport,
+}
+
+/// The error type returned by [`DeviceCaps::require_features`], listing the
+/// features not supported by a device.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MissingFeatures {
+    /// The missing features, in the order they were specified.
+    pub features: Vec<DeviceFeature>,
+}
+
+impl fmt::Display for MissingFeatures {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the device does not support the following features: ")?;
+        for (i, feature) in self.features.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{:?}", feature)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for MissingFeatures {}
+
 bitflags! {
     /// Indicates a set of operations on a specific `ImageFormat` supported by
     /// a device.
@@ -172,4 +265,38 @@ pub trait DeviceCaps: Object {
 
     /// Return the queue families provided by the device.
     fn queue_families(&self) -> &[QueueFamilyInfo];
+
+    /// Return the capabilities of a given queue family.
+    ///
+    /// Panics if `queue_family` is out of range.
+    fn queue_family_caps(&self, queue_family: QueueFamily) -> QueueFamilyCapsFlags {
+        self.queue_families()[queue_family as usize].caps
+    }
+
+    /// Return whether a given optional feature is supported or not.
+    ///
+    /// The default implementation retrieves the answer from [`limits`].
+    ///
+    /// [`limits`]: DeviceCaps::limits
+    fn supports(&self, feature: DeviceFeature) -> bool {
+        self.limits().supports(feature)
+    }
+
+    /// Check if all of given optional features are supported.
+    ///
+    /// Returns `Err(_)` listing the unsupported ones otherwise. This is useful
+    /// for choosing a device that meets the application's requirements.
+    fn require_features(&self, features: &[DeviceFeature]) -> Result<(), MissingFeatures> {
+        let missing: Vec<_> = features
+            .iter()
+            .cloned()
+            .filter(|&feature| !self.supports(feature))
+            .collect();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(MissingFeatures { features: missing })
+        }
+    }
 }
@@ -5,11 +5,13 @@
 //
 //! Builder for render pass objects and render target objects, and other
 //! relevant types.
+use std::{error, fmt};
+
 use crate::formats::ImageFormat;
 use crate::resources::ImageRef;
 use crate::AccessTypeFlags;
 use crate::{Object, Result};
-use crate::{RenderPassTargetIndex, SubpassIndex};
+use crate::{RenderPassTargetIndex, RenderSubpassColorTargetIndex, SubpassIndex};
 
 define_handle! {
     /// Render pass handle.
@@ -98,6 +100,30 @@ pub trait RenderPassBuilder: Object {
     /// The return type of this method is reserved for future extensions.
     fn subpass_ds_target(&mut self, target: Option<RenderPassTargetIndex>);
 
+    /// Define the resolve targets of the current subpass.
+    ///
+    /// Each element corresponds to the color target at the same position
+    /// specified by `subpass_color_targets`. At the end of the subpass, the
+    /// contents of each multisampled color target are resolved into the
+    /// corresponding resolve target (if any).
+    ///
+    /// Defaults to no resolve targets. The return type of this method is
+    /// reserved for future extensions.
+    ///
+    /// # Valid Usage
+    ///
+    /// The following conditions are checked by [`validate_resolve_targets`]:
+    ///
+    ///  - `targets.len()` must be less than or equal to the number of color
+    ///    targets.
+    ///  - Every resolve target must have a corresponding color target.
+    ///  - The color target must have a sample count greater than `1`.
+    ///  - The resolve target must have a sample count of `1`.
+    ///  - The color target and the resolve target must have the same format.
+    ///
+    /// [`validate_resolve_targets`]: validate_resolve_targets
+    fn subpass_resolve_targets(&mut self, targets: &[Option<RenderPassTargetIndex>]);
+
     // TODO: Read-only depth/stencil
 
     // TODO: `next_subpass`
@@ -135,6 +161,112 @@ pub trait RenderPassTarget: Object {
     ///
     /// Defaults to `StoreOp::DontCare`.
     fn set_stencil_store_op(&mut self, v: StoreOp) -> &mut dyn RenderPassTarget;
+
+    /// Set the number of samples per pixel of the render target.
+    ///
+    /// Defaults to `1`.
+    fn set_sample_count(&mut self, v: u32) -> &mut dyn RenderPassTarget;
+}
+
+/// The properties of a render target referenced by
+/// [`validate_resolve_targets`](validate_resolve_targets).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RenderPassTargetInfo {
+    pub format: ImageFormat,
+    pub sample_count: u32,
+}
+
+/// Describes a violation of the valid usage of
+/// [`RenderPassBuilder::subpass_resolve_targets`].
+///
+/// [`RenderPassBuilder::subpass_resolve_targets`]: RenderPassBuilder::subpass_resolve_targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResolveTargetError {
+    /// There are more resolve targets than color targets.
+    TooManyResolveTargets,
+    /// A resolve target does not have a corresponding color target.
+    MissingColorTarget(RenderSubpassColorTargetIndex),
+    /// A color target with a resolve target is not multisampled.
+    ColorTargetNotMultisampled(RenderSubpassColorTargetIndex),
+    /// A resolve target is multisampled.
+    ResolveTargetMultisampled(RenderSubpassColorTargetIndex),
+    /// A color target and its resolve target have different formats.
+    FormatMismatch(RenderSubpassColorTargetIndex),
+}
+
+impl fmt::Display for ResolveTargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveTargetError::TooManyResolveTargets => {
+                write!(f, "there are more resolve targets than color targets")
+            }
+            ResolveTargetError::MissingColorTarget(i) => {
+                write!(f, "resolve target #{} has no corresponding color target", i)
+            }
+            ResolveTargetError::ColorTargetNotMultisampled(i) => {
+                write!(f, "color target #{} is not multisampled", i)
+            }
+            ResolveTargetError::ResolveTargetMultisampled(i) => {
+                write!(f, "resolve target #{} must have a sample count of 1", i)
+            }
+            ResolveTargetError::FormatMismatch(i) => write!(
+                f,
+                "color target #{} and its resolve target have different formats",
+                i
+            ),
+        }
+    }
+}
+
+impl error::Error for ResolveTargetError {}
+
+/// Check the valid usage of
+/// [`RenderPassBuilder::subpass_resolve_targets`].
+///
+/// `targets` contains the properties of the render targets of the render
+/// pass, indexed by `RenderPassTargetIndex`. `color_targets` and
+/// `resolve_targets` are the values passed to `subpass_color_targets` and
+/// `subpass_resolve_targets`, respectively.
+///
+/// This function is intended to be used by backend implementations.
+///
+/// # Panics
+///
+/// Panics if `color_targets` or `resolve_targets` refers to an element of
+/// `targets` that does not exist.
+///
+/// [`RenderPassBuilder::subpass_resolve_targets`]: RenderPassBuilder::subpass_resolve_targets
+pub fn validate_resolve_targets(
+    targets: &[RenderPassTargetInfo],
+    color_targets: &[Option<RenderPassTargetIndex>],
+    resolve_targets: &[Option<RenderPassTargetIndex>],
+) -> std::result::Result<(), ResolveTargetError> {
+    if resolve_targets.len() > color_targets.len() {
+        return Err(ResolveTargetError::TooManyResolveTargets);
+    }
+
+    for (i, (&color, &resolve)) in color_targets.iter().zip(resolve_targets.iter()).enumerate() {
+        let resolve = match resolve {
+            Some(resolve) => &targets[resolve],
+            None => continue,
+        };
+        let color = match color {
+            Some(color) => &targets[color],
+            None => return Err(ResolveTargetError::MissingColorTarget(i)),
+        };
+
+        if color.sample_count <= 1 {
+            return Err(ResolveTargetError::ColorTargetNotMultisampled(i));
+        }
+        if resolve.sample_count != 1 {
+            return Err(ResolveTargetError::ResolveTargetMultisampled(i));
+        }
+        if color.format != resolve.format {
+            return Err(ResolveTargetError::FormatMismatch(i));
+        }
+    }
+
+    Ok(())
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -251,3 +383,90 @@ pub trait RenderTarget: Object {
     /// Defaults to an implementation defined value.
     fn clear_depth_stencil(&mut self, depth: f32, stencil: u32) -> &mut dyn RenderTarget;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGETS: &[RenderPassTargetInfo] = &[
+        // #0: 4x MSAA color target
+        RenderPassTargetInfo {
+            format: ImageFormat::SrgbBgra8,
+            sample_count: 4,
+        },
+        // #1: Single-sampled target with the same format
+        RenderPassTargetInfo {
+            format: ImageFormat::SrgbBgra8,
+            sample_count: 1,
+        },
+        // #2: Single-sampled target with a different format
+        RenderPassTargetInfo {
+            format: ImageFormat::SrgbRgba8,
+            sample_count: 1,
+        },
+        // #3: 4x MSAA target with the same format
+        RenderPassTargetInfo {
+            format: ImageFormat::SrgbBgra8,
+            sample_count: 4,
+        },
+    ];
+
+    #[test]
+    fn resolve_valid() {
+        assert_eq!(validate_resolve_targets(TARGETS, &[Some(0)], &[Some(1)]), Ok(()));
+        assert_eq!(
+            validate_resolve_targets(TARGETS, &[Some(0), Some(3)], &[Some(1)]),
+            Ok(())
+        );
+        assert_eq!(
+            validate_resolve_targets(TARGETS, &[Some(1), Some(0)], &[None, Some(1)]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn resolve_none() {
+        assert_eq!(validate_resolve_targets(TARGETS, &[Some(1)], &[]), Ok(()));
+        assert_eq!(validate_resolve_targets(TARGETS, &[Some(1)], &[None]), Ok(()));
+    }
+
+    #[test]
+    fn resolve_too_many() {
+        assert_eq!(
+            validate_resolve_targets(TARGETS, &[Some(0)], &[Some(1), None]),
+            Err(ResolveTargetError::TooManyResolveTargets)
+        );
+    }
+
+    #[test]
+    fn resolve_missing_color() {
+        assert_eq!(
+            validate_resolve_targets(TARGETS, &[Some(0), None], &[None, Some(1)]),
+            Err(ResolveTargetError::MissingColorTarget(1))
+        );
+    }
+
+    #[test]
+    fn resolve_color_not_multisampled() {
+        assert_eq!(
+            validate_resolve_targets(TARGETS, &[Some(1)], &[Some(1)]),
+            Err(ResolveTargetError::ColorTargetNotMultisampled(0))
+        );
+    }
+
+    #[test]
+    fn resolve_multisampled_resolve_target() {
+        assert_eq!(
+            validate_resolve_targets(TARGETS, &[Some(0)], &[Some(3)]),
+            Err(ResolveTargetError::ResolveTargetMultisampled(0))
+        );
+    }
+
+    #[test]
+    fn resolve_format_mismatch() {
+        assert_eq!(
+            validate_resolve_targets(TARGETS, &[Some(0)], &[Some(2)]),
+            Err(ResolveTargetError::FormatMismatch(0))
+        );
+    }
+}
@@ -7,9 +7,10 @@
 use std::ops::Range;
 
 use crate::arg::RootSigRef;
+use crate::debug;
 use crate::formats::VertexFormat;
 use crate::pass::RenderPassRef;
-use crate::shader::LibraryRef;
+use crate::shader::{LibraryRef, ShaderStageFlags};
 use crate::{
     CmpFn, ColorChannelFlags, DeviceSize, RenderSubpassColorTargetIndex, SubpassIndex,
     VertexAttrIndex, VertexBufferIndex, ViewportIndex,
@@ -63,6 +64,21 @@ pub trait ComputePipelineBuilder: Object {
     /// Mandatory.
     fn root_sig(&mut self, v: &RootSigRef) -> &mut dyn ComputePipelineBuilder;
 
+    /// Set the value of a specialization constant of the compute shader.
+    ///
+    /// Specialization constants not declared by the shader are ignored. Debug
+    /// builds report them as warnings.
+    ///
+    /// # Valid Usage
+    ///
+    ///  - The value of each specialization constant must be set at most once.
+    ///  - The type of `value` must match the declaration in the shader.
+    fn spec_constant(
+        &mut self,
+        id: SpecConstantId,
+        value: SpecValue,
+    ) -> &mut dyn ComputePipelineBuilder;
+
     /// Build an `ComputePipelineRef`.
     ///
     /// # Valid Usage
@@ -133,6 +149,25 @@ pub trait RenderPipelineBuilder: Object {
     /// Mandatory.
     fn root_sig(&mut self, v: &RootSigRef) -> &mut dyn RenderPipelineBuilder;
 
+    /// Set the value of a specialization constant of the shader of the
+    /// specified stage.
+    ///
+    /// Specialization constants not declared by the shader are ignored. Debug
+    /// builds report them as warnings.
+    ///
+    /// # Valid Usage
+    ///
+    ///  - `stage` must be either of `VERTEX` and `FRAGMENT`.
+    ///  - The value of each specialization constant must be set at most once
+    ///    per stage.
+    ///  - The type of `value` must match the declaration in the shader.
+    fn spec_constant(
+        &mut self,
+        stage: ShaderStageFlags,
+        id: SpecConstantId,
+        value: SpecValue,
+    ) -> &mut dyn RenderPipelineBuilder;
+
     /// Set the render pass where the render pipeline will be used.
     ///
     /// Mandatory.
@@ -184,6 +219,102 @@ pub trait RenderPipelineBuilder: Object {
     fn build(&mut self) -> Result<RenderPipelineRef>;
 }
 
+/// Identifies a specialization constant. Corresponds to the `SpecId`
+/// decoration of SPIR-V.
+pub type SpecConstantId = u32;
+
+/// The value of a specialization constant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpecValue {
+    Bool(bool),
+    U32(u32),
+    I32(i32),
+    F32(f32),
+}
+
+impl SpecValue {
+    /// Get the 32-bit representation of the value.
+    ///
+    /// `Bool` is represented by `0` or `1`, matching the representation of
+    /// `VkBool32`.
+    pub fn to_bits(self) -> u32 {
+        match self {
+            SpecValue::Bool(x) => x as u32,
+            SpecValue::U32(x) => x,
+            SpecValue::I32(x) => x as u32,
+            SpecValue::F32(x) => x.to_bits(),
+        }
+    }
+}
+
+/// A set of specialization constant values.
+///
+/// This type is provided for backend implementations to store the values
+/// specified via `spec_constant`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpecConstants {
+    values: Vec<(SpecConstantId, SpecValue)>,
+}
+
+impl SpecConstants {
+    /// Construct an empty `SpecConstants`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a value.
+    ///
+    /// Panics if a value was already inserted with the same `id`.
+    pub fn insert(&mut self, id: SpecConstantId, value: SpecValue) {
+        if self.values.iter().any(|&(i, _)| i == id) {
+            panic!("duplicate specialization constant ID: {}", id);
+        }
+        self.values.push((id, value));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Get an iterator over the inserted values, in the insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (SpecConstantId, SpecValue)> + '_ {
+        self.values.iter().cloned()
+    }
+
+    /// Report the IDs that are not included in `declared_ids` as warnings.
+    ///
+    /// `declared_ids` is usually obtained by
+    /// [`spirv_spec_constant_ids`](crate::shader::spirv_spec_constant_ids).
+    /// `None` indicates that the declared IDs are unknown (e.g., the shader
+    /// module was imported from a raw handle), in which case nothing is
+    /// reported. The warnings are delivered via
+    /// [`report_debug`](crate::debug::report_debug) only in debug builds.
+    pub fn report_unknown_ids(&self, declared_ids: Option<&[SpecConstantId]>, entry_point: &str) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        let declared_ids = if let Some(declared_ids) = declared_ids {
+            declared_ids
+        } else {
+            return;
+        };
+        for (id, _) in self.iter() {
+            if !declared_ids.contains(&id) {
+                let message = format!(
+                    "zangfx: the specialization constant {} is not declared by \
+                     the shader module of the entry point '{}'",
+                    id, entry_point
+                );
+                debug::report_debug(&debug::DebugReport::warning(&message));
+            }
+        }
+    }
+}
+
 /// Trait for defining a vertex buffer binding.
 pub trait VertexBufferBinding: Object {
     /// Set the vertex input rate. Defaults to `Vertex`.
@@ -567,3 +698,76 @@ pub enum BlendFactor {
     Src1Alpha,
     OneMinusSrc1Alpha,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spec_value_to_bits() {
+        assert_eq!(SpecValue::Bool(false).to_bits(), 0);
+        assert_eq!(SpecValue::Bool(true).to_bits(), 1);
+        assert_eq!(SpecValue::U32(0xdeadbeef).to_bits(), 0xdeadbeef);
+        assert_eq!(SpecValue::I32(-1).to_bits(), 0xffffffff);
+        assert_eq!(SpecValue::F32(1.0).to_bits(), 0x3f800000);
+    }
+
+    #[test]
+    fn spec_constants_insert() {
+        let mut spec_constants = SpecConstants::new();
+        spec_constants.insert(4, SpecValue::U32(1));
+        spec_constants.insert(2, SpecValue::Bool(true));
+
+        let values: Vec<_> = spec_constants.iter().collect();
+        assert_eq!(
+            values,
+            vec![(4, SpecValue::U32(1)), (2, SpecValue::Bool(true))]
+        );
+    }
+
+    struct RecordingHandler(std::sync::Mutex<Vec<String>>);
+
+    impl debug::DebugReportHandler for RecordingHandler {
+        fn log(&self, report: &debug::DebugReport<'_>) {
+            self.0.lock().unwrap().push(report.message.to_owned());
+        }
+    }
+
+    #[test]
+    fn spec_constants_report_unknown_ids() {
+        let handler = std::sync::Arc::new(RecordingHandler(Default::default()));
+        debug::add_debug_report_handler(debug::DebugReportTypeFlags::WARNING, handler.clone());
+
+        let mut spec_constants = SpecConstants::new();
+        spec_constants.insert(4, SpecValue::U32(1));
+        spec_constants.insert(2, SpecValue::Bool(true));
+
+        // The IDs declared by the shader module are unknown (e.g., a Vulkan
+        // `Library` created by `from_raw`) - nothing is reported
+        spec_constants.report_unknown_ids(None, "report_unknown_ids_1");
+
+        // No IDs are declared
+        spec_constants.report_unknown_ids(Some(&[]), "report_unknown_ids_2");
+
+        spec_constants.report_unknown_ids(Some(&[2]), "report_unknown_ids_3");
+
+        // Other tests might be generating reports at the same time
+        let count = |entry_point: &str| {
+            let messages = handler.0.lock().unwrap();
+            messages.iter().filter(|m| m.contains(entry_point)).count()
+        };
+        if cfg!(debug_assertions) {
+            assert_eq!(count("report_unknown_ids_1"), 0);
+            assert_eq!(count("report_unknown_ids_2"), 2);
+            assert_eq!(count("report_unknown_ids_3"), 1);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn spec_constants_insert_duplicate() {
+        let mut spec_constants = SpecConstants::new();
+        spec_constants.insert(4, SpecValue::U32(1));
+        spec_constants.insert(4, SpecValue::U32(2));
+    }
+}
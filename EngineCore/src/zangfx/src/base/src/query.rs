@@ -0,0 +1,234 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+//! Builder for query pools, and other relevant types.
+//!
+//! Queries are used to retrieve information regarding the execution of
+//! commands, such as the number of samples that passed the depth and stencil
+//! tests ([`QueryType::Occlusion`]).
+//!
+//! # Query lifecycle
+//!
+//! Every query in a newly created query pool is in the **Unavailable** state.
+//! Before a query can be written, it must be reset by
+//! [`CopyCmdEncoder::reset_queries`]. A query is written by
+//! [`RenderCmdEncoder::begin_query`] and [`RenderCmdEncoder::end_query`]
+//! (occlusion and pipeline statistics queries) or
+//! [`CmdEncoder::write_timestamp`] (timestamp queries). Once written, a query
+//! must be reset again before it is written for the next time.
+//!
+//! # Availability
+//!
+//! The result of a query becomes **available** when the execution of the
+//! command buffer which wrote it has completed, i.e., when the completion
+//! handlers registered via [`CmdBuffer::on_complete`] are called.
+//!
+//!  - [`QueryPool::get_results`] must not be called for queries whose results
+//!    are not available yet. Doing so results in undefined values (but not an
+//!    undefined behavior).
+//!  - [`CopyCmdEncoder::copy_query_results`] waits for the queries written by
+//!    the preceding commands in the same queue. It can be used in the same
+//!    command buffer as the one which wrote the queries.
+//!
+//! [`CopyCmdEncoder::reset_queries`]: crate::CopyCmdEncoder::reset_queries
+//! [`CopyCmdEncoder::copy_query_results`]: crate::CopyCmdEncoder::copy_query_results
+//! [`RenderCmdEncoder::begin_query`]: crate::RenderCmdEncoder::begin_query
+//! [`RenderCmdEncoder::end_query`]: crate::RenderCmdEncoder::end_query
+//! [`CmdEncoder::write_timestamp`]: crate::CmdEncoder::write_timestamp
+//! [`CmdBuffer::on_complete`]: crate::CmdBuffer::on_complete
+use bitflags::bitflags;
+use std::ops::Range;
+
+use crate::handles::CloneHandle;
+use crate::{Object, QueryIndex, Result};
+
+define_handle! {
+    /// Query pool handle.
+    ///
+    /// See [the module-level documentation of `handles`](../handles/index.html)
+    /// for the generic usage of handles.
+    QueryPoolRef: QueryPool
+}
+
+/// Trait for query pool handles.
+pub trait QueryPool: CloneHandle<QueryPoolRef> {
+    /// Get the type of the queries in the query pool.
+    fn query_type(&self) -> QueryType;
+
+    /// Get the number of the queries in the query pool.
+    fn len(&self) -> QueryIndex;
+
+    /// Retrieve the results of the queries in `range` from the host.
+    ///
+    /// Each query produces [`QueryType::num_values`] values, which are stored
+    /// to `out` consecutively.
+    ///
+    /// # Valid Usage
+    ///
+    ///  - `range` must be a subrange of `0..self.len()`.
+    ///  - `out.len()` must be equal to
+    ///    `range.len() * self.query_type().num_values()`.
+    ///  - The results of the queries must be available. See
+    ///    [the module-level documentation](index.html) for the definition.
+    ///
+    fn get_results(&self, range: Range<QueryIndex>, out: &mut [u64]) -> Result<()>;
+}
+
+/// The builder object for query pools.
+pub type QueryPoolBuilderRef = Box<dyn QueryPoolBuilder>;
+
+/// Trait for building query pools.
+///
+/// # Examples
+///
+///     # use zangfx_base::*;
+///     # fn test(device: &Device) {
+///     let query_pool = device.build_query_pool()
+///         .query_type(QueryType::Occlusion)
+///         .len(64)
+///         .build()
+///         .expect("Failed to create a query pool.");
+///     # }
+///
+pub trait QueryPoolBuilder: Object {
+    /// Set the type of the queries.
+    ///
+    /// This property is mandatory.
+    ///
+    /// # Valid Usage
+    ///
+    ///  - `QueryType::Timestamp` requires [`supports_timestamp_query`].
+    ///  - `QueryType::PipelineStats(_)` requires
+    ///    [`supports_pipeline_stats_query`].
+    ///
+    /// [`supports_timestamp_query`]: crate::DeviceLimits::supports_timestamp_query
+    /// [`supports_pipeline_stats_query`]: crate::DeviceLimits::supports_pipeline_stats_query
+    fn query_type(&mut self, v: QueryType) -> &mut dyn QueryPoolBuilder;
+
+    /// Set the number of queries.
+    ///
+    /// This property is mandatory. Must not be zero.
+    fn len(&mut self, v: QueryIndex) -> &mut dyn QueryPoolBuilder;
+
+    /// Build an `QueryPoolRef`.
+    ///
+    /// # Valid Usage
+    ///
+    /// All mandatory properties must have their values set before this method
+    /// is called.
+    fn build(&mut self) -> Result<QueryPoolRef>;
+}
+
+/// Specifies the type of queries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueryType {
+    /// Counts the number of samples that passed the depth and stencil tests.
+    ///
+    /// The count is only guaranteed to be non-zero if any samples passed the
+    /// tests. (In other words, backends might report `1` instead of the
+    /// precise count.)
+    Occlusion,
+
+    /// Records the device's timestamp. The timestamp is measured in the unit
+    /// of [`timestamp_period`].
+    ///
+    /// [`timestamp_period`]: crate::DeviceLimits::timestamp_period
+    Timestamp,
+
+    /// Counts the pipeline statistics specified by [`PipelineStatsFlags`].
+    PipelineStats(PipelineStatsFlags),
+}
+
+impl QueryType {
+    /// Get the number of 64-bit values produced by each query of this type.
+    ///
+    /// # Examples
+    ///
+    ///     # use zangfx_base::*;
+    ///     assert_eq!(QueryType::Occlusion.num_values(), 1);
+    ///     assert_eq!(
+    ///         QueryType::PipelineStats(
+    ///             PipelineStatsFlags::INPUT_VERTICES
+    ///                 | PipelineStatsFlags::FRAGMENT_INVOCATIONS
+    ///         ).num_values(),
+    ///         2,
+    ///     );
+    ///
+    pub fn num_values(self) -> usize {
+        match self {
+            QueryType::Occlusion | QueryType::Timestamp => 1,
+            QueryType::PipelineStats(flags) => flags.bits().count_ones() as usize,
+        }
+    }
+}
+
+bitflags! {
+    /// Specifies a set of pipeline statistics to be counted by a
+    /// `QueryType::PipelineStats` query.
+    ///
+    /// Each query produces one value for each specified statistic. The values
+    /// are ordered by the bit positions of the flags, from the least
+    /// significant bit.
+    pub struct PipelineStatsFlags: u8 {
+        /// The number of vertices processed by the input assembly stage.
+        const INPUT_VERTICES = 0b0000001;
+        /// The number of primitives processed by the input assembly stage.
+        const INPUT_PRIMITIVES = 0b0000010;
+        /// The number of vertex shader invocations.
+        const VERTEX_INVOCATIONS = 0b0000100;
+        /// The number of primitives processed by the clipping stage.
+        const CLIPPING_INVOCATIONS = 0b0001000;
+        /// The number of primitives output by the clipping stage.
+        const CLIPPING_PRIMITIVES = 0b0010000;
+        /// The number of fragment shader invocations.
+        const FRAGMENT_INVOCATIONS = 0b0100000;
+        /// The number of compute shader invocations.
+        const COMPUTE_INVOCATIONS = 0b1000000;
+    }
+}
+
+/// An implementation of `QueryPoolBuilder` that always panics when `build` is
+/// called.
+#[derive(Debug)]
+pub struct NotSupportedQueryPoolBuilder;
+
+zangfx_impl_object! {
+    NotSupportedQueryPoolBuilder:
+        dyn QueryPoolBuilder,
+        dyn (::std::fmt::Debug)
+}
+
+impl QueryPoolBuilder for NotSupportedQueryPoolBuilder {
+    fn query_type(&mut self, _v: QueryType) -> &mut dyn QueryPoolBuilder {
+        self
+    }
+
+    fn len(&mut self, _v: QueryIndex) -> &mut dyn QueryPoolBuilder {
+        self
+    }
+
+    fn build(&mut self) -> Result<QueryPoolRef> {
+        panic!("not supported by this backend")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn num_values() {
+        assert_eq!(QueryType::Occlusion.num_values(), 1);
+        assert_eq!(QueryType::Timestamp.num_values(), 1);
+        assert_eq!(
+            QueryType::PipelineStats(PipelineStatsFlags::empty()).num_values(),
+            0
+        );
+        assert_eq!(
+            QueryType::PipelineStats(PipelineStatsFlags::all()).num_values(),
+            7
+        );
+    }
+}
@@ -174,7 +174,9 @@ pub unsafe trait Buffer: CloneHandle<BufferRef> {
 
     /// Get the address of the underlying storage of a buffer.
     ///
-    /// The returned address must be valid throughout the lifetime of `self`.
+    /// The returned address must be valid throughout the lifetime of `self`,
+    /// or until the buffer is moved if it was created as
+    /// [relocatable](BufferBuilder::relocatable).
     ///
     /// # Valid Usage
     ///
@@ -266,6 +268,12 @@ pub trait ImageBuilder: Object {
     /// Must be `1` for 1D textures.
     fn num_mip_levels(&mut self, v: u32) -> &mut dyn ImageBuilder;
 
+    /// Set the number of samples per pixel.
+    ///
+    /// Values other than `1` are only valid for non-array 2D images with a
+    /// single mipmap level. Defaults to `1`.
+    fn sample_count(&mut self, v: u32) -> &mut dyn ImageBuilder;
+
     /// Set the image format.
     ///
     /// This property is mandatory.
@@ -448,6 +456,17 @@ pub trait BufferBuilder: Object {
     /// (`flags![BufferUsageFlags::{CopyWrite | Uniform}]`).
     fn usage(&mut self, v: BufferUsageFlags) -> &mut dyn BufferBuilder;
 
+    /// Allow the backend to move the buffer to a different location within a
+    /// heap, e.g., during a backend-specific defragmentation operation.
+    ///
+    /// A relocatable buffer's contents are preserved by a move, but the value
+    /// returned by [`Buffer::as_ptr`] may change. A move may also invalidate
+    /// the arg tables referencing the buffer; consult the backend's
+    /// documentation of the operation for how to handle this.
+    ///
+    /// Backends that do not support relocation ignore this property.
+    fn relocatable(&mut self) -> &mut dyn BufferBuilder;
+
     /// Build a `BufferRef`.
     ///
     /// # Valid Usage
@@ -73,7 +73,10 @@ pub trait SamplerBuilder: Object {
 
     /// Set the maximum anisotropic filtering level.
     ///
-    /// Defaults to `1` (minimum).
+    /// Defaults to `1` (minimum). Values other than `1` require
+    /// [`DeviceLimits::supports_sampler_anisotropy`].
+    ///
+    /// [`DeviceLimits::supports_sampler_anisotropy`]: crate::DeviceLimits::supports_sampler_anisotropy
     fn max_anisotropy(&mut self, v: u32) -> &mut dyn SamplerBuilder;
 
     /// Set the comparison function used when sampling from a depth texture.
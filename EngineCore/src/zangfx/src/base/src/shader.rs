@@ -6,8 +6,12 @@
 //! Builder for shader library objects, and other relevant types.
 use bitflags::bitflags;
 
+use crate::pipeline::SpecConstantId;
 use crate::{Object, Result};
 
+mod reflect;
+pub use self::reflect::*;
+
 define_handle! {
     /// Shader library handle.
     ///
@@ -55,3 +59,80 @@ bitflags! {
         const COMPUTE = 0b100;
     }
 }
+
+/// Extract the IDs of the specialization constants declared by a SPIR-V
+/// module, i.e., the values of all `SpecId` decorations.
+///
+/// Malformed modules are not rejected; the function just stops scanning at the
+/// point where an inconsistency was found.
+pub fn spirv_spec_constant_ids(spirv: &[u32]) -> Vec<SpecConstantId> {
+    const OP_DECORATE: u32 = 71;
+    const DECORATION_SPEC_ID: u32 = 1;
+
+    let mut ids = Vec::new();
+
+    // Skip the header (magic number, version, generator, bound, and schema)
+    let mut words = spirv.get(5..).unwrap_or(&[]);
+
+    while let Some(&first) = words.first() {
+        let word_count = (first >> 16) as usize;
+        let opcode = first & 0xffff;
+        if word_count == 0 || word_count > words.len() {
+            break;
+        }
+
+        let inst = &words[..word_count];
+        if opcode == OP_DECORATE && word_count >= 4 && inst[2] == DECORATION_SPEC_ID {
+            ids.push(inst[3]);
+        }
+
+        words = &words[word_count..];
+    }
+
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spec_constant_ids() {
+        let spirv = [
+            // Header
+            0x07230203,
+            0x00010000,
+            0,
+            16,
+            0,
+            // OpCapability Shader
+            (2 << 16) | 17,
+            1,
+            // OpDecorate %5 SpecId 42
+            (4 << 16) | 71,
+            5,
+            1,
+            42,
+            // OpDecorate %6 Binding 3
+            (4 << 16) | 71,
+            6,
+            33,
+            3,
+            // OpDecorate %7 SpecId 7
+            (4 << 16) | 71,
+            7,
+            1,
+            7,
+        ];
+        assert_eq!(spirv_spec_constant_ids(&spirv), vec![42, 7]);
+    }
+
+    #[test]
+    fn spec_constant_ids_malformed() {
+        assert_eq!(spirv_spec_constant_ids(&[]), vec![]);
+        assert_eq!(
+            spirv_spec_constant_ids(&[0x07230203, 0x00010000, 0, 16, 0, (4 << 16) | 71, 5]),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,733 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+//! Extracts the resource interface of SPIR-V modules.
+use std::collections::HashMap;
+use std::{error::Error as StdError, fmt};
+
+use crate::arg::ArgType;
+use crate::formats::{Normalizedness, ScalarFormat, Signedness, VecWidth, VertexFormat};
+use crate::shader::ShaderStageFlags;
+use crate::{ArgArrayIndex, ArgIndex, ArgTableIndex, VertexAttrIndex};
+
+/// The resource interface of a SPIR-V module, extracted by [`spirv_reflect`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShaderReflection {
+    /// The entry points defined by the module.
+    pub entry_points: Vec<EntryPointReflection>,
+    /// The arguments (descriptors) used by the module, sorted by their
+    /// locations.
+    pub args: Vec<ArgReflection>,
+    /// The vertex attributes consumed by the vertex shader entry points,
+    /// sorted by their locations.
+    pub vertex_inputs: Vec<VertexInputReflection>,
+}
+
+/// An entry point of a SPIR-V module.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EntryPointReflection {
+    pub name: String,
+    pub stage: ShaderStageFlags,
+}
+
+/// An argument (descriptor) used by a SPIR-V module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArgReflection {
+    /// The argument table index (`DescriptorSet` decoration).
+    pub table: ArgTableIndex,
+    /// The argument index (`Binding` decoration).
+    pub index: ArgIndex,
+    pub ty: ArgType,
+    /// The number of elements.
+    pub len: ArgArrayIndex,
+    /// The set of shader stages from which this argument is possibly used.
+    ///
+    /// [`spirv_reflect`] does not analyze which entry points actually use the
+    /// argument. Therefore, this is the union of the stages of all entry
+    /// points in the module, which may be wider than necessary if the module
+    /// has multiple entry points.
+    pub stages: ShaderStageFlags,
+}
+
+/// A vertex attribute consumed by a vertex shader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VertexInputReflection {
+    pub location: VertexAttrIndex,
+    /// The format of the attribute as seen by the shader. Integer attributes
+    /// are reported as `Unnormalized`.
+    pub format: VertexFormat,
+}
+
+impl ShaderReflection {
+    /// Get the set of shader stages of the entry points.
+    pub fn stages(&self) -> ShaderStageFlags {
+        self.entry_points
+            .iter()
+            .fold(ShaderStageFlags::empty(), |x, e| x | e.stage)
+    }
+
+    /// Merge the arguments of another `ShaderReflection` into this one, e.g.,
+    /// to compute the union of the arguments used by all shader stages of a
+    /// pipeline.
+    ///
+    /// The stages of the arguments found in both are merged and the larger of
+    /// the lengths is chosen. Entry points and vertex inputs are appended.
+    ///
+    /// Returns an error if an argument location is used by both with different
+    /// argument types. `self` is left unmodified in this case.
+    pub fn merge(&mut self, other: &ShaderReflection) -> Result<(), ReflectionError> {
+        for arg in other.args.iter() {
+            if let Ok(i) = self.find_arg(arg.table, arg.index) {
+                let existing = &self.args[i];
+                if existing.ty != arg.ty {
+                    return Err(ReflectionError::ArgTypeMismatch {
+                        table: arg.table,
+                        index: arg.index,
+                        types: [existing.ty, arg.ty],
+                    });
+                }
+            }
+        }
+
+        for arg in other.args.iter() {
+            match self.find_arg(arg.table, arg.index) {
+                Ok(i) => {
+                    let existing = &mut self.args[i];
+                    existing.len = existing.len.max(arg.len);
+                    existing.stages |= arg.stages;
+                }
+                Err(i) => self.args.insert(i, *arg),
+            }
+        }
+
+        self.entry_points.extend(other.entry_points.iter().cloned());
+
+        for input in other.vertex_inputs.iter() {
+            let i = self
+                .vertex_inputs
+                .binary_search_by_key(&input.location, |x| x.location);
+            if let Err(i) = i {
+                self.vertex_inputs.insert(i, *input);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn find_arg(&self, table: ArgTableIndex, index: ArgIndex) -> Result<usize, usize> {
+        self.args
+            .binary_search_by_key(&(table, index), |a| (a.table, a.index))
+    }
+}
+
+/// An error returned by [`ShaderReflection::merge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReflectionError {
+    /// An argument location is used with different argument types.
+    ArgTypeMismatch {
+        table: ArgTableIndex,
+        index: ArgIndex,
+        types: [ArgType; 2],
+    },
+}
+
+impl fmt::Display for ReflectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReflectionError::ArgTypeMismatch {
+                table,
+                index,
+                types,
+            } => write!(
+                f,
+                "argument type mismatch at table {} index {}: {:?} and {:?}",
+                table, index, types[0], types[1]
+            ),
+        }
+    }
+}
+
+impl StdError for ReflectionError {}
+
+// SPIR-V opcodes
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+
+// Decorations
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+
+// Storage classes
+const STORAGE_CLASS_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_CLASS_INPUT: u32 = 1;
+const STORAGE_CLASS_UNIFORM: u32 = 2;
+const STORAGE_CLASS_STORAGE_BUFFER: u32 = 12;
+
+// Execution models
+const EXECUTION_MODEL_VERTEX: u32 = 0;
+const EXECUTION_MODEL_FRAGMENT: u32 = 4;
+const EXECUTION_MODEL_GL_COMPUTE: u32 = 5;
+
+/// `Dim` of `OpTypeImage` representing a texel buffer.
+const DIM_BUFFER: u32 = 5;
+/// `Dim` of `OpTypeImage` representing an input attachment.
+const DIM_SUBPASS_DATA: u32 = 6;
+
+#[derive(Debug, Clone, Copy)]
+enum Type {
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: u32, count: u32 },
+    Image { dim: u32, sampled: u32 },
+    Sampler,
+    Array { element: u32, len: u32 },
+    Struct,
+    Pointer { pointee: u32 },
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Decorations {
+    block: bool,
+    buffer_block: bool,
+    location: Option<u32>,
+    binding: Option<u32>,
+    descriptor_set: Option<u32>,
+}
+
+#[derive(Debug)]
+struct EntryPoint {
+    stage: ShaderStageFlags,
+    name: String,
+    interface: Vec<u32>,
+}
+
+/// Extract the resource interface of a SPIR-V module.
+///
+/// Only the constructs representable by ZanGFX are reported. For example,
+/// combined image samplers, texel buffers, and input attachments are not
+/// included in [`ShaderReflection::args`]. Vertex attributes of the types not
+/// representable by `VertexFormat` (e.g., 64-bit types and matrices) are not
+/// included in [`ShaderReflection::vertex_inputs`].
+///
+/// [`ArgReflection::stages`] is computed for the whole module, not for each
+/// entry point. See its documentation for details.
+///
+/// Like [`spirv_spec_constant_ids`](crate::shader::spirv_spec_constant_ids),
+/// malformed modules are not rejected; the function just stops scanning at the
+/// point where an inconsistency was found.
+pub fn spirv_reflect(spirv: &[u32]) -> ShaderReflection {
+    let mut entry_points = Vec::new();
+    let mut types = HashMap::new();
+    let mut constants = HashMap::new();
+    let mut decorations: HashMap<u32, Decorations> = HashMap::new();
+    let mut variables = Vec::new();
+
+    // Skip the header (magic number, version, generator, bound, and schema)
+    let mut words = spirv.get(5..).unwrap_or(&[]);
+
+    while let Some(&first) = words.first() {
+        let word_count = (first >> 16) as usize;
+        let opcode = first & 0xffff;
+        if word_count == 0 || word_count > words.len() {
+            break;
+        }
+
+        let inst = &words[1..word_count];
+        words = &words[word_count..];
+
+        match (opcode, inst.len()) {
+            (OP_ENTRY_POINT, len) if len >= 3 => {
+                let stage = match inst[0] {
+                    EXECUTION_MODEL_VERTEX => ShaderStageFlags::VERTEX,
+                    EXECUTION_MODEL_FRAGMENT => ShaderStageFlags::FRAGMENT,
+                    EXECUTION_MODEL_GL_COMPUTE => ShaderStageFlags::COMPUTE,
+                    _ => continue,
+                };
+                let (name, rest) = decode_string(&inst[2..]);
+                entry_points.push(EntryPoint {
+                    stage,
+                    name,
+                    interface: rest.to_vec(),
+                });
+            }
+            (OP_TYPE_INT, 3) => {
+                let ty = Type::Int {
+                    width: inst[1],
+                    signed: inst[2] != 0,
+                };
+                types.insert(inst[0], ty);
+            }
+            (OP_TYPE_FLOAT, 2) => {
+                types.insert(inst[0], Type::Float { width: inst[1] });
+            }
+            (OP_TYPE_VECTOR, 3) => {
+                let ty = Type::Vector {
+                    component: inst[1],
+                    count: inst[2],
+                };
+                types.insert(inst[0], ty);
+            }
+            (OP_TYPE_IMAGE, len) if len >= 8 => {
+                let ty = Type::Image {
+                    dim: inst[2],
+                    sampled: inst[6],
+                };
+                types.insert(inst[0], ty);
+            }
+            (OP_TYPE_SAMPLER, 1) => {
+                types.insert(inst[0], Type::Sampler);
+            }
+            (OP_TYPE_ARRAY, 3) => {
+                let ty = Type::Array {
+                    element: inst[1],
+                    len: inst[2],
+                };
+                types.insert(inst[0], ty);
+            }
+            (OP_TYPE_STRUCT, len) if len >= 1 => {
+                types.insert(inst[0], Type::Struct);
+            }
+            (OP_TYPE_POINTER, 3) => {
+                types.insert(inst[0], Type::Pointer { pointee: inst[2] });
+            }
+            (OP_CONSTANT, len) if len >= 3 => {
+                // Only the lowest word is relevant to array lengths
+                constants.insert(inst[1], inst[2]);
+            }
+            (OP_VARIABLE, len) if len >= 3 => {
+                variables.push((inst[1], inst[0], inst[2]));
+            }
+            (OP_DECORATE, len) if len >= 2 => {
+                let deco = decorations.entry(inst[0]).or_default();
+                let operand = inst.get(2).cloned();
+                match inst[1] {
+                    DECORATION_BLOCK => deco.block = true,
+                    DECORATION_BUFFER_BLOCK => deco.buffer_block = true,
+                    DECORATION_LOCATION => deco.location = operand,
+                    DECORATION_BINDING => deco.binding = operand,
+                    DECORATION_DESCRIPTOR_SET => deco.descriptor_set = operand,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let stages = entry_points
+        .iter()
+        .fold(ShaderStageFlags::empty(), |x, e| x | e.stage);
+    let no_decorations = Decorations::default();
+    let decorations_of = |id: u32| decorations.get(&id).unwrap_or(&no_decorations);
+
+    let mut args = Vec::new();
+    let mut vertex_inputs = Vec::new();
+
+    for &(id, ty, storage_class) in variables.iter() {
+        let pointee = match types.get(&ty) {
+            Some(&Type::Pointer { pointee }) => pointee,
+            _ => continue,
+        };
+        let deco = decorations_of(id);
+
+        match storage_class {
+            STORAGE_CLASS_UNIFORM_CONSTANT
+            | STORAGE_CLASS_UNIFORM
+            | STORAGE_CLASS_STORAGE_BUFFER => {
+                let binding = match deco.binding {
+                    Some(x) => x,
+                    None => continue,
+                };
+
+                // Unwrap an array type
+                let (element, len) = match types.get(&pointee) {
+                    Some(&Type::Array { element, len }) => match constants.get(&len) {
+                        Some(&len) => (element, len),
+                        None => continue,
+                    },
+                    _ => (pointee, 1),
+                };
+
+                let arg_ty = match (storage_class, types.get(&element)) {
+                    (STORAGE_CLASS_UNIFORM_CONSTANT, Some(&Type::Sampler)) => ArgType::Sampler,
+                    (STORAGE_CLASS_UNIFORM_CONSTANT, Some(&Type::Image { dim, sampled }))
+                        if dim != DIM_BUFFER && dim != DIM_SUBPASS_DATA =>
+                    {
+                        if sampled == 2 {
+                            ArgType::StorageImage
+                        } else {
+                            ArgType::SampledImage
+                        }
+                    }
+                    (STORAGE_CLASS_UNIFORM, Some(&Type::Struct)) => {
+                        let deco = decorations_of(element);
+                        if deco.buffer_block {
+                            ArgType::StorageBuffer
+                        } else if deco.block {
+                            ArgType::UniformBuffer
+                        } else {
+                            continue;
+                        }
+                    }
+                    (STORAGE_CLASS_STORAGE_BUFFER, Some(&Type::Struct)) => ArgType::StorageBuffer,
+                    _ => continue,
+                };
+
+                args.push(ArgReflection {
+                    table: deco.descriptor_set.unwrap_or(0) as ArgTableIndex,
+                    index: binding as ArgIndex,
+                    ty: arg_ty,
+                    len: len as ArgArrayIndex,
+                    stages,
+                });
+            }
+            STORAGE_CLASS_INPUT => {
+                let location = match deco.location {
+                    Some(x) => x,
+                    None => continue,
+                };
+
+                let is_vertex_input = entry_points
+                    .iter()
+                    .any(|e| e.stage == ShaderStageFlags::VERTEX && e.interface.contains(&id));
+                if !is_vertex_input {
+                    continue;
+                }
+
+                if let Some(format) = vertex_format(&types, pointee) {
+                    vertex_inputs.push(VertexInputReflection {
+                        location: location as VertexAttrIndex,
+                        format,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    args.sort_by_key(|a| (a.table, a.index));
+    vertex_inputs.sort_by_key(|x| x.location);
+
+    ShaderReflection {
+        entry_points: entry_points
+            .into_iter()
+            .map(|e| EntryPointReflection {
+                name: e.name,
+                stage: e.stage,
+            })
+            .collect(),
+        args,
+        vertex_inputs,
+    }
+}
+
+/// Decode a nul-terminated literal string. Returns the string and the
+/// remaining words.
+fn decode_string(words: &[u32]) -> (String, &[u32]) {
+    let mut bytes = Vec::new();
+    for (i, &word) in words.iter().enumerate() {
+        for k in 0..4 {
+            let byte = (word >> (k * 8)) as u8;
+            if byte == 0 {
+                let name = String::from_utf8_lossy(&bytes).into_owned();
+                return (name, &words[i + 1..]);
+            }
+            bytes.push(byte);
+        }
+    }
+    (String::from_utf8_lossy(&bytes).into_owned(), &[])
+}
+
+fn vertex_format(types: &HashMap<u32, Type>, ty: u32) -> Option<VertexFormat> {
+    let (component, count) = match *types.get(&ty)? {
+        Type::Vector { component, count } => (component, count),
+        _ => (ty, 1),
+    };
+
+    let vec_width = VecWidth::from_width(count as usize)?;
+    let scalar_format = match *types.get(&component)? {
+        Type::Float { width: 32 } => ScalarFormat::F32,
+        Type::Int { width, signed } => {
+            let signedness = if signed {
+                Signedness::Signed
+            } else {
+                Signedness::Unsigned
+            };
+            let norm = Normalizedness::Unnormalized;
+            match width {
+                8 => ScalarFormat::I8(signedness, norm),
+                16 => ScalarFormat::I16(signedness, norm),
+                32 => ScalarFormat::I32(signedness, norm),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    Some(VertexFormat(vec_width, scalar_format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Encode a literal string.
+    fn string(s: &str) -> Vec<u32> {
+        let mut bytes = s.as_bytes().to_vec();
+        bytes.push(0);
+        while bytes.len() % 4 != 0 {
+            bytes.push(0);
+        }
+        bytes
+            .chunks(4)
+            .map(|c| {
+                u32::from(c[0])
+                    | u32::from(c[1]) << 8
+                    | u32::from(c[2]) << 16
+                    | u32::from(c[3]) << 24
+            })
+            .collect()
+    }
+
+    fn inst(opcode: u32, operands: &[u32]) -> Vec<u32> {
+        let mut words = vec![((operands.len() as u32 + 1) << 16) | opcode];
+        words.extend_from_slice(operands);
+        words
+    }
+
+    /// Assemble a module equivalent to the following GLSL code:
+    ///
+    /// ```glsl
+    /// layout(location = 0) in vec3 position;
+    /// layout(location = 2) in uvec2 index;
+    /// layout(set = 0, binding = 0) uniform Globals { ... };
+    /// layout(set = 1, binding = 1) uniform texture2D images[4];
+    /// layout(set = 1, binding = 0) uniform sampler samp;
+    /// layout(set = 0, binding = 2) buffer Storage { ... };
+    /// ```
+    fn vertex_module() -> Vec<u32> {
+        let mut m = vec![0x07230203, 0x00010000, 0, 100, 0];
+        // OpCapability Shader
+        m.extend(inst(17, &[1]));
+        // OpEntryPoint Vertex %1 "main" %20 %21 %22
+        let mut ep = vec![EXECUTION_MODEL_VERTEX, 1];
+        ep.extend(string("main"));
+        ep.extend(&[20, 21, 22]);
+        m.extend(inst(OP_ENTRY_POINT, &ep));
+
+        // Decorations
+        m.extend(inst(OP_DECORATE, &[20, DECORATION_LOCATION, 0]));
+        m.extend(inst(OP_DECORATE, &[21, DECORATION_LOCATION, 2]));
+        m.extend(inst(OP_DECORATE, &[22, 11, 0])); // BuiltIn Position
+        m.extend(inst(OP_DECORATE, &[40, DECORATION_BLOCK]));
+        m.extend(inst(OP_DECORATE, &[30, DECORATION_DESCRIPTOR_SET, 0]));
+        m.extend(inst(OP_DECORATE, &[30, DECORATION_BINDING, 0]));
+        m.extend(inst(OP_DECORATE, &[31, DECORATION_DESCRIPTOR_SET, 1]));
+        m.extend(inst(OP_DECORATE, &[31, DECORATION_BINDING, 1]));
+        m.extend(inst(OP_DECORATE, &[32, DECORATION_DESCRIPTOR_SET, 1]));
+        m.extend(inst(OP_DECORATE, &[32, DECORATION_BINDING, 0]));
+        m.extend(inst(OP_DECORATE, &[41, DECORATION_BUFFER_BLOCK]));
+        m.extend(inst(OP_DECORATE, &[33, DECORATION_DESCRIPTOR_SET, 0]));
+        m.extend(inst(OP_DECORATE, &[33, DECORATION_BINDING, 2]));
+
+        // Types
+        m.extend(inst(OP_TYPE_FLOAT, &[2, 32]));
+        m.extend(inst(OP_TYPE_VECTOR, &[3, 2, 3]));
+        m.extend(inst(OP_TYPE_VECTOR, &[4, 2, 4]));
+        m.extend(inst(OP_TYPE_INT, &[5, 32, 0]));
+        m.extend(inst(OP_TYPE_VECTOR, &[6, 5, 2]));
+        m.extend(inst(OP_CONSTANT, &[5, 7, 4]));
+        m.extend(inst(OP_TYPE_STRUCT, &[40, 4]));
+        m.extend(inst(OP_TYPE_STRUCT, &[41, 4]));
+        m.extend(inst(OP_TYPE_IMAGE, &[8, 2, 1, 0, 0, 0, 1, 0]));
+        m.extend(inst(OP_TYPE_ARRAY, &[9, 8, 7]));
+        m.extend(inst(OP_TYPE_SAMPLER, &[10]));
+        m.extend(inst(OP_TYPE_POINTER, &[11, STORAGE_CLASS_INPUT, 3]));
+        m.extend(inst(OP_TYPE_POINTER, &[12, STORAGE_CLASS_INPUT, 6]));
+        m.extend(inst(OP_TYPE_POINTER, &[13, 3, 4])); // Output
+        m.extend(inst(OP_TYPE_POINTER, &[14, STORAGE_CLASS_UNIFORM, 40]));
+        m.extend(inst(
+            OP_TYPE_POINTER,
+            &[15, STORAGE_CLASS_UNIFORM_CONSTANT, 9],
+        ));
+        m.extend(inst(
+            OP_TYPE_POINTER,
+            &[16, STORAGE_CLASS_UNIFORM_CONSTANT, 10],
+        ));
+        m.extend(inst(OP_TYPE_POINTER, &[17, STORAGE_CLASS_UNIFORM, 41]));
+
+        // Variables
+        m.extend(inst(OP_VARIABLE, &[11, 20, STORAGE_CLASS_INPUT]));
+        m.extend(inst(OP_VARIABLE, &[12, 21, STORAGE_CLASS_INPUT]));
+        m.extend(inst(OP_VARIABLE, &[13, 22, 3]));
+        m.extend(inst(OP_VARIABLE, &[14, 30, STORAGE_CLASS_UNIFORM]));
+        m.extend(inst(OP_VARIABLE, &[15, 31, STORAGE_CLASS_UNIFORM_CONSTANT]));
+        m.extend(inst(OP_VARIABLE, &[16, 32, STORAGE_CLASS_UNIFORM_CONSTANT]));
+        m.extend(inst(OP_VARIABLE, &[17, 33, STORAGE_CLASS_UNIFORM]));
+        m
+    }
+
+    /// Assemble a fragment shader module using a storage image and the
+    /// uniform buffer at `(0, 0)`.
+    fn fragment_module() -> Vec<u32> {
+        let mut m = vec![0x07230203, 0x00010000, 0, 100, 0];
+        let mut ep = vec![EXECUTION_MODEL_FRAGMENT, 1];
+        ep.extend(string("frag_main"));
+        m.extend(inst(OP_ENTRY_POINT, &ep));
+
+        m.extend(inst(OP_DECORATE, &[40, DECORATION_BLOCK]));
+        m.extend(inst(OP_DECORATE, &[30, DECORATION_BINDING, 0]));
+        m.extend(inst(OP_DECORATE, &[31, DECORATION_DESCRIPTOR_SET, 2]));
+        m.extend(inst(OP_DECORATE, &[31, DECORATION_BINDING, 0]));
+
+        m.extend(inst(OP_TYPE_FLOAT, &[2, 32]));
+        m.extend(inst(OP_TYPE_STRUCT, &[40, 2]));
+        m.extend(inst(OP_TYPE_IMAGE, &[8, 2, 1, 0, 0, 0, 2, 1]));
+        m.extend(inst(OP_TYPE_POINTER, &[14, STORAGE_CLASS_UNIFORM, 40]));
+        m.extend(inst(
+            OP_TYPE_POINTER,
+            &[15, STORAGE_CLASS_UNIFORM_CONSTANT, 8],
+        ));
+        m.extend(inst(OP_VARIABLE, &[14, 30, STORAGE_CLASS_UNIFORM]));
+        m.extend(inst(OP_VARIABLE, &[15, 31, STORAGE_CLASS_UNIFORM_CONSTANT]));
+        m
+    }
+
+    #[test]
+    fn reflect_vertex() {
+        let r = spirv_reflect(&vertex_module());
+
+        assert_eq!(
+            r.entry_points,
+            vec![EntryPointReflection {
+                name: "main".to_owned(),
+                stage: ShaderStageFlags::VERTEX,
+            }]
+        );
+        assert_eq!(r.stages(), ShaderStageFlags::VERTEX);
+
+        let arg = |table, index, ty, len| ArgReflection {
+            table,
+            index,
+            ty,
+            len,
+            stages: ShaderStageFlags::VERTEX,
+        };
+        assert_eq!(
+            r.args,
+            vec![
+                arg(0, 0, ArgType::UniformBuffer, 1),
+                arg(0, 2, ArgType::StorageBuffer, 1),
+                arg(1, 0, ArgType::Sampler, 1),
+                arg(1, 1, ArgType::SampledImage, 4),
+            ]
+        );
+
+        assert_eq!(
+            r.vertex_inputs,
+            vec![
+                VertexInputReflection {
+                    location: 0,
+                    format: VertexFormat(VecWidth::Vector3, ScalarFormat::F32),
+                },
+                VertexInputReflection {
+                    location: 2,
+                    format: VertexFormat(
+                        VecWidth::Vector2,
+                        ScalarFormat::I32(Signedness::Unsigned, Normalizedness::Unnormalized)
+                    ),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reflect_fragment() {
+        let r = spirv_reflect(&fragment_module());
+
+        assert_eq!(r.entry_points[0].name, "frag_main");
+        assert_eq!(r.stages(), ShaderStageFlags::FRAGMENT);
+        assert_eq!(r.vertex_inputs, vec![]);
+
+        let arg = |table, index, ty| ArgReflection {
+            table,
+            index,
+            ty,
+            len: 1,
+            stages: ShaderStageFlags::FRAGMENT,
+        };
+        assert_eq!(
+            r.args,
+            vec![
+                arg(0, 0, ArgType::UniformBuffer),
+                arg(2, 0, ArgType::StorageImage),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge() {
+        let mut r = spirv_reflect(&vertex_module());
+        r.merge(&spirv_reflect(&fragment_module())).unwrap();
+
+        assert_eq!(r.entry_points.len(), 2);
+        assert_eq!(
+            r.stages(),
+            ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT
+        );
+
+        let locations: Vec<_> = r.args.iter().map(|a| (a.table, a.index)).collect();
+        assert_eq!(locations, vec![(0, 0), (0, 2), (1, 0), (1, 1), (2, 0)]);
+
+        assert_eq!(
+            r.args[0].stages,
+            ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT
+        );
+        assert_eq!(r.args[1].stages, ShaderStageFlags::VERTEX);
+        assert_eq!(r.args[4].stages, ShaderStageFlags::FRAGMENT);
+        assert_eq!(r.vertex_inputs.len(), 2);
+    }
+
+    #[test]
+    fn merge_conflict() {
+        let mut r = spirv_reflect(&vertex_module());
+        let mut other = spirv_reflect(&fragment_module());
+        other.args[0].ty = ArgType::StorageBuffer;
+
+        assert_eq!(
+            r.merge(&other),
+            Err(ReflectionError::ArgTypeMismatch {
+                table: 0,
+                index: 0,
+                types: [ArgType::UniformBuffer, ArgType::StorageBuffer],
+            })
+        );
+        assert_eq!(r, spirv_reflect(&vertex_module()));
+    }
+
+    #[test]
+    fn reflect_malformed() {
+        assert_eq!(spirv_reflect(&[]), ShaderReflection::default());
+
+        // Truncated in the middle of an instruction
+        let mut m = vertex_module();
+        m.truncate(40);
+        let r = spirv_reflect(&m);
+        assert_eq!(r.entry_points.len(), 1);
+        assert_eq!(r.args, vec![]);
+    }
+}
@@ -14,6 +14,9 @@ use zangfx_utils::prelude::*;
 static SPIRV_READ: ::include_data::DataView =
     include_data!(concat!(env!("OUT_DIR"), "/arg_table_mixed_read.comp.spv"));
 
+static SPIRV_CONV: ::include_data::DataView =
+    include_data!(concat!(env!("OUT_DIR"), "/compute_conv1.comp.spv"));
+
 fn arg_table_sig_create<T: TestDriver>(driver: T, arg_type: gfx::ArgType) {
     driver.for_each_device(&mut |device| {
         let mut builder = device.build_arg_table_sig();
@@ -59,6 +62,44 @@ pub fn arg_pool_no_args<T: TestDriver>(driver: T) {
     });
 }
 
+/// Create signatures from the reflection of a shader module and use them to
+/// create a compute pipeline.
+pub fn arg_root_sig_from_reflection<T: TestDriver>(driver: T) {
+    driver.for_each_device(&mut |device| {
+        let reflection = gfx::spirv_reflect(SPIRV_CONV.as_u32_slice());
+
+        println!("- Creating signatures");
+        let (root_sig, arg_table_sigs) = device
+            .build_root_sig_from_reflection(&[&reflection])
+            .unwrap();
+
+        // `compute_conv1.comp` only uses the argument table 1. An empty
+        // signature is created for the argument table 0.
+        assert_eq!(arg_table_sigs.len(), 2);
+
+        println!("- Creating a library");
+        let library = device.new_library(SPIRV_CONV.as_u32_slice()).unwrap();
+
+        println!("- Creating a pipeline");
+        device
+            .build_compute_pipeline()
+            .compute_shader(&library, "main")
+            .root_sig(&root_sig)
+            .build()
+            .unwrap();
+
+        println!("- Creating signatures from conflicting reflections");
+        let mut conflicting = reflection.clone();
+        conflicting.args[0].ty = match conflicting.args[0].ty {
+            gfx::ArgType::UniformBuffer => gfx::ArgType::StorageBuffer,
+            _ => gfx::ArgType::UniformBuffer,
+        };
+        assert!(device
+            .build_root_sig_from_reflection(&[&reflection, &conflicting])
+            .is_err());
+    });
+}
+
 fn arg_table<T: TestDriver>(driver: T, arg_types: &[gfx::ArgType]) {
     driver.for_each_device(&mut |device| {
         const TABLE_COUNT: usize = 4;
@@ -0,0 +1,33 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use itervalues::IterValues;
+
+use super::TestDriver;
+use zangfx_base as gfx;
+
+pub fn caps_features<T: TestDriver>(driver: T) {
+    driver.for_each_device(&mut |device| {
+        let caps = device.caps();
+        let features: Vec<_> = gfx::DeviceFeature::iter_values().collect();
+
+        // The backend must give an answer for every feature
+        let (supported, missing): (Vec<_>, Vec<_>) =
+            features.iter().cloned().partition(|&f| caps.supports(f));
+        println!("Supported: {:?}", supported);
+        println!("Not supported: {:?}", missing);
+
+        assert_eq!(caps.require_features(&supported), Ok(()));
+        assert_eq!(caps.require_features(&[]), Ok(()));
+        if !missing.is_empty() {
+            assert_eq!(
+                caps.require_features(&features),
+                Err(gfx::MissingFeatures {
+                    features: missing.clone()
+                })
+            );
+        }
+    });
+}
@@ -4,7 +4,11 @@
 // This source code is a part of Nightingales.
 //
 use super::{utils, TestDriver};
+use flags_macro::flags;
+use volatile_view::prelude::*;
 use zangfx_base as gfx;
+use zangfx_base::prelude::*;
+use zangfx_utils::prelude::*;
 
 pub fn cmdqueue_create<T: TestDriver>(driver: T) {
     driver.for_each_device(&mut |device| {
@@ -43,6 +47,69 @@ pub fn cmdqueue_create_fail_missing_queue_family<T: TestDriver>(driver: T) {
     });
 }
 
+pub fn cmdqueue_create_fail_invalid_queue_family<T: TestDriver>(driver: T) {
+    if !driver.is_safe() {
+        panic!("this test was skipped because the backend is unsafe");
+    }
+    driver.for_each_device(&mut |device| {
+        let num_queue_families = device.caps().queue_families().len();
+        device
+            .build_cmd_queue()
+            .queue_family(num_queue_families as _)
+            .build()
+            .unwrap();
+    });
+}
+
+pub fn cmdqueue_create_priority<T: TestDriver>(driver: T) {
+    driver.for_each_device(&mut |device| {
+        for &priority in [0.0, 1.0].iter() {
+            println!("- Creating a command queue with priority {}", priority);
+            let queue: gfx::CmdQueueRef = device
+                .build_cmd_queue()
+                .queue_family(0)
+                .priority(priority)
+                .build()
+                .unwrap();
+
+            println!("- Creating a command buffer");
+            queue.new_cmd_buffer().unwrap();
+        }
+    });
+}
+
+pub fn cmdqueue_queue_families<T: TestDriver>(driver: T) {
+    driver.for_each_device(&mut |device| {
+        use zangfx_base::limits::QueueFamilyCapsFlags;
+
+        let queue_families = device.caps().queue_families();
+        assert!(queue_families.len() > 0, "no queue families");
+
+        for (i, queue_family) in queue_families.iter().enumerate() {
+            println!("- [{}] : {:?}", i, queue_family);
+            assert!(queue_family.count > 0, "empty queue family");
+            assert!(!queue_family.caps.is_empty(), "queue family with no caps");
+            assert_eq!(device.caps().queue_family_caps(i as _), queue_family.caps);
+
+            // Every queue family capable of rendering or compute must also be
+            // capable of copy commands
+            if queue_family
+                .caps
+                .intersects(QueueFamilyCapsFlags::RENDER | QueueFamilyCapsFlags::COMPUTE)
+            {
+                assert!(queue_family.caps.contains(QueueFamilyCapsFlags::COPY));
+            }
+        }
+    });
+}
+
+pub fn cmdqueue_queue_family_caps_fail_out_of_range<T: TestDriver>(driver: T) {
+    driver.for_each_device(&mut |device| {
+        let num_queue_families = device.caps().queue_families().len();
+        device.caps().queue_family_caps(num_queue_families as _);
+    });
+}
+
 pub fn cmdqueue_create_buffer<T: TestDriver>(driver: T) {
     driver.for_each_device(&mut |device| {
         println!("- Creating a command queue");
@@ -92,6 +159,35 @@ pub fn cmdqueue_create_encoder<T: TestDriver>(driver: T) {
     });
 }
 
+pub fn cmdqueue_create_encoder_fail_unsupported_caps<T: TestDriver>(driver: T) {
+    if !driver.is_safe() {
+        panic!("this test was skipped because the backend is unsafe");
+    }
+    driver.for_each_device(&mut |device| {
+        use zangfx_base::limits::QueueFamilyCapsFlags;
+
+        // Find a queue family lacking the compute capability
+        let queue_family = device
+            .caps()
+            .queue_families()
+            .iter()
+            .position(|qf| !qf.caps.contains(QueueFamilyCapsFlags::COMPUTE))
+            .expect("this test was skipped because every queue family supports compute");
+
+        println!("- Creating a command queue for [{}]", queue_family);
+        let queue: gfx::CmdQueueRef = device
+            .build_cmd_queue()
+            .queue_family(queue_family as _)
+            .build()
+            .unwrap();
+
+        let mut buffer = queue.new_cmd_buffer().unwrap();
+
+        println!("- Creating a compute encoder");
+        buffer.encode_compute();
+    });
+}
+
 pub fn cmdqueue_buffer_noop_completes<T: TestDriver>(driver: T) {
     driver.for_each_device(&mut |device| {
         println!("- Creating a command queue");
@@ -219,3 +315,69 @@ pub fn cmdqueue_buffer_fence_update_wait_completes<T: TestDriver>(driver: T) {
         println!("- The execution of the command buffer has completed");
     });
 }
+
+pub fn cmdqueue_wait_idle_empty<T: TestDriver>(driver: T) {
+    driver.for_each_device(&mut |device| {
+        println!("- Creating a command queue");
+        let queue: gfx::CmdQueueRef = device.build_cmd_queue().queue_family(0).build().unwrap();
+
+        println!("- Waiting until the queue is idle");
+        queue.wait_idle().unwrap();
+    });
+}
+
+pub fn cmdqueue_wait_idle_drains<T: TestDriver>(driver: T) {
+    driver.for_each_copy_queue(&mut |device, qf| {
+        println!("- Creating a command queue");
+        let queue = device.build_cmd_queue().queue_family(qf).build().unwrap();
+
+        println!("- Creating a buffer");
+        let buffer1 = device
+            .build_buffer()
+            .size(1024)
+            .usage(gfx::BufferUsageFlags::COPY_WRITE)
+            .queue(&queue)
+            .build()
+            .unwrap();
+
+        let valid_memory_types = buffer1.get_memory_req().unwrap().memory_types;
+        let memory_type = utils::choose_memory_type(
+            device,
+            valid_memory_types,
+            flags![gfx::MemoryTypeCapsFlags::{HOST_VISIBLE | HOST_COHERENT}],
+            flags![gfx::MemoryTypeCapsFlags::{HOST_VISIBLE | HOST_COHERENT}],
+        );
+        device
+            .global_heap(memory_type)
+            .bind((&buffer1).into())
+            .unwrap();
+
+        let buffer1_view = buffer1.as_bytes_volatile();
+        for x in buffer1_view {
+            x.store(0);
+        }
+
+        println!("- Commiting command buffers");
+        for i in 0..4u64 {
+            let mut buffer = queue.new_cmd_buffer().unwrap();
+            let range = i * 256..(i + 1) * 256;
+            buffer
+                .encode_copy()
+                .fill_buffer(&buffer1, range.clone(), 0x40 + i as u8);
+            buffer.host_barrier(gfx::AccessTypeFlags::COPY_WRITE, &[(range, &buffer1)]);
+            buffer.commit().unwrap();
+        }
+
+        println!("- Flushing the command queue");
+        queue.flush();
+
+        println!("- Waiting until the queue is idle");
+        queue.wait_idle().unwrap();
+
+        println!("- Checking the result");
+        let ret: Vec<_> = buffer1_view.load();
+        for i in 0..4usize {
+            assert_eq!(ret[i * 256..(i + 1) * 256], [0x40 + i as u8; 256][..]);
+        }
+    });
+}
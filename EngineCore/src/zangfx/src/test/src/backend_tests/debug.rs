@@ -0,0 +1,44 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use super::TestDriver;
+use flags_macro::flags;
+use zangfx_base as gfx;
+use zangfx_base::prelude::*;
+
+pub fn debug_live_objects_report<T: TestDriver>(driver: T) {
+    if !driver.has_live_objects_report() {
+        println!("this test was skipped because the backend does not track live objects");
+        return;
+    }
+    driver.for_each_device(&mut |device| {
+        let leaked_buffer = {
+            println!("- Creating a buffer");
+            device
+                .build_buffer()
+                .size(1001)
+                .usage(flags![gfx::BufferUsageFlags::{COPY_READ | COPY_WRITE}])
+                .label("Leaked buffer")
+                .build()
+                .unwrap()
+        };
+
+        println!("- Checking the report");
+        let report = device.live_objects_report();
+        println!("{:#?}", report);
+        assert!(report.iter().any(|object| object.kind == gfx::LiveObjectKind::Buffer
+            && object.label.as_ref().map(String::as_str) == Some("Leaked buffer")
+            && object.approx_size == Some(1001)));
+
+        println!("- Releasing the buffer");
+        drop(leaked_buffer);
+
+        let report = device.live_objects_report();
+        println!("{:#?}", report);
+        assert!(!report
+            .iter()
+            .any(|object| object.label.as_ref().map(String::as_str) == Some("Leaked buffer")));
+    });
+}
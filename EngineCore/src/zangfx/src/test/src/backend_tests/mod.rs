@@ -16,6 +16,11 @@ pub trait TestDriver {
         false
     }
 
+    /// Retrieve if the backend implements `Device::live_objects_report`.
+    fn has_live_objects_report(&self) -> bool {
+        false
+    }
+
     fn for_each_compute_queue(&self, runner: &mut dyn FnMut(&gfx::DeviceRef, gfx::QueueFamily)) {
         self.for_each_device(&mut |device| {
             for (i, qf) in device.caps().queue_families().iter().enumerate() {
@@ -61,6 +66,7 @@ pub trait TestDriver {
 macro_rules! zangfx_generate_backend_tests {
     ($driver:expr) => {
         $crate::zangfx_test_single! { create_device, $driver }
+        $crate::zangfx_test_single! { caps_features, $driver }
 
         $crate::zangfx_test_single! { arg_table_sig_create_image, $driver }
         $crate::zangfx_test_single! { arg_table_sig_create_buffer, $driver }
@@ -72,15 +78,23 @@ macro_rules! zangfx_generate_backend_tests {
         $crate::zangfx_test_single! { arg_pool_empty, $driver }
         $crate::zangfx_test_single! { arg_pool_no_tables, $driver }
         $crate::zangfx_test_single! { arg_pool_no_args, $driver }
+        $crate::zangfx_test_single! { arg_root_sig_from_reflection, $driver }
 
         $crate::zangfx_test_single! { cmdqueue_create, $driver }
         $crate::zangfx_test_single! { #[should_panic] cmdqueue_create_fail_missing_queue_family, $driver }
+        $crate::zangfx_test_single! { #[should_panic] cmdqueue_create_fail_invalid_queue_family, $driver }
+        $crate::zangfx_test_single! { cmdqueue_create_priority, $driver }
+        $crate::zangfx_test_single! { cmdqueue_queue_families, $driver }
+        $crate::zangfx_test_single! { #[should_panic] cmdqueue_queue_family_caps_fail_out_of_range, $driver }
         $crate::zangfx_test_single! { cmdqueue_create_buffer, $driver }
         $crate::zangfx_test_single! { cmdqueue_create_encoder, $driver }
+        $crate::zangfx_test_single! { #[should_panic] cmdqueue_create_encoder_fail_unsupported_caps, $driver }
         $crate::zangfx_test_single! { cmdqueue_buffer_noop_completes, $driver }
         $crate::zangfx_test_single! { cmdqueue_buffer_noop_completes_dropped_soon, $driver }
         $crate::zangfx_test_single! { cmdqueue_buffer_noop_multiple_completes, $driver }
         $crate::zangfx_test_single! { cmdqueue_buffer_fence_update_wait_completes, $driver }
+        $crate::zangfx_test_single! { cmdqueue_wait_idle_empty, $driver }
+        $crate::zangfx_test_single! { cmdqueue_wait_idle_drains, $driver }
 
         $crate::zangfx_test_single! { heap_dynamic_create, $driver }
         $crate::zangfx_test_single! { #[should_panic] heap_dynamic_create_fail_zero_size, $driver }
@@ -105,6 +119,16 @@ macro_rules! zangfx_generate_backend_tests {
         $crate::zangfx_test_single! { compute_conv1_indirect, $driver }
 
         $crate::zangfx_test_single! { render_null, $driver }
+
+        $crate::zangfx_test_single! { query_pool_create, $driver }
+        $crate::zangfx_test_single! { query_occlusion_empty, $driver }
+        $crate::zangfx_test_single! { #[should_panic] query_begin_nested, $driver }
+        $crate::zangfx_test_single! { #[should_panic] query_end_without_begin, $driver }
+        $crate::zangfx_test_single! { #[should_panic] query_end_mismatched, $driver }
+        $crate::zangfx_test_single! { #[should_panic] query_begin_out_of_range, $driver }
+        $crate::zangfx_test_single! { #[should_panic] query_reset_out_of_range, $driver }
+
+        $crate::zangfx_test_single! { debug_live_objects_report, $driver }
     }
 }
 
@@ -127,6 +151,9 @@ pub fn create_device<T: TestDriver>(driver: T) {
 mod arg_table;
 pub use self::arg_table::*;
 
+mod caps;
+pub use self::caps::*;
+
 mod cmdqueue;
 pub use self::cmdqueue::*;
 
@@ -150,3 +177,9 @@ pub use self::compute_conv1::*;
 
 mod render_null;
 pub use self::render_null::*;
+
+mod query;
+pub use self::query::*;
+
+mod debug;
+pub use self::debug::*;
@@ -0,0 +1,231 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use super::{utils, TestDriver};
+use flags_macro::flags;
+use volatile_view::prelude::*;
+use zangfx_base as gfx;
+use zangfx_base::prelude::*;
+use zangfx_utils::prelude::*;
+
+/// Create a render target table with a single color target, which can be used
+/// to encode render commands without drawing anything.
+fn new_render_target_table(
+    device: &gfx::DeviceRef,
+    queue: &gfx::CmdQueueRef,
+) -> (gfx::RenderTargetTableRef, gfx::ImageRef) {
+    let pass = {
+        let mut builder = device.build_render_pass();
+        builder.target(0).set_format(<u8>::as_rgba_norm());
+        builder.subpass_color_targets(&[Some(0)]);
+        builder.build().unwrap()
+    };
+
+    let image = device
+        .build_image()
+        .extents(&[16, 16])
+        .format(<u8>::as_rgba_norm())
+        .usage(gfx::ImageUsageFlags::RENDER)
+        .queue(queue)
+        .build()
+        .unwrap();
+
+    let valid_memory_types = image.get_memory_req().unwrap().memory_types;
+    let memory_type = utils::choose_memory_type(
+        device,
+        valid_memory_types,
+        gfx::MemoryTypeCapsFlags::DEVICE_LOCAL,
+        gfx::MemoryTypeCapsFlags::DEVICE_LOCAL,
+    );
+    assert!(device
+        .global_heap(memory_type)
+        .bind((&image).into())
+        .unwrap());
+
+    let rtt = {
+        let mut builder = device.build_render_target_table();
+        builder.target(0, &image);
+        builder
+            .render_pass(&pass)
+            .extents(&[16, 16])
+            .build()
+            .unwrap()
+    };
+
+    (rtt, image)
+}
+
+fn new_occlusion_query_pool(device: &gfx::DeviceRef, len: gfx::QueryIndex) -> gfx::QueryPoolRef {
+    device
+        .build_query_pool()
+        .query_type(gfx::QueryType::Occlusion)
+        .len(len)
+        .build()
+        .unwrap()
+}
+
+pub fn query_pool_create<T: TestDriver>(driver: T) {
+    driver.for_each_device(&mut |device| {
+        println!("- Creating an occlusion query pool");
+        let pool = new_occlusion_query_pool(device, 4);
+        assert_eq!(pool.query_type(), gfx::QueryType::Occlusion);
+        assert_eq!(pool.len(), 4);
+
+        let limits = device.caps().limits();
+
+        if limits.supports_timestamp_query {
+            println!("- Creating a timestamp query pool");
+            device
+                .build_query_pool()
+                .query_type(gfx::QueryType::Timestamp)
+                .len(4)
+                .build()
+                .unwrap();
+        }
+
+        if limits.supports_pipeline_stats_query {
+            println!("- Creating a pipeline statistics query pool");
+            let pool = device
+                .build_query_pool()
+                .query_type(gfx::QueryType::PipelineStats(flags![
+                    gfx::PipelineStatsFlags::{INPUT_VERTICES | FRAGMENT_INVOCATIONS}
+                ]))
+                .len(4)
+                .build()
+                .unwrap();
+            assert_eq!(pool.query_type().num_values(), 2);
+        }
+    });
+}
+
+// Perform an occlusion query without drawing anything.
+pub fn query_occlusion_empty<T: TestDriver>(driver: T) {
+    driver.for_each_render_queue(&mut |device, qf| {
+        println!("- Creating a command queue");
+        let queue = device.build_cmd_queue().queue_family(qf).build().unwrap();
+
+        println!("- Creating a render target table");
+        let (rtt, _image) = new_render_target_table(device, &queue);
+
+        println!("- Creating a query pool");
+        let pool = new_occlusion_query_pool(device, 2);
+
+        println!("- Creating a buffer");
+        let buffer1 = device
+            .build_buffer()
+            .size(16)
+            .usage(gfx::BufferUsageFlags::COPY_WRITE)
+            .queue(&queue)
+            .build()
+            .unwrap();
+
+        let valid_memory_types = buffer1.get_memory_req().unwrap().memory_types;
+        let memory_type = utils::choose_memory_type(
+            device,
+            valid_memory_types,
+            flags![gfx::MemoryTypeCapsFlags::{HOST_VISIBLE | HOST_COHERENT}],
+            flags![gfx::MemoryTypeCapsFlags::{HOST_VISIBLE | HOST_COHERENT}],
+        );
+        device
+            .global_heap(memory_type)
+            .bind((&buffer1).into())
+            .unwrap();
+
+        let buffer1_view = buffer1.as_bytes_volatile();
+        for x in buffer1_view {
+            x.store(0xff);
+        }
+
+        println!("- Creating a command buffer");
+        let mut buffer = queue.new_cmd_buffer().unwrap();
+
+        println!("- Encoding the command buffer");
+        buffer.encode_copy().reset_queries(&pool, 0..2);
+        {
+            let e = buffer.encode_render(&rtt);
+            e.begin_query(&pool, 0);
+            e.end_query(&pool, 0);
+            e.begin_query(&pool, 1);
+            e.end_query(&pool, 1);
+        }
+        buffer
+            .encode_copy()
+            .copy_query_results(&pool, 0..2, &buffer1, 0, 8);
+        buffer.host_barrier(gfx::AccessTypeFlags::COPY_WRITE, &[(0..16, &buffer1)]);
+
+        println!("- Installing a completion handler");
+        let awaiter = utils::CmdBufferAwaiter::new(&mut *buffer);
+
+        println!("- Commiting the command buffer");
+        buffer.commit().unwrap();
+
+        println!("- Flushing the command queue");
+        queue.flush();
+
+        println!("- Waiting for completion");
+        awaiter.wait_until_completed();
+
+        println!("- Retrieving the results from the host");
+        let mut results = [!0u64; 2];
+        pool.get_results(0..2, &mut results).unwrap();
+        assert_eq!(results, [0, 0]);
+
+        println!("- Comparing the copied results");
+        let ret: Vec<_> = buffer1_view.load();
+        assert_eq!(ret[..], [0u8; 16][..]);
+    });
+}
+
+/// Encode render commands using an occlusion query pool of length 2.
+fn encode_render_with_query<T: TestDriver>(
+    driver: T,
+    f: impl Fn(&mut dyn gfx::RenderCmdEncoder, &gfx::QueryPoolRef),
+) {
+    driver.for_each_render_queue(&mut |device, qf| {
+        let queue = device.build_cmd_queue().queue_family(qf).build().unwrap();
+        let (rtt, _image) = new_render_target_table(device, &queue);
+        let pool = new_occlusion_query_pool(device, 2);
+
+        let mut buffer = queue.new_cmd_buffer().unwrap();
+        buffer.encode_copy().reset_queries(&pool, 0..2);
+        f(buffer.encode_render(&rtt), &pool);
+    });
+}
+
+pub fn query_begin_nested<T: TestDriver>(driver: T) {
+    encode_render_with_query(driver, |e, pool| {
+        e.begin_query(pool, 0);
+        e.begin_query(pool, 1);
+    });
+}
+
+pub fn query_end_without_begin<T: TestDriver>(driver: T) {
+    encode_render_with_query(driver, |e, pool| {
+        e.end_query(pool, 0);
+    });
+}
+
+pub fn query_end_mismatched<T: TestDriver>(driver: T) {
+    encode_render_with_query(driver, |e, pool| {
+        e.begin_query(pool, 0);
+        e.end_query(pool, 1);
+    });
+}
+
+pub fn query_begin_out_of_range<T: TestDriver>(driver: T) {
+    encode_render_with_query(driver, |e, pool| {
+        e.begin_query(pool, 2);
+    });
+}
+
+pub fn query_reset_out_of_range<T: TestDriver>(driver: T) {
+    driver.for_each_copy_queue(&mut |device, qf| {
+        let queue = device.build_cmd_queue().queue_family(qf).build().unwrap();
+        let pool = new_occlusion_query_pool(device, 2);
+
+        let mut buffer = queue.new_cmd_buffer().unwrap();
+        buffer.encode_copy().reset_queries(&pool, 1..3);
+    });
+}
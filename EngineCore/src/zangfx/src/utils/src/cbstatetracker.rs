@@ -10,6 +10,10 @@ use std::time::Duration;
 use zangfx_base as base;
 
 /// Tracks the execution state of a command buffer.
+///
+/// The state is shared via an `Arc` and protected by a mutex, so it can be
+/// queried from any thread (e.g., a monitoring thread) without having access
+/// to the command queue or the command buffer.
 #[derive(Debug)]
 pub struct CbStateTracker {
     state: Arc<State>,
@@ -43,10 +47,14 @@ impl CbStateTracker {
         Self { state }
     }
 
+    /// Check if the command buffer has completed its execution without
+    /// blocking.
     pub fn is_completed(&self) -> bool {
         self.state.done.lock().unwrap().is_some()
     }
 
+    /// Wait until the command buffer completes its execution and return the
+    /// result.
     pub fn wait(&self) -> &base::Result<()> {
         let mut done = self.state.done.lock().unwrap();
         while done.is_none() {
@@ -58,6 +66,11 @@ impl CbStateTracker {
         unsafe { &*(result_ref as *const _) }
     }
 
+    /// Wait until the command buffer completes its execution or `timeout`
+    /// elapses.
+    ///
+    /// Returns `Err(WaitTimeoutError::Timeout)` if the command buffer did not
+    /// complete within `timeout`.
     pub fn wait_timeout(&self, timeout: Duration) -> Result<&base::Result<()>, WaitTimeoutError> {
         let mut done = self.state.done.lock().unwrap();
         while done.is_none() {
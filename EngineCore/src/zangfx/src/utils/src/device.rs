@@ -277,6 +277,68 @@ pub trait DeviceUtils: base::Device {
             .build()?;
         Ok(image.get_memory_req()?.memory_types)
     }
+
+    /// Create a root signature and argument table signatures for the
+    /// arguments used by the given shader modules.
+    ///
+    /// The reflections are merged by [`zangfx_base::ShaderReflection::merge`].
+    /// If it fails, an error of kind [`zangfx_base::ErrorKind::Other`]
+    /// wrapping [`zangfx_base::ReflectionError`] is returned.
+    /// Empty argument table signatures are created for the argument table
+    /// indices not used by any modules so that the root signature is tightly
+    /// arranged. The returned `Vec` contains the argument table signatures in
+    /// the ascending order of argument table indices.
+    ///
+    /// # Examples
+    ///
+    ///     # use zangfx_base::*;
+    ///     # use zangfx_utils::DeviceUtils;
+    ///     # fn test(
+    ///     #     device: &Device,
+    ///     #     vertex_spirv: &[u32],
+    ///     #     fragment_spirv: &[u32],
+    ///     # ) -> Result<()> {
+    ///     let (root_sig, arg_table_sigs) = device.build_root_sig_from_reflection(&[
+    ///         &spirv_reflect(vertex_spirv),
+    ///         &spirv_reflect(fragment_spirv),
+    ///     ])?;
+    ///     # Ok(())
+    ///     # }
+    ///
+    fn build_root_sig_from_reflection(
+        &self,
+        reflections: &[&base::ShaderReflection],
+    ) -> Result<(base::RootSigRef, Vec<base::ArgTableSigRef>)> {
+        let mut merged = base::ShaderReflection::default();
+        for reflection in reflections.iter() {
+            merged
+                .merge(reflection)
+                .map_err(|e| Error::with_detail(base::ErrorKind::Other, e))?;
+        }
+
+        // `args` is sorted by `(table, index)`
+        let num_tables = merged.args.last().map(|arg| arg.table + 1).unwrap_or(0);
+
+        let mut arg_table_sigs = Vec::with_capacity(num_tables);
+        for table in 0..num_tables {
+            let mut builder = self.build_arg_table_sig();
+            for arg in merged.args.iter().filter(|arg| arg.table == table) {
+                builder
+                    .arg(arg.index, arg.ty)
+                    .set_len(arg.len)
+                    .set_stages(arg.stages);
+            }
+            arg_table_sigs.push(builder.build()?);
+        }
+
+        let mut builder = self.build_root_sig();
+        for (i, arg_table_sig) in arg_table_sigs.iter().enumerate() {
+            builder.arg_table(i, arg_table_sig);
+        }
+        let root_sig = builder.build()?;
+
+        Ok((root_sig, arg_table_sigs))
+    }
 }
 
 impl<T: base::Device + ?Sized> DeviceUtils for T {}
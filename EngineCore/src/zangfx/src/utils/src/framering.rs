@@ -0,0 +1,254 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+//! Manages per-frame resources with a bounded number of frames in flight.
+//!
+//! Applications usually keep a few sets of per-frame resources (command
+//! buffers, argument pools, staging buffers, etc.) so that the CPU can record
+//! the next frame while the device is still processing the previous ones.
+//! Before reusing a set, the application has to wait until the device is done
+//! with the frame that used it last time. [`FrameRing`] implements this
+//! bookkeeping.
+//!
+//! The completion of a frame is represented by a [`FrameFence`]. ZanGFX does
+//! not provide a host-waitable synchronization primitive, so this module
+//! provides [`CmdBufferFence`], which is built on top of
+//! `CmdBuffer::on_complete`. [`WaitSet`] can be used as a fence of a frame
+//! consisting of multiple command buffers.
+//!
+//! [`WaitSet`]: crate::waitset::WaitSet
+//!
+//! # Examples
+//!
+//!     # use zangfx_base::{CmdQueueRef, Result};
+//!     # fn test(queue: &CmdQueueRef) -> Result<()> {
+//!     use zangfx_utils::framering::{CmdBufferFence, FrameRing};
+//!
+//!     // Triple buffering. The per-frame data is a frame counter here, but
+//!     // would be something like a staging buffer in a real application.
+//!     let mut ring = FrameRing::new(3, || Ok(0u64))?;
+//!
+//!     for _ in 0..10 {
+//!         // Blocks if the slot is still used by the frame submitted three
+//!         // frames ago
+//!         let slot = ring.begin_frame();
+//!         **slot += 1;
+//!
+//!         let mut buffer = queue.new_cmd_buffer()?;
+//!         let fence = CmdBufferFence::new(&mut *buffer);
+//!         buffer.commit()?;
+//!
+//!         ring.end_frame(fence);
+//!     }
+//!
+//!     queue.flush();
+//!     ring.wait_idle_all();
+//!     # Ok(())
+//!     # }
+//!
+use parking_lot::{Condvar, Mutex};
+use std::{collections::VecDeque, fmt, ops, sync::Arc};
+use zangfx_base as base;
+
+use crate::waitset::WaitSet;
+
+/// An object the host can wait on to know the completion of a frame.
+pub trait FrameFence {
+    /// Block the current thread until the frame associated with the fence
+    /// completes.
+    fn wait(&mut self);
+}
+
+/// A [`FrameFence`] signaled upon the completion of a command buffer.
+#[derive(Debug, Clone)]
+pub struct CmdBufferFence {
+    shared: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl CmdBufferFence {
+    /// Construct a `CmdBufferFence` associated with a given command buffer.
+    ///
+    /// This method is implemented using `CmdBuffer::on_complete`, so the valid
+    /// usages of that method must be obeyed.
+    pub fn new(cmd_buffer: &mut dyn base::CmdBuffer) -> Self {
+        let shared = Arc::new((Mutex::new(false), Condvar::new()));
+
+        let shared_2 = Arc::clone(&shared);
+        cmd_buffer.on_complete(Box::new(move |_| {
+            *shared_2.0.lock() = true;
+            shared_2.1.notify_all();
+        }));
+
+        Self { shared }
+    }
+
+    /// Return `true` if the command buffer has completed.
+    pub fn is_signaled(&self) -> bool {
+        *self.shared.0.lock()
+    }
+}
+
+impl FrameFence for CmdBufferFence {
+    fn wait(&mut self) {
+        let mut signaled = self.shared.0.lock();
+        while !*signaled {
+            self.shared.1.wait(&mut signaled);
+        }
+    }
+}
+
+impl FrameFence for WaitSet {
+    fn wait(&mut self) {
+        self.wait_all(None);
+    }
+}
+
+/// Per-frame data managed by [`FrameRing`].
+///
+/// `FrameSlot<T, _>` dereferences to `T`.
+#[derive(Debug)]
+pub struct FrameSlot<T, F> {
+    data: T,
+    /// The fence of the last frame that used this slot and has not been
+    /// waited on yet.
+    fence: Option<F>,
+}
+
+impl<T, F> ops::Deref for FrameSlot<T, F> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.data
+    }
+}
+
+impl<T, F> ops::DerefMut for FrameSlot<T, F> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.data
+    }
+}
+
+impl<T, F: FrameFence> FrameSlot<T, F> {
+    fn wait(&mut self) {
+        if let Some(mut fence) = self.fence.take() {
+            fence.wait();
+        }
+    }
+}
+
+/// Maintains `depth` sets of per-frame data of type `T`, allowing at most
+/// `depth` frames to be in flight at the same time.
+///
+/// See the [module-level documentation](index.html) for details.
+///
+/// `FrameRing` waits for all frames in flight to complete when dropped, so
+/// that per-frame data is never released while the device is still using it.
+pub struct FrameRing<T, F: FrameFence = CmdBufferFence> {
+    /// The slots in the order of use. The front slot is used by the next
+    /// frame, and is the one that was used by the oldest frame.
+    slots: VecDeque<FrameSlot<T, F>>,
+    factory: Box<dyn FnMut() -> base::Result<T> + Send>,
+    /// `true` between `begin_frame` and `end_frame`.
+    in_frame: bool,
+}
+
+impl<T: fmt::Debug, F: FrameFence + fmt::Debug> fmt::Debug for FrameRing<T, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FrameRing")
+            .field("slots", &self.slots)
+            .field("in_frame", &self.in_frame)
+            .finish()
+    }
+}
+
+impl<T, F: FrameFence> FrameRing<T, F> {
+    /// Construct a `FrameRing` with `depth` slots. `factory` is called to
+    /// create the per-frame data of each slot.
+    ///
+    /// Panics if `depth` is zero.
+    pub fn new(
+        depth: usize,
+        factory: impl FnMut() -> base::Result<T> + Send + 'static,
+    ) -> base::Result<Self> {
+        let mut this = Self {
+            slots: VecDeque::with_capacity(depth),
+            factory: Box::new(factory),
+            in_frame: false,
+        };
+        this.resize(depth)?;
+        Ok(this)
+    }
+
+    /// Get the number of slots, i.e., the maximum number of frames in flight.
+    pub fn depth(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Start a new frame and return the slot to be used by the frame.
+    ///
+    /// Blocks until the frame that used the slot last time (i.e., the one
+    /// started `depth` frames ago) completes.
+    ///
+    /// Panics if the previous frame was not ended by [`end_frame`].
+    ///
+    /// [`end_frame`]: FrameRing::end_frame
+    pub fn begin_frame(&mut self) -> &mut FrameSlot<T, F> {
+        assert!(!self.in_frame, "the previous frame has not ended yet");
+        self.in_frame = true;
+
+        let slot = self.slots.front_mut().unwrap();
+        slot.wait();
+        slot
+    }
+
+    /// End the current frame, associating `fence` with the frame's slot.
+    ///
+    /// Panics if there is no current frame.
+    pub fn end_frame(&mut self, fence: F) {
+        assert!(self.in_frame, "no frame has begun");
+        self.in_frame = false;
+
+        let mut slot = self.slots.pop_front().unwrap();
+        debug_assert!(slot.fence.is_none());
+        slot.fence = Some(fence);
+        self.slots.push_back(slot);
+    }
+
+    /// Change the number of slots.
+    ///
+    /// New slots are used first, so growing does not block the next frame.
+    /// Shrinking removes the slots of the oldest frames, waiting for them to
+    /// complete.
+    ///
+    /// Panics if `depth` is zero or if there is a current frame.
+    pub fn resize(&mut self, depth: usize) -> base::Result<()> {
+        assert_ne!(depth, 0, "depth must not be zero");
+        assert!(!self.in_frame, "cannot resize during a frame");
+
+        while self.slots.len() > depth {
+            self.slots.pop_front().unwrap().wait();
+        }
+
+        while self.slots.len() < depth {
+            let data = (self.factory)()?;
+            self.slots.push_front(FrameSlot { data, fence: None });
+        }
+
+        Ok(())
+    }
+
+    /// Wait for all frames in flight to complete.
+    pub fn wait_idle_all(&mut self) {
+        for slot in self.slots.iter_mut() {
+            slot.wait();
+        }
+    }
+}
+
+impl<T, F: FrameFence> Drop for FrameRing<T, F> {
+    fn drop(&mut self) {
+        self.wait_idle_all();
+    }
+}
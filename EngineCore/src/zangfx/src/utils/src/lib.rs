@@ -13,10 +13,13 @@ pub mod asyncheap;
 mod buffer;
 pub mod cbstatetracker;
 mod device;
+pub mod framering;
 pub mod futuresapi;
 pub mod streamer;
+pub mod texture;
 pub mod uploader;
 mod uploaderutils;
+pub mod waitset;
 
 pub use crate::buffer::*;
 #[doc(no_inline)]
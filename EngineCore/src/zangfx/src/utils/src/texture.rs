@@ -0,0 +1,504 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+//! Uploads mipmapped 2D textures from the host memory.
+//!
+//! [`TextureUploader`] creates an image, fills it with the supplied pixel data
+//! via a temporary staging buffer, and submits the copy commands to a command
+//! queue. Image layout transitions are handled by ZanGFX's automatic image
+//! layout tracking, so the created image can be used like any other image
+//! once the returned fence is waited on.
+//!
+//! The staging buffer layout is computed by pure functions ([`mip_extents`],
+//! [`StagingLayout`], etc.) which are exposed for applications that want to
+//! encode the copy commands by themselves.
+//!
+//! # Examples
+//!
+//!     use zangfx_base::*;
+//!     use zangfx_utils::texture::{TextureDesc, TextureUploader};
+//!     # fn test(device: DeviceRef, queue: CmdQueueRef) -> Result<()> {
+//!     let mip0 = vec![0u8; 4 * 4 * 4];
+//!     let mip1 = vec![0u8; 2 * 2 * 4];
+//!     let mip2 = vec![0u8; 1 * 1 * 4];
+//!
+//!     let uploader = TextureUploader::new(device, queue.clone());
+//!     let desc = TextureDesc::new(ImageFormat::SrgbRgba8, [4, 4]).with_full_mip_chain();
+//!     let (image, completion) = uploader.upload(&desc, &[&mip0, &mip1, &mip2])?;
+//!
+//!     queue.flush();
+//!     # Ok(())
+//!     # }
+//!
+use zangfx_base::{self as base, DeviceSize, Result};
+
+use crate::{CmdBufferFutureExt, CmdBufferResult, DeviceUtils};
+
+/// The maximum value of [`BufferImageRange::row_stride`].
+///
+/// [`BufferImageRange::row_stride`]: zangfx_base::BufferImageRange::row_stride
+pub const MAX_ROW_STRIDE: DeviceSize = 32767;
+
+/// Compute the extents of a given mipmap level.
+///
+/// Each dimension is halved (rounding down) per level and clamped to `1`.
+pub fn mip_extents(extents: [u32; 2], level: u32) -> [u32; 2] {
+    let f = |x: u32| x.checked_shr(level).unwrap_or(0).max(1);
+    [f(extents[0]), f(extents[1])]
+}
+
+/// Compute the number of mipmap levels in a full mipmap chain of an image
+/// with given extents, i.e., `floor(log2(max(extents))) + 1`.
+///
+/// # Panics
+///
+/// Panics if both of the extents are zero.
+pub fn max_num_mip_levels(extents: [u32; 2]) -> u32 {
+    let max = extents[0].max(extents[1]);
+    assert_ne!(max, 0, "extents must not be zero");
+    32 - max.leading_zeros()
+}
+
+/// Round `x` up to the nearest multiple of `align`.
+///
+/// # Panics
+///
+/// Panics if `align` is zero.
+pub fn align_up(x: DeviceSize, align: DeviceSize) -> DeviceSize {
+    match x % align {
+        0 => x,
+        rem => x + (align - rem),
+    }
+}
+
+/// Compute the alignment requirement (in bytes) of the offset of an image
+/// data in a staging buffer.
+///
+/// As per [`BufferImageRange::offset`], the offset must be a multiple of `4`
+/// and the pixel size. This function returns their least common multiple.
+///
+/// [`BufferImageRange::offset`]: zangfx_base::BufferImageRange::offset
+pub fn staging_offset_align(bytes_per_pixel: DeviceSize) -> DeviceSize {
+    assert_ne!(bytes_per_pixel, 0);
+    let (mut a, mut b) = (bytes_per_pixel, 4);
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    bytes_per_pixel / a * 4
+}
+
+/// The number of bytes between adjacent rows of a tightly packed image data.
+pub fn row_pitch(bytes_per_pixel: DeviceSize, width: u32) -> DeviceSize {
+    bytes_per_pixel * width as DeviceSize
+}
+
+/// The location of a single 2D image data in a staging buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StagingRegion {
+    /// The value to be passed to [`copy_buffer_to_image`].
+    ///
+    /// [`copy_buffer_to_image`]: zangfx_base::CopyCmdEncoder::copy_buffer_to_image
+    pub range: base::BufferImageRange,
+
+    /// The extents of the image data.
+    pub extents: [u32; 2],
+
+    /// The number of bytes occupied by the image data, starting at
+    /// `range.offset`.
+    pub len: DeviceSize,
+}
+
+/// The layout of a staging buffer containing one or more tightly packed 2D
+/// image data.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StagingLayout {
+    /// The regions in the staging buffer, in the order they were supplied.
+    pub regions: Vec<StagingRegion>,
+
+    /// The required size of the staging buffer, measured in bytes.
+    pub size: DeviceSize,
+}
+
+impl StagingLayout {
+    /// Compute the layout of a staging buffer for image data of given extents.
+    ///
+    /// Each region starts at an offset aligned by [`staging_offset_align`].
+    /// Rows are tightly packed (i.e., `row_stride` is equal to the width).
+    ///
+    /// # Panics
+    ///
+    ///  - `bytes_per_pixel` is zero.
+    ///  - One of the extents is wider than [`MAX_ROW_STRIDE`].
+    ///
+    pub fn new(bytes_per_pixel: usize, extents: impl IntoIterator<Item = [u32; 2]>) -> Self {
+        let bytes_per_pixel = bytes_per_pixel as DeviceSize;
+        let align = staging_offset_align(bytes_per_pixel);
+
+        let mut size = 0;
+        let regions = extents
+            .into_iter()
+            .map(|extents| {
+                let row_stride = extents[0] as DeviceSize;
+                assert!(
+                    row_stride <= MAX_ROW_STRIDE,
+                    "image width {} exceeds the maximum row stride",
+                    row_stride
+                );
+
+                let offset = align_up(size, align);
+                let len = row_pitch(bytes_per_pixel, extents[0]) * extents[1] as DeviceSize;
+                size = offset + len;
+
+                StagingRegion {
+                    range: base::BufferImageRange {
+                        offset,
+                        row_stride,
+                        plane_stride: row_stride * extents[1] as DeviceSize,
+                    },
+                    extents,
+                    len,
+                }
+            })
+            .collect();
+
+        Self { regions, size }
+    }
+}
+
+/// Describes a 2D texture created by [`TextureUploader`].
+#[derive(Debug, Clone, Copy)]
+pub struct TextureDesc {
+    pub format: base::ImageFormat,
+    pub extents: [u32; 2],
+    pub num_mip_levels: u32,
+
+    /// The usage of the image. `COPY_WRITE` is implicitly added.
+    pub usage: base::ImageUsageFlags,
+}
+
+impl TextureDesc {
+    /// Construct a `TextureDesc` with a single mipmap level and the default
+    /// image usage.
+    pub fn new(format: base::ImageFormat, extents: [u32; 2]) -> Self {
+        Self {
+            format,
+            extents,
+            num_mip_levels: 1,
+            usage: base::ImageUsageFlags::default(),
+        }
+    }
+
+    /// Return `self` with a new value for the `num_mip_levels` field.
+    pub fn with_num_mip_levels(self, num_mip_levels: u32) -> Self {
+        Self {
+            num_mip_levels,
+            ..self
+        }
+    }
+
+    /// Return `self` with `num_mip_levels` set to the length of the full
+    /// mipmap chain.
+    pub fn with_full_mip_chain(self) -> Self {
+        self.with_num_mip_levels(max_num_mip_levels(self.extents))
+    }
+
+    /// Return `self` with a new value for the `usage` field.
+    pub fn with_usage(self, usage: base::ImageUsageFlags) -> Self {
+        Self { usage, ..self }
+    }
+
+    /// Get the number of bytes per pixel.
+    pub fn bytes_per_pixel(&self) -> usize {
+        self.format.size_class().num_bytes_per_pixel()
+    }
+
+    /// Get the extents of a given mipmap level.
+    pub fn mip_extents(&self, level: u32) -> [u32; 2] {
+        mip_extents(self.extents, level)
+    }
+}
+
+/// Specifies a rectangular region in a mipmap level of a 2D image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureRegion {
+    pub mip_level: u32,
+    pub origin: [u32; 2],
+    pub extents: [u32; 2],
+}
+
+/// Represents an ongoing upload operation.
+#[derive(Debug)]
+pub struct UploadCompletion {
+    /// The fence updated by the copy commands. Wait on this fence before
+    /// accessing the image on the same queue.
+    pub fence: base::FenceRef,
+
+    /// The result of the command buffer execution.
+    pub result: CmdBufferResult,
+}
+
+/// Creates and updates mipmapped 2D textures using CPU-side data.
+///
+/// Each method call allocates a staging buffer from a global heap and submits
+/// a single command buffer. The staging buffer is released upon the
+/// completion of the command buffer. The queue is not flushed by
+/// `TextureUploader`.
+///
+/// See [the module-level documentation](index.html) for an example.
+#[derive(Debug, Clone)]
+pub struct TextureUploader {
+    device: base::DeviceRef,
+    queue: base::CmdQueueRef,
+    dst_queue_family: Option<base::QueueFamily>,
+}
+
+impl TextureUploader {
+    /// Construct a `TextureUploader`.
+    pub fn new(device: base::DeviceRef, queue: base::CmdQueueRef) -> Self {
+        Self {
+            device,
+            queue,
+            dst_queue_family: None,
+        }
+    }
+
+    /// Return `self` with a new value for the destination queue family.
+    ///
+    /// If set, the uploaded images are released to the specified queue family
+    /// after the copy commands, using the following `QueueOwnershipTransfer`
+    /// (which the receiving end must use to acquire the image):
+    ///
+    /// ```text
+    /// QueueOwnershipTransfer::Image {
+    ///     image, src_layout: CopyWrite, dst_layout: Shader,
+    ///     range: ImageSubRange { mip_levels: None, layers: None },
+    /// }
+    /// ```
+    ///
+    /// [`update_region`] acquires the image back from the queue family before
+    /// the copy commands, with `src_layout` and `dst_layout` swapped.
+    ///
+    /// [`update_region`]: TextureUploader::update_region
+    pub fn with_dst_queue_family(self, dst_queue_family: Option<base::QueueFamily>) -> Self {
+        Self {
+            dst_queue_family,
+            ..self
+        }
+    }
+
+    pub fn device(&self) -> &base::DeviceRef {
+        &self.device
+    }
+
+    pub fn queue(&self) -> &base::CmdQueueRef {
+        &self.queue
+    }
+
+    /// Create an image and upload the contents of all of its mipmap levels.
+    ///
+    /// `mips[i]` contains the tightly packed pixel data of the mipmap level
+    /// `i`.
+    ///
+    /// # Panics
+    ///
+    ///  - `mips.len()` is not equal to `desc.num_mip_levels`.
+    ///  - The length of `mips[i]` does not match the size of the mipmap level
+    ///    `i`.
+    ///
+    pub fn upload(
+        &self,
+        desc: &TextureDesc,
+        mips: &[&[u8]],
+    ) -> Result<(base::ImageRef, UploadCompletion)> {
+        assert_eq!(
+            mips.len(),
+            desc.num_mip_levels as usize,
+            "the number of supplied mipmap levels is wrong"
+        );
+
+        let image = self
+            .device
+            .build_image()
+            .queue(&self.queue)
+            .extents(&desc.extents)
+            .num_mip_levels(desc.num_mip_levels)
+            .format(desc.format)
+            .usage(desc.usage | base::ImageUsageFlags::COPY_WRITE)
+            .build()?;
+
+        let memory_type = self
+            .device
+            .try_choose_memory_type_private(&image)?
+            .unwrap();
+        if !self.device.global_heap(memory_type).bind((&image).into())? {
+            return Err(base::ErrorKind::OutOfDeviceMemory.into());
+        }
+
+        let regions: Vec<_> = (mips.iter().enumerate())
+            .map(|(level, data)| {
+                let region = TextureRegion {
+                    mip_level: level as u32,
+                    origin: [0, 0],
+                    extents: desc.mip_extents(level as u32),
+                };
+                (region, *data)
+            })
+            .collect();
+
+        let completion = self.submit(&image, desc.bytes_per_pixel(), &regions, &[], false)?;
+
+        Ok((image, completion))
+    }
+
+    /// Update a rectangular region of an existing image.
+    ///
+    /// `data` contains the tightly packed pixel data of the region.
+    /// `wait_fences` specifies the fences to wait on before the copy commands
+    /// (e.g., ones updated by passes reading from the image).
+    ///
+    /// # Panics
+    ///
+    ///  - The length of `data` does not match the size of the region.
+    ///
+    /// # Valid Usage
+    ///
+    ///  - `image` must be associated with the queue of this `TextureUploader`.
+    ///  - `image` must have the format `format` and the `COPY_WRITE` usage.
+    ///  - The region must be within the bounds of the mipmap level.
+    ///  - If the destination queue family is set, the ownership of `image` must
+    ///    have been released to this `TextureUploader`'s queue family.
+    ///
+    pub fn update_region(
+        &self,
+        image: &base::ImageRef,
+        format: base::ImageFormat,
+        region: &TextureRegion,
+        data: &[u8],
+        wait_fences: &[&base::FenceRef],
+    ) -> Result<UploadCompletion> {
+        let bytes_per_pixel = format.size_class().num_bytes_per_pixel();
+        self.submit(image, bytes_per_pixel, &[(*region, data)], wait_fences, true)
+    }
+
+    fn submit(
+        &self,
+        image: &base::ImageRef,
+        bytes_per_pixel: usize,
+        regions: &[(TextureRegion, &[u8])],
+        wait_fences: &[&base::FenceRef],
+        acquire: bool,
+    ) -> Result<UploadCompletion> {
+        let layout = StagingLayout::new(bytes_per_pixel, regions.iter().map(|r| r.0.extents));
+
+        for ((region, data), staging) in regions.iter().zip(layout.regions.iter()) {
+            assert_eq!(
+                data.len() as DeviceSize,
+                staging.len,
+                "the size of the pixel data of mipmap level {} is wrong",
+                region.mip_level
+            );
+        }
+
+        // Allocate and fill the staging buffer
+        let buffer = self
+            .device
+            .build_buffer()
+            .size(layout.size.max(1))
+            .usage(base::BufferUsageFlags::COPY_READ)
+            .queue(&self.queue)
+            .build()?;
+
+        let memory_type = self
+            .device
+            .try_choose_memory_type_shared(&buffer)?
+            .unwrap();
+        if !self.device.global_heap(memory_type).bind((&buffer).into())? {
+            return Err(base::ErrorKind::OutOfDeviceMemory.into());
+        }
+
+        {
+            use std::slice::from_raw_parts_mut;
+            let ptr = buffer.as_ptr();
+            let slice = unsafe { from_raw_parts_mut(ptr, layout.size as usize) };
+            for ((_, data), staging) in regions.iter().zip(layout.regions.iter()) {
+                let start = staging.range.offset as usize;
+                slice[start..start + data.len()].copy_from_slice(data);
+            }
+        }
+
+        // Encode commands
+        let ownership_transfer = |src_layout, dst_layout| base::QueueOwnershipTransfer::Image {
+            image,
+            src_layout,
+            dst_layout,
+            range: base::ImageSubRange {
+                mip_levels: None,
+                layers: None,
+            },
+        };
+
+        let mut cmd_buffer = self.queue.new_cmd_buffer()?;
+        let fence = self.queue.new_fence()?;
+
+        if let Some(family) = self.dst_queue_family.filter(|_| acquire) {
+            cmd_buffer.queue_ownership_acquire(
+                family,
+                base::AccessTypeFlags::COPY_WRITE,
+                &[ownership_transfer(
+                    base::ImageLayout::Shader,
+                    base::ImageLayout::CopyWrite,
+                )],
+            );
+        }
+
+        {
+            let encoder = cmd_buffer.encode_copy();
+            for fence in wait_fences {
+                encoder.wait_fence(fence, base::AccessTypeFlags::COPY_WRITE);
+            }
+
+            for ((region, _), staging) in regions.iter().zip(layout.regions.iter()) {
+                encoder.copy_buffer_to_image(
+                    &buffer,
+                    &staging.range,
+                    image,
+                    base::ImageAspect::Color,
+                    &base::ImageLayerRange {
+                        mip_level: region.mip_level,
+                        layers: 0..1,
+                    },
+                    &region.origin,
+                    &region.extents,
+                );
+            }
+
+            encoder.update_fence(&fence, base::AccessTypeFlags::COPY_WRITE);
+        }
+
+        if let Some(family) = self.dst_queue_family {
+            cmd_buffer.queue_ownership_release(
+                family,
+                base::AccessTypeFlags::COPY_WRITE,
+                &[ownership_transfer(
+                    base::ImageLayout::CopyWrite,
+                    base::ImageLayout::Shader,
+                )],
+            );
+        }
+
+        // Keep the staging buffer alive until the copy commands complete
+        let mut buffer_cell = Some(buffer);
+        cmd_buffer.on_complete(Box::new(move |_| {
+            buffer_cell.take();
+        }));
+
+        let result = cmd_buffer.result();
+        cmd_buffer.commit()?;
+
+        Ok(UploadCompletion { fence, result })
+    }
+}
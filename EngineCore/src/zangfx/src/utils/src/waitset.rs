@@ -0,0 +1,156 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+//! Waits for the completion of any or all of multiple command buffers.
+//!
+//! ZanGFX does not provide a host-waitable synchronization primitive. Instead,
+//! [`WaitSet`] is built on top of `CmdBuffer::on_complete`, which is
+//! implemented by every backend. Each registered command buffer signals a
+//! shared condition variable upon completion, so waiting for any number of
+//! command buffers does not require a thread per command buffer.
+//!
+//! # Examples
+//!
+//!     # use zangfx_base::CmdQueueRef;
+//!     # fn test(queue: &CmdQueueRef) {
+//!     use zangfx_utils::waitset::WaitSet;
+//!     use std::time::Duration;
+//!
+//!     let mut wait_set = WaitSet::new();
+//!
+//!     for token in 0..4 {
+//!         let mut buffer = queue.new_cmd_buffer().unwrap();
+//!         wait_set.add(&mut *buffer, token);
+//!         buffer.commit().unwrap();
+//!     }
+//!     queue.flush();
+//!
+//!     while let Some(token) = wait_set.wait_any(Some(Duration::from_secs(1))) {
+//!         println!("command buffer {} completed", token);
+//!     }
+//!     # }
+//!
+use parking_lot::{Condvar, Mutex};
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use zangfx_base as base;
+
+/// A set of command buffers that can be waited on collectively.
+///
+/// Each command buffer is identified by a token supplied by the application
+/// when it's [added](WaitSet::add).
+#[derive(Debug, Default)]
+pub struct WaitSet {
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+    cv: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The tokens of completed command buffers not retrieved by `wait_any` yet.
+    completed: VecDeque<usize>,
+    /// The number of command buffers that have not completed yet.
+    num_pending: usize,
+}
+
+impl WaitSet {
+    /// Construct an empty `WaitSet`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a command buffer to the set.
+    ///
+    /// `token` is returned by [`wait_any`](WaitSet::wait_any) when the command
+    /// buffer completes. Tokens do not have to be unique.
+    ///
+    /// This method is implemented using `CmdBuffer::on_complete`, so the valid
+    /// usages of that method must be obeyed.
+    pub fn add(&mut self, cmd_buffer: &mut dyn base::CmdBuffer, token: usize) {
+        self.shared.state.lock().num_pending += 1;
+
+        let shared = Arc::clone(&self.shared);
+        cmd_buffer.on_complete(Box::new(move |_| {
+            let mut state = shared.state.lock();
+            state.num_pending -= 1;
+            state.completed.push_back(token);
+            shared.cv.notify_all();
+        }));
+    }
+
+    /// Get the number of command buffers in the set, i.e., those that have not
+    /// completed yet or have not been retrieved by `wait_any` yet.
+    pub fn len(&self) -> usize {
+        let state = self.shared.state.lock();
+        state.num_pending + state.completed.len()
+    }
+
+    /// Return `true` if the set contains no command buffers.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Wait until any of the command buffers in the set completes, and remove
+    /// it from the set.
+    ///
+    /// Returns the token of the completed command buffer. Returns `None` if
+    /// the set is empty or `timeout` has elapsed. `None` for `timeout` means
+    /// the wait never times out.
+    ///
+    /// Command buffers that have already completed are returned first in the
+    /// order of completion, so calling this method repeatedly yields every
+    /// command buffer exactly once.
+    pub fn wait_any(&mut self, timeout: Option<Duration>) -> Option<usize> {
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let mut state = self.shared.state.lock();
+
+        loop {
+            if let Some(token) = state.completed.pop_front() {
+                return Some(token);
+            }
+            if state.num_pending == 0 {
+                return None;
+            }
+
+            if let Some(deadline) = deadline {
+                if self.shared.cv.wait_until(&mut state, deadline).timed_out() {
+                    return state.completed.pop_front();
+                }
+            } else {
+                self.shared.cv.wait(&mut state);
+            }
+        }
+    }
+
+    /// Wait until all command buffers in the set complete.
+    ///
+    /// Returns `false` if `timeout` has elapsed. `None` for `timeout` means
+    /// the wait never times out. The completed command buffers remain in the
+    /// set and can still be retrieved by [`wait_any`](WaitSet::wait_any).
+    pub fn wait_all(&self, timeout: Option<Duration>) -> bool {
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let mut state = self.shared.state.lock();
+
+        while state.num_pending > 0 {
+            if let Some(deadline) = deadline {
+                if self.shared.cv.wait_until(&mut state, deadline).timed_out() {
+                    return state.num_pending == 0;
+                }
+            } else {
+                self.shared.cv.wait(&mut state);
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,171 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use std::sync::{Arc, Mutex};
+
+use zangfx_utils::framering::{FrameFence, FrameRing};
+
+/// A mock fence recording when it is waited on.
+#[derive(Debug)]
+struct Fence {
+    frame: usize,
+    log: Arc<Mutex<Vec<usize>>>,
+}
+
+impl FrameFence for Fence {
+    fn wait(&mut self) {
+        self.log.lock().unwrap().push(self.frame);
+    }
+}
+
+/// Construct a `FrameRing` whose per-frame data is a sequential slot ID.
+fn new_ring(depth: usize) -> FrameRing<usize, Fence> {
+    let mut next_id = 0;
+    FrameRing::new(depth, move || {
+        next_id += 1;
+        Ok(next_id - 1)
+    })
+    .unwrap()
+}
+
+/// Run a frame, returning the slot ID.
+fn run_frame(
+    ring: &mut FrameRing<usize, Fence>,
+    frame: usize,
+    log: &Arc<Mutex<Vec<usize>>>,
+) -> usize {
+    let id = **ring.begin_frame();
+    ring.end_frame(Fence {
+        frame,
+        log: Arc::clone(log),
+    });
+    id
+}
+
+fn take_log(log: &Arc<Mutex<Vec<usize>>>) -> Vec<usize> {
+    std::mem::replace(&mut *log.lock().unwrap(), Vec::new())
+}
+
+#[test]
+fn rotation() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut ring = new_ring(3);
+    assert_eq!(ring.depth(), 3);
+
+    let ids: Vec<_> = (0..3).map(|i| run_frame(&mut ring, i, &log)).collect();
+
+    // The first `depth` frames do not wait
+    assert_eq!(take_log(&log), vec![]);
+    let mut sorted_ids = ids.clone();
+    sorted_ids.sort();
+    assert_eq!(sorted_ids, vec![0, 1, 2]);
+
+    // Each subsequent frame waits for the frame `depth` frames ago and reuses
+    // its slot
+    for i in 3..10 {
+        assert_eq!(run_frame(&mut ring, i, &log), ids[i % 3]);
+        assert_eq!(take_log(&log), vec![i - 3]);
+    }
+}
+
+#[test]
+fn wait_idle_all() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut ring = new_ring(3);
+
+    for i in 0..5 {
+        run_frame(&mut ring, i, &log);
+    }
+    take_log(&log);
+
+    ring.wait_idle_all();
+    assert_eq!(take_log(&log), vec![2, 3, 4]);
+
+    // Fences are waited on only once
+    ring.wait_idle_all();
+    assert_eq!(take_log(&log), vec![]);
+    run_frame(&mut ring, 5, &log);
+    assert_eq!(take_log(&log), vec![]);
+}
+
+#[test]
+fn drop_waits() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut ring = new_ring(2);
+
+    for i in 0..3 {
+        run_frame(&mut ring, i, &log);
+    }
+    take_log(&log);
+
+    drop(ring);
+    assert_eq!(take_log(&log), vec![1, 2]);
+}
+
+#[test]
+fn resize_grow() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut ring = new_ring(2);
+
+    for i in 0..2 {
+        run_frame(&mut ring, i, &log);
+    }
+
+    ring.resize(4).unwrap();
+    assert_eq!(ring.depth(), 4);
+
+    // The new slots are used first without waiting
+    assert_eq!(run_frame(&mut ring, 2, &log), 3);
+    assert_eq!(run_frame(&mut ring, 3, &log), 2);
+    assert_eq!(take_log(&log), vec![]);
+
+    // Then the old slots are reused
+    for i in 4..8 {
+        run_frame(&mut ring, i, &log);
+        assert_eq!(take_log(&log), vec![i - 4]);
+    }
+}
+
+#[test]
+fn resize_shrink() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut ring = new_ring(4);
+
+    for i in 0..4 {
+        run_frame(&mut ring, i, &log);
+    }
+
+    // The oldest frames are waited on
+    ring.resize(2).unwrap();
+    assert_eq!(ring.depth(), 2);
+    assert_eq!(take_log(&log), vec![0, 1]);
+
+    for i in 4..8 {
+        run_frame(&mut ring, i, &log);
+        assert_eq!(take_log(&log), vec![i - 2]);
+    }
+}
+
+#[test]
+#[should_panic]
+fn begin_frame_twice() {
+    let mut ring = new_ring(2);
+    ring.begin_frame();
+    ring.begin_frame();
+}
+
+#[test]
+#[should_panic]
+fn end_frame_without_begin() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut ring = new_ring(2);
+    ring.end_frame(Fence { frame: 0, log });
+}
+
+#[test]
+#[should_panic]
+fn zero_depth() {
+    new_ring(0);
+}
@@ -0,0 +1,202 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use zangfx_base::{BufferImageRange, ImageFormat};
+use zangfx_utils::texture::*;
+
+#[test]
+fn mip_extents_square() {
+    let extents: Vec<_> = (0..5).map(|i| mip_extents([16, 16], i)).collect();
+    assert_eq!(extents, vec![[16, 16], [8, 8], [4, 4], [2, 2], [1, 1]]);
+}
+
+#[test]
+fn mip_extents_non_square() {
+    let extents: Vec<_> = (0..6).map(|i| mip_extents([40, 5], i)).collect();
+    assert_eq!(
+        extents,
+        vec![[40, 5], [20, 2], [10, 1], [5, 1], [2, 1], [1, 1]]
+    );
+}
+
+#[test]
+fn mip_extents_huge_level() {
+    assert_eq!(mip_extents([16, 16], 31), [1, 1]);
+    assert_eq!(mip_extents([16, 16], 32), [1, 1]);
+    assert_eq!(mip_extents([u32::max_value(); 2], 100), [1, 1]);
+}
+
+#[test]
+fn max_num_mip_levels_exhaustive() {
+    for width in 1..=300u32 {
+        for &height in &[1u32, 2, 3, 255, 256, 257] {
+            let num_levels = max_num_mip_levels([width, height]);
+
+            // The last level is 1x1 and the one before it isn't
+            assert_eq!(mip_extents([width, height], num_levels - 1), [1, 1]);
+            if num_levels > 1 {
+                assert_ne!(mip_extents([width, height], num_levels - 2), [1, 1]);
+            }
+        }
+    }
+}
+
+#[test]
+fn max_num_mip_levels_edges() {
+    assert_eq!(max_num_mip_levels([1, 1]), 1);
+    assert_eq!(max_num_mip_levels([1, 2]), 2);
+    assert_eq!(max_num_mip_levels([3, 1]), 2);
+    assert_eq!(max_num_mip_levels([4, 1]), 3);
+    assert_eq!(max_num_mip_levels([16384, 1]), 15);
+    assert_eq!(max_num_mip_levels([u32::max_value(), 1]), 32);
+}
+
+#[test]
+#[should_panic]
+fn max_num_mip_levels_zero() {
+    max_num_mip_levels([0, 0]);
+}
+
+#[test]
+fn align_up_exhaustive() {
+    for align in 1..=32u64 {
+        for x in 0..=256u64 {
+            let y = align_up(x, align);
+            assert_eq!(y % align, 0);
+            assert!(y >= x);
+            assert!(y - x < align);
+        }
+    }
+}
+
+#[test]
+fn staging_offset_align_all_formats() {
+    for format in ImageFormat::values() {
+        let bytes_per_pixel = format.size_class().num_bytes_per_pixel() as u64;
+        let align = staging_offset_align(bytes_per_pixel);
+        assert_eq!(align % 4, 0, "{:?}", format);
+        assert_eq!(align % bytes_per_pixel, 0, "{:?}", format);
+
+        // It must be the *least* common multiple
+        for smaller in 1..align {
+            assert!(smaller % 4 != 0 || smaller % bytes_per_pixel != 0);
+        }
+    }
+}
+
+#[test]
+fn staging_offset_align_values() {
+    let aligns: Vec<_> = (1..=16).map(staging_offset_align).collect();
+    assert_eq!(
+        aligns,
+        vec![4, 4, 12, 4, 20, 12, 28, 8, 36, 20, 44, 12, 52, 28, 60, 16]
+    );
+}
+
+#[test]
+fn row_pitch_values() {
+    assert_eq!(row_pitch(4, 0), 0);
+    assert_eq!(row_pitch(4, 13), 52);
+    assert_eq!(row_pitch(5, 7), 35);
+}
+
+#[test]
+fn staging_layout_mip_chain() {
+    let desc = TextureDesc::new(ImageFormat::SrgbRgba8, [5, 3]).with_full_mip_chain();
+    assert_eq!(desc.num_mip_levels, 3);
+
+    let layout = StagingLayout::new(
+        desc.bytes_per_pixel(),
+        (0..desc.num_mip_levels).map(|i| desc.mip_extents(i)),
+    );
+
+    assert_eq!(
+        layout.regions,
+        vec![
+            StagingRegion {
+                range: BufferImageRange {
+                    offset: 0,
+                    row_stride: 5,
+                    plane_stride: 15,
+                },
+                extents: [5, 3],
+                len: 60,
+            },
+            StagingRegion {
+                range: BufferImageRange {
+                    offset: 60,
+                    row_stride: 2,
+                    plane_stride: 2,
+                },
+                extents: [2, 1],
+                len: 8,
+            },
+            StagingRegion {
+                range: BufferImageRange {
+                    offset: 68,
+                    row_stride: 1,
+                    plane_stride: 1,
+                },
+                extents: [1, 1],
+                len: 4,
+            },
+        ]
+    );
+    assert_eq!(layout.size, 72);
+}
+
+#[test]
+fn staging_layout_exhaustive() {
+    for &bytes_per_pixel in &[1usize, 2, 3, 4, 5, 8, 16] {
+        for width in 1..=20u32 {
+            for height in 1..=20u32 {
+                let num_levels = max_num_mip_levels([width, height]);
+                let layout = StagingLayout::new(
+                    bytes_per_pixel,
+                    (0..num_levels).map(|i| mip_extents([width, height], i)),
+                );
+                let align = staging_offset_align(bytes_per_pixel as u64);
+
+                assert_eq!(layout.regions.len(), num_levels as usize);
+
+                let mut end = 0;
+                for (i, region) in layout.regions.iter().enumerate() {
+                    let extents = mip_extents([width, height], i as u32);
+                    let bpp = bytes_per_pixel as u64;
+
+                    assert_eq!(region.extents, extents);
+                    assert_eq!(region.range.offset % align, 0);
+                    assert!(region.range.offset >= end);
+                    assert!(region.range.offset - end < align);
+                    assert_eq!(region.range.row_stride, extents[0] as u64);
+                    assert_eq!(
+                        region.range.plane_stride,
+                        (extents[0] * extents[1]) as u64
+                    );
+                    assert_eq!(
+                        region.len,
+                        row_pitch(bpp, extents[0]) * extents[1] as u64
+                    );
+
+                    end = region.range.offset + region.len;
+                }
+                assert_eq!(layout.size, end);
+            }
+        }
+    }
+}
+
+#[test]
+fn staging_layout_empty() {
+    let layout = StagingLayout::new(4, None);
+    assert_eq!(layout.regions, vec![]);
+    assert_eq!(layout.size, 0);
+}
+
+#[test]
+#[should_panic]
+fn staging_layout_too_wide() {
+    StagingLayout::new(4, Some([MAX_ROW_STRIDE as u32 + 1, 1]));
+}
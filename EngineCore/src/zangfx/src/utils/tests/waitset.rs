@@ -0,0 +1,175 @@
+//
+// Copyright 2018 yvt, all rights reserved.
+//
+// This source code is a part of Nightingales.
+//
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use zangfx_base::{self as base, zangfx_impl_object, Result};
+use zangfx_utils::waitset::WaitSet;
+
+type CompletionHandler = Box<dyn FnMut(Result<()>) + Sync + Send>;
+
+/// A mock command buffer whose completion is signaled manually.
+#[derive(Clone, Default)]
+struct CmdBuffer {
+    handlers: Arc<Mutex<Vec<CompletionHandler>>>,
+}
+
+impl fmt::Debug for CmdBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CmdBuffer").finish()
+    }
+}
+
+zangfx_impl_object! { CmdBuffer: dyn base::CmdBuffer, dyn (std::fmt::Debug) }
+
+impl base::CmdBuffer for CmdBuffer {
+    fn commit(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn encode_render(&mut self, _: &base::RenderTargetTableRef) -> &mut dyn base::RenderCmdEncoder {
+        unreachable!()
+    }
+
+    fn encode_compute(&mut self) -> &mut dyn base::ComputeCmdEncoder {
+        unreachable!()
+    }
+
+    fn encode_copy(&mut self) -> &mut dyn base::CopyCmdEncoder {
+        unreachable!()
+    }
+
+    fn on_complete(&mut self, cb: CompletionHandler) {
+        self.handlers.lock().unwrap().push(cb);
+    }
+}
+
+impl CmdBuffer {
+    fn signal(&self) {
+        for mut handler in self.handlers.lock().unwrap().drain(..) {
+            handler(Ok(()));
+        }
+    }
+
+    fn signal_later(&self, delay: Duration) -> thread::JoinHandle<()> {
+        let this = self.clone();
+        thread::spawn(move || {
+            thread::sleep(delay);
+            this.signal();
+        })
+    }
+}
+
+#[test]
+fn empty() {
+    let mut wait_set = WaitSet::new();
+    assert!(wait_set.is_empty());
+    assert_eq!(wait_set.wait_any(None), None);
+    assert!(wait_set.wait_all(None));
+}
+
+#[test]
+fn wait_any_from_another_thread() {
+    let mut wait_set = WaitSet::new();
+    let mut cbs = vec![CmdBuffer::default(), CmdBuffer::default()];
+    wait_set.add(&mut cbs[0], 10);
+    wait_set.add(&mut cbs[1], 20);
+    assert_eq!(wait_set.len(), 2);
+
+    let thread = cbs[1].signal_later(Duration::from_millis(50));
+    assert_eq!(wait_set.wait_any(None), Some(20));
+    thread.join().unwrap();
+    assert_eq!(wait_set.len(), 1);
+
+    let thread = cbs[0].signal_later(Duration::from_millis(50));
+    assert_eq!(wait_set.wait_any(None), Some(10));
+    thread.join().unwrap();
+    assert!(wait_set.is_empty());
+    assert_eq!(wait_set.wait_any(None), None);
+}
+
+#[test]
+fn wait_any_completion_order() {
+    let mut wait_set = WaitSet::new();
+    let mut cbs = vec![
+        CmdBuffer::default(),
+        CmdBuffer::default(),
+        CmdBuffer::default(),
+    ];
+    for (i, cb) in cbs.iter_mut().enumerate() {
+        wait_set.add(cb, i);
+    }
+
+    cbs[2].signal();
+    cbs[0].signal();
+    cbs[1].signal();
+
+    assert_eq!(wait_set.wait_any(None), Some(2));
+    assert_eq!(wait_set.wait_any(None), Some(0));
+    assert_eq!(wait_set.wait_any(None), Some(1));
+    assert_eq!(wait_set.wait_any(None), None);
+}
+
+#[test]
+fn wait_any_timeout() {
+    let mut wait_set = WaitSet::new();
+    let mut cb = CmdBuffer::default();
+    wait_set.add(&mut cb, 1);
+
+    assert_eq!(wait_set.wait_any(Some(Duration::from_millis(20))), None);
+    assert_eq!(wait_set.len(), 1);
+
+    cb.signal();
+    assert_eq!(wait_set.wait_any(Some(Duration::from_millis(20))), Some(1));
+}
+
+#[test]
+fn wait_all_from_other_threads() {
+    let mut wait_set = WaitSet::new();
+    let mut cbs = vec![
+        CmdBuffer::default(),
+        CmdBuffer::default(),
+        CmdBuffer::default(),
+    ];
+    for (i, cb) in cbs.iter_mut().enumerate() {
+        wait_set.add(cb, i);
+    }
+
+    let threads: Vec<_> = cbs
+        .iter()
+        .enumerate()
+        .map(|(i, cb)| cb.signal_later(Duration::from_millis(20 * i as u64)))
+        .collect();
+
+    assert!(wait_set.wait_all(None));
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    // The completed command buffers can still be retrieved
+    assert_eq!(wait_set.len(), 3);
+    let mut tokens: Vec<_> = (0..3).map(|_| wait_set.wait_any(None).unwrap()).collect();
+    tokens.sort();
+    assert_eq!(tokens, vec![0, 1, 2]);
+}
+
+#[test]
+fn wait_all_timeout() {
+    let mut wait_set = WaitSet::new();
+    let mut cbs = vec![CmdBuffer::default(), CmdBuffer::default()];
+    wait_set.add(&mut cbs[0], 0);
+    wait_set.add(&mut cbs[1], 1);
+
+    cbs[0].signal();
+    assert!(!wait_set.wait_all(Some(Duration::from_millis(20))));
+
+    cbs[1].signal();
+    assert!(wait_set.wait_all(Some(Duration::from_millis(20))));
+}